@@ -1,6 +1,24 @@
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_prost_build::compile_protos("proto/xrfq3/v1/app.proto")?;
-    tonic_prost_build::compile_protos("proto/account/v1/account.proto")?;
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+    // the descriptors of the served services, for server reflection
+    tonic_prost_build::configure()
+        .file_descriptor_set_path(out_dir.join("xrfq3_descriptor.bin"))
+        .compile_protos(
+            &[
+                "proto/xrfq3/v1/app.proto",
+                "proto/account/v1/account.proto",
+                "proto/transaction/v1/transaction.proto",
+                "proto/audit/v1/audit.proto",
+            ],
+            &[
+                "proto/xrfq3/v1",
+                "proto/account/v1",
+                "proto/transaction/v1",
+                "proto/audit/v1",
+            ],
+        )?;
     tonic_prost_build::compile_protos("proto/currency/v1/currency.proto")?;
     Ok(())
 }
@@ -0,0 +1,13 @@
+-- Link monetary transactions to the block & ledger entries created for them
+ALTER TABLE monetary_transaction
+    ADD COLUMN IF NOT EXISTS block_id VARCHAR(100);
+
+ALTER TABLE ledger_entry
+    ADD COLUMN IF NOT EXISTS transaction_id VARCHAR(500) REFERENCES monetary_transaction (transaction_id);
+
+-- Transaction history is always read per account, ordered by time
+CREATE INDEX IF NOT EXISTS idx_monetary_tx_account_timestamp
+    ON monetary_transaction (account_id, timestamp, transaction_id);
+
+CREATE INDEX IF NOT EXISTS idx_ledger_entry_transaction
+    ON ledger_entry (transaction_id);
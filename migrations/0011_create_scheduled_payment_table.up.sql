@@ -0,0 +1,46 @@
+CREATE TYPE scheduled_payment_type AS ENUM ('Debit', 'Credit', 'Transfer');
+CREATE TYPE schedule_recurrence AS ENUM ('Once', 'Daily', 'Weekly', 'Monthly', 'Cron');
+CREATE TYPE scheduled_payment_status AS ENUM ('Active', 'Completed', 'Cancelled');
+
+CREATE TABLE IF NOT EXISTS scheduled_payment
+(
+    id                     VARCHAR(500)             NOT NULL PRIMARY KEY,
+    user_fp                VARCHAR(255)             NOT NULL,
+    account_id             VARCHAR(255)             NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    -- only set for transfers, the account credited with the debited amount
+    destination_account_id VARCHAR(255) REFERENCES user_account (id) ON DELETE CASCADE,
+    amount                 NUMERIC(25, 4)           NOT NULL,
+    payment_type           scheduled_payment_type   NOT NULL,
+    recurrence             schedule_recurrence      NOT NULL,
+    cron_expression        VARCHAR(255),
+    -- recurrences are evaluated in the account's timezone
+    timezone               VARCHAR(100)             NOT NULL,
+    status                 scheduled_payment_status NOT NULL,
+    starts_at              TIMESTAMP WITH TIME ZONE NOT NULL,
+    next_run_at            TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_run_at            TIMESTAMP WITH TIME ZONE,
+    creation_time          TIMESTAMP WITH TIME ZONE NOT NULL,
+    modification_time      TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- The worker polls for active payments that are due
+CREATE INDEX IF NOT EXISTS idx_scheduled_payment_due
+    ON scheduled_payment (next_run_at)
+    WHERE status = 'Active';
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_payment_account
+    ON scheduled_payment (account_id);
+
+CREATE TABLE IF NOT EXISTS scheduled_payment_execution
+(
+    id                   VARCHAR(500)             NOT NULL PRIMARY KEY,
+    scheduled_payment_id VARCHAR(500)             NOT NULL REFERENCES scheduled_payment (id) ON DELETE CASCADE,
+    transaction_id       VARCHAR(500) REFERENCES monetary_transaction (transaction_id),
+    succeeded            BOOLEAN                  NOT NULL,
+    failure_reason       TEXT,
+    scheduled_for        TIMESTAMP WITH TIME ZONE NOT NULL,
+    executed_at          TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_payment_execution_payment
+    ON scheduled_payment_execution (scheduled_payment_id, executed_at);
@@ -0,0 +1,39 @@
+CREATE TYPE payout_batch_mode AS ENUM ('AllOrNothing', 'BestEffort');
+CREATE TYPE payout_batch_status AS ENUM ('Processing', 'Completed', 'PartiallyCompleted', 'Failed');
+CREATE TYPE payout_item_type AS ENUM ('Credit', 'Transfer');
+CREATE TYPE payout_item_status AS ENUM ('Pending', 'Completed', 'Failed');
+
+CREATE TABLE IF NOT EXISTS payout_batch
+(
+    id                VARCHAR(500)             NOT NULL PRIMARY KEY,
+    user_fp           VARCHAR(255)             NOT NULL,
+    mode              payout_batch_mode        NOT NULL,
+    status            payout_batch_status      NOT NULL,
+    item_count        INTEGER                  NOT NULL,
+    -- the single block holding the ledger entries of every completed item
+    block_id          VARCHAR(100),
+    creation_time     TIMESTAMP WITH TIME ZONE NOT NULL,
+    modification_time TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS payout_batch_item
+(
+    id                VARCHAR(500)             NOT NULL PRIMARY KEY,
+    batch_id          VARCHAR(500)             NOT NULL REFERENCES payout_batch (id) ON DELETE CASCADE,
+    user_fp           VARCHAR(255)             NOT NULL,
+    idempotency_key   VARCHAR(255)             NOT NULL,
+    item_type         payout_item_type         NOT NULL,
+    account_id        VARCHAR(255)             NOT NULL,
+    source_account_id VARCHAR(255),
+    amount            NUMERIC(25, 4)           NOT NULL,
+    status            payout_item_status       NOT NULL,
+    transaction_ids   VARCHAR(500)[]           NOT NULL DEFAULT '{}',
+    failure_reason    TEXT,
+    creation_time     TIMESTAMP WITH TIME ZONE NOT NULL,
+    modification_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- a retried item with the same key returns the first result instead of paying twice
+    CONSTRAINT uq_payout_batch_item_idempotency UNIQUE (user_fp, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_payout_batch_item_batch
+    ON payout_batch_item (batch_id);
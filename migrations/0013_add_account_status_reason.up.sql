@@ -0,0 +1,14 @@
+CREATE TYPE account_status_reason AS ENUM (
+    'SuspectedFraud',
+    'UnpaidDebt',
+    'LegalHold',
+    'CustomerRequest',
+    'ReviewCleared',
+    'CustomerActivity',
+    'Dormancy');
+
+-- why the account moved to its current status, who moved it there and until when it applies
+ALTER TABLE user_account
+    ADD COLUMN status_reason     account_status_reason,
+    ADD COLUMN status_changed_by VARCHAR(255),
+    ADD COLUMN status_expires_at TIMESTAMP WITH TIME ZONE;
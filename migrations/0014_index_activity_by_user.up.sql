@@ -0,0 +1,2 @@
+-- dormancy detection looks for the latest activity of each account owner
+CREATE INDEX IF NOT EXISTS idx_activity_on_user_timestamp ON activity (user_fp, timestamp DESC);
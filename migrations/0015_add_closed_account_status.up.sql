@@ -0,0 +1,2 @@
+-- a closed account is terminal, it keeps its history but accepts no further operations
+ALTER TYPE account_status ADD VALUE IF NOT EXISTS 'Closed';
@@ -0,0 +1,12 @@
+-- Transaction limits set on one account, overriding the defaults of its type. Unset columns keep
+-- the default.
+CREATE TABLE IF NOT EXISTS account_limit
+(
+    account_id        VARCHAR(255)             NOT NULL PRIMARY KEY REFERENCES user_account (id) ON DELETE CASCADE,
+    max_amount        NUMERIC(25, 4),
+    daily_debit       NUMERIC(25, 4),
+    monthly_debit     NUMERIC(25, 4),
+    hourly_count      INTEGER,
+    changed_by        VARCHAR(255)             NOT NULL,
+    modification_time TIMESTAMP WITH TIME ZONE NOT NULL
+);
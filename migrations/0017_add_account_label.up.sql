@@ -0,0 +1,8 @@
+-- an optional nickname, letting a user hold several accounts of the same type
+ALTER TABLE user_account
+    ADD COLUMN label VARCHAR(64);
+
+-- a user has at most one open account per type and label, the unlabelled one included
+CREATE UNIQUE INDEX IF NOT EXISTS uq_user_account_type_label
+    ON user_account (user_fp, acct_type, COALESCE(label, ''))
+    WHERE status <> 'Closed';
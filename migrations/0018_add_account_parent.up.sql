@@ -0,0 +1,7 @@
+-- child accounts, e.g. departments or savings pots, belong to the same user as their parent
+ALTER TABLE user_account
+    ADD COLUMN parent_account_id VARCHAR(255) REFERENCES user_account (id);
+
+CREATE INDEX IF NOT EXISTS idx_user_account_parent
+    ON user_account (parent_account_id)
+    WHERE parent_account_id IS NOT NULL;
@@ -0,0 +1,54 @@
+CREATE TYPE account_role AS ENUM ('Viewer', 'Spender', 'Owner');
+CREATE TYPE approval_status AS ENUM ('Pending', 'Approved', 'Expired');
+
+-- Users sharing an account with its owner (user_account.user_fp), who always has the Owner role
+CREATE TABLE IF NOT EXISTS account_member
+(
+    account_id        VARCHAR(255)             NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    user_fp           VARCHAR(255)             NOT NULL,
+    role              account_role             NOT NULL,
+    added_by          VARCHAR(255)             NOT NULL,
+    creation_time     TIMESTAMP WITH TIME ZONE NOT NULL,
+    modification_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (account_id, user_fp)
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_member_user
+    ON account_member (user_fp);
+
+-- Debits above the threshold wait for `required_approvals` distinct approvals
+CREATE TABLE IF NOT EXISTS signing_policy
+(
+    account_id         VARCHAR(255)             NOT NULL PRIMARY KEY REFERENCES user_account (id) ON DELETE CASCADE,
+    threshold          NUMERIC(25, 4)           NOT NULL,
+    required_approvals INTEGER                  NOT NULL CHECK (required_approvals > 0),
+    approval_window    INTEGER                  NOT NULL CHECK (approval_window > 0), -- seconds
+    changed_by         VARCHAR(255)             NOT NULL,
+    modification_time  TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- A debit held as `Pending` until it is approved or expires
+CREATE TABLE IF NOT EXISTS approval_request
+(
+    transaction_id     VARCHAR(500)             NOT NULL PRIMARY KEY REFERENCES monetary_transaction (transaction_id),
+    account_id         VARCHAR(255)             NOT NULL,
+    requested_by       VARCHAR(255)             NOT NULL,
+    commission         NUMERIC(25, 4)           NOT NULL,
+    required_approvals INTEGER                  NOT NULL,
+    status             approval_status          NOT NULL,
+    expires_at         TIMESTAMP WITH TIME ZONE NOT NULL,
+    creation_time      TIMESTAMP WITH TIME ZONE NOT NULL,
+    modification_time  TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_request_pending
+    ON approval_request (expires_at) WHERE status = 'Pending';
+
+-- One row per distinct approver, the requester approves when asking
+CREATE TABLE IF NOT EXISTS transaction_approval
+(
+    transaction_id VARCHAR(500)             NOT NULL REFERENCES approval_request (transaction_id) ON DELETE CASCADE,
+    user_fp        VARCHAR(255)             NOT NULL,
+    approved_at    TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (transaction_id, user_fp)
+);
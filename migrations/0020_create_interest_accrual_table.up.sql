@@ -0,0 +1,21 @@
+-- interest is credited from the configured interest-expense account when capitalized
+ALTER TYPE monetary_tx_type ADD VALUE IF NOT EXISTS 'Interest';
+
+-- Interest accrued on the end-of-day balance of one day (in the account timezone), paid when
+-- capitalized; `transaction_id` is null when the capitalized amount rounds to zero
+CREATE TABLE IF NOT EXISTS interest_accrual
+(
+    account_id     VARCHAR(255)             NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    accrual_date   DATE                     NOT NULL,
+    currency       currency_enum            NOT NULL,
+    balance        NUMERIC(18, 4)           NOT NULL,
+    apr            NUMERIC(10, 6)           NOT NULL,
+    amount         NUMERIC(30, 10)          NOT NULL,
+    transaction_id VARCHAR(500)             NULL REFERENCES monetary_transaction (transaction_id),
+    capitalized_at TIMESTAMP WITH TIME ZONE NULL,
+    creation_time  TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (account_id, accrual_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_interest_accrual_unpaid
+    ON interest_accrual (account_id, accrual_date) WHERE capitalized_at IS NULL;
@@ -0,0 +1,20 @@
+-- a wallet may only go negative down to its credit line, wallets without one stay non-negative
+ALTER TABLE wallet
+    ADD COLUMN IF NOT EXISTS overdraft_limit NUMERIC(18, 4) NOT NULL DEFAULT 0
+        CONSTRAINT wallet_overdraft_limit_non_negative CHECK (overdraft_limit >= 0),
+    ADD CONSTRAINT wallet_balance_within_overdraft CHECK (balance >= -overdraft_limit);
+
+-- The overdraft fee and interest charged for a day (in the account timezone) the wallet ended
+-- below zero; days it did not are recorded with nothing charged
+CREATE TABLE IF NOT EXISTS overdraft_charge
+(
+    account_id     VARCHAR(255)             NOT NULL REFERENCES user_account (id) ON DELETE CASCADE,
+    charge_date    DATE                     NOT NULL,
+    currency       currency_enum            NOT NULL,
+    balance        NUMERIC(18, 4)           NOT NULL,
+    fee            NUMERIC(18, 4)           NOT NULL,
+    interest       NUMERIC(18, 4)           NOT NULL,
+    transaction_id VARCHAR(500)             NULL REFERENCES monetary_transaction (transaction_id),
+    creation_time  TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (account_id, charge_date)
+);
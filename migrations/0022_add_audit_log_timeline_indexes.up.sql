@@ -0,0 +1,5 @@
+-- entity timelines and audit searches page through the logs by (creation_time, id)
+DROP INDEX IF EXISTS idx_audit_log_on_entity;
+CREATE INDEX IF NOT EXISTS idx_audit_log_on_entity_timeline ON audit_log (entity_type, entity_id, creation_time, id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_on_user_timeline ON audit_log (user_fp, creation_time, id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_on_timeline ON audit_log (creation_time, id);
@@ -0,0 +1,20 @@
+-- Audit logs form one hash chain: each log hashes its content with the hash of the log before it,
+-- in sequence_num order. Logs written before the chain existed keep these columns NULL
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS sequence_num BIGINT      NULL,
+    ADD COLUMN IF NOT EXISTS prev_hash    VARCHAR(64) NULL,
+    ADD COLUMN IF NOT EXISTS hash         VARCHAR(64) NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_log_on_sequence_num ON audit_log (sequence_num);
+
+-- The chain head anchored into a block of the Cassandra block chain, the block being the
+-- reference a verification trusts
+CREATE TABLE IF NOT EXISTS audit_checkpoint
+(
+    sequence_num  BIGINT                   NOT NULL PRIMARY KEY,
+    audit_id      VARCHAR(255)             NOT NULL,
+    hash          VARCHAR(64)              NOT NULL,
+    block_id      VARCHAR(255)             NOT NULL,
+    chain_id      VARCHAR(255)             NOT NULL,
+    creation_time TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,39 @@
+-- Audit logs past the retention of their entity type are moved out of audit_log into gzip JSONL
+-- files of the archive store, one archive per entity type and day of creation
+CREATE TABLE IF NOT EXISTS audit_archive
+(
+    id            VARCHAR(255)             NOT NULL PRIMARY KEY,
+    entity_type   VARCHAR(255)             NOT NULL,
+    archive_date  DATE                     NOT NULL,
+    object_path   TEXT                     NOT NULL,
+    manifest_path TEXT                     NOT NULL,
+    row_count     BIGINT                   NOT NULL,
+    -- SHA3-256 of the archive file
+    checksum      VARCHAR(64)              NOT NULL,
+    from_time     TIMESTAMP WITH TIME ZONE NOT NULL,
+    to_time       TIMESTAMP WITH TIME ZONE NOT NULL,
+    creation_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- set while the archived logs are loaded back into audit_log
+    restored_at   TIMESTAMP WITH TIME ZONE NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_archive_on_date ON audit_archive (entity_type, archive_date);
+
+-- The links of archived logs, keeping the audit chain verifiable without their content
+CREATE TABLE IF NOT EXISTS audit_log_archived
+(
+    sequence_num BIGINT       NOT NULL PRIMARY KEY,
+    audit_id     VARCHAR(255) NOT NULL,
+    prev_hash    VARCHAR(64)  NULL,
+    hash         VARCHAR(64)  NOT NULL,
+    archive_id   VARCHAR(255) NOT NULL REFERENCES audit_archive (id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_archived_on_archive ON audit_log_archived (archive_id);
+
+-- The archive a log was restored from, restored logs are not archived again
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS restored_from VARCHAR(255) NULL REFERENCES audit_archive (id);
+
+-- the archive job takes the oldest logs of an entity type first
+CREATE INDEX IF NOT EXISTS idx_audit_log_on_entity_type_timeline ON audit_log (entity_type, creation_time, id);
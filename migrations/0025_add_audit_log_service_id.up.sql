@@ -0,0 +1,4 @@
+-- The internal service that made the change, authenticated by its client certificate. NULL for
+-- changes made by other callers
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS service_id VARCHAR(255) NULL;
@@ -0,0 +1,6 @@
+-- a temporary freeze lifted by the platform once its expiry passed
+ALTER TYPE account_status_reason ADD VALUE IF NOT EXISTS 'FreezeExpired';
+
+-- the freeze expiry job takes the frozen accounts whose expiry passed first
+CREATE INDEX IF NOT EXISTS idx_user_account_on_status_expiry ON user_account (status_expires_at)
+    WHERE status = 'Frozen' AND status_expires_at IS NOT NULL;
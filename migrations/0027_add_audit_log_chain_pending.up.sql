@@ -0,0 +1,8 @@
+-- Logs are saved unchained with the change they audit and chained later by the checkpoint job, so
+-- money movements never wait on the chain. Legacy logs, never chained, keep this FALSE
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS chain_pending BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- the checkpoint job chains the pending logs in the order they were created
+CREATE INDEX IF NOT EXISTS idx_audit_log_on_chain_pending ON audit_log (creation_time, id)
+    WHERE chain_pending;
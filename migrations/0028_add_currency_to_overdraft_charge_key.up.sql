@@ -0,0 +1,4 @@
+-- Each wallet with a credit line is charged on its own, a day is charged once per wallet
+ALTER TABLE overdraft_charge
+    DROP CONSTRAINT IF EXISTS overdraft_charge_pkey,
+    ADD PRIMARY KEY (account_id, currency, charge_date);
@@ -1,6 +1,5 @@
 use chrono;
 use std::fmt::Display;
-use tokio::task_local;
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
@@ -17,8 +16,3 @@ pub fn generate_request_id() -> String {
     let time_ms = chrono::Utc::now().timestamp_millis();
     format!("xrf_ilz_q3_{}*{}", Uuid::new_v4().to_string(), time_ms)
 }
-
-// TASK-LOCAL STORAGE: This creates a static variable that is local to each async task.
-task_local! {
-    static REQUEST_ID: RequestId;
-}
@@ -1,17 +1,239 @@
 use crate::configurations::DatabaseConfig;
+use crate::core::{
+    AccountType, AuditRetentionPolicy, Currency, InterestProduct, OverdraftTerms, TransactionLimits,
+};
 use crate::Environment;
 use config::{self, ConfigError};
 use serde::Deserialize;
+use std::net::IpAddr;
+
+#[derive(Deserialize, Clone)]
+pub struct FailedAttemptsConfig {
+    // failures of the same class, within `window` seconds, that lock an account
+    pub threshold: u32,
+    pub window: u64,
+}
+
+/// Default transaction limits of each account type, accounts can override them one by one.
+#[derive(Deserialize, Clone, Default)]
+pub struct TransactionLimitsConfig {
+    #[serde(default)]
+    pub normal: TransactionLimits,
+    #[serde(default)]
+    pub wallet: TransactionLimits,
+    #[serde(default)]
+    pub escrow: TransactionLimits,
+    #[serde(default)]
+    pub system_fee: TransactionLimits,
+}
+
+impl TransactionLimitsConfig {
+    pub fn limits_for(&self, account_type: &AccountType) -> &TransactionLimits {
+        match account_type {
+            AccountType::Normal => &self.normal,
+            AccountType::Wallet => &self.wallet,
+            AccountType::Escrow => &self.escrow,
+            AccountType::SystemFee => &self.system_fee,
+        }
+    }
+}
+
+/// Interest paid on the accounts matching a product, out of the interest-expense account. No
+/// interest is capitalized while ***expense_account_id*** is unset.
+#[derive(Deserialize, Clone, Default)]
+pub struct InterestConfig {
+    pub expense_account_id: Option<String>,
+    #[serde(default)]
+    pub products: Vec<InterestProduct>,
+}
+
+impl InterestConfig {
+    pub fn product_for(
+        &self,
+        account_type: &AccountType,
+        currency: &Currency,
+    ) -> Option<&InterestProduct> {
+        self.products
+            .iter()
+            .find(|product| product.account_type == *account_type && product.currency == *currency)
+    }
+}
+
+/// Commission charged on wallet transactions, paid to the fee account. No commission is charged
+/// while ***fee_account_id*** is unset.
+#[derive(Deserialize, Clone, Default)]
+pub struct CommissionConfig {
+    pub fee_account_id: Option<String>,
+}
+
+/// Overdraft fee and interest of each currency, paid to the fee account. Overdrawn wallets are not
+/// charged while ***fee_account_id*** is unset.
+#[derive(Deserialize, Clone, Default)]
+pub struct OverdraftConfig {
+    pub fee_account_id: Option<String>,
+    #[serde(default)]
+    pub terms: Vec<OverdraftTerms>,
+}
+
+/// Where audit archives are written: a directory of the local disk or an S3-compatible bucket.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuditArchiveStorageConfig {
+    Local {
+        path: String,
+    },
+    S3 {
+        bucket: String,
+        region: String,
+        // set for S3-compatible stores such as MinIO
+        endpoint: Option<String>,
+        access_key_id: Option<String>,
+        secret_access_key: Option<String>,
+        #[serde(default)]
+        allow_http: bool,
+    },
+}
+
+/// Audit logs are moved to the archive store once past the retention of their entity type.
+/// Nothing is archived while ***storage*** is unset.
+#[derive(Deserialize, Clone, Default)]
+pub struct AuditRetentionConfig {
+    pub storage: Option<AuditArchiveStorageConfig>,
+    #[serde(default)]
+    pub policies: Vec<AuditRetentionPolicy>,
+}
 
 #[derive(Deserialize, Clone)]
 pub struct ApplicationConfig {
     pub name: String,
+    // users allowed to perform admin-only operations such as unfreezing an account
+    #[serde(default)]
+    pub admin_user_fps: Vec<String>,
+    pub failed_attempts: FailedAttemptsConfig,
+    #[serde(default)]
+    pub transaction_limits: TransactionLimitsConfig,
+    #[serde(default)]
+    pub interest: InterestConfig,
+    #[serde(default)]
+    pub commission: CommissionConfig,
+    #[serde(default)]
+    pub overdraft: OverdraftConfig,
+    #[serde(default)]
+    pub audit_retention: AuditRetentionConfig,
+}
+
+/// A client allowed to call the API, ***key_hash*** being the SHA3-256 of its API key, hex
+/// encoded. The key itself is never stored.
+#[derive(Deserialize, Clone)]
+pub struct ApiClientConfig {
+    pub client_id: String,
+    pub key_hash: String,
+}
+
+/// A key user tokens are verified with, picked by the `kid` of the token. HS* algorithms use
+/// ***secret***, the others the PEM encoded ***public_key***.
+#[derive(Deserialize, Clone)]
+pub struct TokenKeyConfig {
+    pub kid: String,
+    pub algorithm: String,
+    pub secret: Option<String>,
+    pub public_key: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct UserTokenConfig {
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    // seconds of clock skew tolerated on `exp` and `nbf`
+    #[serde(default)]
+    pub leeway: u64,
+    #[serde(default)]
+    pub keys: Vec<TokenKeyConfig>,
+}
+
+/// Every call needs the API key of a registered client and a signed user token, whose subject is
+/// the user fingerprint. Without clients or keys every call is rejected.
+#[derive(Deserialize, Clone, Default)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub api_clients: Vec<ApiClientConfig>,
+    #[serde(default)]
+    pub token: UserTokenConfig,
+}
+
+/// An internal service authenticated by its client certificate, whose subject common name or one
+/// of its DNS or URI subject alternative names is in ***names***. It may only call
+/// ***allowed_rpcs***, full method paths such as `/proto.account.v1.AccountService/FindWallet`, or
+/// `/proto.account.v1.AccountService/*` for every method of a service.
+#[derive(Deserialize, Clone)]
+pub struct ServiceIdentityConfig {
+    pub service_id: String,
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub allowed_rpcs: Vec<String>,
+}
+
+impl ServiceIdentityConfig {
+    pub fn allows(&self, path: &str) -> bool {
+        self.allowed_rpcs
+            .iter()
+            .any(|rpc| match rpc.strip_suffix('*') {
+                Some(service) => service.ends_with('/') && path.starts_with(service),
+                None => rpc == path,
+            })
+    }
+}
+
+/// Client certificate authentication, verified with the CA bundle at `ILZ_Q3_PEM_CLIENT_CA_PATH`.
+/// Calls without a certificate are still accepted, with an API key, unless
+/// ***require_client_cert*** is set.
+#[derive(Deserialize, Clone, Default)]
+pub struct MtlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub require_client_cert: bool,
+    #[serde(default)]
+    pub services: Vec<ServiceIdentityConfig>,
+}
+
+/// A token bucket of up to ***capacity*** calls, refilled with ***refill_per_second*** calls.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct TokenBucketConfig {
+    pub capacity: u32,
+    pub refill_per_second: f64,
+}
+
+/// The buckets of each class of RPC, so a burst of reads can't use up the calls moving money.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RateLimitBucketsConfig {
+    pub read: TokenBucketConfig,
+    pub write: TokenBucketConfig,
+    pub money_movement: TokenBucketConfig,
+}
+
+/// Rate limits kept in Redis, so they hold across instances. Every RPC method has a bucket per
+/// API client, shared by its users, and a bucket per user of the client.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RateLimitConfig {
+    pub per_client: RateLimitBucketsConfig,
+    pub per_user: RateLimitBucketsConfig,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct GrpcServerConfig {
     pub port: String,
     pub timeout: u16,
+    // proxies allowed to set `x-forwarded-for`, the client address of their requests
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub mtls: MtlsConfig,
+    // calls are not rate limited when None
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -27,10 +249,114 @@ pub struct LogConfig {
     pub prefix: String,
 }
 
+#[derive(Deserialize, Clone)]
+pub struct ScheduledPaymentWorkerConfig {
+    // seconds between two polls for due payments
+    pub interval: u64,
+    pub batch_size: u32,
+}
+
+/// Days without activity after which an account of each type becomes `Inactive`. Types without
+/// a term never become dormant.
+#[derive(Deserialize, Clone, Default)]
+pub struct DormancyTermsConfig {
+    pub normal: Option<u32>,
+    pub wallet: Option<u32>,
+    pub escrow: Option<u32>,
+    pub system_fee: Option<u32>,
+}
+
+impl DormancyTermsConfig {
+    pub fn days_for(&self, account_type: &AccountType) -> Option<u32> {
+        match account_type {
+            AccountType::Normal => self.normal,
+            AccountType::Wallet => self.wallet,
+            AccountType::Escrow => self.escrow,
+            AccountType::SystemFee => self.system_fee,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct DormancyWorkerConfig {
+    // seconds between two scans for dormant accounts
+    pub interval: u64,
+    pub batch_size: u32,
+    #[serde(default)]
+    pub terms: DormancyTermsConfig,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FreezeExpiryWorkerConfig {
+    // seconds between two scans for freezes past their expiry
+    pub interval: u64,
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ApprovalWorkerConfig {
+    // seconds between two scans for debits not approved in time
+    pub interval: u64,
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct InterestWorkerConfig {
+    // seconds between two runs of the accrual and capitalization job
+    pub interval: u64,
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OverdraftWorkerConfig {
+    // seconds between two runs of the overdraft charges
+    pub interval: u64,
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AuditCheckpointWorkerConfig {
+    // seconds between two runs chaining the new audit logs and anchoring the chain head, the
+    // longest a log waits unchained
+    pub interval: u64,
+    // audit logs read per query when checking the logs appended since the last anchor
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AuditArchiveWorkerConfig {
+    // seconds between two runs of the audit archive job
+    pub interval: u64,
+    // audit logs moved per archive file at most
+    pub batch_size: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct HealthWorkerConfig {
+    // seconds between two probes of the dependencies
+    pub interval: u64,
+    // milliseconds a dependency has to answer its probe
+    pub timeout: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WorkerConfig {
+    pub scheduled_payments: ScheduledPaymentWorkerConfig,
+    pub dormancy: DormancyWorkerConfig,
+    pub freeze_expiry: FreezeExpiryWorkerConfig,
+    pub approvals: ApprovalWorkerConfig,
+    pub interest: InterestWorkerConfig,
+    pub overdraft: OverdraftWorkerConfig,
+    pub audit_checkpoint: AuditCheckpointWorkerConfig,
+    pub audit_archive: AuditArchiveWorkerConfig,
+    pub health: HealthWorkerConfig,
+}
+
 #[derive(serde::Deserialize, Clone)]
 pub struct Configurations {
     pub log: LogConfig,
     pub server: ServerConfig,
+    pub worker: WorkerConfig,
     pub app: ApplicationConfig,
     pub database: DatabaseConfig,
 }
@@ -2,4 +2,13 @@ mod database;
 mod load;
 
 pub use database::{CassandraConfig, DatabaseConfig, PostgresConfig, RedisConfig, TimescaleConfig};
-pub use load::{load_config, Configurations, GrpcServerConfig, LogConfig, ServerConfig};
+pub use load::{
+    load_config, ApiClientConfig, ApplicationConfig, ApprovalWorkerConfig,
+    AuditArchiveStorageConfig, AuditArchiveWorkerConfig, AuditCheckpointWorkerConfig,
+    AuditRetentionConfig, AuthConfig, CommissionConfig, Configurations, DormancyTermsConfig,
+    DormancyWorkerConfig, FailedAttemptsConfig, FreezeExpiryWorkerConfig, GrpcServerConfig,
+    HealthWorkerConfig, InterestConfig, InterestWorkerConfig, LogConfig, MtlsConfig,
+    OverdraftConfig, OverdraftWorkerConfig, RateLimitBucketsConfig, RateLimitConfig,
+    ScheduledPaymentWorkerConfig, ServerConfig, ServiceIdentityConfig, TokenBucketConfig,
+    TokenKeyConfig, TransactionLimitsConfig, UserTokenConfig, WorkerConfig,
+};
@@ -8,12 +8,44 @@ pub const ILZ_Q3_POSTGRES_DB_URL_ENV_KEY: &str = "ILZ_Q3_DB_URL";
 pub const XRF_USER_FINGERPRINT: &str = "xrf-user-fp";
 pub const XRF_USER_TIMEZONE: &str = "xrf-user-timezone";
 pub const REQUEST_ID_KEY: &str = "request-id";
-pub const ILZ_Q3_ENV_KEY: &str = "XRF_Q3_API_KEY";
+pub const XRF_API_KEY: &str = "xrf-api-key";
+// `Bearer {user token}`
+pub const AUTHORIZATION_KEY: &str = "authorization";
 pub const CLIENT_REQ_ID: &'static str = "request_id";
 
 //////////
 pub const KEY_PEM_PATH: &str = "ILZ_Q3_PEM_KEY_PATH";
 pub const CERT_PEM_PATH: &str = "ILZ_Q3_PEM_CERT_PATH";
+// CA bundle client certificates are verified with, when mTLS is enabled
+pub const CLIENT_CA_PEM_PATH: &str = "ILZ_Q3_PEM_CLIENT_CA_PATH";
 pub const CREATE_NEW_USER_ACCOUNT: &str = "CREATE NEW USER ACCOUNT ACTIVITY";
 
 pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+////////// Pagination
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+////////// Payout batches
+pub const MAX_PAYOUT_BATCH_ITEMS: usize = 1000;
+// items of a best-effort batch applied at the same time, each holds a pool connection
+pub const PAYOUT_BATCH_CONCURRENCY: usize = 8;
+
+////////// Account lifecycle
+// recorded as the actor of changes the platform makes on its own, e.g. marking accounts dormant
+pub const SYSTEM_USER_FP: &str = "system";
+pub const ACCOUNT_STATUS_EVENTS_CHANNEL: &str = "xrfq3:account-status-events";
+// levels an account hierarchy can have, the root account included
+pub const MAX_ACCOUNT_DEPTH: usize = 3;
+// seconds a debit waits for approvals when the signing policy does not say
+pub const DEFAULT_APPROVAL_WINDOW: u32 = 86_400;
+// followed by `{account_id}:{failure class}`
+pub const FAILED_ATTEMPTS_KEY_PREFIX: &str = "xrfq3:failed-tx-attempts";
+
+////////// Transaction limits
+// followed by `{account_id}:{window}`
+pub const LIMIT_USAGE_KEY_PREFIX: &str = "xrfq3:limit-usage";
+
+////////// Rate limits
+// followed by `{client_id}:{rpc method}` or `{client_id}:{user_fp}:{rpc method}`
+pub const RATE_LIMIT_KEY_PREFIX: &str = "xrfq3:rate-limit";
@@ -1,6 +1,10 @@
 use crate::core::BlockRegion;
-use crate::storage::{get_redis_client, PreparedAppStatements};
-use crate::{Environment, RedisConfig};
+use crate::storage::{get_archive_store, get_redis_client, PreparedAppStatements};
+use crate::{
+    ApplicationConfig, AuditRetentionConfig, CommissionConfig, Environment, FailedAttemptsConfig,
+    InterestConfig, OverdraftConfig, RedisConfig, TransactionLimitsConfig,
+};
+use object_store::ObjectStore;
 use redis::aio::ConnectionManager;
 use std::fmt::{Debug, Display, Formatter};
 use std::str::FromStr;
@@ -13,6 +17,15 @@ pub struct ApplicationContext {
     pub app_env: Environment,
     pub block_region: BlockRegion,
     pub redis_conn: ConnectionManager,
+    pub admin_user_fps: Arc<Vec<String>>,
+    pub failed_attempts: FailedAttemptsConfig,
+    pub transaction_limits: Arc<TransactionLimitsConfig>,
+    pub interest: Arc<InterestConfig>,
+    pub commission: Arc<CommissionConfig>,
+    pub overdraft: Arc<OverdraftConfig>,
+    pub audit_retention: Arc<AuditRetentionConfig>,
+    // None while no archive storage is configured
+    pub audit_archive: Option<Arc<dyn ObjectStore>>,
     pub statements: Arc<PreparedAppStatements>,
 }
 
@@ -38,6 +51,7 @@ impl ApplicationContext {
         region: String,
         redis_config: &RedisConfig,
         statements: PreparedAppStatements,
+        app_config: &ApplicationConfig,
     ) -> Result<Self, String> {
         let block_region = match BlockRegion::from_str(&region) {
             Ok(region) => region,
@@ -45,11 +59,25 @@ impl ApplicationContext {
         };
         let statements = Arc::new(statements);
         let redis_conn = get_redis_client(redis_config).await?;
+        let audit_archive = app_config
+            .audit_retention
+            .storage
+            .as_ref()
+            .map(get_archive_store)
+            .transpose()?;
 
         Ok(ApplicationContext {
             app_id,
             statements,
             redis_conn,
+            admin_user_fps: Arc::new(app_config.admin_user_fps.clone()),
+            failed_attempts: app_config.failed_attempts.clone(),
+            transaction_limits: Arc::new(app_config.transaction_limits.clone()),
+            interest: Arc::new(app_config.interest.clone()),
+            commission: Arc::new(app_config.commission.clone()),
+            overdraft: Arc::new(app_config.overdraft.clone()),
+            audit_retention: Arc::new(app_config.audit_retention.clone()),
+            audit_archive,
             block_region,
             is_test_ctx: false,
             app_env: Environment::Dev, // TODO: Change this and load environment
@@ -61,15 +89,30 @@ impl ApplicationContext {
         region: String,
         redis_config: &RedisConfig,
         statements: PreparedAppStatements,
+        app_config: &ApplicationConfig,
     ) -> Result<Self, String> {
         let block_region =
             BlockRegion::from_str(&region).unwrap_or_else(|_e| BlockRegion::MexicoCentral);
         let statements = Arc::new(statements);
         let redis_conn = get_redis_client(redis_config).await?;
+        let audit_archive = app_config
+            .audit_retention
+            .storage
+            .as_ref()
+            .map(get_archive_store)
+            .transpose()?;
         Ok(ApplicationContext {
             app_id,
             statements,
             redis_conn,
+            admin_user_fps: Arc::new(app_config.admin_user_fps.clone()),
+            failed_attempts: app_config.failed_attempts.clone(),
+            transaction_limits: Arc::new(app_config.transaction_limits.clone()),
+            interest: Arc::new(app_config.interest.clone()),
+            commission: Arc::new(app_config.commission.clone()),
+            overdraft: Arc::new(app_config.overdraft.clone()),
+            audit_retention: Arc::new(app_config.audit_retention.clone()),
+            audit_archive,
             block_region,
             is_test_ctx: true,
             app_env: Environment::Test,
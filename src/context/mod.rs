@@ -1,7 +1,9 @@
 mod application;
 mod request;
+mod roles;
 mod user;
 
 pub use application::ApplicationContext;
 pub use request::RequestContext;
+pub use roles::CallerRoles;
 pub use user::UserContext;
@@ -5,4 +5,8 @@ pub struct RequestContext {
     pub request_ip: Option<String>,
     pub user_agent: Option<String>,
     pub request_id: Option<RequestId>,
+    // the internal service calling with a client certificate, None for other callers
+    pub service_id: Option<String>,
+    // the full path of the RPC called, e.g. `/proto.account.v1.AccountService/FindWallet`
+    pub rpc_method: Option<String>,
 }
@@ -0,0 +1,32 @@
+use crate::core::UserRole;
+
+/// The roles of the authenticated caller of a request.
+#[derive(Debug, Clone, Default)]
+pub struct CallerRoles {
+    pub roles: Vec<UserRole>,
+}
+
+impl CallerRoles {
+    pub fn has_role(&self, role: UserRole) -> bool {
+        self.roles.contains(&role)
+    }
+
+    pub fn has_any_role(&self, roles: &[UserRole]) -> bool {
+        roles.iter().any(|role| self.has_role(*role))
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.has_role(UserRole::Admin)
+    }
+
+    /// Support, compliance and admins may read any account, e.g. its wallets and limits.
+    pub fn may_read_any_account(&self) -> bool {
+        self.has_any_role(&[UserRole::Support, UserRole::Compliance, UserRole::Admin])
+    }
+
+    /// Credits without a funding account create money, only internal services and admins may
+    /// issue them.
+    pub fn may_issue_credits(&self) -> bool {
+        self.has_any_role(&[UserRole::Service, UserRole::Admin])
+    }
+}
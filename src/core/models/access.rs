@@ -0,0 +1,58 @@
+use crate::DomainError;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// What the caller of an RPC is, resolved from its authenticated identity. A caller can hold
+/// several roles, every caller with a user token is a ***User***.
+///
+/// ***Support*** helps users with their accounts.
+///
+/// ***Compliance*** investigates accounts, it can freeze them and read the audit logs.
+///
+/// ***Admin*** operates the ledger, e.g. unfreezes accounts and sets limits.
+///
+/// ***Service*** is an internal service authenticated by its client certificate.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UserRole {
+    User,
+    Support,
+    Compliance,
+    Admin,
+    Service,
+}
+
+impl FromStr for UserRole {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "User" | "user" => Ok(UserRole::User),
+            "Support" | "support" => Ok(UserRole::Support),
+            "Compliance" | "compliance" => Ok(UserRole::Compliance),
+            "Admin" | "admin" => Ok(UserRole::Admin),
+            "Service" | "service" => Ok(UserRole::Service),
+            _ => Err(DomainError::ParseError(format!("unrecognized role: {}", s))),
+        }
+    }
+}
+
+impl Display for UserRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserRole::User => write!(f, "User"),
+            UserRole::Support => write!(f, "Support"),
+            UserRole::Compliance => write!(f, "Compliance"),
+            UserRole::Admin => write!(f, "Admin"),
+            UserRole::Service => write!(f, "Service"),
+        }
+    }
+}
+
+/// A call rejected because none of the caller's ***roles*** may call ***rpc_method***, kept in
+/// the audit logs.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccessDenial {
+    pub rpc_method: String,
+    pub client_id: String,
+    pub roles: Vec<UserRole>,
+}
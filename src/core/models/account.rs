@@ -14,6 +14,7 @@ pub enum AccountStatus {
     Active,
     Frozen,   // For a result of suspected fraud, unpaid debt, or legal issues
     Inactive, // Lack of customer-initiated txs for a specific period (e.g., 30, 60 days) determined by the terms.
+    Closed, // Terminal, the balance was swept to another account and no operation is accepted anymore
 }
 
 impl FromStr for AccountStatus {
@@ -23,6 +24,7 @@ impl FromStr for AccountStatus {
             "Frozen" => Ok(AccountStatus::Frozen),
             "Active" => Ok(AccountStatus::Active),
             "Inactive" => Ok(AccountStatus::Inactive),
+            "Closed" => Ok(AccountStatus::Closed),
             _ => Err(DomainError::ParseError(
                 "unrecognized account status".to_string(),
             )),
@@ -42,6 +44,81 @@ impl Display for AccountStatus {
             AccountStatus::Inactive => {
                 write!(f, "Inactive")
             }
+            AccountStatus::Closed => {
+                write!(f, "Closed")
+            }
+        }
+    }
+}
+
+/// Why an account was moved to its current status. Each reason only applies to the statuses
+/// listed in [AccountStatusReason::applies_to].
+#[derive(Serialize, Debug, Clone, Deserialize, sqlx::Type, PartialEq, Eq)]
+#[sqlx(type_name = "account_status_reason")]
+#[sqlx(rename_all = "PascalCase")]
+pub enum AccountStatusReason {
+    SuspectedFraud,
+    UnpaidDebt,
+    LegalHold,
+    CustomerRequest,
+    ReviewCleared,    // an admin reviewed a frozen account and released it
+    CustomerActivity, // a customer-initiated tx on an inactive account
+    Dormancy,
+    FreezeExpired, // the platform lifted a temporary freeze once its expiry passed
+}
+
+impl AccountStatusReason {
+    pub fn applies_to(&self, status: &AccountStatus) -> bool {
+        match status {
+            AccountStatus::Frozen => matches!(
+                self,
+                AccountStatusReason::SuspectedFraud
+                    | AccountStatusReason::UnpaidDebt
+                    | AccountStatusReason::LegalHold
+                    | AccountStatusReason::CustomerRequest
+            ),
+            AccountStatus::Active => matches!(
+                self,
+                AccountStatusReason::ReviewCleared
+                    | AccountStatusReason::CustomerActivity
+                    | AccountStatusReason::FreezeExpired
+            ),
+            AccountStatus::Inactive => *self == AccountStatusReason::Dormancy,
+            AccountStatus::Closed => *self == AccountStatusReason::CustomerRequest,
+        }
+    }
+}
+
+impl FromStr for AccountStatusReason {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SuspectedFraud" | "suspected_fraud" => Ok(AccountStatusReason::SuspectedFraud),
+            "UnpaidDebt" | "unpaid_debt" => Ok(AccountStatusReason::UnpaidDebt),
+            "LegalHold" | "legal_hold" => Ok(AccountStatusReason::LegalHold),
+            "CustomerRequest" | "customer_request" => Ok(AccountStatusReason::CustomerRequest),
+            "ReviewCleared" | "review_cleared" => Ok(AccountStatusReason::ReviewCleared),
+            "CustomerActivity" | "customer_activity" => Ok(AccountStatusReason::CustomerActivity),
+            "Dormancy" | "dormancy" => Ok(AccountStatusReason::Dormancy),
+            "FreezeExpired" | "freeze_expired" => Ok(AccountStatusReason::FreezeExpired),
+            _ => Err(DomainError::ParseError(
+                "unrecognized account status reason".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for AccountStatusReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountStatusReason::SuspectedFraud => write!(f, "SuspectedFraud"),
+            AccountStatusReason::UnpaidDebt => write!(f, "UnpaidDebt"),
+            AccountStatusReason::LegalHold => write!(f, "LegalHold"),
+            AccountStatusReason::CustomerRequest => write!(f, "CustomerRequest"),
+            AccountStatusReason::ReviewCleared => write!(f, "ReviewCleared"),
+            AccountStatusReason::CustomerActivity => write!(f, "CustomerActivity"),
+            AccountStatusReason::Dormancy => write!(f, "Dormancy"),
+            AccountStatusReason::FreezeExpired => write!(f, "FreezeExpired"),
         }
     }
 }
@@ -112,6 +189,8 @@ impl Display for AccountType {
     }
 }
 
+const MAX_LABEL_LENGTH: usize = 64;
+
 #[derive(Serialize, Debug, Clone)]
 pub struct Account {
     pub id: String,
@@ -123,6 +202,14 @@ pub struct Account {
     pub currency: Currency,
     pub status: AccountStatus,
     pub account_type: AccountType,
+    // tells apart the accounts a user holds of the same type, unique per user & type
+    pub label: Option<String>,
+    // set on child accounts, a child belongs to the same user as its parent
+    pub parent_account_id: Option<String>,
+    // set by every status transition, `status_changed_by` is None for system-driven transitions
+    pub status_reason: Option<AccountStatusReason>,
+    pub status_changed_by: Option<String>,
+    pub status_expires_at: Option<DateTime<Utc>>,
     pub creation_time: DateTime<Utc>,
     pub modification_time: DateTime<Utc>,
 }
@@ -140,13 +227,141 @@ impl Account {
             timezone,
             currency,
             account_type,
+            label: None,
             locked: false,
+            parent_account_id: None,
             creation_time: now,
             id: generate_str_id(),
             modification_time: now,
+            status_reason: None,
+            status_expires_at: None,
+            status_changed_by: None,
             status: AccountStatus::Active,
         }
     }
+
+    /// Trims a label given by the user, a blank label is no label.
+    pub fn parse_label(label: Option<String>) -> Result<Option<String>, DomainError> {
+        let label = match label.as_deref().map(str::trim) {
+            None | Some("") => return Ok(None),
+            Some(label) => label,
+        };
+        if label.chars().count() > MAX_LABEL_LENGTH {
+            return Err(DomainError::InvalidArgument(format!(
+                "label can not be longer than {} characters",
+                MAX_LABEL_LENGTH
+            )));
+        }
+        Ok(Some(label.to_string()))
+    }
+
+    /// Moves the account to `status`. The allowed transitions are:
+    ///
+    ///     Active   -> Frozen    needs an operator
+    ///     Inactive -> Frozen    needs an operator
+    ///     Frozen   -> Active    needs an operator, who must be an admin (checked by the caller),
+    ///                           or the expiry of the freeze to have passed
+    ///     Inactive -> Active    on customer activity
+    ///     Active   -> Inactive  on dormancy
+    ///     Active   -> Closed    needs an operator
+    ///     Inactive -> Closed    needs an operator
+    ///
+    /// Closed is terminal. `reason` must apply to the target status. Only a freeze can expire,
+    /// `expires_at`, if any, must be in the future.
+    pub fn transition_status(
+        &mut self,
+        status: AccountStatus,
+        reason: AccountStatusReason,
+        changed_by: Option<String>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DomainError> {
+        let needs_operator = match (&self.status, &status) {
+            (AccountStatus::Active | AccountStatus::Inactive, AccountStatus::Frozen) => true,
+            (AccountStatus::Frozen, AccountStatus::Active) => {
+                reason != AccountStatusReason::FreezeExpired
+            }
+            (AccountStatus::Inactive, AccountStatus::Active) => false,
+            (AccountStatus::Active, AccountStatus::Inactive) => false,
+            (AccountStatus::Active | AccountStatus::Inactive, AccountStatus::Closed) => true,
+            (from, to) => {
+                return Err(DomainError::InvalidState(format!(
+                    "account status cannot change from {} to {}",
+                    from, to
+                )));
+            }
+        };
+        if !reason.applies_to(&status) {
+            return Err(DomainError::InvalidArgument(format!(
+                "reason {} does not apply to status {}",
+                reason, status
+            )));
+        }
+        if reason == AccountStatusReason::FreezeExpired
+            && self
+                .status_expires_at
+                .is_none_or(|expiry| expiry > Utc::now())
+        {
+            return Err(DomainError::InvalidState(
+                "the freeze of the account has not expired".to_string(),
+            ));
+        }
+        if needs_operator && changed_by.as_ref().is_none_or(|op| op.trim().is_empty()) {
+            return Err(DomainError::InvalidArgument(format!(
+                "an operator is required to move an account to {}",
+                status
+            )));
+        }
+        if expires_at.is_some() && status != AccountStatus::Frozen {
+            return Err(DomainError::InvalidArgument(
+                "only a freeze can expire".to_string(),
+            ));
+        }
+        if expires_at.is_some_and(|expiry| expiry <= Utc::now()) {
+            return Err(DomainError::InvalidArgument(
+                "status expiry must be in the future".to_string(),
+            ));
+        }
+
+        self.status = status;
+        self.status_reason = Some(reason);
+        self.status_changed_by = changed_by;
+        self.status_expires_at = expires_at;
+        self.modification_time = Utc::now();
+        Ok(())
+    }
+
+    /// True when the account can take part in a transaction, inactive accounts only can when the
+    /// transaction reactivates them.
+    pub fn accepts_transactions(&self, reactivates: bool) -> bool {
+        !self.locked
+            && match self.status {
+                AccountStatus::Active => true,
+                AccountStatus::Inactive => reactivates,
+                AccountStatus::Frozen | AccountStatus::Closed => false,
+            }
+    }
+
+    /// Changes the account type, which is only allowed while every wallet of the account is empty.
+    pub fn change_type(
+        &mut self,
+        account_type: AccountType,
+        wallets: &[WalletHolding],
+    ) -> Result<(), DomainError> {
+        if self.account_type == account_type {
+            return Err(DomainError::InvalidArgument(format!(
+                "account is already a {}",
+                account_type
+            )));
+        }
+        if wallets.iter().any(|wallet| !wallet.balance.is_zero()) {
+            return Err(DomainError::InvalidState(
+                "account type can only change while the balance is zero".to_string(),
+            ));
+        }
+        self.account_type = account_type;
+        self.modification_time = Utc::now();
+        Ok(())
+    }
 }
 
 impl Display for Account {
@@ -154,13 +369,38 @@ impl Display for Account {
         write(
             f,
             format_args!(
-                "Acct id={}, timezone={}, acctType={}",
-                self.id, self.timezone, self.account_type
+                "Acct id={}, timezone={}, acctType={}, label={}",
+                self.id,
+                self.timezone,
+                self.account_type,
+                self.label.as_deref().unwrap_or_default()
             ),
         )
     }
 }
 
+/// Notifies other services that the platform changed the status of an account on its own.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountStatusEvent {
+    pub account_id: String,
+    pub user_fp: String,
+    pub status: AccountStatus,
+    pub reason: Option<AccountStatusReason>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<&Account> for AccountStatusEvent {
+    fn from(account: &Account) -> Self {
+        AccountStatusEvent {
+            account_id: account.id.clone(),
+            user_fp: account.user_fp.clone(),
+            status: account.status.clone(),
+            reason: account.status_reason.clone(),
+            occurred_at: account.modification_time,
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Clone)]
 pub struct BeneficiaryAccount {
     pub id: String,
@@ -209,62 +449,28 @@ impl Display for BeneficiaryAccount {
     }
 }
 
+/// Updates the account settings. Status and type changes have their own transitions, see
+/// [Account::transition_status] and [Account::change_type].
 pub struct UpdateAccountReq {
     pub locked: Option<bool>,
     pub timezone: Option<String>,
-    pub status: Option<AccountStatus>,
-    pub account_type: Option<AccountType>,
 }
 
 impl UpdateAccountReq {
-    pub fn new(
-        locked: Option<bool>,
-        timezone: Option<String>,
-        status: Option<AccountStatus>,
-        account_type: Option<AccountType>,
-    ) -> Self {
-        UpdateAccountReq {
-            locked,
-            status,
-            timezone,
-            account_type,
-        }
-    }
-
-    pub fn build(
-        locked: Option<bool>,
-        status: Option<String>,
-        timezone: Option<String>,
-        account_type: Option<String>,
-    ) -> Result<UpdateAccountReq, DomainError> {
-        let update_status = match status {
-            Some(s) => {
-                Some(AccountStatus::from_str(s.as_str()).map_err(|_| DomainError::ParseError(s))?)
-            }
-            None => None,
-        };
-        let update_account_type = match account_type {
-            Some(a) => {
-                Some(AccountType::from_str(a.as_str()).map_err(|_| DomainError::ParseError(a))?)
-            }
-            None => None,
-        };
-
-        Ok(UpdateAccountReq::new(
-            locked,
-            timezone,
-            update_status,
-            update_account_type,
-        ))
+    pub fn new(locked: Option<bool>, timezone: Option<String>) -> Self {
+        UpdateAccountReq { locked, timezone }
     }
 }
 
+/// ***overdraft_limit*** is the credit line of the wallet: how far below zero the balance may go.
+/// Wallets without one can not go negative.
 #[derive(Serialize, Debug, Clone, sqlx::FromRow)]
 pub struct WalletHolding {
     pub balance: Decimal,
     pub currency: Currency,
     pub account_id: String, // there should be a 1:1 (account_type x account_id) entry for this
     pub modification_time: DateTime<Utc>,
+    pub overdraft_limit: Decimal,
 }
 
 impl WalletHolding {
@@ -274,7 +480,40 @@ impl WalletHolding {
             account_id,
             balance: Decimal::zero(),
             modification_time: Utc::now(),
+            overdraft_limit: Decimal::zero(),
+        }
+    }
+
+    /// Identifies the wallet in the audit logs.
+    pub fn entity_id(&self) -> String {
+        format!("{}:{}", self.account_id, self.currency)
+    }
+
+    /// What can be debited: the balance plus what is left of the credit line.
+    pub fn available_balance(&self) -> Decimal {
+        self.balance + self.overdraft_limit
+    }
+
+    /// What is left of the credit line.
+    pub fn available_credit(&self) -> Decimal {
+        self.overdraft_limit + self.balance.min(Decimal::zero())
+    }
+
+    /// Changes the credit line, which can not drop below what is already drawn from it.
+    pub fn set_overdraft_limit(&mut self, overdraft_limit: Decimal) -> Result<(), DomainError> {
+        if overdraft_limit.is_sign_negative() {
+            return Err(DomainError::InvalidArgument(
+                "overdraft limit can not be negative".to_string(),
+            ));
         }
+        if self.balance + overdraft_limit < Decimal::zero() {
+            return Err(DomainError::InvalidState(
+                "overdraft limit is lower than the overdrawn balance".to_string(),
+            ));
+        }
+        self.overdraft_limit = overdraft_limit;
+        self.modification_time = Utc::now();
+        Ok(())
     }
 }
 
@@ -287,3 +526,233 @@ impl Display for WalletHolding {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn build_account(status: AccountStatus) -> Account {
+        let mut account = Account::new(
+            "user_fp".to_string(),
+            "UTC".to_string(),
+            Currency::USD,
+            AccountType::Normal,
+        );
+        account.status = status;
+        account
+    }
+
+    #[test]
+    fn test_allowed_status_transitions() {
+        let operator = Some("operator_fp".to_string());
+
+        let mut account = build_account(AccountStatus::Active);
+        let expiry = Utc::now() + Duration::days(30);
+        assert!(account
+            .transition_status(
+                AccountStatus::Frozen,
+                AccountStatusReason::SuspectedFraud,
+                operator.clone(),
+                Some(expiry),
+            )
+            .is_ok());
+        assert_eq!(account.status, AccountStatus::Frozen);
+        assert_eq!(account.status_expires_at, Some(expiry));
+
+        assert!(account
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::ReviewCleared,
+                operator,
+                None,
+            )
+            .is_ok());
+        assert_eq!(
+            account.status_reason,
+            Some(AccountStatusReason::ReviewCleared)
+        );
+
+        assert!(account
+            .transition_status(
+                AccountStatus::Inactive,
+                AccountStatusReason::Dormancy,
+                None,
+                None
+            )
+            .is_ok());
+        assert!(account
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::CustomerActivity,
+                None,
+                None,
+            )
+            .is_ok());
+        assert_eq!(account.status_changed_by, None);
+    }
+
+    #[test]
+    fn test_rejected_status_transitions() {
+        let operator = Some("operator_fp".to_string());
+
+        let mut frozen = build_account(AccountStatus::Frozen);
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Inactive,
+                AccountStatusReason::Dormancy,
+                None,
+                None
+            )
+            .is_err());
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::ReviewCleared,
+                None,
+                None
+            )
+            .is_err());
+
+        let mut active = build_account(AccountStatus::Active);
+        assert!(active
+            .transition_status(
+                AccountStatus::Frozen,
+                AccountStatusReason::Dormancy,
+                operator.clone(),
+                None,
+            )
+            .is_err());
+        assert!(active
+            .transition_status(
+                AccountStatus::Frozen,
+                AccountStatusReason::LegalHold,
+                operator,
+                Some(Utc::now() - Duration::days(1)),
+            )
+            .is_err());
+        assert_eq!(active.status, AccountStatus::Active);
+    }
+
+    #[test]
+    fn test_freezes_only_lift_on_their_own_once_expired() {
+        let mut frozen = build_account(AccountStatus::Frozen);
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::FreezeExpired,
+                None,
+                None,
+            )
+            .is_err());
+
+        frozen.status_expires_at = Some(Utc::now() + Duration::days(1));
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::FreezeExpired,
+                None,
+                None,
+            )
+            .is_err());
+
+        frozen.status_expires_at = Some(Utc::now() - Duration::seconds(1));
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::FreezeExpired,
+                None,
+                None,
+            )
+            .is_ok());
+        assert_eq!(frozen.status, AccountStatus::Active);
+        assert_eq!(frozen.status_expires_at, None);
+
+        let mut active = build_account(AccountStatus::Active);
+        assert!(active
+            .transition_status(
+                AccountStatus::Closed,
+                AccountStatusReason::CustomerRequest,
+                Some("user_fp".to_string()),
+                Some(Utc::now() + Duration::days(1)),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_closed_is_terminal() {
+        let operator = Some("user_fp".to_string());
+        let mut account = build_account(AccountStatus::Inactive);
+        assert!(account
+            .transition_status(
+                AccountStatus::Closed,
+                AccountStatusReason::CustomerRequest,
+                operator.clone(),
+                None,
+            )
+            .is_ok());
+        assert!(!account.accepts_transactions(true));
+        assert!(account
+            .transition_status(
+                AccountStatus::Active,
+                AccountStatusReason::ReviewCleared,
+                operator,
+                None,
+            )
+            .is_err());
+
+        let mut frozen = build_account(AccountStatus::Frozen);
+        assert!(frozen
+            .transition_status(
+                AccountStatus::Closed,
+                AccountStatusReason::CustomerRequest,
+                Some("user_fp".to_string()),
+                None,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_change_type_needs_empty_wallets() {
+        let mut account = build_account(AccountStatus::Active);
+        let mut wallet = WalletHolding::new(account.id.clone(), Currency::USD);
+        wallet.balance = Decimal::from(5);
+        assert!(account
+            .change_type(AccountType::Wallet, &[wallet.clone()])
+            .is_err());
+
+        wallet.balance = Decimal::ZERO;
+        assert!(account.change_type(AccountType::Wallet, &[wallet]).is_ok());
+        assert_eq!(account.account_type, AccountType::Wallet);
+        assert!(account.change_type(AccountType::Wallet, &[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(Account::parse_label(None).unwrap(), None);
+        assert_eq!(Account::parse_label(Some("  ".to_string())).unwrap(), None);
+        assert_eq!(
+            Account::parse_label(Some(" savings ".to_string())).unwrap(),
+            Some("savings".to_string())
+        );
+        assert!(Account::parse_label(Some("x".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_overdraft_limit_bounds_the_available_credit() {
+        let mut wallet = WalletHolding::new("account_id".to_string(), Currency::USD);
+        wallet.balance = Decimal::from(50);
+        assert!(wallet.set_overdraft_limit(Decimal::from(100)).is_ok());
+        assert_eq!(wallet.available_balance(), Decimal::from(150));
+        assert_eq!(wallet.available_credit(), Decimal::from(100));
+
+        wallet.balance = Decimal::from(-30);
+        assert_eq!(wallet.available_balance(), Decimal::from(70));
+        assert_eq!(wallet.available_credit(), Decimal::from(70));
+
+        assert!(wallet.set_overdraft_limit(Decimal::from(-1)).is_err());
+        assert!(wallet.set_overdraft_limit(Decimal::from(20)).is_err());
+        assert!(wallet.set_overdraft_limit(Decimal::from(30)).is_ok());
+        assert_eq!(wallet.available_credit(), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::core::models::unique::{decode_time_cursor, encode_time_cursor};
+use crate::core::{generate_timebase_str_id, AuditLog, EntityType};
+use crate::DomainError;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::io::{BufRead, BufReader, Write};
+
+/// How many ***days*** the audit logs of ***entity_type*** stay in `audit_log` before they are
+/// moved to the archive store. Entity types without a policy are never archived.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditRetentionPolicy {
+    pub entity_type: EntityType,
+    pub days: u32,
+}
+
+impl AuditRetentionPolicy {
+    /// Logs created before the cutoff are past retention.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(self.days as i64)
+    }
+}
+
+/// A gzip JSONL file of the audit logs of ***entity_type*** created on ***archive_date***, UTC.
+/// The manifest stored next to it is this struct, ***checksum*** being the SHA3-256 of the file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditArchive {
+    pub id: String,
+    pub entity_type: EntityType,
+    pub archive_date: NaiveDate,
+    pub object_path: String,
+    pub manifest_path: String,
+    pub row_count: i64,
+    pub checksum: String,
+    pub from_time: DateTime<Utc>,
+    pub to_time: DateTime<Utc>,
+    pub creation_time: DateTime<Utc>,
+    // set while the logs are restored into audit_log
+    #[serde(skip)]
+    pub restored_at: Option<DateTime<Utc>>,
+}
+
+impl AuditArchive {
+    /// Encodes `audit_logs`, all created on the same UTC day, into an archive file.
+    /// Returns the archive and the file content.
+    pub fn build(
+        entity_type: EntityType,
+        audit_logs: &[AuditLog],
+    ) -> Result<(Self, Vec<u8>), DomainError> {
+        let (first, last) = match (audit_logs.first(), audit_logs.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => {
+                return Err(DomainError::InvalidArgument(
+                    "an audit archive needs at least one log".to_string(),
+                ))
+            }
+        };
+        let archive_date = first.creation_time.date_naive();
+        if audit_logs.iter().any(|log| {
+            log.creation_time.date_naive() != archive_date || log.entity_type != entity_type
+        }) {
+            return Err(DomainError::InvalidArgument(format!(
+                "audit archive {} {} mixes logs of other days or entity types",
+                entity_type, archive_date
+            )));
+        }
+
+        let data = encode_audit_logs(audit_logs)?;
+        let id = generate_timebase_str_id();
+        let object_path = format!(
+            "audit/{}/{}/{}.jsonl.gz",
+            entity_type,
+            archive_date.format("%Y/%m/%d"),
+            id
+        );
+        let archive = AuditArchive {
+            manifest_path: format!(
+                "{}.manifest.json",
+                object_path.trim_end_matches(".jsonl.gz")
+            ),
+            object_path,
+            entity_type,
+            archive_date,
+            row_count: audit_logs.len() as i64,
+            checksum: archive_checksum(&data),
+            from_time: first.creation_time,
+            to_time: last.creation_time,
+            creation_time: Utc::now(),
+            restored_at: None,
+            id,
+        };
+        Ok((archive, data))
+    }
+
+    pub fn manifest(&self) -> Result<Vec<u8>, DomainError> {
+        serde_json::to_vec_pretty(self).map_err(|err| DomainError::ParseError(err.to_string()))
+    }
+
+    /// Decodes the archive file, after checking it is the one the archive was written with.
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<AuditLog>, DomainError> {
+        if archive_checksum(data) != self.checksum {
+            return Err(DomainError::InvalidState(format!(
+                "audit archive {} does not match its checksum",
+                self.id
+            )));
+        }
+        let audit_logs = decode_audit_logs(data)?;
+        if audit_logs.len() as i64 != self.row_count {
+            return Err(DomainError::InvalidState(format!(
+                "audit archive {} holds {} logs, {} expected",
+                self.id,
+                audit_logs.len(),
+                self.row_count
+            )));
+        }
+        Ok(audit_logs)
+    }
+}
+
+/// SHA3-256 of an archive file.
+fn archive_checksum(data: &[u8]) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(data);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn encode_audit_logs(audit_logs: &[AuditLog]) -> Result<Vec<u8>, DomainError> {
+    let to_err = |err: std::io::Error| DomainError::ParseError(err.to_string());
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for audit_log in audit_logs {
+        serde_json::to_writer(&mut encoder, audit_log)
+            .map_err(|err| DomainError::ParseError(err.to_string()))?;
+        encoder.write_all(b"\n").map_err(to_err)?;
+    }
+    encoder.finish().map_err(to_err)
+}
+
+fn decode_audit_logs(data: &[u8]) -> Result<Vec<AuditLog>, DomainError> {
+    BufReader::new(GzDecoder::new(data))
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.is_empty()))
+        .map(|line| {
+            let line = line.map_err(|err| DomainError::ParseError(err.to_string()))?;
+            serde_json::from_str(&line).map_err(|err| DomainError::ParseError(err.to_string()))
+        })
+        .collect()
+}
+
+/// AuditArchiveCursor points at the last archive of a page, like [`crate::core::AuditCursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditArchiveCursor {
+    pub archive_id: String,
+    pub creation_time: DateTime<Utc>,
+}
+
+impl AuditArchiveCursor {
+    pub fn new(archive: &AuditArchive) -> Self {
+        AuditArchiveCursor {
+            archive_id: archive.id.clone(),
+            creation_time: archive.creation_time,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        encode_time_cursor(&self.archive_id, &self.creation_time)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, DomainError> {
+        let (archive_id, creation_time) = decode_time_cursor(cursor)?;
+        Ok(AuditArchiveCursor {
+            archive_id,
+            creation_time,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AuditEventType, AuditOrigin};
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn audit_logs(len: i64, day: u32) -> Vec<AuditLog> {
+        let mut prev_hash = None;
+        (1..=len)
+            .map(|sequence_num| {
+                let mut log = AuditLog::build(
+                    "user_fp".to_string(),
+                    format!("account_{}", sequence_num),
+                    EntityType::Account,
+                    AuditEventType::UPDATE,
+                    AuditOrigin {
+                        request_ip: Some("10.0.0.1".to_string()),
+                        ..AuditOrigin::default()
+                    },
+                    None,
+                    Some(json!({"status": "Active", "balance": 12.5})),
+                )
+                .expect("failed to build audit log");
+                log.creation_time = Utc
+                    .with_ymd_and_hms(2019, 3, day, 10, sequence_num as u32, 0)
+                    .unwrap();
+                log.chain_to(sequence_num, prev_hash.clone());
+                prev_hash = log.hash.clone();
+                log
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archive_round_trip_keeps_logs_intact() {
+        let logs = audit_logs(3, 7);
+        let (archive, data) =
+            AuditArchive::build(EntityType::Account, &logs).expect("failed to build archive");
+        assert_eq!(archive.row_count, 3);
+        assert_eq!(
+            archive.archive_date,
+            NaiveDate::from_ymd_opt(2019, 3, 7).unwrap()
+        );
+        assert_eq!(
+            archive.object_path,
+            format!("audit/Account/2019/03/07/{}.jsonl.gz", archive.id)
+        );
+        assert_eq!(
+            archive.manifest_path,
+            format!("audit/Account/2019/03/07/{}.manifest.json", archive.id)
+        );
+        assert_eq!(archive.from_time, logs[0].creation_time);
+        assert_eq!(archive.to_time, logs[2].creation_time);
+
+        let restored = archive.decode(&data).expect("failed to decode archive");
+        assert_eq!(restored.len(), 3);
+        for (restored, log) in restored.iter().zip(&logs) {
+            assert!(restored.is_intact());
+            assert_eq!(restored.hash, log.hash);
+            assert_eq!(restored.request_ip, log.request_ip);
+        }
+
+        let manifest: AuditArchive =
+            serde_json::from_slice(&archive.manifest().expect("failed to write manifest"))
+                .expect("failed to read manifest");
+        assert_eq!(manifest, archive);
+    }
+
+    #[test]
+    fn test_archive_rejects_tampered_files() {
+        let logs = audit_logs(2, 7);
+        let (archive, mut data) =
+            AuditArchive::build(EntityType::Account, &logs).expect("failed to build archive");
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        assert!(matches!(
+            archive.decode(&data),
+            Err(DomainError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn test_archive_holds_a_single_day_and_entity_type() {
+        let mut logs = audit_logs(2, 7);
+        logs.extend(audit_logs(1, 8));
+        assert!(AuditArchive::build(EntityType::Account, &logs).is_err());
+        assert!(AuditArchive::build(EntityType::Wallet, &logs[..2]).is_err());
+        assert!(AuditArchive::build(EntityType::Account, &[]).is_err());
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let policy = AuditRetentionPolicy {
+            entity_type: EntityType::Account,
+            days: 30,
+        };
+        let now = Utc.with_ymd_and_hms(2024, 3, 31, 12, 0, 0).unwrap();
+        assert_eq!(
+            policy.cutoff(now),
+            Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,453 @@
+use crate::core::AuditLog;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// The head of the audit chain anchored into the Cassandra block ***block_id***.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditCheckpoint {
+    pub sequence_num: i64,
+    pub audit_id: String,
+    pub hash: String,
+    pub block_id: String,
+    pub chain_id: String,
+    pub creation_time: DateTime<Utc>,
+}
+
+impl AuditCheckpoint {
+    /// The entry the checkpoint block carries.
+    pub fn anchor(&self) -> String {
+        audit_anchor(self.sequence_num, &self.hash)
+    }
+}
+
+/// The chain position of an archived audit log, kept in place of the log so the chain stays
+/// verifiable without its content.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedAuditLink {
+    pub sequence_num: i64,
+    pub audit_id: String,
+    pub prev_hash: Option<String>,
+    pub hash: String,
+    pub archive_id: String,
+}
+
+pub fn audit_anchor(sequence_num: i64, hash: &str) -> String {
+    format!("audit_log:{}:{}", sequence_num, hash)
+}
+
+/// Something wrong found by [`AuditChainVerifier`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub enum AuditChainIssue {
+    /// Logs `from_sequence..=to_sequence` are gone.
+    Missing {
+        from_sequence: i64,
+        to_sequence: i64,
+    },
+    /// The log content no longer matches its hash.
+    Modified { sequence_num: i64, audit_id: String },
+    /// The log does not link to the hash of the log before it.
+    Relinked { sequence_num: i64, audit_id: String },
+    /// The log hash differs from the one anchored at that position.
+    AnchorMismatch { sequence_num: i64, audit_id: String },
+    /// The checkpoint has no matching anchor block.
+    Unanchored { sequence_num: i64 },
+    /// The chain ends before the last anchored position.
+    Truncated {
+        last_sequence: i64,
+        anchored_sequence: i64,
+    },
+}
+
+impl AuditChainIssue {
+    pub fn sequence_num(&self) -> i64 {
+        match self {
+            AuditChainIssue::Missing { from_sequence, .. } => *from_sequence,
+            AuditChainIssue::Modified { sequence_num, .. }
+            | AuditChainIssue::Relinked { sequence_num, .. }
+            | AuditChainIssue::AnchorMismatch { sequence_num, .. }
+            | AuditChainIssue::Unanchored { sequence_num } => *sequence_num,
+            AuditChainIssue::Truncated { last_sequence, .. } => *last_sequence,
+        }
+    }
+
+    pub fn audit_id(&self) -> Option<&str> {
+        match self {
+            AuditChainIssue::Modified { audit_id, .. }
+            | AuditChainIssue::Relinked { audit_id, .. }
+            | AuditChainIssue::AnchorMismatch { audit_id, .. } => Some(audit_id),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AuditChainIssue::Missing { .. } => "Missing",
+            AuditChainIssue::Modified { .. } => "Modified",
+            AuditChainIssue::Relinked { .. } => "Relinked",
+            AuditChainIssue::AnchorMismatch { .. } => "AnchorMismatch",
+            AuditChainIssue::Unanchored { .. } => "Unanchored",
+            AuditChainIssue::Truncated { .. } => "Truncated",
+        }
+    }
+}
+
+impl Display for AuditChainIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditChainIssue::Missing {
+                from_sequence,
+                to_sequence,
+            } => write!(
+                f,
+                "audit logs {}..={} are missing",
+                from_sequence, to_sequence
+            ),
+            AuditChainIssue::Modified {
+                sequence_num,
+                audit_id,
+            } => write!(f, "audit log {} ({}) was modified", sequence_num, audit_id),
+            AuditChainIssue::Relinked {
+                sequence_num,
+                audit_id,
+            } => write!(
+                f,
+                "audit log {} ({}) does not link to the log before it",
+                sequence_num, audit_id
+            ),
+            AuditChainIssue::AnchorMismatch {
+                sequence_num,
+                audit_id,
+            } => write!(
+                f,
+                "audit log {} ({}) differs from its anchor",
+                sequence_num, audit_id
+            ),
+            AuditChainIssue::Unanchored { sequence_num } => {
+                write!(f, "checkpoint {} has no anchor block", sequence_num)
+            }
+            AuditChainIssue::Truncated {
+                last_sequence,
+                anchored_sequence,
+            } => write!(
+                f,
+                "audit chain ends at {}, before the anchor at {}",
+                last_sequence, anchored_sequence
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AuditChainReport {
+    pub checked: u64,
+    pub last_sequence: Option<i64>,
+    pub issues: Vec<AuditChainIssue>,
+}
+
+impl AuditChainReport {
+    pub fn is_intact(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Walks chained audit logs in sequence order, fed one page at a time.
+///
+/// Verification starts after a trusted link, e.g. the last checkpoint, or at the start of the
+/// chain. Anchored hashes registered with [`AuditChainVerifier::expect_anchor`] are compared to
+/// the logs at their position.
+#[derive(Debug, Default)]
+pub struct AuditChainVerifier {
+    last_link: Option<(i64, String)>,
+    anchors: BTreeMap<i64, String>,
+    report: AuditChainReport,
+}
+
+impl AuditChainVerifier {
+    pub fn new(trusted_link: Option<(i64, String)>) -> Self {
+        AuditChainVerifier {
+            report: AuditChainReport {
+                last_sequence: trusted_link.as_ref().map(|(sequence_num, _)| *sequence_num),
+                ..AuditChainReport::default()
+            },
+            last_link: trusted_link,
+            anchors: BTreeMap::new(),
+        }
+    }
+
+    pub fn expect_anchor(&mut self, sequence_num: i64, hash: String) {
+        self.anchors.insert(sequence_num, hash);
+    }
+
+    /// The sequence number of the last log checked, or of the trusted link.
+    pub fn last_sequence(&self) -> i64 {
+        self.last_link
+            .as_ref()
+            .map(|(sequence_num, _)| *sequence_num)
+            .unwrap_or(0)
+    }
+
+    pub fn check(&mut self, log: &AuditLog) {
+        if let Some(sequence_num) = log.sequence_num {
+            self.check_link(
+                sequence_num,
+                &log.id,
+                log.prev_hash.as_ref(),
+                log.hash.as_deref().unwrap_or_default(),
+                log.is_intact(),
+            );
+        }
+    }
+
+    /// Checks the link of an archived log, its content is checked on restore.
+    pub fn check_archived(&mut self, link: &ArchivedAuditLink) {
+        self.check_link(
+            link.sequence_num,
+            &link.audit_id,
+            link.prev_hash.as_ref(),
+            &link.hash,
+            true,
+        );
+    }
+
+    /// Checks a page of logs and a page of archived links, both read after
+    /// [`AuditChainVerifier::last_sequence`], in chain order. A full page may stop before the
+    /// other one, so only the positions both pages reach are checked, the next pages are read
+    /// after the new last sequence.
+    pub fn check_page(&mut self, logs: &[AuditLog], links: &[ArchivedAuditLink], page_size: usize) {
+        let log_reach = logs
+            .last()
+            .filter(|_| logs.len() >= page_size)
+            .and_then(|log| log.sequence_num);
+        let link_reach = links
+            .last()
+            .filter(|_| links.len() >= page_size)
+            .map(|link| link.sequence_num);
+        let reach = log_reach.into_iter().chain(link_reach).min();
+        let within_reach = |sequence_num: i64| reach.is_none_or(|reach| sequence_num <= reach);
+
+        let mut logs = logs
+            .iter()
+            .filter(|log| log.sequence_num.is_some_and(within_reach))
+            .peekable();
+        let mut links = links
+            .iter()
+            .filter(|link| within_reach(link.sequence_num))
+            .peekable();
+        loop {
+            match (logs.peek(), links.peek()) {
+                (Some(log), Some(link))
+                    if log.sequence_num.unwrap_or_default() > link.sequence_num =>
+                {
+                    self.check_archived(link);
+                    links.next();
+                }
+                (Some(log), _) => {
+                    self.check(log);
+                    logs.next();
+                }
+                (None, Some(link)) => {
+                    self.check_archived(link);
+                    links.next();
+                }
+                (None, None) => break,
+            }
+        }
+    }
+
+    fn check_link(
+        &mut self,
+        sequence_num: i64,
+        audit_id: &str,
+        prev_hash: Option<&String>,
+        hash: &str,
+        is_intact: bool,
+    ) {
+        let audit_id = audit_id.to_string();
+        let expected = self.last_sequence() + 1;
+
+        // a gap breaks the link too, only the gap is reported
+        if sequence_num > expected {
+            self.report.issues.push(AuditChainIssue::Missing {
+                from_sequence: expected,
+                to_sequence: sequence_num - 1,
+            });
+        } else if prev_hash != self.last_link.as_ref().map(|(_, hash)| hash) {
+            self.report.issues.push(AuditChainIssue::Relinked {
+                sequence_num,
+                audit_id: audit_id.clone(),
+            });
+        }
+        if !is_intact {
+            self.report.issues.push(AuditChainIssue::Modified {
+                sequence_num,
+                audit_id: audit_id.clone(),
+            });
+        }
+        if let Some(anchored_hash) = self.anchors.get(&sequence_num) {
+            if hash != anchored_hash {
+                self.report.issues.push(AuditChainIssue::AnchorMismatch {
+                    sequence_num,
+                    audit_id,
+                });
+            }
+        }
+
+        self.last_link = Some((sequence_num, hash.to_string()));
+        self.report.last_sequence = Some(sequence_num);
+        self.report.checked += 1;
+    }
+
+    pub fn finish(mut self) -> AuditChainReport {
+        let last_sequence = self.last_sequence();
+        if let Some((&anchored_sequence, _)) = self.anchors.last_key_value() {
+            if anchored_sequence > last_sequence {
+                self.report.issues.push(AuditChainIssue::Truncated {
+                    last_sequence,
+                    anchored_sequence,
+                });
+            }
+        }
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{AuditEventType, AuditOrigin, EntityType};
+    use serde_json::json;
+
+    fn chain(len: i64) -> Vec<AuditLog> {
+        let mut prev_hash = None;
+        (1..=len)
+            .map(|sequence_num| {
+                let mut log = AuditLog::build(
+                    "user_fp".to_string(),
+                    format!("account_{}", sequence_num),
+                    EntityType::Account,
+                    AuditEventType::UPDATE,
+                    AuditOrigin::default(),
+                    None,
+                    Some(json!({"status": "Active"})),
+                )
+                .expect("failed to build audit log");
+                log.chain_to(sequence_num, prev_hash.clone());
+                prev_hash = log.hash.clone();
+                log
+            })
+            .collect()
+    }
+
+    fn verify(logs: &[AuditLog], anchors: &[(i64, String)]) -> AuditChainReport {
+        let mut verifier = AuditChainVerifier::new(None);
+        for (sequence_num, hash) in anchors {
+            verifier.expect_anchor(*sequence_num, hash.clone());
+        }
+        logs.iter().for_each(|log| verifier.check(log));
+        verifier.finish()
+    }
+
+    #[test]
+    fn test_intact_chain_has_no_issues() {
+        let logs = chain(4);
+        let anchor = (4, logs[3].hash.clone().expect("missing hash"));
+        let report = verify(&logs, &[anchor]);
+        assert!(report.is_intact());
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.last_sequence, Some(4));
+    }
+
+    #[test]
+    fn test_reports_deleted_and_modified_logs() {
+        let mut logs = chain(5);
+        logs.remove(1);
+        logs[2].changes = json!({"new": {"status": "Frozen"}});
+
+        let report = verify(&logs, &[]);
+        assert_eq!(
+            report.issues,
+            vec![
+                AuditChainIssue::Missing {
+                    from_sequence: 2,
+                    to_sequence: 2
+                },
+                AuditChainIssue::Modified {
+                    sequence_num: 4,
+                    audit_id: logs[2].id.clone()
+                },
+            ]
+        );
+    }
+
+    fn archive(logs: &[AuditLog]) -> Vec<ArchivedAuditLink> {
+        logs.iter()
+            .map(|log| ArchivedAuditLink {
+                sequence_num: log.sequence_num.expect("missing sequence"),
+                audit_id: log.id.clone(),
+                prev_hash: log.prev_hash.clone(),
+                hash: log.hash.clone().expect("missing hash"),
+                archive_id: "archive_id".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_archived_links_fill_the_chain() {
+        let mut logs = chain(6);
+        let anchor = (2, logs[1].hash.clone().expect("missing hash"));
+        let links = archive(&logs.drain(..3).collect::<Vec<_>>());
+
+        let mut verifier = AuditChainVerifier::new(None);
+        verifier.expect_anchor(anchor.0, anchor.1);
+        // pages of two: the full link page stops at 2, the logs from 4 wait for the next page
+        verifier.check_page(&logs[..2], &links[..2], 2);
+        assert_eq!(verifier.last_sequence(), 2);
+        verifier.check_page(&logs[..2], &links[2..], 2);
+        assert_eq!(verifier.last_sequence(), 5);
+        verifier.check_page(&logs[2..], &[], 2);
+        let report = verifier.finish();
+        assert!(report.is_intact(), "{:?}", report.issues);
+        assert_eq!(report.checked, 6);
+
+        // an archived link rewritten in place breaks the link of the log after it
+        let mut links = links;
+        links[2].hash = "hash".to_string();
+        let mut verifier = AuditChainVerifier::new(None);
+        verifier.check_page(&logs, &links, 10);
+        assert_eq!(
+            verifier.finish().issues,
+            vec![AuditChainIssue::Relinked {
+                sequence_num: 4,
+                audit_id: logs[0].id.clone()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reports_rehashed_and_truncated_chain() {
+        let mut logs = chain(4);
+        let anchor = (3, logs[2].hash.clone().expect("missing hash"));
+        // rewritten and re-hashed in place, only the anchor tells
+        logs[2].changes = json!({"new": {"status": "Frozen"}});
+        let prev_hash = logs[2].prev_hash.clone();
+        logs[2].chain_to(3, prev_hash);
+        logs.truncate(3);
+
+        let report = verify(&logs, &[anchor, (4, "hash".to_string())]);
+        assert_eq!(
+            report.issues,
+            vec![
+                AuditChainIssue::AnchorMismatch {
+                    sequence_num: 3,
+                    audit_id: logs[2].id.clone()
+                },
+                AuditChainIssue::Truncated {
+                    last_sequence: 3,
+                    anchored_sequence: 4
+                },
+            ]
+        );
+    }
+}
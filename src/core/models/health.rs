@@ -0,0 +1,14 @@
+/// Whether each dependency of the server answered its last probe in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DependencyHealth {
+    pub postgres: bool,
+    pub cassandra: bool,
+    pub redis: bool,
+}
+
+impl DependencyHealth {
+    /// The server is ready for traffic once every dependency is up.
+    pub fn is_ready(&self) -> bool {
+        self.postgres && self.cassandra && self.redis
+    }
+}
@@ -1,9 +1,14 @@
 use crate::core::generate_timebase_str_id;
+use crate::core::models::unique::{decode_time_cursor, encode_time_cursor};
 use crate::DomainError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha3::{Digest, Sha3_256};
 use sqlx::types::JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "audit_event_type")]
@@ -24,6 +29,22 @@ impl Display for AuditEventType {
     }
 }
 
+impl FromStr for AuditEventType {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "CREATE" => Ok(AuditEventType::CREATE),
+            "DELETE" => Ok(AuditEventType::DELETE),
+            "UPDATE" => Ok(AuditEventType::UPDATE),
+            _ => Err(DomainError::ParseError(format!(
+                "invalid audit event type: {}",
+                s
+            ))),
+        }
+    }
+}
+
 // A generic struct to represent the data within the 'changes' JSONB column.
 // Using generics here (`<T>`) to make this reusable for any entity type.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,32 +57,87 @@ pub struct ChangeData<T> {
     pub new: Option<T>,
 }
 
-#[derive(Serialize, Debug, Clone)]
+/// The model an audit log is about, one per table the orchestrators write to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum EntityType {
     Account,
     Transaction,
+    Wallet,
+    ChainStamp,
+    BeneficiaryAccount,
+    CurrencyRate,
+    AccountMember,
+    ApprovalRequest,
+    ScheduledPayment,
+    PayoutBatch,
+    InterestAccrual,
+    OverdraftCharge,
+    AccessDenial,
+    AccountLimits,
 }
 
-impl Display for EntityType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl EntityType {
+    pub const ALL: [EntityType; 14] = [
+        EntityType::Account,
+        EntityType::Transaction,
+        EntityType::Wallet,
+        EntityType::ChainStamp,
+        EntityType::BeneficiaryAccount,
+        EntityType::CurrencyRate,
+        EntityType::AccountMember,
+        EntityType::ApprovalRequest,
+        EntityType::ScheduledPayment,
+        EntityType::PayoutBatch,
+        EntityType::InterestAccrual,
+        EntityType::OverdraftCharge,
+        EntityType::AccessDenial,
+        EntityType::AccountLimits,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
         match self {
-            EntityType::Account => f.write_str("Account"),
-            EntityType::Transaction => f.write_str("Transaction"),
+            EntityType::Account => "Account",
+            EntityType::Transaction => "Transaction",
+            EntityType::Wallet => "Wallet",
+            EntityType::ChainStamp => "ChainStamp",
+            EntityType::BeneficiaryAccount => "BeneficiaryAccount",
+            EntityType::CurrencyRate => "CurrencyRate",
+            EntityType::AccountMember => "AccountMember",
+            EntityType::ApprovalRequest => "ApprovalRequest",
+            EntityType::ScheduledPayment => "ScheduledPayment",
+            EntityType::PayoutBatch => "PayoutBatch",
+            EntityType::InterestAccrual => "InterestAccrual",
+            EntityType::OverdraftCharge => "OverdraftCharge",
+            EntityType::AccessDenial => "AccessDenial",
+            EntityType::AccountLimits => "AccountLimits",
         }
     }
 }
 
+impl Display for EntityType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl From<String> for EntityType {
     fn from(s: String) -> Self {
-        match s.as_ref() {
-            "Account" => EntityType::Account,
-            "Transaction" => EntityType::Transaction,
-            _ => EntityType::Account,
-        }
+        EntityType::from_str(&s).unwrap_or(EntityType::Account)
     }
 }
 
-#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+impl FromStr for EntityType {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EntityType::ALL
+            .into_iter()
+            .find(|entity_type| entity_type.as_str() == s)
+            .ok_or_else(|| DomainError::ParseError(format!("invalid entity type: {}", s)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
 pub struct AuditLog {
     pub id: String,
     pub user_fp: String,
@@ -75,6 +151,22 @@ pub struct AuditLog {
     pub request_id: Option<String>,
     pub creation_time: DateTime<Utc>,
     pub request_user_agent: Option<String>,
+
+    // position in the audit hash chain, None on logs written before the chain existed
+    pub sequence_num: Option<i64>,
+    pub prev_hash: Option<String>,
+    pub hash: Option<String>,
+    // the internal service that made the change, see `RequestContext::service_id`
+    pub service_id: Option<String>,
+}
+
+/// The request that made an audited change, all unset for changes made by the jobs.
+#[derive(Debug, Clone, Default)]
+pub struct AuditOrigin {
+    pub request_ip: Option<String>,
+    pub request_id: Option<String>,
+    pub user_agent: Option<String>,
+    pub service_id: Option<String>,
 }
 
 impl AuditLog {
@@ -83,9 +175,7 @@ impl AuditLog {
         entity_id: String,
         entity_type: EntityType,
         audit_type: AuditEventType,
-        request_ip: Option<String>,
-        request_id: Option<String>,
-        req_user_agent: Option<String>,
+        origin: AuditOrigin,
         old: Option<T>,
         new: Option<T>,
     ) -> Result<Self, DomainError> {
@@ -100,12 +190,213 @@ impl AuditLog {
             entity_id,
             audit_type,
             entity_type,
-            request_ip,
-            request_id,
             changes: changes_json,
             creation_time: Utc::now(),
             id: generate_timebase_str_id(),
-            request_user_agent: req_user_agent,
+            request_ip: origin.request_ip,
+            request_id: origin.request_id,
+            request_user_agent: origin.user_agent,
+            service_id: origin.service_id,
+            hash: None,
+            prev_hash: None,
+            sequence_num: None,
+        })
+    }
+
+    /// Hashes the content of a log waiting to be chained, with no chain position, so a change
+    /// made to it before it is chained is detected when it is.
+    pub fn seal(&mut self) {
+        self.sequence_num = None;
+        self.prev_hash = None;
+        self.hash = Some(self.compute_hash());
+    }
+
+    /// Appends the log to the audit chain after the log hashed `prev_hash`, `None` for the first
+    /// log of the chain.
+    pub fn chain_to(&mut self, sequence_num: i64, prev_hash: Option<String>) {
+        self.sequence_num = Some(sequence_num);
+        self.prev_hash = prev_hash;
+        self.hash = Some(self.compute_hash());
+    }
+
+    /// SHA3-256 of the log content and its link to the previous log. Timestamps are hashed to
+    /// the microsecond, the precision Postgres keeps. The service id is only hashed when set, so
+    /// logs chained before it existed keep their hash.
+    pub fn compute_hash(&self) -> String {
+        let mut content = json!([
+            self.prev_hash,
+            self.sequence_num,
+            self.id,
+            self.user_fp,
+            self.entity_id,
+            self.entity_type.to_string(),
+            self.audit_type.to_string(),
+            self.changes,
+            self.request_ip,
+            self.request_id,
+            self.creation_time.timestamp_micros(),
+            self.request_user_agent,
+        ]);
+        if let (Some(service_id), Some(content)) = (&self.service_id, content.as_array_mut()) {
+            content.push(json!(service_id));
+        }
+        let mut hasher = Sha3_256::new();
+        hasher.update(content.to_string().as_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// False when the log was changed after it was sealed or chained.
+    pub fn is_intact(&self) -> bool {
+        self.hash.as_deref() == Some(self.compute_hash().as_str())
+    }
+}
+
+impl AuditLog {
+    /// The fields the change touched, by dotted path for nested values, e.g. `limits.daily`.
+    /// Fields missing from the old value were created, the ones missing from the new value were
+    /// removed.
+    pub fn field_changes(&self) -> Vec<FieldChange> {
+        let mut old = BTreeMap::new();
+        let mut new = BTreeMap::new();
+        if let Some(value) = self.changes.get("old") {
+            flatten_value(String::new(), value, &mut old);
+        }
+        if let Some(value) = self.changes.get("new") {
+            flatten_value(String::new(), value, &mut new);
+        }
+
+        let fields = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+        fields
+            .into_iter()
+            .filter_map(|field| {
+                let old_value = old.get(field).cloned();
+                let new_value = new.get(field).cloned();
+                (old_value != new_value).then(|| FieldChange {
+                    field: field.clone(),
+                    old: old_value,
+                    new: new_value,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Collects the leaves of `value` under their dotted path. Arrays are kept whole, nulls are
+/// treated as missing.
+fn flatten_value(path: String, value: &JsonValue, fields: &mut BTreeMap<String, String>) {
+    match value {
+        JsonValue::Null => {}
+        JsonValue::Object(map) => {
+            for (key, value) in map {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten_value(path, value, fields);
+            }
+        }
+        JsonValue::String(value) => {
+            fields.insert(field_path(path), value.clone());
+        }
+        value => {
+            fields.insert(field_path(path), value.to_string());
+        }
+    }
+}
+
+fn field_path(path: String) -> String {
+    if path.is_empty() {
+        "value".to_string()
+    } else {
+        path
+    }
+}
+
+/// A field of an audited entity before and after a change, rendered as `status: Active → Frozen`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} → {}",
+            self.field,
+            self.old.as_deref().unwrap_or("(none)"),
+            self.new.as_deref().unwrap_or("(none)")
+        )
+    }
+}
+
+/// AuditCursor points at the last audit log of a page, like [`crate::core::TransactionCursor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditCursor {
+    pub audit_id: String,
+    pub creation_time: DateTime<Utc>,
+}
+
+impl AuditCursor {
+    pub fn new(audit_log: &AuditLog) -> Self {
+        AuditCursor {
+            audit_id: audit_log.id.clone(),
+            creation_time: audit_log.creation_time,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        encode_time_cursor(&self.audit_id, &self.creation_time)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, DomainError> {
+        let (audit_id, creation_time) = decode_time_cursor(cursor)?;
+        Ok(AuditCursor {
+            audit_id,
+            creation_time,
+        })
+    }
+}
+
+/// Search criteria of audit logs, newest first. A `None` criterion means "any".
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub user_fp: Option<String>,
+    pub from_time: Option<DateTime<Utc>>,
+    pub to_time: Option<DateTime<Utc>>,
+    pub audit_type: Option<AuditEventType>,
+}
+
+impl AuditFilter {
+    pub fn build(
+        user_fp: Option<String>,
+        from_time: Option<DateTime<Utc>>,
+        to_time: Option<DateTime<Utc>>,
+        audit_type: Option<String>,
+    ) -> Result<Self, DomainError> {
+        let audit_type = match audit_type.filter(|s| !s.is_empty()) {
+            Some(audit_type) => Some(AuditEventType::from_str(&audit_type)?),
+            None => None,
+        };
+        if let (Some(from), Some(to)) = (from_time, to_time) {
+            if from > to {
+                return Err(DomainError::InvalidArgument(
+                    "from_time must be before to_time".to_string(),
+                ));
+            }
+        }
+        Ok(AuditFilter {
+            user_fp: user_fp.filter(|s| !s.is_empty()),
+            from_time,
+            to_time,
+            audit_type,
         })
     }
 }
@@ -119,3 +410,125 @@ impl Display for AuditLog {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audit_log(audit_type: AuditEventType, changes: JsonValue) -> AuditLog {
+        AuditLog {
+            id: generate_timebase_str_id(),
+            user_fp: "user_fp".to_string(),
+            entity_id: "account_id".to_string(),
+            entity_type: EntityType::Account,
+            changes,
+            audit_type,
+            request_ip: None,
+            request_id: None,
+            creation_time: Utc::now(),
+            request_user_agent: None,
+            sequence_num: None,
+            prev_hash: None,
+            hash: None,
+            service_id: None,
+        }
+    }
+
+    #[test]
+    fn test_field_changes_of_an_update_skip_unchanged_fields() {
+        let log = audit_log(
+            AuditEventType::UPDATE,
+            json!({
+                "old": {"status": "Active", "locked": false, "limits": {"daily": "100", "monthly": "900"}},
+                "new": {"status": "Frozen", "locked": false, "limits": {"daily": "250", "monthly": "900"}}
+            }),
+        );
+        let changes = log
+            .field_changes()
+            .iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec!["limits.daily: 100 → 250", "status: Active → Frozen"]
+        );
+    }
+
+    #[test]
+    fn test_field_changes_of_a_create_have_no_old_value() {
+        let log = audit_log(
+            AuditEventType::CREATE,
+            json!({"new": {"label": "Savings", "nickname": null, "balance": 10}}),
+        );
+        let changes = log.field_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].to_string(), "balance: (none) → 10");
+        assert_eq!(changes[1].to_string(), "label: (none) → Savings");
+    }
+
+    #[test]
+    fn test_audit_cursor_round_trip() {
+        let log = audit_log(AuditEventType::UPDATE, json!({}));
+        let cursor = AuditCursor::new(&log);
+
+        let decoded = AuditCursor::decode(&cursor.encode()).expect("failed to decode");
+        assert_eq!(decoded.audit_id, log.id);
+        assert_eq!(
+            decoded.creation_time.timestamp_micros(),
+            log.creation_time.timestamp_micros()
+        );
+        assert!(AuditCursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_parse_entity_and_event_types() {
+        assert_eq!(
+            EntityType::from_str("Account").ok(),
+            Some(EntityType::Account)
+        );
+        assert!(EntityType::from_str("Credit").is_err());
+        assert_eq!(
+            AuditEventType::from_str("update").ok(),
+            Some(AuditEventType::UPDATE)
+        );
+        assert!(AuditFilter::build(None, None, None, Some("MOVE".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_chained_log_detects_changes() {
+        let mut log = audit_log(
+            AuditEventType::UPDATE,
+            json!({"old": {"status": "Active"}, "new": {"status": "Frozen"}}),
+        );
+        log.chain_to(2, Some("prev".to_string()));
+        assert!(log.is_intact());
+
+        let mut relinked = log.clone();
+        relinked.prev_hash = Some("other".to_string());
+        assert!(!relinked.is_intact());
+
+        let mut reattributed = log.clone();
+        reattributed.service_id = Some("settlement".to_string());
+        assert!(!reattributed.is_intact());
+
+        log.changes = json!({"old": {"status": "Active"}, "new": {"status": "Closed"}});
+        assert!(!log.is_intact());
+    }
+
+    #[test]
+    fn test_sealed_log_detects_changes_before_it_is_chained() {
+        let mut log = audit_log(
+            AuditEventType::UPDATE,
+            json!({"old": {"status": "Active"}, "new": {"status": "Frozen"}}),
+        );
+        log.seal();
+        assert!(log.is_intact());
+
+        let mut reattributed = log.clone();
+        reattributed.user_fp = "other_user_fp".to_string();
+        assert!(!reattributed.is_intact());
+
+        log.changes = json!({"old": {"status": "Active"}, "new": {"status": "Closed"}});
+        assert!(!log.is_intact());
+    }
+}
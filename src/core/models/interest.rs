@@ -0,0 +1,140 @@
+use crate::core::{AccountType, Currency, WalletHolding};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// The yearly rate ***apr*** paid on the accounts of ***account_type*** held in ***currency***,
+/// e.g. `0.035` for 3.5%.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InterestProduct {
+    pub account_type: AccountType,
+    pub currency: Currency,
+    pub apr: Decimal,
+}
+
+impl InterestProduct {
+    /// Interest earned over `accrual_date` on an end-of-day `balance`. Overdrawn balances earn
+    /// nothing.
+    pub fn daily_interest(&self, balance: Decimal, accrual_date: NaiveDate) -> Decimal {
+        if balance <= Decimal::ZERO || self.apr <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+        (balance * self.apr / Decimal::from(days_in_year(accrual_date.year()))).round_dp(10)
+    }
+}
+
+pub(crate) fn days_in_year(year: i32) -> u32 {
+    if NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366
+    } else {
+        365
+    }
+}
+
+/// Interest accrued on an account for one day of its timezone. It is unpaid until it is
+/// capitalized, ***transaction_id*** being the credit that paid it.
+#[derive(Serialize, Debug, Clone)]
+pub struct InterestAccrual {
+    pub account_id: String,
+    pub accrual_date: NaiveDate,
+    pub currency: Currency,
+    pub balance: Decimal,
+    pub apr: Decimal,
+    pub amount: Decimal,
+    pub transaction_id: Option<String>,
+    pub capitalized_at: Option<DateTime<Utc>>,
+    pub creation_time: DateTime<Utc>,
+}
+
+impl InterestAccrual {
+    pub fn new(
+        account_id: String,
+        accrual_date: NaiveDate,
+        balance: Decimal,
+        product: &InterestProduct,
+    ) -> Self {
+        InterestAccrual {
+            account_id,
+            accrual_date,
+            currency: product.currency.clone(),
+            balance,
+            apr: product.apr,
+            amount: product.daily_interest(balance, accrual_date),
+            transaction_id: None,
+            capitalized_at: None,
+            creation_time: Utc::now(),
+        }
+    }
+
+    /// Identifies the accrued day in the audit logs.
+    pub fn entity_id(&self) -> String {
+        format!("{}:{}", self.account_id, self.accrual_date)
+    }
+}
+
+/// Interest accrued on an account and not capitalized yet, next to the balance it accrues on.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccruedInterest {
+    pub wallet: WalletHolding,
+    pub apr: Option<Decimal>,
+    pub amount: Decimal,
+    pub accrued_since: Option<NaiveDate>,
+    pub accrued_until: Option<NaiveDate>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(apr: &str) -> InterestProduct {
+        InterestProduct {
+            account_type: AccountType::Wallet,
+            currency: Currency::USD,
+            apr: apr.parse().expect("invalid apr"),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("invalid date")
+    }
+
+    #[test]
+    fn test_daily_interest_uses_days_of_the_year() {
+        let product = product("0.0365");
+        assert_eq!(
+            product.daily_interest(Decimal::from(1000), date(2025, 6, 1)),
+            Decimal::new(1, 1)
+        );
+        assert_eq!(
+            product.daily_interest(Decimal::from(1098), date(2024, 6, 1)),
+            Decimal::new(1095, 4)
+        );
+    }
+
+    #[test]
+    fn test_daily_interest_is_zero_without_positive_balance() {
+        let product = product("0.05");
+        assert_eq!(
+            product.daily_interest(Decimal::ZERO, date(2025, 1, 1)),
+            Decimal::ZERO
+        );
+        assert_eq!(
+            product.daily_interest(Decimal::from(-500), date(2025, 1, 1)),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_accrual_keeps_the_rate_it_was_computed_with() {
+        let accrual = InterestAccrual::new(
+            "account_id".to_string(),
+            date(2025, 3, 1),
+            Decimal::from(730),
+            &product("0.05"),
+        );
+        assert_eq!(accrual.apr, "0.05".parse().expect("invalid decimal"));
+        assert_eq!(accrual.amount, Decimal::new(1, 1));
+        assert_eq!(accrual.currency, Currency::USD);
+        assert!(accrual.transaction_id.is_none());
+    }
+}
@@ -49,13 +49,20 @@ pub struct LedgerEntry {
     pub entry_type: EntryType,
     pub timestamp: DateTime<Utc>,
     pub description: Option<String>,
+    pub transaction_id: Option<String>,
 }
 
 impl LedgerEntry {
-    pub fn new(account_id: String, desc: Option<String>, entry_type: EntryType) -> Self {
+    pub fn new(
+        account_id: String,
+        desc: Option<String>,
+        entry_type: EntryType,
+        transaction_id: Option<String>,
+    ) -> Self {
         LedgerEntry {
             account_id,
             entry_type,
+            transaction_id,
             description: desc,
             sequence_number: 0,
             timestamp: Utc::now(),
@@ -0,0 +1,327 @@
+use crate::DomainError;
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// Limits applied to the customer-initiated transactions of an account. A limit left unset is
+/// not enforced.
+///
+/// ***max_amount*** caps a single transaction.
+///
+/// ***daily_debit*** and ***monthly_debit*** cap what is debited within a UTC calendar day/month.
+///
+/// ***hourly_count*** caps how many transactions are made within a UTC calendar hour.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionLimits {
+    pub max_amount: Option<Decimal>,
+    pub daily_debit: Option<Decimal>,
+    pub monthly_debit: Option<Decimal>,
+    pub hourly_count: Option<u32>,
+}
+
+impl TransactionLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_amount.is_none()
+            && self.daily_debit.is_none()
+            && self.monthly_debit.is_none()
+            && self.hourly_count.is_none()
+    }
+
+    /// The limits of an account: what it overrides replaces the default of its type.
+    pub fn overridden_by(&self, overrides: &AccountLimits) -> TransactionLimits {
+        TransactionLimits {
+            max_amount: overrides.max_amount.or(self.max_amount),
+            daily_debit: overrides.daily_debit.or(self.daily_debit),
+            monthly_debit: overrides.monthly_debit.or(self.monthly_debit),
+            hourly_count: overrides
+                .hourly_count
+                .map(|count| count.max(0) as u32)
+                .or(self.hourly_count),
+        }
+    }
+
+    pub fn check_amount(&self, amount: Decimal) -> Result<(), LimitBreach> {
+        match self.max_amount {
+            Some(max_amount) if amount > max_amount => Err(LimitBreach::MaxAmount(max_amount)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that one more transaction of `amount` fits in what is left of the windows.
+    pub fn check_usage(
+        &self,
+        amount: Decimal,
+        is_debit: bool,
+        usage: &LimitUsage,
+    ) -> Result<(), LimitBreach> {
+        if let Some(hourly_count) = self.hourly_count {
+            if usage.hourly_count >= hourly_count as u64 {
+                return Err(LimitBreach::HourlyCount(hourly_count));
+            }
+        }
+        if !is_debit {
+            return Ok(());
+        }
+        if let Some(daily_debit) = self.daily_debit {
+            if usage.daily_debit + amount > daily_debit {
+                return Err(LimitBreach::DailyDebit(daily_debit));
+            }
+        }
+        if let Some(monthly_debit) = self.monthly_debit {
+            if usage.monthly_debit + amount > monthly_debit {
+                return Err(LimitBreach::MonthlyDebit(monthly_debit));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Limits set on one account, overriding the defaults of its type.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountLimits {
+    pub account_id: String,
+    pub max_amount: Option<Decimal>,
+    pub daily_debit: Option<Decimal>,
+    pub monthly_debit: Option<Decimal>,
+    pub hourly_count: Option<i32>,
+    pub changed_by: String,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl AccountLimits {
+    pub fn build(
+        account_id: String,
+        max_amount: Option<Decimal>,
+        daily_debit: Option<Decimal>,
+        monthly_debit: Option<Decimal>,
+        hourly_count: Option<u32>,
+        changed_by: String,
+    ) -> Result<Self, DomainError> {
+        let amounts = [max_amount, daily_debit, monthly_debit];
+        if amounts
+            .iter()
+            .flatten()
+            .any(|amount| *amount <= Decimal::ZERO)
+        {
+            return Err(DomainError::InvalidArgument(
+                "limits must be greater than zero".to_string(),
+            ));
+        }
+        let hourly_count = match hourly_count {
+            Some(0) => {
+                return Err(DomainError::InvalidArgument(
+                    "limits must be greater than zero".to_string(),
+                ));
+            }
+            Some(count) => Some(i32::try_from(count).map_err(|_| {
+                DomainError::InvalidArgument("hourly count limit is too large".to_string())
+            })?),
+            None => None,
+        };
+        Ok(AccountLimits {
+            account_id,
+            max_amount,
+            daily_debit,
+            monthly_debit,
+            hourly_count,
+            changed_by,
+            modification_time: Utc::now(),
+        })
+    }
+}
+
+/// Limits as submitted by an admin, parsed & validated into [AccountLimits].
+pub struct AccountLimitsReq {
+    pub max_amount: Option<String>,
+    pub daily_debit: Option<String>,
+    pub monthly_debit: Option<String>,
+    pub hourly_count: Option<u32>,
+}
+
+/// What an account already used in the windows that contain `now`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LimitUsage {
+    pub hourly_count: u64,
+    pub daily_debit: Decimal,
+    pub monthly_debit: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitBreach {
+    MaxAmount(Decimal),
+    HourlyCount(u32),
+    DailyDebit(Decimal),
+    MonthlyDebit(Decimal),
+}
+
+impl Display for LimitBreach {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitBreach::MaxAmount(limit) => {
+                write!(f, "amount exceeds the limit of {} per transaction", limit)
+            }
+            LimitBreach::HourlyCount(limit) => {
+                write!(f, "limit of {} transactions per hour reached", limit)
+            }
+            LimitBreach::DailyDebit(limit) => {
+                write!(f, "daily debit limit of {} exceeded", limit)
+            }
+            LimitBreach::MonthlyDebit(limit) => {
+                write!(f, "monthly debit limit of {} exceeded", limit)
+            }
+        }
+    }
+}
+
+/// The UTC calendar windows limits are counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitWindow {
+    Hour,
+    Day,
+    Month,
+}
+
+impl LimitWindow {
+    pub const ALL: [LimitWindow; 3] = [LimitWindow::Hour, LimitWindow::Day, LimitWindow::Month];
+
+    pub fn start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let (year, month, day, hour) = match self {
+            LimitWindow::Hour => (now.year(), now.month(), now.day(), now.hour()),
+            LimitWindow::Day => (now.year(), now.month(), now.day(), 0),
+            LimitWindow::Month => (now.year(), now.month(), 1, 0),
+        };
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0)
+            .single()
+            .unwrap_or(now)
+    }
+
+    pub fn end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let start = self.start(now);
+        match self {
+            LimitWindow::Hour => start + Duration::hours(1),
+            LimitWindow::Day => start + Duration::days(1),
+            LimitWindow::Month => start
+                .checked_add_months(chrono::Months::new(1))
+                .unwrap_or(start + Duration::days(31)),
+        }
+    }
+
+    /// Identifies the window containing `now`, e.g. `day:20240131`.
+    pub fn bucket(&self, now: DateTime<Utc>) -> String {
+        match self {
+            LimitWindow::Hour => format!("hour:{}", now.format("%Y%m%d%H")),
+            LimitWindow::Day => format!("day:{}", now.format("%Y%m%d")),
+            LimitWindow::Month => format!("month:{}", now.format("%Y%m")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> TransactionLimits {
+        TransactionLimits {
+            max_amount: Some(Decimal::from(100)),
+            daily_debit: Some(Decimal::from(200)),
+            monthly_debit: Some(Decimal::from(1000)),
+            hourly_count: Some(3),
+        }
+    }
+
+    #[test]
+    fn test_check_amount() {
+        assert!(limits().check_amount(Decimal::from(100)).is_ok());
+        assert_eq!(
+            limits().check_amount(Decimal::from(101)),
+            Err(LimitBreach::MaxAmount(Decimal::from(100)))
+        );
+        assert!(TransactionLimits::default()
+            .check_amount(Decimal::from(1_000_000))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_check_usage() {
+        let usage = LimitUsage {
+            hourly_count: 2,
+            daily_debit: Decimal::from(150),
+            monthly_debit: Decimal::from(950),
+        };
+        assert!(limits()
+            .check_usage(Decimal::from(50), true, &usage)
+            .is_ok());
+        assert_eq!(
+            limits().check_usage(Decimal::from(51), true, &usage),
+            Err(LimitBreach::DailyDebit(Decimal::from(200)))
+        );
+        // credits only count towards the hourly count
+        assert!(limits()
+            .check_usage(Decimal::from(500), false, &usage)
+            .is_ok());
+
+        let busy_hour = LimitUsage {
+            hourly_count: 3,
+            ..usage
+        };
+        assert_eq!(
+            limits().check_usage(Decimal::from(1), false, &busy_hour),
+            Err(LimitBreach::HourlyCount(3))
+        );
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let overrides = AccountLimits::build(
+            "account_id".to_string(),
+            None,
+            Some(Decimal::from(500)),
+            None,
+            Some(10),
+            "admin".to_string(),
+        )
+        .expect("failed to build account limits");
+        let merged = limits().overridden_by(&overrides);
+
+        assert_eq!(merged.max_amount, Some(Decimal::from(100)));
+        assert_eq!(merged.daily_debit, Some(Decimal::from(500)));
+        assert_eq!(merged.hourly_count, Some(10));
+    }
+
+    #[test]
+    fn test_build_rejects_non_positive_limits() {
+        let build = |amount: i64, count: u32| {
+            AccountLimits::build(
+                "account_id".to_string(),
+                Some(Decimal::from(amount)),
+                None,
+                None,
+                Some(count),
+                "admin".to_string(),
+            )
+        };
+        assert!(build(0, 1).is_err());
+        assert!(build(1, 0).is_err());
+        assert!(build(1, 1).is_ok());
+    }
+
+    #[test]
+    fn test_windows() {
+        let now = Utc.with_ymd_and_hms(2024, 12, 31, 23, 45, 10).unwrap();
+
+        assert_eq!(
+            LimitWindow::Hour.start(now),
+            Utc.with_ymd_and_hms(2024, 12, 31, 23, 0, 0).unwrap()
+        );
+        assert_eq!(
+            LimitWindow::Day.end(now),
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            LimitWindow::Month.end(now),
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(LimitWindow::Month.bucket(now), "month:202412");
+    }
+}
@@ -0,0 +1,279 @@
+use crate::core::MonetaryTransaction;
+use crate::DomainError;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// What a user can do on an account shared with them. Each role grants what the roles before it
+/// grant.
+///
+/// ***Viewer*** reads the account, its wallets and its transactions.
+///
+/// ***Spender*** also debits the account and approves debits waiting for approvals.
+///
+/// ***Owner*** also manages the members and the signing policy of the account.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[sqlx(type_name = "account_role")]
+#[sqlx(rename_all = "PascalCase")]
+pub enum AccountRole {
+    Viewer,
+    Spender,
+    Owner,
+}
+
+impl AccountRole {
+    pub fn grants(&self, required: &AccountRole) -> bool {
+        self >= required
+    }
+}
+
+impl FromStr for AccountRole {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Viewer" | "viewer" => Ok(AccountRole::Viewer),
+            "Spender" | "spender" => Ok(AccountRole::Spender),
+            "Owner" | "owner" => Ok(AccountRole::Owner),
+            _ => Err(DomainError::ParseError(
+                "unrecognized account role".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for AccountRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountRole::Viewer => write!(f, "Viewer"),
+            AccountRole::Spender => write!(f, "Spender"),
+            AccountRole::Owner => write!(f, "Owner"),
+        }
+    }
+}
+
+/// A user sharing an account with its owner.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountMember {
+    pub account_id: String,
+    pub user_fp: String,
+    pub role: AccountRole,
+    pub added_by: String,
+    pub creation_time: DateTime<Utc>,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl AccountMember {
+    pub fn new(account_id: String, user_fp: String, role: AccountRole, added_by: String) -> Self {
+        let now = Utc::now();
+        AccountMember {
+            account_id,
+            user_fp,
+            role,
+            added_by,
+            creation_time: now,
+            modification_time: now,
+        }
+    }
+
+    /// Identifies the membership in the audit logs.
+    pub fn entity_id(&self) -> String {
+        format!("{}:{}", self.account_id, self.user_fp)
+    }
+}
+
+/// Debits of more than ***threshold*** wait in `Pending` until ***required_approvals*** distinct
+/// owners/spenders approved them, for at most ***approval_window*** seconds.
+#[derive(Serialize, Debug, Clone)]
+pub struct SigningPolicy {
+    pub account_id: String,
+    pub threshold: Decimal,
+    pub required_approvals: i32,
+    pub approval_window: i32,
+    pub changed_by: String,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl SigningPolicy {
+    pub fn build(
+        account_id: String,
+        threshold: Decimal,
+        required_approvals: u32,
+        approval_window: u32,
+        changed_by: String,
+    ) -> Result<Self, DomainError> {
+        if threshold.is_sign_negative() {
+            return Err(DomainError::InvalidArgument(
+                "threshold can not be negative".to_string(),
+            ));
+        }
+        let to_i32 = |value: u32, name: &str| match i32::try_from(value) {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(DomainError::InvalidArgument(format!(
+                "{} must be between 1 and {}",
+                name,
+                i32::MAX
+            ))),
+        };
+        Ok(SigningPolicy {
+            account_id,
+            threshold,
+            required_approvals: to_i32(required_approvals, "required approvals")?,
+            approval_window: to_i32(approval_window, "approval window")?,
+            changed_by,
+            modification_time: Utc::now(),
+        })
+    }
+
+    /// A single approval is the request itself, so only policies asking for more hold debits.
+    pub fn requires_approvals(&self, amount: Decimal) -> bool {
+        self.required_approvals > 1 && amount > self.threshold
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "approval_status")]
+#[sqlx(rename_all = "PascalCase")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Expired,
+}
+
+impl Display for ApprovalStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApprovalStatus::Pending => write!(f, "Pending"),
+            ApprovalStatus::Approved => write!(f, "Approved"),
+            ApprovalStatus::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// A debit held for approvals. The debit itself is the `Pending` transaction ***transaction_id***,
+/// applied once approved and rejected once expired.
+#[derive(Serialize, Debug, Clone)]
+pub struct ApprovalRequest {
+    pub transaction_id: String,
+    pub account_id: String,
+    pub requested_by: String,
+    pub commission: Decimal,
+    pub required_approvals: i32,
+    pub status: ApprovalStatus,
+    pub expires_at: DateTime<Utc>,
+    pub creation_time: DateTime<Utc>,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl ApprovalRequest {
+    pub fn new(
+        transaction: &MonetaryTransaction,
+        requested_by: String,
+        commission: Decimal,
+        policy: &SigningPolicy,
+    ) -> Self {
+        let now = Utc::now();
+        ApprovalRequest {
+            transaction_id: transaction.id.clone(),
+            account_id: transaction.account_id.clone(),
+            requested_by,
+            commission,
+            required_approvals: policy.required_approvals,
+            status: ApprovalStatus::Pending,
+            expires_at: now + Duration::seconds(policy.approval_window as i64),
+            creation_time: now,
+            modification_time: now,
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Moves a pending request to `Approved` or `Expired`, both are final.
+    pub fn close(&mut self, status: ApprovalStatus) -> Result<(), DomainError> {
+        if self.status != ApprovalStatus::Pending || status == ApprovalStatus::Pending {
+            return Err(DomainError::InvalidState(format!(
+                "approval request can not move from {} to {}",
+                self.status, status
+            )));
+        }
+        self.status = status;
+        self.modification_time = Utc::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{TransactionStatus, TransactionType};
+
+    fn policy(threshold: i64, required_approvals: u32) -> SigningPolicy {
+        SigningPolicy::build(
+            "account_id".to_string(),
+            Decimal::from(threshold),
+            required_approvals,
+            3600,
+            "owner".to_string(),
+        )
+        .expect("failed to build signing policy")
+    }
+
+    #[test]
+    fn test_roles_grant_lower_roles() {
+        assert!(AccountRole::Owner.grants(&AccountRole::Spender));
+        assert!(AccountRole::Spender.grants(&AccountRole::Viewer));
+        assert!(AccountRole::Spender.grants(&AccountRole::Spender));
+        assert!(!AccountRole::Viewer.grants(&AccountRole::Spender));
+        assert!(!AccountRole::Spender.grants(&AccountRole::Owner));
+    }
+
+    #[test]
+    fn test_requires_approvals_above_threshold() {
+        assert!(!policy(100, 2).requires_approvals(Decimal::from(100)));
+        assert!(policy(100, 2).requires_approvals(Decimal::from(101)));
+        assert!(!policy(100, 1).requires_approvals(Decimal::from(1000)));
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_policies() {
+        let build = |threshold: i64, required_approvals: u32, approval_window: u32| {
+            SigningPolicy::build(
+                "account_id".to_string(),
+                Decimal::from(threshold),
+                required_approvals,
+                approval_window,
+                "owner".to_string(),
+            )
+        };
+        assert!(build(-1, 2, 60).is_err());
+        assert!(build(100, 0, 60).is_err());
+        assert!(build(100, 2, 0).is_err());
+        assert!(build(0, 2, 60).is_ok());
+    }
+
+    #[test]
+    fn test_approval_request_closes_once() {
+        let transaction = MonetaryTransaction::build(
+            Decimal::from(500),
+            "account_id".to_string(),
+            TransactionType::Payment,
+            TransactionStatus::Pending,
+        )
+        .expect("failed to build transaction");
+        let mut request = ApprovalRequest::new(
+            &transaction,
+            "owner".to_string(),
+            Decimal::ZERO,
+            &policy(100, 2),
+        );
+
+        assert!(!request.is_expired(request.creation_time));
+        assert!(request.is_expired(request.creation_time + Duration::seconds(3600)));
+        assert!(request.close(ApprovalStatus::Pending).is_err());
+        assert!(request.close(ApprovalStatus::Approved).is_ok());
+        assert!(request.close(ApprovalStatus::Expired).is_err());
+    }
+}
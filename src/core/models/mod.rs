@@ -1,20 +1,53 @@
+mod access;
 mod account;
+mod audit_archive;
+mod audit_chain;
 mod block;
 pub mod chain_stamp;
 mod currency;
+mod health;
 mod history;
+mod interest;
 mod ledger;
+mod limit;
+mod member;
+mod overdraft;
+mod payout;
+mod schedule;
 mod transaction;
 mod unique;
 
+pub use access::{AccessDenial, UserRole};
 pub use account::{
-    Account, AccountStatus, AccountType, BeneficiaryAccount, UpdateAccountReq, WalletHolding,
+    Account, AccountStatus, AccountStatusEvent, AccountStatusReason, AccountType,
+    BeneficiaryAccount, UpdateAccountReq, WalletHolding,
+};
+pub use audit_archive::{AuditArchive, AuditArchiveCursor, AuditRetentionPolicy};
+pub use audit_chain::{
+    audit_anchor, ArchivedAuditLink, AuditChainIssue, AuditChainReport, AuditChainVerifier,
+    AuditCheckpoint,
 };
 pub use block::{Block, BlockRegion};
 pub use currency::{get_currency_hash, Currency, CurrencyRate};
-pub use history::{AuditEventType, AuditLog, EntityType};
+pub use health::DependencyHealth;
+pub use history::{
+    AuditCursor, AuditEventType, AuditFilter, AuditLog, AuditOrigin, EntityType, FieldChange,
+};
+pub use interest::{AccruedInterest, InterestAccrual, InterestProduct};
 pub use ledger::{EntryType, LedgerEntry};
+pub use limit::{AccountLimits, AccountLimitsReq, LimitUsage, LimitWindow, TransactionLimits};
+pub use member::{AccountMember, AccountRole, ApprovalRequest, ApprovalStatus, SigningPolicy};
+pub use overdraft::{OverdraftCharge, OverdraftTerms};
+pub use payout::{
+    PayoutBatch, PayoutBatchItem, PayoutBatchMode, PayoutBatchStatus, PayoutItemReq,
+    PayoutItemStatus, PayoutItemType,
+};
+pub use schedule::{
+    PaymentSchedule, Recurrence, ScheduledPayment, ScheduledPaymentExecution, ScheduledPaymentReq,
+    ScheduledPaymentStatus, ScheduledPaymentType,
+};
 pub use transaction::{
-    ActivityTransaction, MonetaryTransaction, TransactionStatus, TransactionType,
+    ActivityTransaction, FailedAttemptClass, MonetaryTransaction, TransactionCursor,
+    TransactionFilter, TransactionStatus, TransactionType,
 };
 pub use unique::{generate_str_id, generate_timebase_str_id};
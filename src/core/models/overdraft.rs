@@ -0,0 +1,130 @@
+use super::interest::days_in_year;
+use crate::core::Currency;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// What an overdrawn wallet of ***currency*** is charged for each day it ends below zero: the flat
+/// ***daily_fee*** and interest at the yearly rate ***apr*** on the overdrawn amount.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OverdraftTerms {
+    pub currency: Currency,
+    #[serde(default)]
+    pub daily_fee: Decimal,
+    #[serde(default)]
+    pub apr: Decimal,
+}
+
+/// The overdraft fee and interest charged for a day of the account timezone, ***transaction_id***
+/// being the debit that charged them. Days the wallet did not end overdrawn are charged nothing.
+#[derive(Serialize, Debug, Clone)]
+pub struct OverdraftCharge {
+    pub account_id: String,
+    pub charge_date: NaiveDate,
+    pub currency: Currency,
+    pub balance: Decimal,
+    pub fee: Decimal,
+    pub interest: Decimal,
+    pub transaction_id: Option<String>,
+    pub creation_time: DateTime<Utc>,
+}
+
+impl OverdraftCharge {
+    pub fn new(
+        account_id: String,
+        charge_date: NaiveDate,
+        balance: Decimal,
+        terms: &OverdraftTerms,
+    ) -> Self {
+        let (fee, interest) = if balance < Decimal::ZERO {
+            let interest = -balance * terms.apr.max(Decimal::ZERO)
+                / Decimal::from(days_in_year(charge_date.year()));
+            (terms.daily_fee.max(Decimal::ZERO), interest.round_dp(4))
+        } else {
+            (Decimal::ZERO, Decimal::ZERO)
+        };
+        OverdraftCharge {
+            account_id,
+            charge_date,
+            currency: terms.currency.clone(),
+            balance,
+            fee,
+            interest,
+            transaction_id: None,
+            creation_time: Utc::now(),
+        }
+    }
+
+    pub fn total(&self) -> Decimal {
+        self.fee + self.interest
+    }
+
+    /// Identifies the charged day of the wallet in the audit logs.
+    pub fn entity_id(&self) -> String {
+        format!("{}:{}:{}", self.account_id, self.currency, self.charge_date)
+    }
+
+    /// Charges at most `available`, what the wallet can still be debited: the fee first, then the
+    /// interest. What does not fit is waived.
+    pub fn cap_to(&mut self, available: Decimal) {
+        let available = available.max(Decimal::ZERO);
+        self.fee = self.fee.min(available);
+        self.interest = self.interest.min(available - self.fee);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms() -> OverdraftTerms {
+        OverdraftTerms {
+            currency: Currency::USD,
+            daily_fee: Decimal::from(2),
+            apr: "0.365".parse().expect("invalid apr"),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("invalid date")
+    }
+
+    #[test]
+    fn test_only_overdrawn_days_are_charged() {
+        let charge = OverdraftCharge::new(
+            "account_id".to_string(),
+            date(2025, 5, 1),
+            Decimal::from(10),
+            &terms(),
+        );
+        assert_eq!(charge.total(), Decimal::ZERO);
+
+        let charge = OverdraftCharge::new(
+            "account_id".to_string(),
+            date(2025, 5, 1),
+            Decimal::from(-100),
+            &terms(),
+        );
+        assert_eq!(charge.fee, Decimal::from(2));
+        assert_eq!(charge.interest, Decimal::new(1, 1));
+        assert_eq!(charge.total(), Decimal::new(21, 1));
+    }
+
+    #[test]
+    fn test_cap_waives_what_the_wallet_can_not_pay() {
+        let mut charge = OverdraftCharge::new(
+            "account_id".to_string(),
+            date(2025, 5, 1),
+            Decimal::from(-1000),
+            &terms(),
+        );
+        assert_eq!(charge.total(), Decimal::from(3));
+
+        charge.cap_to(Decimal::new(25, 1));
+        assert_eq!(charge.fee, Decimal::from(2));
+        assert_eq!(charge.interest, Decimal::new(5, 1));
+
+        charge.cap_to(Decimal::from(-5));
+        assert_eq!(charge.total(), Decimal::ZERO);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::core::generate_timebase_str_id;
+use crate::DomainError;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// ***AllOrNothing*** every item is applied or none is.
+///
+/// ***BestEffort*** items are applied independently, a failed item does not affect the others.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "payout_batch_mode")]
+pub enum PayoutBatchMode {
+    AllOrNothing,
+    BestEffort,
+}
+
+impl Display for PayoutBatchMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PayoutBatchMode::AllOrNothing => write!(f, "AllOrNothing"),
+            PayoutBatchMode::BestEffort => write!(f, "BestEffort"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "payout_batch_status")]
+pub enum PayoutBatchStatus {
+    Processing,
+    Completed,
+    PartiallyCompleted,
+    Failed,
+}
+
+impl Display for PayoutBatchStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PayoutBatchStatus::Processing => write!(f, "Processing"),
+            PayoutBatchStatus::Completed => write!(f, "Completed"),
+            PayoutBatchStatus::PartiallyCompleted => write!(f, "PartiallyCompleted"),
+            PayoutBatchStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+/// ***Credit*** credits `account_id`.
+///
+/// ***Transfer*** debits `source_account_id` and credits `account_id`.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "payout_item_type")]
+pub enum PayoutItemType {
+    Credit,
+    Transfer,
+}
+
+impl FromStr for PayoutItemType {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Credit" | "credit" => Ok(PayoutItemType::Credit),
+            "Transfer" | "transfer" => Ok(PayoutItemType::Transfer),
+            _ => Err(DomainError::InvalidArgument(
+                "unsupported payout item type".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for PayoutItemType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PayoutItemType::Credit => write!(f, "Credit"),
+            PayoutItemType::Transfer => write!(f, "Transfer"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "payout_item_status")]
+pub enum PayoutItemStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+impl Display for PayoutItemStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PayoutItemStatus::Pending => write!(f, "Pending"),
+            PayoutItemStatus::Completed => write!(f, "Completed"),
+            PayoutItemStatus::Failed => write!(f, "Failed"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PayoutBatch {
+    pub id: String,
+    pub user_fp: String,
+    pub mode: PayoutBatchMode,
+    pub status: PayoutBatchStatus,
+    pub item_count: i32,
+    pub block_id: Option<String>,
+    pub creation_time: DateTime<Utc>,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl PayoutBatch {
+    pub fn new(user_fp: String, mode: PayoutBatchMode, item_count: usize) -> Self {
+        let now = Utc::now();
+        PayoutBatch {
+            mode,
+            user_fp,
+            block_id: None,
+            creation_time: now,
+            modification_time: now,
+            item_count: item_count as i32,
+            id: generate_timebase_str_id(),
+            status: PayoutBatchStatus::Processing,
+        }
+    }
+
+    /// Derives the batch status from the outcome of its items.
+    pub fn complete(&mut self, items: &[PayoutBatchItem], block_id: Option<String>) {
+        let completed = items
+            .iter()
+            .filter(|item| item.status == PayoutItemStatus::Completed)
+            .count();
+        self.status = if completed == items.len() {
+            PayoutBatchStatus::Completed
+        } else if completed == 0 {
+            PayoutBatchStatus::Failed
+        } else {
+            PayoutBatchStatus::PartiallyCompleted
+        };
+        self.block_id = block_id;
+        self.modification_time = Utc::now();
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PayoutBatchItem {
+    pub id: String,
+    pub batch_id: String,
+    pub user_fp: String,
+    pub idempotency_key: String,
+    pub item_type: PayoutItemType,
+    pub account_id: String,
+    pub source_account_id: Option<String>,
+    pub amount: Decimal,
+    pub status: PayoutItemStatus,
+    pub transaction_ids: Vec<String>,
+    pub failure_reason: Option<String>,
+    pub creation_time: DateTime<Utc>,
+    pub modification_time: DateTime<Utc>,
+}
+
+impl PayoutBatchItem {
+    pub fn build(
+        batch_id: String,
+        user_fp: String,
+        idempotency_key: String,
+        item_type: PayoutItemType,
+        account_id: String,
+        source_account_id: Option<String>,
+        amount: Decimal,
+    ) -> Result<Self, DomainError> {
+        if idempotency_key.trim().is_empty() {
+            return Err(DomainError::InvalidArgument(
+                "idempotency key is required".to_string(),
+            ));
+        }
+        if amount <= Decimal::ZERO {
+            return Err(DomainError::InvalidArgument(
+                "amount must be greater than zero".to_string(),
+            ));
+        }
+        match (&item_type, &source_account_id) {
+            (PayoutItemType::Transfer, None) => {
+                return Err(DomainError::InvalidArgument(
+                    "transfers need a source account".to_string(),
+                ));
+            }
+            (PayoutItemType::Transfer, Some(source)) if *source == account_id => {
+                return Err(DomainError::InvalidArgument(
+                    "cannot transfer to the same account".to_string(),
+                ));
+            }
+            (PayoutItemType::Credit, Some(_)) => {
+                return Err(DomainError::InvalidArgument(
+                    "only transfers have a source account".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        let now = Utc::now();
+        Ok(PayoutBatchItem {
+            amount,
+            user_fp,
+            batch_id,
+            item_type,
+            account_id,
+            idempotency_key,
+            source_account_id,
+            creation_time: now,
+            failure_reason: None,
+            modification_time: now,
+            transaction_ids: vec![],
+            id: generate_timebase_str_id(),
+            status: PayoutItemStatus::Pending,
+        })
+    }
+
+    /// True when `other` asks for the same payout, used to tell a retry from a reused key.
+    pub fn is_same_payout(&self, other: &PayoutBatchItem) -> bool {
+        self.item_type == other.item_type
+            && self.account_id == other.account_id
+            && self.source_account_id == other.source_account_id
+            && self.amount == other.amount
+    }
+
+    pub fn succeed(&mut self, transaction_ids: Vec<String>) {
+        self.transaction_ids = transaction_ids;
+        self.status = PayoutItemStatus::Completed;
+        self.failure_reason = None;
+        self.modification_time = Utc::now();
+    }
+
+    pub fn fail(&mut self, reason: String) {
+        self.transaction_ids = vec![];
+        self.status = PayoutItemStatus::Failed;
+        self.failure_reason = Some(reason);
+        self.modification_time = Utc::now();
+    }
+}
+
+/// A payout item as submitted by the caller, parsed & validated into a [PayoutBatchItem].
+pub struct PayoutItemReq {
+    pub idempotency_key: String,
+    pub item_type: String,
+    pub account_id: String,
+    pub source_account_id: Option<String>,
+    pub amount: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_item(item_type: PayoutItemType, source: Option<&str>) -> PayoutBatchItem {
+        PayoutBatchItem::build(
+            "batch_id".to_string(),
+            "user_fp".to_string(),
+            "key-1".to_string(),
+            item_type,
+            "account_id".to_string(),
+            source.map(str::to_string),
+            Decimal::from(10),
+        )
+        .expect("failed to build payout item")
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_items() {
+        let build = |key: &str, item_type, source: Option<&str>, amount: i64| {
+            PayoutBatchItem::build(
+                "batch_id".to_string(),
+                "user_fp".to_string(),
+                key.to_string(),
+                item_type,
+                "account_id".to_string(),
+                source.map(str::to_string),
+                Decimal::from(amount),
+            )
+        };
+
+        assert!(build("", PayoutItemType::Credit, None, 10).is_err());
+        assert!(build("key", PayoutItemType::Credit, None, 0).is_err());
+        assert!(build("key", PayoutItemType::Credit, Some("source"), 10).is_err());
+        assert!(build("key", PayoutItemType::Transfer, None, 10).is_err());
+        assert!(build("key", PayoutItemType::Transfer, Some("account_id"), 10).is_err());
+        assert!(build("key", PayoutItemType::Transfer, Some("source"), 10).is_ok());
+    }
+
+    #[test]
+    fn test_batch_status_follows_items() {
+        let mut batch = PayoutBatch::new("user_fp".to_string(), PayoutBatchMode::BestEffort, 2);
+        let mut completed = build_item(PayoutItemType::Credit, None);
+        completed.succeed(vec!["tx_id".to_string()]);
+        let mut failed = build_item(PayoutItemType::Transfer, Some("source"));
+        failed.fail("insufficient funds".to_string());
+
+        batch.complete(&[completed.clone(), failed.clone()], None);
+        assert_eq!(batch.status, PayoutBatchStatus::PartiallyCompleted);
+        batch.complete(&[completed], Some("block_id".to_string()));
+        assert_eq!(batch.status, PayoutBatchStatus::Completed);
+        batch.complete(&[failed], None);
+        assert_eq!(batch.status, PayoutBatchStatus::Failed);
+    }
+
+    #[test]
+    fn test_is_same_payout() {
+        let item = build_item(PayoutItemType::Credit, None);
+        let mut retry = build_item(PayoutItemType::Credit, None);
+        assert!(item.is_same_payout(&retry));
+
+        retry.amount = Decimal::from(11);
+        assert!(!item.is_same_payout(&retry));
+    }
+}
@@ -0,0 +1,521 @@
+use crate::core::generate_timebase_str_id;
+use crate::DomainError;
+use chrono::{DateTime, Days, LocalResult, Months, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// What a scheduled payment does with its account when it runs.
+///
+/// ***Transfer*** debits the account and credits `destination_account_id` with the same amount
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "scheduled_payment_type")]
+pub enum ScheduledPaymentType {
+    Debit,
+    Credit,
+    Transfer,
+}
+
+impl FromStr for ScheduledPaymentType {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Debit" | "debit" => Ok(ScheduledPaymentType::Debit),
+            "Credit" | "credit" => Ok(ScheduledPaymentType::Credit),
+            "Transfer" | "transfer" => Ok(ScheduledPaymentType::Transfer),
+            _ => Err(DomainError::InvalidArgument(
+                "unsupported scheduled payment type".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for ScheduledPaymentType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduledPaymentType::Debit => write!(f, "Debit"),
+            ScheduledPaymentType::Credit => write!(f, "Credit"),
+            ScheduledPaymentType::Transfer => write!(f, "Transfer"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "schedule_recurrence")]
+pub enum Recurrence {
+    Once,
+    Daily,
+    Weekly,
+    Monthly,
+    Cron,
+}
+
+impl FromStr for Recurrence {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Once" | "once" => Ok(Recurrence::Once),
+            "Daily" | "daily" => Ok(Recurrence::Daily),
+            "Weekly" | "weekly" => Ok(Recurrence::Weekly),
+            "Monthly" | "monthly" => Ok(Recurrence::Monthly),
+            "Cron" | "cron" => Ok(Recurrence::Cron),
+            _ => Err(DomainError::InvalidArgument(
+                "unsupported recurrence".to_string(),
+            )),
+        }
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Once => write!(f, "Once"),
+            Recurrence::Daily => write!(f, "Daily"),
+            Recurrence::Weekly => write!(f, "Weekly"),
+            Recurrence::Monthly => write!(f, "Monthly"),
+            Recurrence::Cron => write!(f, "Cron"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "scheduled_payment_status")]
+pub enum ScheduledPaymentStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+impl Display for ScheduledPaymentStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduledPaymentStatus::Active => write!(f, "Active"),
+            ScheduledPaymentStatus::Completed => write!(f, "Completed"),
+            ScheduledPaymentStatus::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+/// A debit, credit or transfer that runs at `next_run_at` and then again on its `recurrence`.
+///
+/// Recurrences are evaluated in the account's `timezone`: a daily payment created for 09:00 runs
+/// at 09:00 local time on both sides of a DST change, and a monthly payment started on the 31st
+/// runs on the last day of shorter months without drifting.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScheduledPayment {
+    pub id: String,
+    pub user_fp: String,
+    pub account_id: String,
+    pub destination_account_id: Option<String>,
+    pub amount: Decimal,
+    pub payment_type: ScheduledPaymentType,
+    pub recurrence: Recurrence,
+    pub cron_expression: Option<String>,
+    pub timezone: String,
+    pub status: ScheduledPaymentStatus,
+    pub starts_at: DateTime<Utc>,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub creation_time: DateTime<Utc>,
+    pub modification_time: DateTime<Utc>,
+}
+
+/// When a scheduled payment runs: first at ***starts_at***, now when unset, then on its
+/// ***recurrence*** evaluated in ***timezone***.
+pub struct PaymentSchedule {
+    pub recurrence: Recurrence,
+    pub cron_expression: Option<String>,
+    pub timezone: String,
+    pub starts_at: Option<DateTime<Utc>>,
+}
+
+/// A scheduled payment as submitted by the caller, parsed & validated into a [ScheduledPayment].
+pub struct ScheduledPaymentReq {
+    pub account_id: String,
+    pub destination_account_id: Option<String>,
+    pub amount: String,
+    pub payment_type: String,
+    pub recurrence: String,
+    pub cron_expression: Option<String>,
+    pub starts_at: Option<DateTime<Utc>>,
+}
+
+impl ScheduledPayment {
+    pub fn build(
+        user_fp: String,
+        account_id: String,
+        destination_account_id: Option<String>,
+        amount: Decimal,
+        payment_type: ScheduledPaymentType,
+        schedule: PaymentSchedule,
+    ) -> Result<Self, DomainError> {
+        let PaymentSchedule {
+            recurrence,
+            cron_expression,
+            timezone,
+            starts_at,
+        } = schedule;
+        let now = Utc::now();
+        let starts_at = starts_at.unwrap_or(now);
+        if amount <= Decimal::ZERO {
+            return Err(DomainError::InvalidArgument(
+                "amount must be greater than zero".to_string(),
+            ));
+        }
+        if starts_at < now - TimeDelta::minutes(1) {
+            return Err(DomainError::InvalidArgument(
+                "start time must not be in the past".to_string(),
+            ));
+        }
+        match (&payment_type, &destination_account_id) {
+            (ScheduledPaymentType::Transfer, None) => {
+                return Err(DomainError::InvalidArgument(
+                    "transfers need a destination account".to_string(),
+                ));
+            }
+            (ScheduledPaymentType::Transfer, Some(destination)) if *destination == account_id => {
+                return Err(DomainError::InvalidArgument(
+                    "cannot transfer to the same account".to_string(),
+                ));
+            }
+            (ScheduledPaymentType::Debit | ScheduledPaymentType::Credit, Some(_)) => {
+                return Err(DomainError::InvalidArgument(
+                    "only transfers have a destination account".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        match (&recurrence, &cron_expression) {
+            (Recurrence::Cron, Some(expression)) => {
+                parse_cron(expression)?;
+            }
+            (Recurrence::Cron, None) => {
+                return Err(DomainError::InvalidArgument(
+                    "cron recurrence needs a cron expression".to_string(),
+                ));
+            }
+            (_, Some(_)) => {
+                return Err(DomainError::InvalidArgument(
+                    "cron expression is only allowed for cron recurrence".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        parse_timezone(&timezone)?;
+
+        let mut scheduled_payment = ScheduledPayment {
+            amount,
+            user_fp,
+            timezone,
+            starts_at,
+            account_id,
+            recurrence,
+            payment_type,
+            cron_expression,
+            last_run_at: None,
+            next_run_at: starts_at,
+            creation_time: now,
+            modification_time: now,
+            destination_account_id,
+            id: generate_timebase_str_id(),
+            status: ScheduledPaymentStatus::Active,
+        };
+        if scheduled_payment.recurrence == Recurrence::Cron {
+            // the first run is the first cron occurrence at or after the start time
+            scheduled_payment.next_run_at = scheduled_payment
+                .next_occurrence(starts_at - TimeDelta::seconds(1))?
+                .ok_or_else(|| {
+                    DomainError::InvalidArgument("cron expression never runs".to_string())
+                })?;
+        }
+        Ok(scheduled_payment)
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.status == ScheduledPaymentStatus::Active && self.next_run_at <= now
+    }
+
+    /// Marks the run due at `next_run_at` as taken and moves the schedule to its next occurrence
+    /// after `now`. Occurrences missed while the worker was down are collapsed into this run.
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Result<(), DomainError> {
+        if !self.is_due(now) {
+            return Err(DomainError::InvalidState(
+                "scheduled payment is not due".to_string(),
+            ));
+        }
+        let after = now.max(self.next_run_at);
+        match self.next_occurrence(after)? {
+            Some(next_run_at) => self.next_run_at = next_run_at,
+            None => self.status = ScheduledPaymentStatus::Completed,
+        }
+        self.last_run_at = Some(now);
+        self.modification_time = now;
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) -> Result<(), DomainError> {
+        if self.status != ScheduledPaymentStatus::Active {
+            return Err(DomainError::InvalidState(format!(
+                "cannot cancel a {} scheduled payment",
+                self.status
+            )));
+        }
+        self.status = ScheduledPaymentStatus::Cancelled;
+        self.modification_time = Utc::now();
+        Ok(())
+    }
+
+    /// First occurrence strictly after `after`, `None` when the schedule does not run again.
+    pub fn next_occurrence(
+        &self,
+        after: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, DomainError> {
+        let tz = parse_timezone(&self.timezone)?;
+        let start = self.starts_at.with_timezone(&tz).naive_local();
+
+        // nth occurrence, always counted from the start so the 31st does not become the 28th for good
+        let nth_occurrence = |n: u32| -> Option<NaiveDateTime> {
+            match self.recurrence {
+                Recurrence::Daily => start.checked_add_days(Days::new(n as u64)),
+                Recurrence::Weekly => start.checked_add_days(Days::new(7 * n as u64)),
+                _ => start.checked_add_months(Months::new(n)),
+            }
+        };
+
+        match self.recurrence {
+            Recurrence::Once => Ok(None),
+            Recurrence::Cron => {
+                let expression = self.cron_expression.as_deref().unwrap_or_default();
+                let schedule = parse_cron(expression)?;
+                Ok(schedule
+                    .after(&after.with_timezone(&tz))
+                    .next()
+                    .map(|next| next.with_timezone(&Utc)))
+            }
+            Recurrence::Daily | Recurrence::Weekly | Recurrence::Monthly => {
+                // jump close to `after` and walk forward from there
+                let elapsed_days = (after - self.starts_at).num_days().max(0) as u32;
+                let mut n = match self.recurrence {
+                    Recurrence::Daily => elapsed_days,
+                    Recurrence::Weekly => elapsed_days / 7,
+                    _ => elapsed_days / 31,
+                }
+                .saturating_sub(1);
+                loop {
+                    let local = nth_occurrence(n).ok_or_else(|| {
+                        DomainError::InvalidState("next run is out of range".to_string())
+                    })?;
+                    if let Some(occurrence) = resolve_local(&tz, local) {
+                        if occurrence > after {
+                            return Ok(Some(occurrence));
+                        }
+                    }
+                    n += 1;
+                }
+            }
+        }
+    }
+}
+
+/// One run of a scheduled payment. `transaction_id` is the transaction it produced, if any.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScheduledPaymentExecution {
+    pub id: String,
+    pub scheduled_payment_id: String,
+    pub transaction_id: Option<String>,
+    pub succeeded: bool,
+    pub failure_reason: Option<String>,
+    pub scheduled_for: DateTime<Utc>,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl ScheduledPaymentExecution {
+    pub fn succeeded(
+        scheduled_payment_id: String,
+        transaction_id: String,
+        scheduled_for: DateTime<Utc>,
+    ) -> Self {
+        ScheduledPaymentExecution {
+            scheduled_for,
+            succeeded: true,
+            failure_reason: None,
+            scheduled_payment_id,
+            executed_at: Utc::now(),
+            id: generate_timebase_str_id(),
+            transaction_id: Some(transaction_id),
+        }
+    }
+
+    pub fn failed(
+        scheduled_payment_id: String,
+        failure_reason: String,
+        scheduled_for: DateTime<Utc>,
+    ) -> Self {
+        ScheduledPaymentExecution {
+            scheduled_for,
+            succeeded: false,
+            transaction_id: None,
+            scheduled_payment_id,
+            executed_at: Utc::now(),
+            id: generate_timebase_str_id(),
+            failure_reason: Some(failure_reason),
+        }
+    }
+}
+
+fn parse_timezone(timezone: &str) -> Result<Tz, DomainError> {
+    Tz::from_str(timezone)
+        .map_err(|_| DomainError::InvalidArgument(format!("invalid timezone: {}", timezone)))
+}
+
+fn parse_cron(expression: &str) -> Result<Schedule, DomainError> {
+    Schedule::from_str(expression)
+        .map_err(|err| DomainError::InvalidArgument(format!("invalid cron expression: {}", err)))
+}
+
+/// Maps a local wall time to UTC. Ambiguous times (DST fall back) take the first occurrence and
+/// times skipped by a DST jump run an hour later.
+fn resolve_local(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt.with_timezone(&Utc)),
+        LocalResult::None => tz
+            .from_local_datetime(&(local + TimeDelta::hours(1)))
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+
+    fn build_schedule(
+        recurrence: Recurrence,
+        cron_expression: Option<String>,
+        timezone: &str,
+        starts_at: DateTime<Utc>,
+    ) -> ScheduledPayment {
+        let mut schedule = ScheduledPayment::build(
+            "user_fp".to_string(),
+            "account_id".to_string(),
+            None,
+            Decimal::from(10),
+            ScheduledPaymentType::Debit,
+            PaymentSchedule {
+                recurrence,
+                cron_expression,
+                timezone: timezone.to_string(),
+                starts_at: None,
+            },
+        )
+        .expect("failed to build scheduled payment");
+        schedule.starts_at = starts_at;
+        schedule.next_run_at = starts_at;
+        schedule
+    }
+
+    #[test]
+    fn test_daily_recurrence_keeps_local_time_across_dst() {
+        // 2025-03-08 09:00 in New York is 14:00 UTC, DST starts the next day
+        let starts_at = Utc.with_ymd_and_hms(2025, 3, 8, 14, 0, 0).unwrap();
+        let schedule = build_schedule(Recurrence::Daily, None, "America/New_York", starts_at);
+
+        let next = schedule.next_occurrence(starts_at).unwrap().unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 3, 9, 13, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_monthly_recurrence_does_not_drift() {
+        let starts_at = Utc.with_ymd_and_hms(2025, 1, 31, 10, 0, 0).unwrap();
+        let schedule = build_schedule(Recurrence::Monthly, None, "UTC", starts_at);
+
+        let february = schedule.next_occurrence(starts_at).unwrap().unwrap();
+        assert_eq!(february.day(), 28);
+        let march = schedule.next_occurrence(february).unwrap().unwrap();
+        assert_eq!(march.month(), 3);
+        assert_eq!(march.day(), 31);
+    }
+
+    #[test]
+    fn test_weekly_recurrence_skips_missed_runs() {
+        let starts_at = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+        let schedule = build_schedule(Recurrence::Weekly, None, "UTC", starts_at);
+
+        let after = Utc.with_ymd_and_hms(2025, 2, 3, 0, 0, 0).unwrap();
+        let next = schedule.next_occurrence(after).unwrap().unwrap();
+        assert_eq!(next, Utc.with_ymd_and_hms(2025, 2, 5, 8, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_cron_recurrence_in_account_timezone() {
+        let starts_at = Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap();
+        let schedule = build_schedule(
+            Recurrence::Cron,
+            Some("0 30 9 * * Mon *".to_string()),
+            "Europe/Berlin",
+            starts_at,
+        );
+
+        let next = schedule.next_occurrence(starts_at).unwrap().unwrap();
+        let local = next.with_timezone(&chrono_tz::Europe::Berlin);
+        assert_eq!(local.weekday(), chrono::Weekday::Mon);
+        assert_eq!((local.hour(), local.minute()), (9, 30));
+    }
+
+    #[test]
+    fn test_advance_completes_one_off_payment() {
+        let now = Utc::now();
+        let mut schedule = build_schedule(Recurrence::Once, None, "UTC", now);
+
+        schedule.advance(now).unwrap();
+        assert_eq!(schedule.status, ScheduledPaymentStatus::Completed);
+        assert!(schedule.cancel().is_err());
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_schedules() {
+        let build = |payment_type, destination: Option<&str>, recurrence, cron: Option<&str>| {
+            ScheduledPayment::build(
+                "user_fp".to_string(),
+                "account_id".to_string(),
+                destination.map(str::to_string),
+                Decimal::from(10),
+                payment_type,
+                PaymentSchedule {
+                    recurrence,
+                    cron_expression: cron.map(str::to_string),
+                    timezone: "UTC".to_string(),
+                    starts_at: None,
+                },
+            )
+        };
+
+        assert!(build(ScheduledPaymentType::Transfer, None, Recurrence::Once, None).is_err());
+        assert!(build(
+            ScheduledPaymentType::Transfer,
+            Some("account_id"),
+            Recurrence::Once,
+            None
+        )
+        .is_err());
+        assert!(build(ScheduledPaymentType::Debit, None, Recurrence::Cron, None).is_err());
+        assert!(build(
+            ScheduledPaymentType::Debit,
+            None,
+            Recurrence::Cron,
+            Some("not a cron")
+        )
+        .is_err());
+        assert!(build(
+            ScheduledPaymentType::Transfer,
+            Some("other_account"),
+            Recurrence::Daily,
+            None
+        )
+        .is_ok());
+    }
+}
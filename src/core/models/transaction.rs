@@ -1,4 +1,5 @@
 use crate::core::generate_timebase_str_id;
+use crate::core::models::unique::{decode_time_cursor, encode_time_cursor};
 use crate::DomainError;
 use chrono::{DateTime, Utc};
 use rust_decimal::prelude::Zero;
@@ -8,6 +9,7 @@ use std::fmt::{write, Display, Formatter};
 use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "monetary_tx_type")]
 pub enum TransactionType {
     Payment,
     Transfer,
@@ -15,6 +17,7 @@ pub enum TransactionType {
     Commission,
     Correction,
     Initialization,
+    Interest,
 }
 
 impl Display for TransactionType {
@@ -38,6 +41,9 @@ impl Display for TransactionType {
             TransactionType::Initialization => {
                 write!(f, "Initiation")
             }
+            TransactionType::Interest => {
+                write!(f, "Interest")
+            }
         }
     }
 }
@@ -47,6 +53,12 @@ impl TransactionType {
         *self == TransactionType::Payment
             || *self == TransactionType::Transfer
             || *self == TransactionType::Commission
+            || *self == TransactionType::Interest
+    }
+
+    /// Transactions a customer asks for, as opposed to the ones the platform books on its own.
+    pub fn is_customer_initiated(&self) -> bool {
+        *self == TransactionType::Payment || *self == TransactionType::Transfer
     }
 
     pub fn is_credit_transaction(&self) -> bool {
@@ -65,6 +77,7 @@ impl FromStr for TransactionType {
             "Reversal" | "reversal" => Ok(TransactionType::Reversal),
             "Commission" | "commission" => Ok(TransactionType::Commission),
             "Correction" | "correction" => Ok(TransactionType::Correction),
+            "Interest" | "interest" => Ok(TransactionType::Interest),
             _ => Err(DomainError::InvalidArgument(
                 "unsupported transaction type".to_string(),
             )),
@@ -72,10 +85,37 @@ impl FromStr for TransactionType {
     }
 }
 
+/// The kinds of failed transaction attempts that count towards locking an account.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FailedAttemptClass {
+    InsufficientFunds,
+    InvalidState,
+    BadCurrency,
+}
+
+impl FailedAttemptClass {
+    pub const ALL: [FailedAttemptClass; 3] = [
+        FailedAttemptClass::InsufficientFunds,
+        FailedAttemptClass::InvalidState,
+        FailedAttemptClass::BadCurrency,
+    ];
+}
+
+impl Display for FailedAttemptClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailedAttemptClass::InsufficientFunds => write!(f, "insufficient_funds"),
+            FailedAttemptClass::InvalidState => write!(f, "invalid_state"),
+            FailedAttemptClass::BadCurrency => write!(f, "bad_currency"),
+        }
+    }
+}
+
 /// ***!IMPORTANT***: _Once a Transaction is Completed, its associated LedgerEntry records should never be changed or deleted.
 /// Corrections should be made via new transactions (e.g., a Reversal or Correction transaction type)
 /// that create new offsetting LedgerEntry records. Enforce this through app logic & DB permissions_
 #[derive(Debug, Clone, Serialize, Eq, PartialEq, sqlx::Type, Deserialize)]
+#[sqlx(type_name = "monetary_tx_status")]
 pub enum TransactionStatus {
     Failed,
     Pending,
@@ -93,6 +133,22 @@ impl TransactionStatus {
     }
 }
 
+impl FromStr for TransactionStatus {
+    type Err = DomainError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Failed" | "failed" => Ok(TransactionStatus::Failed),
+            "Pending" | "pending" => Ok(TransactionStatus::Pending),
+            "Rejected" | "rejected" => Ok(TransactionStatus::Rejected),
+            "Reverted" | "reverted" => Ok(TransactionStatus::Reverted),
+            "Completed" | "completed" => Ok(TransactionStatus::Completed),
+            _ => Err(DomainError::InvalidArgument(
+                "unsupported transaction status".to_string(),
+            )),
+        }
+    }
+}
+
 impl Display for TransactionStatus {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -122,6 +178,7 @@ pub struct MonetaryTransaction {
     pub account_id: String,
     pub timestamp: DateTime<Utc>,
     pub status: TransactionStatus,
+    pub block_id: Option<String>,
     pub modification_date: DateTime<Utc>,
     pub transaction_type: TransactionType,
 }
@@ -131,6 +188,7 @@ impl MonetaryTransaction {
         MonetaryTransaction {
             amount,
             account_id,
+            block_id: None,
             timestamp: Utc::now(),
             modification_date: Utc::now(),
             id: generate_timebase_str_id(),
@@ -172,6 +230,7 @@ impl MonetaryTransaction {
             amount,
             status,
             account_id,
+            block_id: None,
             timestamp: now,
             modification_date: now,
             transaction_type: tx_type,
@@ -215,6 +274,105 @@ impl Display for MonetaryTransaction {
     }
 }
 
+/// Filters applied when reading an account's transaction history.
+/// Empty `transaction_types`/`statuses` mean "any".
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub ascending: bool,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub from_time: Option<DateTime<Utc>>,
+    pub to_time: Option<DateTime<Utc>>,
+    pub statuses: Vec<TransactionStatus>,
+    pub transaction_types: Vec<TransactionType>,
+}
+
+impl TransactionFilter {
+    pub fn build(
+        ascending: bool,
+        tx_types: &[String],
+        statuses: &[String],
+        from_time: Option<DateTime<Utc>>,
+        to_time: Option<DateTime<Utc>>,
+        min_amount: Option<String>,
+        max_amount: Option<String>,
+    ) -> Result<Self, DomainError> {
+        let transaction_types = tx_types
+            .iter()
+            .map(|s| TransactionType::from_str(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        let statuses = statuses
+            .iter()
+            .map(|s| TransactionStatus::from_str(s))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parse_amount = |amount: Option<String>| match amount {
+            Some(a) => Decimal::from_str(&a)
+                .map(Some)
+                .map_err(|_| DomainError::InvalidArgument(format!("invalid amount: {}", a))),
+            None => Ok(None),
+        };
+        let min_amount = parse_amount(min_amount)?;
+        let max_amount = parse_amount(max_amount)?;
+
+        if let (Some(min), Some(max)) = (min_amount, max_amount) {
+            if min > max {
+                return Err(DomainError::InvalidArgument(
+                    "min amount is greater than max amount".to_string(),
+                ));
+            }
+        }
+        if let (Some(from), Some(to)) = (from_time, to_time) {
+            if from > to {
+                return Err(DomainError::InvalidArgument(
+                    "from time is after to time".to_string(),
+                ));
+            }
+        }
+
+        Ok(TransactionFilter {
+            ascending,
+            statuses,
+            to_time,
+            from_time,
+            min_amount,
+            max_amount,
+            transaction_types,
+        })
+    }
+}
+
+/// TransactionCursor points at the last transaction of a page.
+///
+/// Transaction ids are UUIDv7 (time ordered), the cursor carries the id & timestamp of the last
+/// returned row so the next page starts strictly after it. Clients only ever see the encoded form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionCursor {
+    pub transaction_id: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl TransactionCursor {
+    pub fn new(transaction: &MonetaryTransaction) -> Self {
+        TransactionCursor {
+            timestamp: transaction.timestamp,
+            transaction_id: transaction.id.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        encode_time_cursor(&self.transaction_id, &self.timestamp)
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, DomainError> {
+        let (transaction_id, timestamp) = decode_time_cursor(cursor)?;
+        Ok(TransactionCursor {
+            timestamp,
+            transaction_id,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ActivityTransaction {
     pub id: String,
@@ -252,3 +410,84 @@ impl Display for ActivityTransaction {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_transaction_cursor_round_trip() {
+        let transaction = MonetaryTransaction::payment(Decimal::from(10), "account_id".to_string());
+        let cursor = TransactionCursor::new(&transaction);
+
+        let decoded = TransactionCursor::decode(&cursor.encode()).expect("failed to decode");
+        assert_eq!(decoded.transaction_id, transaction.id);
+        assert_eq!(
+            decoded.timestamp.timestamp_micros(),
+            transaction.timestamp.timestamp_micros()
+        );
+    }
+
+    #[test]
+    fn test_transaction_cursor_rejects_invalid_input() {
+        assert!(TransactionCursor::decode("not-a-cursor").is_err());
+        assert!(TransactionCursor::decode("abc").is_err());
+
+        // a well-formed cursor whose id is not a UUIDv7
+        let cursor = TransactionCursor {
+            timestamp: Utc::now(),
+            transaction_id: Uuid::new_v4().to_string(),
+        };
+        assert!(TransactionCursor::decode(&cursor.encode()).is_err());
+    }
+
+    #[test]
+    fn test_change_status_only_from_pending() {
+        let mut transaction = MonetaryTransaction::build(
+            Decimal::from(10),
+            "account_id".to_string(),
+            TransactionType::Payment,
+            TransactionStatus::Pending,
+        )
+        .expect("failed to build transaction");
+
+        assert!(transaction
+            .change_status(TransactionStatus::Pending)
+            .is_err());
+        transaction
+            .change_status(TransactionStatus::Completed)
+            .expect("failed to complete transaction");
+        assert_eq!(transaction.status, TransactionStatus::Completed);
+        assert!(transaction
+            .change_status(TransactionStatus::Failed)
+            .is_err());
+    }
+
+    #[test]
+    fn test_transaction_filter_rejects_inverted_ranges() {
+        let filter = TransactionFilter::build(
+            false,
+            &[],
+            &[],
+            None,
+            None,
+            Some("10".to_string()),
+            Some("1".to_string()),
+        );
+        assert!(filter.is_err());
+
+        let filter = TransactionFilter::build(
+            false,
+            &["Payment".to_string()],
+            &["Completed".to_string()],
+            None,
+            None,
+            None,
+            None,
+        )
+        .expect("failed to build filter");
+        assert_eq!(filter.transaction_types, vec![TransactionType::Payment]);
+        assert_eq!(filter.statuses, vec![TransactionStatus::Completed]);
+    }
+}
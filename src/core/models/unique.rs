@@ -1,4 +1,6 @@
-use uuid::Uuid;
+use crate::DomainError;
+use chrono::{DateTime, Utc};
+use uuid::{Uuid, Version};
 
 pub fn generate_timebase_str_id() -> String {
     Uuid::now_v7().to_string()
@@ -7,3 +9,37 @@ pub fn generate_timebase_str_id() -> String {
 pub fn generate_str_id() -> String {
     Uuid::new_v4().to_string()
 }
+
+/// Hex encodes a time ordered ***id*** (UUIDv7) with the ***timestamp*** of its row into an
+/// opaque page cursor.
+pub(crate) fn encode_time_cursor(id: &str, timestamp: &DateTime<Utc>) -> String {
+    format!("{}|{}", id, timestamp.timestamp_micros())
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>()
+}
+
+/// Reverses [`encode_time_cursor`], rejecting cursors whose id is not a UUIDv7.
+pub(crate) fn decode_time_cursor(cursor: &str) -> Result<(String, DateTime<Utc>), DomainError> {
+    let invalid_cursor = || DomainError::InvalidArgument("invalid cursor".to_string());
+    if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+        return Err(invalid_cursor());
+    }
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| invalid_cursor())?;
+    let decoded = String::from_utf8(bytes).map_err(|_| invalid_cursor())?;
+
+    let (id, micros) = decoded.split_once('|').ok_or_else(invalid_cursor)?;
+    let uuid = Uuid::parse_str(id).map_err(|_| invalid_cursor())?;
+    if uuid.get_version() != Some(Version::SortRand) {
+        return Err(invalid_cursor());
+    }
+    let micros = micros.parse::<i64>().map_err(|_| invalid_cursor())?;
+    let timestamp = DateTime::from_timestamp_micros(micros).ok_or_else(invalid_cursor)?;
+
+    Ok((id.to_string(), timestamp))
+}
@@ -94,6 +94,16 @@ pub enum OrchestrateError {
     RecordAlreadyExists(String),
     #[error("`{0}`")]
     IllegalState(String),
+    #[error("`{0}`")]
+    PermissionDenied(String),
+    #[error("`{0}`")]
+    InsufficientFunds(String),
+    #[error("`{0}`")]
+    UnsupportedCurrency(String),
+    #[error("`{0}`")]
+    LimitExceeded(String),
+    #[error("`{0}`")]
+    AmountLimitExceeded(String),
 }
 
 impl OrchestrateError {
@@ -106,6 +116,11 @@ impl OrchestrateError {
             OrchestrateError::InvalidArgument(_) => 400,
             OrchestrateError::InvalidRecordState(_) => 400,
             OrchestrateError::RecordAlreadyExists(_) => 409,
+            OrchestrateError::PermissionDenied(_) => 403,
+            OrchestrateError::InsufficientFunds(_) => 400,
+            OrchestrateError::UnsupportedCurrency(_) => 400,
+            OrchestrateError::LimitExceeded(_) => 429,
+            OrchestrateError::AmountLimitExceeded(_) => 400,
         }
     }
 }
@@ -160,3 +175,31 @@ pub enum CassandraDBError {
     #[error("`{0}`")]
     SetValueError(String),
 }
+
+#[derive(Debug, Error)]
+pub enum ArchiveStoreError {
+    #[error("`{0}`")]
+    NotFound(String),
+    #[error("`{0}`")]
+    Unknown(String),
+}
+
+impl From<object_store::Error> for ArchiveStoreError {
+    fn from(e: object_store::Error) -> Self {
+        match e {
+            object_store::Error::NotFound { path, .. } => ArchiveStoreError::NotFound(path),
+            _ => ArchiveStoreError::Unknown(e.to_string()),
+        }
+    }
+}
+
+impl From<ArchiveStoreError> for OrchestrateError {
+    fn from(e: ArchiveStoreError) -> Self {
+        match e {
+            ArchiveStoreError::NotFound(path) => {
+                OrchestrateError::NotFoundError(format!("archive object {} not found", path))
+            }
+            ArchiveStoreError::Unknown(val) => OrchestrateError::ServerError(val),
+        }
+    }
+}
@@ -10,14 +10,19 @@ mod server;
 mod startup;
 pub mod storage;
 mod telemetry;
+mod worker;
 
 pub use common::{generate_request_id, RequestId};
 pub use configurations::*;
 pub use constants::*;
 pub use context::ApplicationContext;
 pub use environment::Environment;
-pub use error::{CassandraDBError, DomainError, PgDatabaseError};
+pub use error::{ArchiveStoreError, CassandraDBError, DomainError, PgDatabaseError};
 pub use orchestrator::*;
 pub use server::*;
 pub use startup::Server;
 pub use telemetry::*;
+pub use worker::{
+    ApprovalExpiryWorker, AuditArchiveWorker, AuditCheckpointWorker, DormancyWorker,
+    FreezeExpiryWorker, HealthWorker, InterestWorker, OverdraftWorker, ScheduledPaymentWorker,
+};
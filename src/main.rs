@@ -75,6 +75,7 @@ pub async fn main() -> anyhow::Result<()> {
         region,
         &config.database.redis,
         prepared_stmts,
+        &config.app,
     )
     .await
     {
@@ -94,9 +95,9 @@ pub async fn main() -> anyhow::Result<()> {
     // start the servers
     // these tasks is spawn in a thread
     // let grpc_server_task = tokio::spawn(server.grpc_server.run_until_stopped(&environment.clone()));
+    let grpc_server = server.grpc_server;
     let grpc_server_task = tokio::spawn(async move {
-        server
-            .grpc_server
+        grpc_server
             .run_until_stopped(&environment)
             .await
             .map_err(|err| {
@@ -105,8 +106,27 @@ pub async fn main() -> anyhow::Result<()> {
             })
     });
 
+    let scheduled_payment_task = tokio::spawn(server.scheduled_payment_worker.run_until_stopped());
+    let dormancy_task = tokio::spawn(server.dormancy_worker.run_until_stopped());
+    let freeze_expiry_task = tokio::spawn(server.freeze_expiry_worker.run_until_stopped());
+    let approval_task = tokio::spawn(server.approval_worker.run_until_stopped());
+    let interest_task = tokio::spawn(server.interest_worker.run_until_stopped());
+    let overdraft_task = tokio::spawn(server.overdraft_worker.run_until_stopped());
+    let audit_checkpoint_task = tokio::spawn(server.audit_checkpoint_worker.run_until_stopped());
+    let audit_archive_task = tokio::spawn(server.audit_archive_worker.run_until_stopped());
+    let health_task = tokio::spawn(server.health_worker.run_until_stopped());
+
     tokio::select! {
         outcome = grpc_server_task => report_exit("gRPC-worker", outcome),
+        outcome = scheduled_payment_task => report_exit("scheduled-payment-worker", outcome),
+        outcome = dormancy_task => report_exit("dormancy-worker", outcome),
+        outcome = freeze_expiry_task => report_exit("freeze-expiry-worker", outcome),
+        outcome = approval_task => report_exit("approval-worker", outcome),
+        outcome = interest_task => report_exit("interest-worker", outcome),
+        outcome = overdraft_task => report_exit("overdraft-worker", outcome),
+        outcome = audit_checkpoint_task => report_exit("audit-checkpoint-worker", outcome),
+        outcome = audit_archive_task => report_exit("audit-archive-worker", outcome),
+        outcome = health_task => report_exit("health-worker", outcome),
     }
 
     info!("!!! xrf197ilz35aq3 started successfully !!!");
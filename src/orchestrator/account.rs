@@ -1,77 +1,106 @@
 use crate::context::{ApplicationContext, RequestContext, UserContext};
 use crate::core::{
-    Account, AccountStatus, AccountType, AuditEventType, AuditLog, Currency, EntityType,
+    Account, AccountStatus, AccountStatusReason, AccountType, Currency, EntityType,
     UpdateAccountReq, WalletHolding,
 };
-use crate::core::{BeneficiaryAccount, EntryType};
+use crate::core::{AccountRole, BeneficiaryAccount, EntryType};
 use crate::error::OrchestrateError;
 use crate::orchestrator::create_wallet_holding;
+use crate::orchestrator::hierarchy::find_parent_for_child;
+use crate::orchestrator::joint::find_member_account;
 use crate::storage::{
-    fetch_user_accounts_by_currencies_and_types, fetch_user_wallets, find_account_by_acct_type,
-    find_account_by_currency_and_acct_type, find_account_by_id, save_account,
-    save_beneficiary_account,
+    fetch_user_accounts_by_currencies_and_types, fetch_user_wallets, fetch_wallets_for_update,
+    find_account_by_acct_type, find_account_by_currency_and_acct_type, find_account_by_id,
+    save_account, save_beneficiary_account, update_account,
 };
 use crate::{
-    commit_db_transaction, create_initial_block_chain, create_new_audit,
-    find_user_wallets_for_acct, rollback_db_transaction, start_db_transaction,
+    audit_change, commit_db_transaction, create_initial_block_chain, find_user_wallet_for_acct,
+    find_user_wallets_for_acct, rollback_db_transaction, start_db_transaction, DomainError,
+    SYSTEM_USER_FP,
 };
 use cassandra_cpp::{PreparedStatement, Session};
+use chrono::{DateTime, Utc};
 use config::Map;
 use sqlx::{PgPool, Postgres, Transaction};
 use std::collections::HashMap;
 use std::str::FromStr;
 use tracing::{error, info};
 
+/// Creates an account, or adds a wallet in `currency` to the user's open account of the same type
+/// and label. A user can hold several accounts of a type as long as their labels differ. Accounts
+/// created with a `parent_account_id` are children of that account.
 pub async fn create_account(
     pool: &PgPool,
     currency: String,
     acct_type: String,
+    label: Option<String>,
+    parent_account_id: Option<String>,
     user_ctx: &UserContext,
     cassandra_session: &Session,
     app_cxt: &ApplicationContext,
     request_context: RequestContext,
 ) -> Result<(Account, WalletHolding), OrchestrateError> {
     let event = "createAccount";
-    let mut db_tx = start_db_transaction(pool, event).await?;
-
     let acct_type = AccountType::from_str(&acct_type)
         .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
 
     let curr = Currency::from_str(&currency)
         .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let label = Account::parse_label(label)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if let Some(parent_id) = &parent_account_id {
+        find_parent_for_child(&mut *db_tx, parent_id, user_ctx).await?;
+    }
 
     let mut ledger_description = "initialization for newly created account".to_string();
 
-    let created_or_saved_acct =
-        match find_account_by_acct_type(&mut *db_tx, &user_ctx.user_fp, acct_type.clone()).await? {
-            Some(saved_acct) => {
-                if saved_acct.currency == curr {
-                    return Err(OrchestrateError::RecordAlreadyExists(
-                        "account already exists".to_string(),
-                    ));
-                }
-                ledger_description = "creating another wallet for an existing account".to_string();
-                saved_acct
+    let created_or_saved_acct = match find_account_by_acct_type(
+        &mut *db_tx,
+        &user_ctx.user_fp,
+        acct_type.clone(),
+        label.as_deref(),
+    )
+    .await?
+    {
+        Some(saved_acct) => {
+            if saved_acct.currency == curr || saved_acct.parent_account_id != parent_account_id {
+                return Err(OrchestrateError::RecordAlreadyExists(
+                    "account already exists".to_string(),
+                ));
             }
-            None => {
-                if let Some(acct) =
-                    create_new_acct(&mut db_tx, curr, acct_type, &user_ctx, request_context).await?
-                {
-                    acct
-                } else {
-                    rollback_db_transaction(db_tx, event).await?;
-                    return Err(OrchestrateError::ServerError(
-                        "failed to create new account".to_string(),
-                    ));
-                }
+            ledger_description = "creating another wallet for an existing account".to_string();
+            saved_acct
+        }
+        None => {
+            if let Some(acct) = create_new_acct(
+                &mut db_tx,
+                curr.clone(),
+                acct_type,
+                label,
+                parent_account_id,
+                &user_ctx,
+                request_context,
+            )
+            .await?
+            {
+                acct
+            } else {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(OrchestrateError::ServerError(
+                    "failed to create new account".to_string(),
+                ));
             }
-        };
+        }
+    };
 
     ////// 3. create a wallet that belongs to the account
     let wallet_holding = if let Some(wallet) = create_wallet_holding(
-        &mut *db_tx,
+        &mut db_tx,
         created_or_saved_acct.id.clone(),
-        created_or_saved_acct.currency.clone(),
+        curr,
+        &user_ctx.user_fp,
     )
     .await?
     {
@@ -105,7 +134,7 @@ pub async fn create_account(
         Err(err) => {
             error!("failed to create blockchain: {}", err);
             rollback_db_transaction(db_tx, event).await?;
-            return Err(err.into());
+            return Err(err);
         }
     };
     info!(
@@ -120,16 +149,20 @@ async fn create_new_acct(
     tx: &mut Transaction<'_, Postgres>,
     currency: Currency,
     acct_type: AccountType,
+    label: Option<String>,
+    parent_account_id: Option<String>,
     user_ctx: &UserContext,
     req_context: RequestContext,
 ) -> Result<Option<Account>, OrchestrateError> {
     ////// 1. create an account
-    let account = Account::new(
+    let mut account = Account::new(
         user_ctx.user_fp.clone(),
         user_ctx.timezone.clone(),
         currency,
         acct_type.clone(),
     );
+    account.label = label;
+    account.parent_account_id = parent_account_id;
 
     ///// 1.1 Save the new account to DB
     let acct_created = save_account(&mut **tx, &account).await?;
@@ -138,30 +171,28 @@ async fn create_new_acct(
     }
 
     // create audit log
-    let audit_log = AuditLog::build(
-        user_ctx.user_fp.clone(),
-        account.id.to_string(),
+    audit_change(
+        tx,
         EntityType::Account,
-        AuditEventType::CREATE,
-        req_context.request_ip.clone(),
-        req_context.request_ip,
-        req_context.user_agent,
+        &account.id,
         None,
-        Some(account.clone()),
+        Some(&account),
+        &user_ctx.user_fp,
+        Some(&req_context),
     )
-    .map_err(|err| OrchestrateError::ServerError(format!("failed to build audit log: {}", err)))?;
-    if !create_new_audit(audit_log, tx).await? {
-        return Ok(None);
-    }
+    .await?;
 
     Ok(Some(account))
 }
 
+/// Updates the settings of an account. Owners and admins can update an account, only admins can
+/// unlock it.
 pub async fn update_user_account(
     pool: &PgPool,
     acct_id: &str,
     user_ctx: &UserContext,
     request: UpdateAccountReq,
+    is_admin: bool,
     req_context: RequestContext,
 ) -> Result<bool, OrchestrateError> {
     if !is_valid_request(&request) {
@@ -171,25 +202,16 @@ pub async fn update_user_account(
     }
     let event = "updateAccount";
     let mut db_tx = start_db_transaction(pool, event).await?;
-    let saved_acct = match find_account_by_id(&mut *db_tx, acct_id).await? {
-        Some(saved_account) => {
-            // Owners are the only ones allowed to update their accounts for now.
-            if saved_account.user_fp != user_ctx.user_fp {
-                return Err(OrchestrateError::NotFoundError(
-                    "account not found".to_string(),
-                ));
-            }
-            saved_account
-        }
-        None => {
-            return Err(OrchestrateError::NotFoundError(
-                "account not found".to_string(),
-            ));
-        }
-    };
-    if saved_acct.status == AccountStatus::Frozen {
-        return Err(OrchestrateError::IllegalState(
-            "account is frozen".to_string(),
+    let saved_acct = find_account_for_change(&mut db_tx, acct_id, user_ctx, is_admin).await?;
+    if saved_acct.status == AccountStatus::Frozen || saved_acct.status == AccountStatus::Closed {
+        return Err(OrchestrateError::IllegalState(format!(
+            "account is {}",
+            saved_acct.status
+        )));
+    }
+    if request.locked == Some(false) && saved_acct.locked && !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can unlock an account".to_string(),
         ));
     }
     if request.locked.is_none() && saved_acct.locked {
@@ -199,7 +221,164 @@ pub async fn update_user_account(
     }
 
     let updated_acct_details = update_account_mapper(saved_acct.clone(), &request);
-    if !save_account(&mut *db_tx, &updated_acct_details).await? {
+    save_account_change(
+        event,
+        db_tx,
+        saved_acct,
+        updated_acct_details,
+        user_ctx,
+        req_context,
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Moves an account to `status`, see [Account::transition_status] for the allowed transitions.
+/// Owners and admins can change the status of an account but only admins can unfreeze one. The
+/// caller is recorded as the operator of the transition.
+pub async fn change_account_status(
+    pool: &PgPool,
+    acct_id: &str,
+    status: AccountStatus,
+    reason: &str,
+    expires_at: Option<DateTime<Utc>>,
+    is_admin: bool,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<Account, OrchestrateError> {
+    let reason = AccountStatusReason::from_str(reason)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let event = "changeAccountStatus";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let saved_acct = find_account_for_change(&mut db_tx, acct_id, user_ctx, is_admin).await?;
+    if saved_acct.status == AccountStatus::Frozen && !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can unfreeze an account".to_string(),
+        ));
+    }
+
+    let mut updated_acct = saved_acct.clone();
+    updated_acct
+        .transition_status(status, reason, Some(user_ctx.user_fp.clone()), expires_at)
+        .map_err(map_transition_err)?;
+    save_account_change(
+        event,
+        db_tx,
+        saved_acct,
+        updated_acct.clone(),
+        user_ctx,
+        req_context,
+    )
+    .await?;
+    info!(
+        "account {} is now {}, reason={}",
+        updated_acct.id,
+        updated_acct.status,
+        updated_acct
+            .status_reason
+            .as_ref()
+            .map(|reason| reason.to_string())
+            .unwrap_or_default()
+    );
+    Ok(updated_acct)
+}
+
+/// Changes the type of an account. The wallets of the account stay locked until the change is
+/// committed, so their balances cannot move away from zero in the meantime.
+pub async fn change_account_type(
+    pool: &PgPool,
+    acct_id: &str,
+    acct_type: &str,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<Account, OrchestrateError> {
+    let acct_type = AccountType::from_str(acct_type)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let event = "changeAccountType";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let saved_acct = find_account_for_change(&mut db_tx, acct_id, user_ctx, false).await?;
+    if saved_acct.status != AccountStatus::Active || saved_acct.locked {
+        return Err(OrchestrateError::IllegalState(
+            "account type can only change on an active, unlocked account".to_string(),
+        ));
+    }
+
+    let wallets = fetch_wallets_for_update(&mut db_tx, acct_id).await?;
+    let mut updated_acct = saved_acct.clone();
+    updated_acct
+        .change_type(acct_type, &wallets)
+        .map_err(map_transition_err)?;
+    save_account_change(
+        event,
+        db_tx,
+        saved_acct,
+        updated_acct.clone(),
+        user_ctx,
+        req_context,
+    )
+    .await?;
+    Ok(updated_acct)
+}
+
+/// Applies a status change the platform makes on its own, such as marking an account dormant or
+/// reactivating it on customer activity, and audits it. Nothing is committed here.
+pub async fn apply_system_status_change(
+    account: Account,
+    status: AccountStatus,
+    reason: AccountStatusReason,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Account, OrchestrateError> {
+    let mut updated_acct = account.clone();
+    updated_acct
+        .transition_status(status, reason, None, None)
+        .map_err(map_transition_err)?;
+    if !update_account(&mut **db_tx, &updated_acct.id, &updated_acct).await? {
+        return Err(OrchestrateError::ServerError(
+            "failed to update account status".to_string(),
+        ));
+    }
+
+    audit_change(
+        db_tx,
+        EntityType::Account,
+        &updated_acct.id,
+        Some(&account),
+        Some(&updated_acct),
+        SYSTEM_USER_FP,
+        None,
+    )
+    .await?;
+    Ok(updated_acct)
+}
+
+pub async fn find_account_for_change(
+    db_tx: &mut Transaction<'_, Postgres>,
+    acct_id: &str,
+    user_ctx: &UserContext,
+    is_admin: bool,
+) -> Result<Account, OrchestrateError> {
+    match find_account_by_id(&mut **db_tx, acct_id).await? {
+        Some(saved_account) if is_admin || saved_account.user_fp == user_ctx.user_fp => {
+            Ok(saved_account)
+        }
+        _ => Err(OrchestrateError::NotFoundError(
+            "account not found".to_string(),
+        )),
+    }
+}
+
+/// Persists `updated_acct` and its audit log, then commits `db_tx`.
+pub async fn save_account_change(
+    event: &str,
+    mut db_tx: Transaction<'_, Postgres>,
+    saved_acct: Account,
+    updated_acct: Account,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<(), OrchestrateError> {
+    if !update_account(&mut *db_tx, &updated_acct.id, &updated_acct).await? {
         // roll back if the account is not updated
         rollback_db_transaction(db_tx, event).await?;
         return Err(OrchestrateError::ServerError(
@@ -207,59 +386,68 @@ pub async fn update_user_account(
         ));
     }
 
-    // create audit log
-    let audit_log = AuditLog::build(
-        user_ctx.user_fp.clone(),
-        acct_id.to_string(),
+    // create audit log, roll back the update if it is not created
+    if let Err(err) = audit_change(
+        &mut db_tx,
         EntityType::Account,
-        AuditEventType::UPDATE,
-        req_context.request_ip.clone(),
-        req_context.request_ip,
-        req_context.user_agent,
-        Some(saved_acct),
-        Some(updated_acct_details),
+        &updated_acct.id,
+        Some(&saved_acct),
+        Some(&updated_acct),
+        &user_ctx.user_fp,
+        Some(&req_context),
     )
-    .map_err(|err| OrchestrateError::ServerError(format!("failed to build audit log: {}", err)))?;
-    if !create_new_audit(audit_log, &mut db_tx).await? {
-        // roll back if the account audit log is not created
+    .await
+    {
         rollback_db_transaction(db_tx, event).await?;
-        return Err(OrchestrateError::ServerError(
-            "failed to update account details due to failure when creating an audit log"
-                .to_string(),
-        ));
-    };
+        return Err(err);
+    }
 
     commit_db_transaction(db_tx, event).await?;
-    Ok(true)
+    Ok(())
 }
 
-fn update_account_mapper(mut account: Account, update_req: &UpdateAccountReq) -> Account {
-    if update_req.locked.is_some() {
-        account.locked = update_req.locked.clone().unwrap()
-    }
-    if update_req.account_type.is_some() {
-        account.account_type = update_req.account_type.clone().unwrap()
+pub fn map_transition_err(err: DomainError) -> OrchestrateError {
+    match err {
+        DomainError::InvalidState(err) => OrchestrateError::IllegalState(err),
+        err => OrchestrateError::InvalidArgument(err.to_string()),
     }
-    if update_req.timezone.is_some() {
-        account.timezone = update_req.timezone.clone().unwrap()
+}
+
+fn update_account_mapper(mut account: Account, update_req: &UpdateAccountReq) -> Account {
+    if let Some(locked) = update_req.locked {
+        account.locked = locked
     }
-    if update_req.status.is_some() {
-        account.status = update_req.status.clone().unwrap()
+    if let Some(timezone) = &update_req.timezone {
+        account.timezone = timezone.clone()
     }
+    account.modification_time = Utc::now();
     account
 }
 
+/// Finds the caller's open account with the given currency, type and label.
 pub async fn find_account_by_currency_and_type(
     pool: &PgPool,
     currency: &str,
     acct_type: &str,
+    label: Option<String>,
+    user_ctx: &UserContext,
 ) -> Result<Option<(Account, Vec<WalletHolding>)>, OrchestrateError> {
     let currency = Currency::from_str(currency)
         .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
     let acct_type = AccountType::from_str(acct_type)
         .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let label = Account::parse_label(label)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
 
-    match find_account_by_currency_and_acct_type(pool, currency, acct_type).await? {
+    match find_account_by_currency_and_acct_type(
+        pool,
+        &user_ctx.user_fp,
+        currency,
+        acct_type,
+        label.as_deref(),
+    )
+    .await?
+    {
         None => Ok(None),
         Some(account) => {
             let wallets = find_user_wallets_for_acct(pool, &account.id).await?;
@@ -309,10 +497,25 @@ pub async fn create_new_beneficiary_acct(
     if !ben_acct_saved {
         return Ok(None);
     }
+    audit_change(
+        &mut db_tx,
+        EntityType::BeneficiaryAccount,
+        &beneficiary_acct.id,
+        None,
+        Some(&beneficiary_acct),
+        &user_ctx.user_fp,
+        None,
+    )
+    .await?;
 
     ////// 2. create a wallet that belongs to the account
-    if let Some(wallet) =
-        create_wallet_holding(&mut *db_tx, beneficiary_acct.id.clone(), curr).await?
+    if let Some(wallet) = create_wallet_holding(
+        &mut db_tx,
+        beneficiary_acct.id.clone(),
+        curr,
+        &user_ctx.user_fp,
+    )
+    .await?
     {
         wallet
     } else {
@@ -344,7 +547,7 @@ pub async fn create_new_beneficiary_acct(
         Err(err) => {
             error!("failed to create blockchain: {}", err);
             rollback_db_transaction(db_tx, event).await?;
-            return Err(err.into());
+            return Err(err);
         }
     };
     info!(
@@ -398,7 +601,7 @@ pub async fn get_user_accounts_by_currencies_or_types(
     for wallet in wallets {
         user_wallet_holdings
             .entry(wallet.account_id.clone())
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(wallet);
     }
 
@@ -424,15 +627,10 @@ pub async fn get_user_account_by_id(
     pool: &PgPool,
     account_id: &str,
     include_wallet: bool,
+    user_ctx: &UserContext,
 ) -> Result<(Account, Vec<WalletHolding>), OrchestrateError> {
-    let account = match find_account_by_id(pool, account_id).await? {
-        Some(account) => account,
-        None => {
-            return Err(OrchestrateError::NotFoundError(
-                "no account found".to_string(),
-            ));
-        }
-    };
+    // users only see their own accounts and the ones shared with them
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
     if !include_wallet {
         return Ok((account, vec![]));
     }
@@ -447,12 +645,23 @@ pub async fn get_user_account_by_id(
     Ok((account, wallets))
 }
 
+/// The wallet of an account in `currency`. Users only see the wallets of their own accounts and of
+/// the ones shared with them, admins see every wallet.
+pub async fn get_account_wallet(
+    pool: &PgPool,
+    account_id: &str,
+    currency: &str,
+    user_ctx: &UserContext,
+    is_admin: bool,
+) -> Result<Option<WalletHolding>, OrchestrateError> {
+    if !is_admin {
+        find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+    }
+    find_user_wallet_for_acct(pool, account_id, currency).await
+}
+
 fn is_valid_request(request: &UpdateAccountReq) -> bool {
-    if request.locked.is_none()
-        && request.status.is_none()
-        && request.timezone.is_none()
-        && request.account_type.is_none()
-    {
+    if request.locked.is_none() && request.timezone.is_none() {
         return false;
     }
     true
@@ -1,24 +1,413 @@
-use crate::core::{AuditLog, EntryType};
+use crate::context::{ApplicationContext, RequestContext};
+use crate::core::{
+    audit_anchor, AccessDenial, AuditChainIssue, AuditChainReport, AuditChainVerifier,
+    AuditCheckpoint, AuditCursor, AuditEventType, AuditFilter, AuditLog, AuditOrigin, Block,
+    EntityType,
+};
 use crate::error::OrchestrateError;
-use crate::storage::{find_audit_logs, save_audit_log};
-use sqlx::{Executor, Postgres, Transaction};
+use crate::storage::{
+    chain_audit_log, find_archived_audit_links, find_audit_chain_head, find_audit_checkpoints,
+    find_audit_logs, find_block_entry_ids, find_chain_stamp_by_id, find_chained_audit_logs,
+    find_last_audit_checkpoint, find_unchained_audit_logs, lock_audit_chain, save_audit_checkpoint,
+    save_audit_log, save_block_chain, search_audit_logs,
+};
+use crate::{
+    commit_db_transaction, create_chain_stamp, rollback_db_transaction, start_db_transaction,
+    DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, SYSTEM_USER_FP,
+};
+use cassandra_cpp::Session;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::str::FromStr;
+use tracing::{error, info};
 
+/// Saves the log inside `db_tx`. It is appended to the audit hash chain once committed, by
+/// `checkpoint_audit_chain`, so transactions don't wait on each other for the chain.
+///
+/// Until then, up to the `interval` of the checkpoint worker, the log is only protected by the
+/// hash of its own content: a change to it is detected when it is chained, but the log can be
+/// deleted without a trace.
 pub async fn create_new_audit(
-    audit_log: AuditLog,
+    mut audit_log: AuditLog,
     db_tx: &mut Transaction<'_, Postgres>,
 ) -> Result<bool, OrchestrateError> {
+    audit_log.seal();
     let saved = save_audit_log(&mut **db_tx, audit_log).await?;
     Ok(saved)
 }
 
-pub async fn fetch_audit_history<'a, E>(
-    pool: E,
+/// Appends the committed logs not chained yet to the audit hash chain, oldest first, `batch_size`
+/// per DB transaction. Appends wait on each other until their transaction ends, so the chain has
+/// no forks. Returns the number of logs chained.
+async fn chain_audit_logs(pool: &PgPool, batch_size: i64) -> Result<usize, OrchestrateError> {
+    let event = "chainAuditLogs";
+    let mut chained = 0;
+    loop {
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        match chain_audit_batch(batch_size, &mut db_tx).await {
+            Ok(count) => {
+                commit_db_transaction(db_tx, event).await?;
+                chained += count;
+                if count < batch_size as usize {
+                    return Ok(chained);
+                }
+            }
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        }
+    }
+}
+
+async fn chain_audit_batch(
+    batch_size: i64,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<usize, OrchestrateError> {
+    lock_audit_chain(&mut **db_tx).await?;
+    let mut head = find_audit_chain_head(&mut **db_tx).await?;
+    let audit_logs = find_unchained_audit_logs(&mut **db_tx, batch_size).await?;
+    let count = audit_logs.len();
+    for mut audit_log in audit_logs {
+        if !audit_log.is_intact() {
+            return Err(OrchestrateError::IllegalState(format!(
+                "audit log {} was modified before it was chained",
+                audit_log.id
+            )));
+        }
+        match head {
+            Some((sequence_num, hash)) => audit_log.chain_to(sequence_num + 1, Some(hash)),
+            None => audit_log.chain_to(1, None),
+        }
+        if !chain_audit_log(&mut **db_tx, &audit_log).await? {
+            return Err(OrchestrateError::InvalidRecordState(format!(
+                "audit log {} was chained already",
+                audit_log.id
+            )));
+        }
+        head = audit_log.sequence_num.zip(audit_log.hash);
+    }
+    Ok(count)
+}
+
+/// Audits a change to an entity inside the DB transaction making it, so the change and its audit
+/// log are committed or rolled back together. `old` is None for a creation, `new` for a deletion.
+pub async fn audit_change<T: Serialize>(
+    db_tx: &mut Transaction<'_, Postgres>,
+    entity_type: EntityType,
     entity_id: &str,
-    entry_type: EntryType,
-) -> Result<Vec<AuditLog>, OrchestrateError>
-where
-    E: Executor<'a, Database = Postgres>,
-{
-    let saved_logs = find_audit_logs(pool, entity_id, &entry_type.to_string()).await?;
-    Ok(saved_logs)
+    old: Option<&T>,
+    new: Option<&T>,
+    user_fp: &str,
+    req_context: Option<&RequestContext>,
+) -> Result<(), OrchestrateError> {
+    let audit_type = match (&old, &new) {
+        (None, _) => AuditEventType::CREATE,
+        (Some(_), None) => AuditEventType::DELETE,
+        (Some(_), Some(_)) => AuditEventType::UPDATE,
+    };
+    let origin = match req_context {
+        Some(req_context) => AuditOrigin {
+            request_ip: req_context.request_ip.clone(),
+            request_id: req_context
+                .request_id
+                .as_ref()
+                .map(|request_id| request_id.0.clone()),
+            user_agent: req_context.user_agent.clone(),
+            service_id: req_context.service_id.clone(),
+        },
+        None => AuditOrigin::default(),
+    };
+    let audit_log = AuditLog::build(
+        user_fp.to_string(),
+        entity_id.to_string(),
+        entity_type.clone(),
+        audit_type,
+        origin,
+        old,
+        new,
+    )
+    .map_err(|err| OrchestrateError::ServerError(format!("failed to build audit log: {}", err)))?;
+    if !create_new_audit(audit_log, db_tx).await? {
+        return Err(OrchestrateError::ServerError(format!(
+            "failed to create an audit log for {} {}",
+            entity_type, entity_id
+        )));
+    }
+    Ok(())
+}
+
+/// Audits a call rejected for the roles of its caller, the entity being the RPC method.
+pub async fn audit_access_denial(
+    pool: &PgPool,
+    denial: &AccessDenial,
+    user_fp: &str,
+    req_context: &RequestContext,
+) -> Result<(), OrchestrateError> {
+    let event = "auditAccessDenial";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::AccessDenial,
+        &denial.rpc_method,
+        None,
+        Some(denial),
+        user_fp,
+        Some(req_context),
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    Ok(())
+}
+
+/// The audit timeline of an entity, oldest first. Admin only.
+/// Returns the page and the cursor for the next page, if there is one.
+pub async fn fetch_audit_history(
+    pool: &PgPool,
+    entity_type: &str,
+    entity_id: &str,
+    cursor: Option<String>,
+    page_size: u32,
+    is_admin: bool,
+) -> Result<(Vec<AuditLog>, Option<String>), OrchestrateError> {
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can read the audit history".to_string(),
+        ));
+    }
+    let entity_type = EntityType::from_str(entity_type)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let (cursor, page_size) = decode_page(cursor, page_size)?;
+
+    // fetch one extra row to know if there is a next page
+    let audit_logs = find_audit_logs(
+        pool,
+        entity_id,
+        &entity_type,
+        cursor.as_ref(),
+        page_size as i64 + 1,
+    )
+    .await?;
+    Ok(next_page(audit_logs, page_size))
+}
+
+/// Audit logs matching `filter`, newest first. Admin only.
+/// Returns the page and the cursor for the next page, if there is one.
+pub async fn search_audit_history(
+    pool: &PgPool,
+    filter: AuditFilter,
+    cursor: Option<String>,
+    page_size: u32,
+    is_admin: bool,
+) -> Result<(Vec<AuditLog>, Option<String>), OrchestrateError> {
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can search the audit history".to_string(),
+        ));
+    }
+    let (cursor, page_size) = decode_page(cursor, page_size)?;
+
+    let audit_logs =
+        search_audit_logs(pool, &filter, cursor.as_ref(), page_size as i64 + 1).await?;
+    Ok(next_page(audit_logs, page_size))
+}
+
+fn decode_page(
+    cursor: Option<String>,
+    page_size: u32,
+) -> Result<(Option<AuditCursor>, usize), OrchestrateError> {
+    let cursor = match cursor.filter(|c| !c.is_empty()) {
+        Some(c) => Some(
+            AuditCursor::decode(&c)
+                .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let page_size = match page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    } as usize;
+    Ok((cursor, page_size))
+}
+
+fn next_page(mut audit_logs: Vec<AuditLog>, page_size: usize) -> (Vec<AuditLog>, Option<String>) {
+    let next_cursor = if audit_logs.len() > page_size {
+        audit_logs.truncate(page_size);
+        audit_logs
+            .last()
+            .map(|last_log| AuditCursor::new(last_log).encode())
+    } else {
+        None
+    };
+    (audit_logs, next_cursor)
+}
+
+/// Chains the logs saved since the last run, then anchors the head of the audit chain into a
+/// Cassandra block after checking the logs appended since the last checkpoint. Returns None when
+/// nothing was appended since.
+pub async fn checkpoint_audit_chain(
+    pool: &PgPool,
+    batch_size: i64,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<Option<AuditCheckpoint>, OrchestrateError> {
+    let event = "checkpointAuditChain";
+
+    ////// 0. Chain the logs saved since the last run
+    let chained = chain_audit_logs(pool, batch_size).await?;
+    if chained > 0 {
+        info!("chained {} audit logs", chained);
+    }
+
+    ////// 1. Check the logs appended since the last checkpoint
+    let last_checkpoint = find_last_audit_checkpoint(pool).await?;
+    let mut verifier = AuditChainVerifier::new(
+        last_checkpoint
+            .as_ref()
+            .map(|checkpoint| (checkpoint.sequence_num, checkpoint.hash.clone())),
+    );
+    let mut head = None;
+    loop {
+        let logs = find_chained_audit_logs(pool, verifier.last_sequence(), batch_size).await?;
+        logs.iter().for_each(|log| verifier.check(log));
+        match logs.into_iter().last() {
+            Some(log) => head = Some(log),
+            None => break,
+        }
+    }
+    let report = verifier.finish();
+    if !report.is_intact() {
+        for issue in &report.issues {
+            error!("event={} :: {}", event, issue);
+        }
+        return Err(OrchestrateError::IllegalState(format!(
+            "audit chain broken after checkpoint {}, not anchored",
+            last_checkpoint.map_or(0, |checkpoint| checkpoint.sequence_num)
+        )));
+    }
+    let (head, sequence_num, hash) = match head {
+        Some(AuditLog {
+            id,
+            sequence_num: Some(sequence_num),
+            hash: Some(hash),
+            ..
+        }) => (id, sequence_num, hash),
+        _ => return Ok(None),
+    };
+
+    ////// 2. Chain the checkpoint block to the previous one
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let parent_chain_stamp = match &last_checkpoint {
+        Some(checkpoint) => find_chain_stamp_by_id(&mut *db_tx, &checkpoint.chain_id).await?,
+        None => None,
+    };
+    let chain_stamp = match create_chain_stamp(&mut db_tx, parent_chain_stamp, SYSTEM_USER_FP).await
+    {
+        Ok(chain_stamp) => chain_stamp,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err);
+        }
+    };
+    let mut block = Block::build(
+        app_cxt.app_id.to_string(),
+        app_cxt.block_region.clone(),
+        vec![audit_anchor(sequence_num, &hash)],
+        chain_stamp.stamp.clone(),
+    )
+    .map_err(|err| OrchestrateError::ServerError(err.to_string()))?;
+    // keyed by the chain position so checkpoint blocks don't replace each other
+    block.sequence_num = sequence_num as u64;
+
+    let checkpoint = AuditCheckpoint {
+        sequence_num,
+        hash,
+        audit_id: head,
+        block_id: block.id.clone(),
+        chain_id: chain_stamp.stamp,
+        creation_time: Utc::now(),
+    };
+    if let Err(err) = save_audit_checkpoint(&mut *db_tx, &checkpoint).await {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err.into());
+    }
+
+    ////// 3. Anchor the block, the checkpoint only counts once it is in Cassandra
+    if let Err(err) = save_block_chain(
+        &block,
+        cassandra_session,
+        &app_cxt.statements.insert_block_stmt,
+    )
+    .await
+    {
+        error!(
+            "event={} :: failed to save block to cassandra DB: {}",
+            event, err
+        );
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(err.to_string()));
+    }
+    commit_db_transaction(db_tx, event).await?;
+    info!(
+        "anchored audit chain at {} :: blockId={}",
+        checkpoint.sequence_num, checkpoint.block_id
+    );
+    Ok(Some(checkpoint))
+}
+
+/// Walks the whole audit chain, reporting missing, modified or re-linked logs and logs that
+/// differ from what their checkpoint block anchored. Archived logs are checked by their chain link
+/// only, their content is checked when they are restored. Admin only.
+pub async fn verify_audit_chain(
+    pool: &PgPool,
+    batch_size: i64,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    is_admin: bool,
+) -> Result<AuditChainReport, OrchestrateError> {
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can verify the audit chain".to_string(),
+        ));
+    }
+
+    ////// 1. Only trust the checkpoints Cassandra has an anchor for
+    let mut unanchored = Vec::new();
+    let mut verifier = AuditChainVerifier::new(None);
+    for checkpoint in find_audit_checkpoints(pool).await? {
+        let entry_ids = find_block_entry_ids(
+            &app_cxt.app_id.to_string(),
+            checkpoint.sequence_num,
+            cassandra_session,
+        )
+        .await
+        .map_err(|err| OrchestrateError::ServerError(err.to_string()))?;
+        if entry_ids.is_some_and(|entry_ids| entry_ids.contains(&checkpoint.anchor())) {
+            verifier.expect_anchor(checkpoint.sequence_num, checkpoint.hash);
+        } else {
+            unanchored.push(AuditChainIssue::Unanchored {
+                sequence_num: checkpoint.sequence_num,
+            });
+        }
+    }
+
+    ////// 2. Walk the chain from its first log, archived logs by their chain link
+    loop {
+        let after_sequence = verifier.last_sequence();
+        let logs = find_chained_audit_logs(pool, after_sequence, batch_size).await?;
+        let links = find_archived_audit_links(pool, after_sequence, batch_size).await?;
+        if logs.is_empty() && links.is_empty() {
+            break;
+        }
+        verifier.check_page(&logs, &links, batch_size as usize);
+    }
+    let mut report = verifier.finish();
+    report.issues.extend(unanchored);
+    report.issues.sort_by_key(|issue| issue.sequence_num());
+    Ok(report)
 }
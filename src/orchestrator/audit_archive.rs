@@ -0,0 +1,306 @@
+use crate::context::ApplicationContext;
+use crate::core::{AuditArchive, AuditArchiveCursor, EntityType};
+use crate::error::OrchestrateError;
+use crate::storage::{
+    delete_archived_audit_links, delete_audit_logs, find_audit_archive_by_id, find_audit_archives,
+    find_last_audit_checkpoint, find_restored_audit_ids, get_archive_object,
+    lock_archivable_audit_logs, put_archive_object, save_archived_audit_links, save_audit_archive,
+    save_restored_audit_log, update_audit_archive_restored_at,
+};
+use crate::{
+    commit_db_transaction, rollback_db_transaction, start_db_transaction, DEFAULT_PAGE_SIZE,
+    MAX_PAGE_SIZE,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use object_store::ObjectStore;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tracing::info;
+
+/// Moves the audit logs past the retention of their entity type to the archive store, one archive
+/// of up to `batch_size` logs per entity type and day. Only logs before the last checkpoint are
+/// archived, their chain links stay behind so the chain can still be verified. Returns how many
+/// logs were archived.
+pub async fn archive_audit_logs(
+    pool: &PgPool,
+    batch_size: i64,
+    app_cxt: &ApplicationContext,
+) -> Result<i64, OrchestrateError> {
+    let store = match &app_cxt.audit_archive {
+        Some(store) => store.as_ref(),
+        None => return Ok(0),
+    };
+    let before_sequence = find_last_audit_checkpoint(pool)
+        .await?
+        .map_or(0, |checkpoint| checkpoint.sequence_num);
+
+    let now = Utc::now();
+    let mut archived = 0;
+    for policy in &app_cxt.audit_retention.policies {
+        let cutoff = policy.cutoff(now);
+        while let Some(archive) = archive_audit_batch(
+            pool,
+            store,
+            &policy.entity_type,
+            cutoff,
+            before_sequence,
+            batch_size,
+        )
+        .await?
+        {
+            archived += archive.row_count;
+        }
+    }
+    Ok(archived)
+}
+
+/// Archives the oldest logs of `entity_type` past `cutoff`, None when there are none left.
+async fn archive_audit_batch(
+    pool: &PgPool,
+    store: &dyn ObjectStore,
+    entity_type: &EntityType,
+    cutoff: DateTime<Utc>,
+    before_sequence: i64,
+    batch_size: i64,
+) -> Result<Option<AuditArchive>, OrchestrateError> {
+    let event = "archiveAuditLogs";
+
+    ////// 1. Lock the oldest logs, an archive only holds the logs of a day
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let mut audit_logs = lock_archivable_audit_logs(
+        &mut *db_tx,
+        entity_type,
+        cutoff,
+        before_sequence,
+        batch_size,
+    )
+    .await?;
+    let archive_date = match audit_logs.first() {
+        Some(audit_log) => audit_log.creation_time.date_naive(),
+        None => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Ok(None);
+        }
+    };
+    audit_logs.retain(|audit_log| audit_log.creation_time.date_naive() == archive_date);
+    let (archive, data) = match AuditArchive::build(entity_type.clone(), &audit_logs) {
+        Ok(archive) => archive,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(OrchestrateError::ServerError(err.to_string()));
+        }
+    };
+    let manifest = archive
+        .manifest()
+        .map_err(|err| OrchestrateError::ServerError(err.to_string()))?;
+
+    ////// 2. Write the archive before the logs are deleted. A file whose transaction then fails
+    ////// has no archive row and is never read
+    if let Err(err) = put_archive_object(store, &archive.object_path, data).await {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err.into());
+    }
+    if let Err(err) = put_archive_object(store, &archive.manifest_path, manifest).await {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err.into());
+    }
+
+    ////// 3. Replace the logs with their chain links
+    let audit_ids = audit_logs
+        .iter()
+        .map(|audit_log| audit_log.id.clone())
+        .collect::<Vec<_>>();
+    if !save_audit_archive(&mut *db_tx, &archive).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "failed to save audit archive".to_string(),
+        ));
+    }
+    save_archived_audit_links(&mut *db_tx, &archive.id, &audit_ids).await?;
+    let deleted = delete_audit_logs(&mut *db_tx, &audit_ids).await?;
+    if deleted != audit_ids.len() as u64 {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::IllegalState(format!(
+            "archived {} audit logs but deleted {}",
+            audit_ids.len(),
+            deleted
+        )));
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "archived {} {} audit logs of {} :: archiveId={}",
+        archive.row_count, entity_type, archive_date, archive.id
+    );
+    Ok(Some(archive))
+}
+
+/// Archives of the audit logs created between `from_date` and `to_date`, oldest archive first.
+/// Admin only. Returns the page and the cursor for the next page, if there is one.
+pub async fn list_audit_archives(
+    pool: &PgPool,
+    entity_type: Option<String>,
+    from_date: Option<NaiveDate>,
+    to_date: Option<NaiveDate>,
+    cursor: Option<String>,
+    page_size: u32,
+    is_admin: bool,
+) -> Result<(Vec<AuditArchive>, Option<String>), OrchestrateError> {
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can list audit archives".to_string(),
+        ));
+    }
+    let entity_type = match entity_type.filter(|s| !s.is_empty()) {
+        Some(entity_type) => Some(
+            EntityType::from_str(&entity_type)
+                .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let cursor = match cursor.filter(|c| !c.is_empty()) {
+        Some(c) => Some(
+            AuditArchiveCursor::decode(&c)
+                .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let page_size = match page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    } as usize;
+
+    // fetch one extra row to know if there is a next page
+    let mut archives = find_audit_archives(
+        pool,
+        entity_type.as_ref(),
+        from_date,
+        to_date,
+        cursor.as_ref(),
+        page_size as i64 + 1,
+    )
+    .await?;
+    let next_cursor = if archives.len() > page_size {
+        archives.truncate(page_size);
+        archives
+            .last()
+            .map(|last_archive| AuditArchiveCursor::new(last_archive).encode())
+    } else {
+        None
+    };
+    Ok((archives, next_cursor))
+}
+
+/// Loads the logs of an archive back into `audit_log`, for an investigation, after checking the
+/// archive file and every log against their hashes. Restored logs are not archived again until
+/// the archive is released. Admin only.
+pub async fn restore_audit_archive(
+    pool: &PgPool,
+    archive_id: &str,
+    app_cxt: &ApplicationContext,
+    is_admin: bool,
+) -> Result<AuditArchive, OrchestrateError> {
+    let event = "restoreAuditArchive";
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can restore audit archives".to_string(),
+        ));
+    }
+    let store = app_cxt.audit_archive.as_ref().ok_or_else(|| {
+        OrchestrateError::IllegalState("no audit archive storage is configured".to_string())
+    })?;
+    let mut archive = find_archive(pool, archive_id).await?;
+    if archive.restored_at.is_some() {
+        return Err(OrchestrateError::InvalidRecordState(format!(
+            "audit archive {} is already restored",
+            archive_id
+        )));
+    }
+
+    ////// 1. Read the archive back, it must be the one that was written
+    let data = get_archive_object(store.as_ref(), &archive.object_path).await?;
+    let audit_logs = archive
+        .decode(&data)
+        .map_err(|err| OrchestrateError::IllegalState(err.to_string()))?;
+    if let Some(audit_log) = audit_logs
+        .iter()
+        .find(|audit_log| audit_log.sequence_num.is_some() && !audit_log.is_intact())
+    {
+        return Err(OrchestrateError::IllegalState(format!(
+            "audit archive {} holds the modified log {}",
+            archive_id, audit_log.id
+        )));
+    }
+
+    ////// 2. Put the logs back in place of their chain links
+    let restored_at = Utc::now();
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !update_audit_archive_restored_at(&mut *db_tx, archive_id, Some(restored_at)).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidRecordState(format!(
+            "audit archive {} is already restored",
+            archive_id
+        )));
+    }
+    for audit_log in &audit_logs {
+        if let Err(err) = save_restored_audit_log(&mut *db_tx, audit_log, archive_id).await {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err.into());
+        }
+    }
+    delete_archived_audit_links(&mut *db_tx, archive_id).await?;
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "restored {} audit logs :: archiveId={}",
+        audit_logs.len(),
+        archive_id
+    );
+    archive.restored_at = Some(restored_at);
+    Ok(archive)
+}
+
+/// Removes the logs restored from an archive once the investigation is over, the archive file
+/// still holds them. Admin only.
+pub async fn release_audit_archive(
+    pool: &PgPool,
+    archive_id: &str,
+    is_admin: bool,
+) -> Result<AuditArchive, OrchestrateError> {
+    let event = "releaseAuditArchive";
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can release audit archives".to_string(),
+        ));
+    }
+    let mut archive = find_archive(pool, archive_id).await?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !update_audit_archive_restored_at(&mut *db_tx, archive_id, None).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidRecordState(format!(
+            "audit archive {} is not restored",
+            archive_id
+        )));
+    }
+    let audit_ids = find_restored_audit_ids(&mut *db_tx, archive_id).await?;
+    save_archived_audit_links(&mut *db_tx, archive_id, &audit_ids).await?;
+    delete_audit_logs(&mut *db_tx, &audit_ids).await?;
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "released {} restored audit logs :: archiveId={}",
+        audit_ids.len(),
+        archive_id
+    );
+    archive.restored_at = None;
+    Ok(archive)
+}
+
+async fn find_archive(pool: &PgPool, archive_id: &str) -> Result<AuditArchive, OrchestrateError> {
+    find_audit_archive_by_id(pool, archive_id)
+        .await?
+        .ok_or_else(|| {
+            OrchestrateError::NotFoundError(format!("audit archive {} not found", archive_id))
+        })
+}
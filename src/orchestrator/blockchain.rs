@@ -18,6 +18,7 @@ pub async fn create_chained_block_chain(
     cassandra_session: &Session,
     app_cxt: &ApplicationContext,
     ledger_descriptions: Vec<String>,
+    transaction_id: Option<String>,
     db_tx: &mut Transaction<'_, Postgres>,
 ) -> Result<Block, OrchestrateError> {
     let parent_chain_stamp = match get_parent_chain(&user_ctx, db_tx).await? {
@@ -36,6 +37,7 @@ pub async fn create_chained_block_chain(
         cassandra_session,
         &app_cxt,
         ledger_descriptions,
+        transaction_id,
         &app_cxt.statements.insert_block_stmt,
         db_tx,
         Some(parent_chain_stamp),
@@ -50,6 +52,7 @@ async fn create_block_chain(
     cassandra_session: &Session,
     app_cxt: &ApplicationContext,
     ledger_descriptions: Vec<String>,
+    transaction_id: Option<String>,
     insert_block_stmt: &PreparedStatement,
     db_tx: &mut Transaction<'_, Postgres>,
     parent_chain_stamp: Option<ChainStamp>,
@@ -59,7 +62,12 @@ async fn create_block_chain(
     let mut ledgers: Vec<LedgerEntry> = Vec::new();
 
     for desc in ledger_descriptions {
-        let ledger = LedgerEntry::new(acct_id.clone(), Some(desc), entry.clone());
+        let ledger = LedgerEntry::new(
+            acct_id.clone(),
+            Some(desc),
+            entry.clone(),
+            transaction_id.clone(),
+        );
         entry_ids.push(ledger.id.clone());
         ledgers.push(ledger);
     }
@@ -71,8 +79,59 @@ async fn create_block_chain(
         ));
     }
 
+    seal_block(
+        entry_ids,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        insert_block_stmt,
+        db_tx,
+        parent_chain_stamp,
+    )
+    .await
+}
+
+/// Creates one block chained to the user's last activity for ledger entries that are already
+/// saved, e.g. the entries of every item of a payout batch.
+pub async fn create_chained_block(
+    entry_ids: Vec<String>,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Block, OrchestrateError> {
+    let parent_chain_stamp = match get_parent_chain(user_ctx, db_tx).await? {
+        Some(chain_stamp) => chain_stamp,
+        None => {
+            return Err(OrchestrateError::InvalidRecordState(
+                "There's no parent chain stamp".to_string(),
+            ));
+        }
+    };
+
+    seal_block(
+        entry_ids,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        &app_cxt.statements.insert_block_stmt,
+        db_tx,
+        Some(parent_chain_stamp),
+    )
+    .await
+}
+
+async fn seal_block(
+    entry_ids: Vec<String>,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    insert_block_stmt: &PreparedStatement,
+    db_tx: &mut Transaction<'_, Postgres>,
+    parent_chain_stamp: Option<ChainStamp>,
+) -> Result<Block, OrchestrateError> {
     ////// 3.2 Create a new chain stamp for this transaction.
-    let chain_stamp = match create_chain_stamp(db_tx, parent_chain_stamp).await {
+    let chain_stamp = match create_chain_stamp(db_tx, parent_chain_stamp, &user_ctx.user_fp).await {
         Ok(chain_stamp) => chain_stamp,
         Err(err) => {
             return Err(err);
@@ -149,6 +208,7 @@ pub async fn create_initial_block_chain(
         cassandra_session,
         app_cxt,
         ledger_descriptions,
+        None,
         insert_block_stmt,
         db_tx,
         None,
@@ -1,11 +1,14 @@
 use crate::core::chain_stamp::ChainStamp;
+use crate::core::EntityType;
 use crate::error::OrchestrateError;
+use crate::orchestrator::audit_change;
 use crate::storage::{add_child_cs_to_parent, save_chain_stamp};
 use sqlx::{Postgres, Transaction};
 
 pub async fn create_chain_stamp(
     db_tx: &mut Transaction<'_, Postgres>,
     root_cs: Option<ChainStamp>,
+    user_fp: &str,
 ) -> Result<ChainStamp, OrchestrateError> {
     let chain_stamp = ChainStamp::build(root_cs.clone());
     let cs_created = save_chain_stamp(db_tx, &chain_stamp).await?;
@@ -16,10 +19,20 @@ pub async fn create_chain_stamp(
             chain_stamp
         )));
     }
+    audit_change(
+        db_tx,
+        EntityType::ChainStamp,
+        chain_stamp.stamp_id(),
+        None,
+        Some(&chain_stamp),
+        user_fp,
+        None,
+    )
+    .await?;
 
     // add this chain stamp to parent
     if root_cs.is_some() {
-        add_child_chain_stamp(&chain_stamp, root_cs.unwrap(), db_tx).await?;
+        add_child_chain_stamp(&chain_stamp, root_cs.unwrap(), user_fp, db_tx).await?;
     }
     Ok(chain_stamp)
 }
@@ -27,6 +40,7 @@ pub async fn create_chain_stamp(
 async fn add_child_chain_stamp(
     child_cs: &ChainStamp,
     mut parent_chain_stamp: ChainStamp,
+    user_fp: &str,
     db_tx: &mut Transaction<'_, Postgres>,
 ) -> Result<bool, OrchestrateError> {
     if parent_chain_stamp.is_child_chain(&child_cs) {
@@ -38,6 +52,7 @@ async fn add_child_chain_stamp(
         ));
     }
 
+    let saved_chain_stamp = parent_chain_stamp.clone();
     parent_chain_stamp.child_stamp = Some(child_cs.stamp.clone());
 
     if !add_child_cs_to_parent(db_tx, parent_chain_stamp.stamp_id(), child_cs.stamp_id()).await? {
@@ -45,6 +60,16 @@ async fn add_child_chain_stamp(
             "failed to add chain stamp".to_string(),
         ));
     }
+    audit_change(
+        db_tx,
+        EntityType::ChainStamp,
+        parent_chain_stamp.stamp_id(),
+        Some(&saved_chain_stamp),
+        Some(&parent_chain_stamp),
+        user_fp,
+        None,
+    )
+    .await?;
 
     Ok(true)
 }
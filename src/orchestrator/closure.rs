@@ -0,0 +1,370 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::{
+    Account, AccountStatus, AccountStatusReason, EntityType, EntryType, LedgerEntry,
+    MonetaryTransaction, TransactionStatus, TransactionType,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::account::{
+    find_account_for_change, map_transition_err, save_account_change,
+};
+use crate::orchestrator::transaction::apply_wallet_mutation;
+use crate::storage::{
+    bulk_save_ledger, cancel_account_scheduled_payments, fetch_wallets_for_update,
+    find_account_by_id, find_child_accounts, save_monetary_tx, set_monetary_txs_block,
+};
+use crate::{
+    audit_change, change_transaction_status, convert_amount, create_chained_block, debit_wallet,
+    rollback_db_transaction, start_db_transaction,
+};
+use cassandra_cpp::Session;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::info;
+
+/// Closes an account for good. The balance of every wallet is swept to the main wallet of
+/// `destination_account_id`, converted to its currency when needed, and the ledger entries of the
+/// sweep go into one block. Scheduled payments from or to the account are cancelled. Everything
+/// happens in one DB transaction. Returns the closed account and the sweep transactions.
+pub async fn close_account(
+    pool: &PgPool,
+    account_id: &str,
+    destination_account_id: &str,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    req_context: RequestContext,
+) -> Result<(Account, Vec<MonetaryTransaction>), OrchestrateError> {
+    let event = "closeAccount";
+    if account_id == destination_account_id {
+        return Err(OrchestrateError::InvalidArgument(
+            "an account can not be swept into itself".to_string(),
+        ));
+    }
+
+    ////// 0. Only owners can close their unlocked accounts
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let saved_acct = find_account_for_change(&mut db_tx, account_id, user_ctx, false).await?;
+    if saved_acct.locked {
+        return Err(OrchestrateError::IllegalState(
+            "account must be unlocked to be closed".to_string(),
+        ));
+    }
+    if !find_child_accounts(&mut *db_tx, account_id, false)
+        .await?
+        .is_empty()
+    {
+        return Err(OrchestrateError::IllegalState(
+            "child accounts must be closed first".to_string(),
+        ));
+    }
+    let mut closed_acct = saved_acct.clone();
+    closed_acct
+        .transition_status(
+            AccountStatus::Closed,
+            AccountStatusReason::CustomerRequest,
+            Some(user_ctx.user_fp.clone()),
+            None,
+        )
+        .map_err(map_transition_err)?;
+
+    let destination_acct = match find_account_by_id(&mut *db_tx, destination_account_id).await? {
+        Some(account) if account.status != AccountStatus::Closed => account,
+        _ => {
+            return Err(OrchestrateError::NotFoundError(
+                "destination account not found".to_string(),
+            ));
+        }
+    };
+    // the sweep is not customer-initiated, it can't reactivate an inactive destination
+    if !destination_acct.accepts_transactions(false) {
+        return Err(OrchestrateError::InvalidRecordState(
+            "the destination account is locked/frozen/inactive".to_string(),
+        ));
+    }
+
+    ////// 1. Sweep the balances and stop the scheduled payments
+    let sweep_txs = match sweep_account(
+        &saved_acct,
+        &destination_acct,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        &mut db_tx,
+    )
+    .await
+    {
+        Ok(sweep_txs) => sweep_txs,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err);
+        }
+    };
+
+    ////// 2. Close the account, committed with the audit log
+    save_account_change(
+        event,
+        db_tx,
+        saved_acct,
+        closed_acct.clone(),
+        user_ctx,
+        req_context,
+    )
+    .await?;
+    info!(
+        "closed account {}, swept into {} with {} transactions",
+        closed_acct.id,
+        destination_acct.id,
+        sweep_txs.len()
+    );
+    Ok((closed_acct, sweep_txs))
+}
+
+async fn sweep_account(
+    account: &Account,
+    destination: &Account,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<MonetaryTransaction>, OrchestrateError> {
+    let wallets = fetch_wallets_for_update(db_tx, &account.id).await?;
+    if wallets.iter().any(|wallet| wallet.balance < Decimal::ZERO) {
+        return Err(OrchestrateError::IllegalState(
+            "negative balances must be settled before closing the account".to_string(),
+        ));
+    }
+
+    let mut sweep_txs = Vec::new();
+    let mut ledgers = Vec::new();
+    for wallet in wallets
+        .iter()
+        .filter(|wallet| wallet.balance > Decimal::ZERO)
+    {
+        ////// 1.1 Empty the wallet, whatever its currency
+        let debit_tx = MonetaryTransaction::build(
+            wallet.balance,
+            account.id.clone(),
+            TransactionType::Transfer,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+        if !save_monetary_tx(&mut **db_tx, &debit_tx).await? {
+            return Err(OrchestrateError::ServerError(
+                "could not save sweep transaction".to_string(),
+            ));
+        }
+        audit_change(
+            db_tx,
+            EntityType::Transaction,
+            &debit_tx.id,
+            None,
+            Some(&debit_tx),
+            &user_ctx.user_fp,
+            None,
+        )
+        .await?;
+        if !debit_wallet(
+            db_tx,
+            wallet.balance,
+            &account.id,
+            wallet.currency.clone(),
+            &user_ctx.user_fp,
+        )
+        .await?
+        {
+            return Err(OrchestrateError::ServerError(
+                "could not update wallet balance".to_string(),
+            ));
+        }
+
+        ////// 1.2 Credit the destination in its own currency, without commission
+        let converted = convert_amount(
+            &mut **db_tx,
+            wallet.balance,
+            wallet.currency.clone(),
+            destination.currency.clone(),
+            &mut app_cxt.redis_conn.clone(),
+        )
+        .await?;
+        let credit_tx = MonetaryTransaction::build(
+            converted,
+            destination.id.clone(),
+            TransactionType::Transfer,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+        apply_wallet_mutation(
+            &credit_tx,
+            Decimal::ZERO,
+            EntryType::Credit,
+            &user_ctx.user_fp,
+            app_cxt,
+            db_tx,
+        )
+        .await?;
+
+        ledgers.push(LedgerEntry::new(
+            account.id.clone(),
+            Some(format!("closing sweep of the {} wallet", wallet.currency)),
+            EntryType::Debit,
+            Some(debit_tx.id.clone()),
+        ));
+        ledgers.push(LedgerEntry::new(
+            destination.id.clone(),
+            Some(format!("closing sweep from account {}", account.id)),
+            EntryType::Credit,
+            Some(credit_tx.id.clone()),
+        ));
+        sweep_txs.push(debit_tx);
+        sweep_txs.push(credit_tx);
+    }
+
+    if !sweep_txs.is_empty() {
+        ////// 1.3 Post the final ledger entries in one block
+        let entry_ids = ledgers
+            .iter()
+            .map(|ledger| ledger.id.clone())
+            .collect::<Vec<_>>();
+        let ledgers_saved = bulk_save_ledger(&mut **db_tx, ledgers).await? as usize;
+        if ledgers_saved != entry_ids.len() {
+            return Err(OrchestrateError::InvalidRecordState(
+                "ledgers count is not equal".to_string(),
+            ));
+        }
+        let block =
+            create_chained_block(entry_ids, user_ctx, cassandra_session, app_cxt, db_tx).await?;
+        let transaction_ids = sweep_txs
+            .iter()
+            .map(|transaction| transaction.id.clone())
+            .collect::<Vec<_>>();
+        let linked = set_monetary_txs_block(&mut **db_tx, &transaction_ids, &block.id).await?;
+        if linked as usize != transaction_ids.len() {
+            return Err(OrchestrateError::ServerError(
+                "could not link transactions to block".to_string(),
+            ));
+        }
+        for transaction in sweep_txs.iter_mut() {
+            transaction.block_id = Some(block.id.clone());
+            change_transaction_status(
+                db_tx,
+                transaction,
+                TransactionStatus::Completed,
+                &user_ctx.user_fp,
+            )
+            .await?;
+        }
+    }
+
+    let cancelled =
+        cancel_account_scheduled_payments(&mut **db_tx, &account.id, Utc::now()).await?;
+    if cancelled > 0 {
+        info!(
+            "cancelled {} scheduled payments of closed account {}",
+            cancelled, account.id
+        );
+    }
+    Ok(sweep_txs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_user_wallet_for_acct;
+    use crate::orchestrator::testing::{test_request_context, TestEnv};
+    use crate::orchestrator::transaction::perform_wallet_transaction;
+
+    async fn apply_transaction(
+        env: &TestEnv,
+        account_id: &str,
+        amount: i64,
+        tx_entry_type: EntryType,
+        user_ctx: &UserContext,
+    ) -> Result<MonetaryTransaction, OrchestrateError> {
+        perform_wallet_transaction(
+            "test",
+            &env.pool,
+            Decimal::from(amount),
+            account_id.to_string(),
+            user_ctx,
+            TransactionType::Transfer,
+            tx_entry_type,
+            &env.cassandra_session,
+            &env.app_cxt,
+            vec!["test transaction".to_string()],
+        )
+        .await
+    }
+
+    async fn balance_of(env: &TestEnv, account_id: &str) -> Decimal {
+        find_user_wallet_for_acct(&env.pool, account_id, "USD")
+            .await
+            .expect("Failed to find wallet")
+            .expect("wallet not found")
+            .balance
+    }
+
+    async fn set_locked(env: &TestEnv, account_id: &str, locked: bool) {
+        sqlx::query("UPDATE user_account SET locked = $2 WHERE id = $1")
+            .bind(account_id)
+            .bind(locked)
+            .execute(&env.pool)
+            .await
+            .expect("Failed to lock account");
+    }
+
+    #[tokio::test]
+    pub async fn test_closing_sweeps_into_a_destination_accepting_transactions() {
+        let env = TestEnv::start().await;
+        let user_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&user_ctx, "Normal", "USD").await;
+        let destination = env.open_account(&user_ctx, "Normal", "USD").await;
+        apply_transaction(&env, &account.id, 100, EntryType::Credit, &user_ctx)
+            .await
+            .expect("Failed to credit account");
+
+        // a locked destination is rejected before anything moves
+        set_locked(&env, &destination.id, true).await;
+        let result = close_account(
+            &env.pool,
+            &account.id,
+            &destination.id,
+            &user_ctx,
+            &env.cassandra_session,
+            &env.app_cxt,
+            test_request_context(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(OrchestrateError::InvalidRecordState(_))
+        ));
+        assert_eq!(balance_of(&env, &account.id).await, Decimal::from(100));
+
+        set_locked(&env, &destination.id, false).await;
+        let (closed_acct, sweep_txs) = close_account(
+            &env.pool,
+            &account.id,
+            &destination.id,
+            &user_ctx,
+            &env.cassandra_session,
+            &env.app_cxt,
+            test_request_context(),
+        )
+        .await
+        .expect("Failed to close account");
+        assert_eq!(closed_acct.status, AccountStatus::Closed);
+        assert!(!sweep_txs.is_empty());
+        assert_eq!(balance_of(&env, &account.id).await, Decimal::ZERO);
+        assert_eq!(balance_of(&env, &destination.id).await, Decimal::from(100));
+
+        // the closed account takes no more transactions
+        for tx_entry_type in [EntryType::Credit, EntryType::Debit] {
+            let result = apply_transaction(&env, &account.id, 10, tx_entry_type, &user_ctx).await;
+            assert!(matches!(
+                result,
+                Err(OrchestrateError::InvalidRecordState(_))
+            ));
+        }
+    }
+}
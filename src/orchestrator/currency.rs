@@ -1,24 +1,26 @@
 use crate::context::ApplicationContext;
-use crate::core::{get_currency_hash, Currency, CurrencyRate};
+use crate::core::{get_currency_hash, Currency, CurrencyRate, EntityType};
 use crate::error::OrchestrateError;
 use crate::storage::{
     fetch_currency_rate, get_exchange_rate, save_currency_rate_record, save_exchange_rate,
 };
+use crate::{
+    audit_change, commit_db_transaction, rollback_db_transaction, start_db_transaction,
+    SYSTEM_USER_FP,
+};
 use redis::aio::ConnectionManager;
 use rust_decimal::Decimal;
-use sqlx::{Executor, Postgres};
+use sqlx::{Executor, PgPool, Postgres};
 use tracing::warn;
 
-pub async fn save_currencies_rate<'a, E>(
-    pg_pool: E,
+pub async fn save_currencies_rate(
+    pg_pool: &PgPool,
     rate: Decimal,
     base_currency: Currency,
     quote_currency: Currency,
     app_cxt: ApplicationContext,
-) -> Result<(), OrchestrateError>
-where
-    E: Executor<'a, Database = Postgres>,
-{
+) -> Result<(), OrchestrateError> {
+    let event = "saveCurrenciesRate";
     if base_currency == quote_currency {
         return Err(OrchestrateError::InvalidArgument(
             "same currencies".to_string(),
@@ -47,7 +49,24 @@ where
         }
         Some(currencies_rate) => {
             ////// Save rate to DB first
-            save_currency_rate_record(pg_pool, &currencies_rate).await?;
+            let mut db_tx = start_db_transaction(pg_pool, event).await?;
+            if save_currency_rate_record(&mut *db_tx, &currencies_rate).await? {
+                if let Err(err) = audit_change(
+                    &mut db_tx,
+                    EntityType::CurrencyRate,
+                    &currencies_rate.hash,
+                    None,
+                    Some(&currencies_rate),
+                    SYSTEM_USER_FP,
+                    None,
+                )
+                .await
+                {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(err);
+                }
+            }
+            commit_db_transaction(db_tx, event).await?;
             ///// return fetched currency
             currencies_rate
         }
@@ -0,0 +1,79 @@
+use crate::context::ApplicationContext;
+use crate::core::{AccountStatus, AccountStatusEvent, AccountStatusReason, AccountType};
+use crate::error::OrchestrateError;
+use crate::orchestrator::account::apply_system_status_change;
+use crate::storage::{lock_dormant_accounts, publish_account_status_event};
+use crate::{commit_db_transaction, rollback_db_transaction, start_db_transaction};
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+/// Marks accounts whose owner had no activity within the term of their account type `Inactive`.
+/// Each account type is handled in its own DB transaction, with up to `batch_size` accounts per
+/// type. A notification is published for every account once its change is committed. Returns the
+/// number of accounts marked inactive.
+pub async fn mark_dormant_accounts(
+    pool: &PgPool,
+    terms: &[(AccountType, u32)],
+    batch_size: i64,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let event = "markDormantAccounts";
+    let mut marked = 0;
+
+    for (acct_type, days) in terms {
+        let inactive_since = Utc::now() - Duration::days(*days as i64);
+
+        ////// 1. Claim the dormant accounts of this type
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        let dormant_accts =
+            match lock_dormant_accounts(&mut *db_tx, acct_type.clone(), inactive_since, batch_size)
+                .await
+            {
+                Ok(accounts) => accounts,
+                Err(err) => {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(err.into());
+                }
+            };
+
+        ////// 2. Mark each of them inactive
+        let mut updated_accts = Vec::with_capacity(dormant_accts.len());
+        for account in dormant_accts {
+            match apply_system_status_change(
+                account,
+                AccountStatus::Inactive,
+                AccountStatusReason::Dormancy,
+                &mut db_tx,
+            )
+            .await
+            {
+                Ok(updated) => updated_accts.push(updated),
+                Err(err) => {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(err);
+                }
+            }
+        }
+        commit_db_transaction(db_tx, event).await?;
+
+        ////// 3. Let other services know
+        for account in &updated_accts {
+            info!(
+                "account {} marked inactive after {} days without activity",
+                account.id, days
+            );
+            let status_event = AccountStatusEvent::from(account);
+            if let Err(err) =
+                publish_account_status_event(&status_event, &mut app_cxt.redis_conn.clone()).await
+            {
+                warn!(
+                    "event={} :: no notification sent for account {}: {}",
+                    event, account.id, err
+                );
+            }
+        }
+        marked += updated_accts.len();
+    }
+    Ok(marked)
+}
@@ -0,0 +1,40 @@
+use crate::context::ApplicationContext;
+use crate::convert_amount;
+use crate::core::{Account, Currency, MonetaryTransaction, TransactionStatus, TransactionType};
+use crate::error::OrchestrateError;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+/// The legs of a fee of `amount` charged to `account_id`: a `Commission` debit of the account,
+/// followed by the `Commission` credit of the fee account converted to its currency. Both are
+/// applied with `apply_transfer_legs`.
+pub(crate) async fn build_fee_transactions(
+    pool: &PgPool,
+    account_id: &str,
+    amount: Decimal,
+    currency: Currency,
+    fee_account: &Account,
+    app_cxt: &ApplicationContext,
+) -> Result<Vec<MonetaryTransaction>, OrchestrateError> {
+    let converted = convert_amount(
+        pool,
+        amount,
+        currency,
+        fee_account.currency.clone(),
+        &mut app_cxt.redis_conn.clone(),
+    )
+    .await?;
+    let build_tx = |amount: Decimal, account_id: &str| {
+        MonetaryTransaction::build(
+            amount,
+            account_id.to_string(),
+            TransactionType::Commission,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))
+    };
+    Ok(vec![
+        build_tx(amount, account_id)?,
+        build_tx(converted, &fee_account.id)?,
+    ])
+}
@@ -0,0 +1,66 @@
+use crate::context::ApplicationContext;
+use crate::core::{AccountStatus, AccountStatusEvent, AccountStatusReason};
+use crate::error::OrchestrateError;
+use crate::orchestrator::account::apply_system_status_change;
+use crate::storage::{lock_expired_frozen_accounts, publish_account_status_event};
+use crate::{commit_db_transaction, rollback_db_transaction, start_db_transaction};
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+/// Lifts the freezes whose expiry passed, moving up to `batch_size` accounts back to `Active` in
+/// one DB transaction. A notification is published for every account once its change is
+/// committed. Returns the number of accounts unfrozen.
+pub async fn lift_expired_freezes(
+    pool: &PgPool,
+    batch_size: i64,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let event = "liftExpiredFreezes";
+
+    ////// 1. Claim the accounts whose freeze expired
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let expired_accts =
+        match lock_expired_frozen_accounts(&mut *db_tx, Utc::now(), batch_size).await {
+            Ok(accounts) => accounts,
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err.into());
+            }
+        };
+
+    ////// 2. Move each of them back to active
+    let mut updated_accts = Vec::with_capacity(expired_accts.len());
+    for account in expired_accts {
+        match apply_system_status_change(
+            account,
+            AccountStatus::Active,
+            AccountStatusReason::FreezeExpired,
+            &mut db_tx,
+        )
+        .await
+        {
+            Ok(updated) => updated_accts.push(updated),
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        }
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    ////// 3. Let other services know
+    for account in &updated_accts {
+        info!("account {} unfrozen, its freeze expired", account.id);
+        let status_event = AccountStatusEvent::from(account);
+        if let Err(err) =
+            publish_account_status_event(&status_event, &mut app_cxt.redis_conn.clone()).await
+        {
+            warn!(
+                "event={} :: no notification sent for account {}: {}",
+                event, account.id, err
+            );
+        }
+    }
+    Ok(updated_accts.len())
+}
@@ -0,0 +1,47 @@
+use crate::context::ApplicationContext;
+use crate::core::DependencyHealth;
+use crate::storage::{ping_cassandra, ping_postgres, ping_redis};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Probes Postgres, Cassandra and Redis at once, a probe not answering within `timeout` failed.
+pub async fn probe_dependencies(
+    pool: &PgPool,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    timeout: Duration,
+) -> DependencyHealth {
+    let mut redis_conn = app_cxt.redis_conn.clone();
+    let (postgres, cassandra, redis) = tokio::join!(
+        probe("postgres", timeout, ping_postgres(pool)),
+        probe("cassandra", timeout, ping_cassandra(cassandra_session)),
+        probe("redis", timeout, ping_redis(&mut redis_conn)),
+    );
+    DependencyHealth {
+        postgres,
+        cassandra,
+        redis,
+    }
+}
+
+async fn probe<E: Display>(
+    dependency: &str,
+    timeout: Duration,
+    ping: impl Future<Output = Result<(), E>>,
+) -> bool {
+    match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(())) => true,
+        Ok(Err(err)) => {
+            warn!("{} health probe failed: {}", dependency, err);
+            false
+        }
+        Err(_) => {
+            warn!("{} health probe timed out after {:?}", dependency, timeout);
+            false
+        }
+    }
+}
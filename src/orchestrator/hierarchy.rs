@@ -0,0 +1,225 @@
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    Account, AccountRole, AccountStatus, Currency, EntryType, MonetaryTransaction,
+    TransactionStatus, TransactionType, WalletHolding,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::joint::{find_member_account, reject_unapproved_debit};
+use crate::orchestrator::limits::{check_transaction_limits, record_limit_usage};
+use crate::orchestrator::transaction::{
+    apply_transfer_legs, find_owned_account, record_unsuccessful_transaction,
+};
+use crate::storage::{
+    fetch_account_tree_wallets, fetch_user_wallets, find_account_ancestors, find_child_accounts,
+};
+use crate::{
+    commit_db_transaction, convert_amount, rollback_db_transaction, start_db_transaction,
+    MAX_ACCOUNT_DEPTH,
+};
+use cassandra_cpp::Session;
+use rust_decimal::Decimal;
+use sqlx::{Executor, PgPool, Postgres};
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// Checks that `parent_account_id` can take one more child for the caller: it must be one of the
+/// caller's open accounts and the child must not be deeper than [MAX_ACCOUNT_DEPTH].
+pub async fn find_parent_for_child<'a, E>(
+    pool: E,
+    parent_account_id: &str,
+    user_ctx: &UserContext,
+) -> Result<Account, OrchestrateError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let lineage = find_account_ancestors(pool, parent_account_id).await?;
+    let parent = match lineage.first() {
+        Some(parent) if parent.user_fp == user_ctx.user_fp => parent.clone(),
+        _ => {
+            return Err(OrchestrateError::NotFoundError(
+                "parent account not found".to_string(),
+            ));
+        }
+    };
+    if parent.status == AccountStatus::Closed {
+        return Err(OrchestrateError::IllegalState(
+            "parent account is closed".to_string(),
+        ));
+    }
+    if lineage.len() >= MAX_ACCOUNT_DEPTH {
+        return Err(OrchestrateError::InvalidArgument(format!(
+            "account hierarchies can not be deeper than {} levels",
+            MAX_ACCOUNT_DEPTH
+        )));
+    }
+    Ok(parent)
+}
+
+/// The direct children of one of the caller's accounts, with their wallets when asked for.
+pub async fn list_child_accounts(
+    pool: &PgPool,
+    parent_account_id: &str,
+    include_closed: bool,
+    include_wallets: bool,
+    user_ctx: &UserContext,
+) -> Result<Vec<(Account, Vec<WalletHolding>)>, OrchestrateError> {
+    find_member_account(pool, parent_account_id, user_ctx, AccountRole::Viewer).await?;
+    let children = find_child_accounts(pool, parent_account_id, include_closed).await?;
+    if !include_wallets {
+        return Ok(children.into_iter().map(|child| (child, vec![])).collect());
+    }
+
+    let child_ids = children
+        .iter()
+        .map(|child| child.id.clone())
+        .collect::<Vec<_>>();
+    let mut child_wallets: HashMap<String, Vec<WalletHolding>> = HashMap::new();
+    for wallet in fetch_user_wallets(pool, &child_ids).await? {
+        child_wallets
+            .entry(wallet.account_id.clone())
+            .or_default()
+            .push(wallet);
+    }
+    Ok(children
+        .into_iter()
+        .map(|child| {
+            let wallets = child_wallets.remove(&child.id).unwrap_or_default();
+            (child, wallets)
+        })
+        .collect())
+}
+
+/// Adds up the wallets of an account and of all its open descendants, converted to `currency`
+/// or to the currency of the account when none is given. Returns the currency of the total, the
+/// total and the wallets it adds up.
+pub async fn get_consolidated_balance(
+    pool: &PgPool,
+    account_id: &str,
+    currency: Option<String>,
+    user_ctx: &UserContext,
+    app_cxt: &ApplicationContext,
+) -> Result<(Currency, Decimal, Vec<WalletHolding>), OrchestrateError> {
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+    let currency = match currency {
+        Some(currency) => Currency::from_str(&currency)
+            .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?,
+        None => account.currency,
+    };
+
+    let wallets = fetch_account_tree_wallets(pool, account_id).await?;
+    let mut redis_conn = app_cxt.redis_conn.clone();
+    let mut total = Decimal::ZERO;
+    for wallet in wallets.iter().filter(|wallet| !wallet.balance.is_zero()) {
+        total += convert_amount(
+            pool,
+            wallet.balance,
+            wallet.currency.clone(),
+            currency.clone(),
+            &mut redis_conn,
+        )
+        .await?;
+    }
+    Ok((currency, total, wallets))
+}
+
+/// Moves `amount` between two accounts of the same hierarchy, converted to the currency of the
+/// destination. Internal transfers are free of commission but count towards the limits of the
+/// source. Returns the debit and the credit transactions.
+pub async fn transfer_within_hierarchy(
+    pool: &PgPool,
+    source_account_id: &str,
+    destination_account_id: &str,
+    amount: String,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<Vec<MonetaryTransaction>, OrchestrateError> {
+    let event = "internalTransfer";
+    let amount = Decimal::from_str(&amount)
+        .map_err(|_| OrchestrateError::InvalidArgument("cannot parse amount".to_string()))?;
+    if source_account_id == destination_account_id {
+        return Err(OrchestrateError::InvalidArgument(
+            "cannot transfer to the same account".to_string(),
+        ));
+    }
+
+    ////// 0. Both accounts belong to the caller and share the same root
+    let source = find_owned_account(pool, source_account_id, user_ctx).await?;
+    let destination = find_owned_account(pool, destination_account_id, user_ctx).await?;
+    if find_root_id(pool, &source).await? != find_root_id(pool, &destination).await? {
+        return Err(OrchestrateError::InvalidArgument(
+            "internal transfers are only allowed within an account hierarchy".to_string(),
+        ));
+    }
+    reject_unapproved_debit(pool, &source.id, amount).await?;
+    check_transaction_limits(pool, &source.id, amount, &EntryType::Debit, app_cxt).await?;
+
+    let build_tx = |amount: Decimal, account_id: &str| {
+        MonetaryTransaction::build(
+            amount,
+            account_id.to_string(),
+            TransactionType::Transfer,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))
+    };
+    let converted = convert_amount(
+        pool,
+        amount,
+        source.currency.clone(),
+        destination.currency.clone(),
+        &mut app_cxt.redis_conn.clone(),
+    )
+    .await?;
+    let mut transactions = vec![
+        build_tx(amount, &source.id)?,
+        build_tx(converted, &destination.id)?,
+    ];
+
+    ////// 1. Debit the source and credit the destination in one DB transaction
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    match apply_transfer_legs(
+        &mut transactions,
+        Decimal::ZERO,
+        "internal transfer within account hierarchy",
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        &mut db_tx,
+    )
+    .await
+    {
+        Ok(()) => {
+            commit_db_transaction(db_tx, event).await?;
+            info!(
+                "transferred {} from account {} to account {}",
+                amount, source.id, destination.id
+            );
+            record_limit_usage(&source.id, amount, &EntryType::Debit, app_cxt).await;
+            Ok(transactions)
+        }
+        Err(err) => {
+            error!(
+                "event={} :: failed to transfer from account {} to account {}: {}",
+                event, source.id, destination.id, err
+            );
+            rollback_db_transaction(db_tx, event).await?;
+            for transaction in transactions {
+                record_unsuccessful_transaction(pool, transaction, &err).await;
+            }
+            Err(err)
+        }
+    }
+}
+
+async fn find_root_id(pool: &PgPool, account: &Account) -> Result<String, OrchestrateError> {
+    if account.parent_account_id.is_none() {
+        return Ok(account.id.clone());
+    }
+    let lineage = find_account_ancestors(pool, &account.id).await?;
+    Ok(lineage
+        .last()
+        .map(|root| root.id.clone())
+        .unwrap_or_else(|| account.id.clone()))
+}
@@ -0,0 +1,335 @@
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    Account, AccountRole, AccruedInterest, EntityType, InterestAccrual, MonetaryTransaction,
+    TransactionStatus, TransactionType,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::joint::find_member_account;
+use crate::orchestrator::transaction::{apply_transfer_legs, record_unsuccessful_transaction};
+use crate::storage::{
+    capitalize_interest_accruals, fetch_wallets, find_account_by_id,
+    find_capitalization_candidates, find_interest_candidates, find_unpaid_interest,
+    lock_unpaid_interest_accruals, save_interest_accrual, CapitalizationCandidate,
+};
+use crate::{
+    audit_change, commit_db_transaction, convert_amount, rollback_db_transaction,
+    start_db_transaction, SYSTEM_USER_FP,
+};
+use cassandra_cpp::Session;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info, warn};
+
+/// Accrues one day of interest on up to `batch_size` accounts of each interest product, the day
+/// before the current one in the account timezone or an older day the job missed. Days without
+/// interest are recorded too, so each account is accrued once per day. Returns how many days were
+/// accrued.
+pub async fn accrue_interest(
+    pool: &PgPool,
+    batch_size: i64,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let now = Utc::now();
+    let mut accrued = 0;
+
+    for product in &app_cxt.interest.products {
+        let candidates = find_interest_candidates(
+            pool,
+            product.account_type.clone(),
+            product.currency.clone(),
+            now,
+            batch_size,
+        )
+        .await?;
+        for candidate in candidates {
+            let accrual = InterestAccrual::new(
+                candidate.account_id,
+                candidate.accrual_date,
+                candidate.balance,
+                product,
+            );
+            if record_interest_accrual(pool, &accrual).await? {
+                accrued += 1;
+            }
+        }
+    }
+    Ok(accrued)
+}
+
+/// Saves and audits the accrual of a day. Returns false when another worker accrued the same day
+/// first.
+async fn record_interest_accrual(
+    pool: &PgPool,
+    accrual: &InterestAccrual,
+) -> Result<bool, OrchestrateError> {
+    let event = "recordInterestAccrual";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !save_interest_accrual(&mut *db_tx, accrual).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Ok(false);
+    }
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::InterestAccrual,
+        &accrual.entity_id(),
+        None,
+        Some(accrual),
+        SYSTEM_USER_FP,
+        None,
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    Ok(true)
+}
+
+/// Pays the interest accrued before the current month of each account, up to `batch_size`
+/// accounts, as an `Interest` debit of the interest-expense account and an `Interest` credit of the
+/// account. Returns how many accounts were capitalized; an account failing is left for the next
+/// run.
+pub async fn capitalize_interest(
+    pool: &PgPool,
+    batch_size: i64,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let event = "capitalizeInterest";
+    let expense_account_id = match &app_cxt.interest.expense_account_id {
+        Some(expense_account_id) => expense_account_id,
+        None => return Ok(0),
+    };
+    let expense_account = match find_account_by_id(pool, expense_account_id).await? {
+        Some(account) => account,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "interest expense account not found".to_string(),
+            ));
+        }
+    };
+
+    let candidates = find_capitalization_candidates(pool, Utc::now(), batch_size).await?;
+    let mut capitalized = 0;
+    for candidate in &candidates {
+        match capitalize_account_interest(
+            pool,
+            candidate,
+            &expense_account,
+            cassandra_session,
+            app_cxt,
+        )
+        .await
+        {
+            Ok(()) => capitalized += 1,
+            Err(err) => {
+                warn!(
+                    "event={} :: interest of account {} not capitalized: {}",
+                    event, candidate.account_id, err
+                );
+            }
+        }
+    }
+    Ok(capitalized)
+}
+
+async fn capitalize_account_interest(
+    pool: &PgPool,
+    candidate: &CapitalizationCandidate,
+    expense_account: &Account,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<(), OrchestrateError> {
+    let event = "capitalizeAccountInterest";
+    let now = Utc::now();
+
+    ////// 1. Claim the unpaid accruals, another worker may hold them already
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let accruals = match lock_unpaid_interest_accruals(
+        &mut *db_tx,
+        &candidate.account_id,
+        candidate.accrued_until,
+    )
+    .await
+    {
+        Ok(accruals) if !accruals.is_empty() => accruals,
+        Ok(_) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Ok(());
+        }
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err.into());
+        }
+    };
+    // wallets hold 4 decimals, what is accrued below that is not paid
+    let amount = accruals
+        .iter()
+        .map(|accrual| accrual.amount)
+        .sum::<Decimal>()
+        .round_dp(4);
+
+    ////// 2. Pay the interest, nothing is posted when it rounds to zero
+    let mut transactions = Vec::new();
+    if !amount.is_zero() {
+        match build_interest_transactions(
+            pool,
+            amount,
+            candidate,
+            &accruals,
+            expense_account,
+            app_cxt,
+        )
+        .await
+        {
+            Ok(built) => transactions = built,
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        }
+        let user_ctx = UserContext::load_user_context(
+            candidate.user_fp.clone(),
+            candidate.timezone.clone(),
+            Some(candidate.account_id.clone()),
+            None,
+        );
+        if let Err(err) = apply_transfer_legs(
+            &mut transactions,
+            Decimal::ZERO,
+            "interest capitalization",
+            &user_ctx,
+            cassandra_session,
+            app_cxt,
+            &mut db_tx,
+        )
+        .await
+        {
+            error!(
+                "event={} :: failed to pay the interest of account {}: {}",
+                event, candidate.account_id, err
+            );
+            rollback_db_transaction(db_tx, event).await?;
+            for transaction in transactions {
+                record_unsuccessful_transaction(pool, transaction, &err).await;
+            }
+            return Err(err);
+        }
+    }
+
+    ////// 3. Mark the accruals paid by the credit
+    let transaction_id = transactions.last().map(|credit| credit.id.clone());
+    if let Err(err) =
+        capitalize_accruals(&mut db_tx, candidate, &accruals, transaction_id, now).await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    info!(
+        "capitalized {} of interest on account {} for {} days",
+        amount,
+        candidate.account_id,
+        accruals.len()
+    );
+    Ok(())
+}
+
+/// Marks the claimed accruals paid by `transaction_id` and audits each of them.
+async fn capitalize_accruals(
+    db_tx: &mut Transaction<'_, Postgres>,
+    candidate: &CapitalizationCandidate,
+    accruals: &[InterestAccrual],
+    transaction_id: Option<String>,
+    capitalized_at: DateTime<Utc>,
+) -> Result<(), OrchestrateError> {
+    capitalize_interest_accruals(
+        &mut **db_tx,
+        &candidate.account_id,
+        candidate.accrued_until,
+        transaction_id.clone(),
+        capitalized_at,
+    )
+    .await?;
+    for accrual in accruals {
+        let mut capitalized = accrual.clone();
+        capitalized.transaction_id = transaction_id.clone();
+        capitalized.capitalized_at = Some(capitalized_at);
+        audit_change(
+            db_tx,
+            EntityType::InterestAccrual,
+            &accrual.entity_id(),
+            Some(accrual),
+            Some(&capitalized),
+            SYSTEM_USER_FP,
+            None,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// The debit of the expense account, converted to its currency, followed by the credit of the
+/// account.
+async fn build_interest_transactions(
+    pool: &PgPool,
+    amount: Decimal,
+    candidate: &CapitalizationCandidate,
+    accruals: &[InterestAccrual],
+    expense_account: &Account,
+    app_cxt: &ApplicationContext,
+) -> Result<Vec<MonetaryTransaction>, OrchestrateError> {
+    let currency = accruals[0].currency.clone();
+    let converted = convert_amount(
+        pool,
+        amount,
+        currency,
+        expense_account.currency.clone(),
+        &mut app_cxt.redis_conn.clone(),
+    )
+    .await?;
+    let build_tx = |amount: Decimal, account_id: &str| {
+        MonetaryTransaction::build(
+            amount,
+            account_id.to_string(),
+            TransactionType::Interest,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))
+    };
+    Ok(vec![
+        build_tx(converted, &expense_account.id)?,
+        build_tx(amount, &candidate.account_id)?,
+    ])
+}
+
+/// The interest accrued on an account and not capitalized yet, next to the balance of its
+/// account-currency wallet and the rate of its interest product, if any.
+pub async fn get_accrued_interest(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+    app_cxt: &ApplicationContext,
+) -> Result<AccruedInterest, OrchestrateError> {
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+    let wallet = fetch_wallets(pool, &account.id)
+        .await?
+        .into_iter()
+        .find(|wallet| wallet.currency == account.currency)
+        .ok_or_else(|| OrchestrateError::NotFoundError("wallet not found".to_string()))?;
+    let (amount, accrued_since, accrued_until) = find_unpaid_interest(pool, &account.id).await?;
+    let apr = app_cxt
+        .interest
+        .product_for(&account.account_type, &account.currency)
+        .map(|product| product.apr);
+
+    Ok(AccruedInterest {
+        wallet,
+        apr,
+        amount,
+        accrued_since,
+        accrued_until,
+    })
+}
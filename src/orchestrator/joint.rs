@@ -0,0 +1,666 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::{
+    Account, AccountMember, AccountRole, AccountStatus, AccountType, ApprovalRequest,
+    ApprovalStatus, EntityType, EntryType, MonetaryTransaction, SigningPolicy, TransactionStatus,
+    TransactionType,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::limits::check_transaction_limits;
+use crate::orchestrator::transaction::{
+    record_unsuccessful_transaction, settle_wallet_transaction, split_commission,
+};
+use crate::storage::{
+    close_approval_request, delete_account_member, find_account_by_id, find_account_member,
+    find_account_members, find_approval_request, find_monetary_tx_by_id, find_signing_policy,
+    find_transaction_approvers, lock_expired_approval_requests, save_account_member,
+    save_approval_request, save_monetary_tx, save_signing_policy, save_transaction_approval,
+};
+use crate::{
+    audit_change, change_transaction_status, commit_db_transaction, rollback_db_transaction,
+    start_db_transaction, DEFAULT_APPROVAL_WINDOW, SYSTEM_USER_FP,
+};
+use cassandra_cpp::Session;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Finds an account the caller has at least the `required` role on. The owner of an account has
+/// every role on it, members the role they were given.
+pub async fn find_member_account(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+    required: AccountRole,
+) -> Result<Account, OrchestrateError> {
+    let account = match find_account_by_id(pool, account_id).await? {
+        Some(account) => account,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "account not found".to_string(),
+            ));
+        }
+    };
+    if account.user_fp == user_ctx.user_fp {
+        return Ok(account);
+    }
+    match find_account_member(pool, account_id, &user_ctx.user_fp).await? {
+        Some(member) if member.role.grants(&required) => Ok(account),
+        Some(member) => Err(OrchestrateError::PermissionDenied(format!(
+            "the {} role does not allow this on the account",
+            member.role
+        ))),
+        None => Err(OrchestrateError::NotFoundError(
+            "account not found".to_string(),
+        )),
+    }
+}
+
+/// Shares one of the caller's regular accounts with another user, or changes the role of a
+/// member. Only owners manage members.
+pub async fn add_account_member(
+    pool: &PgPool,
+    account_id: &str,
+    member_fp: String,
+    role: String,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<AccountMember, OrchestrateError> {
+    let event = "addAccountMember";
+    let role = AccountRole::from_str(&role)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let member_fp = member_fp.trim().to_string();
+    if member_fp.is_empty() {
+        return Err(OrchestrateError::InvalidArgument(
+            "member fingerprint is required".to_string(),
+        ));
+    }
+
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Owner).await?;
+    if account.account_type != AccountType::Normal {
+        return Err(OrchestrateError::InvalidArgument(
+            "only regular accounts can be shared".to_string(),
+        ));
+    }
+    if account.status == AccountStatus::Closed {
+        return Err(OrchestrateError::IllegalState(
+            "account is closed".to_string(),
+        ));
+    }
+    if member_fp == account.user_fp {
+        return Err(OrchestrateError::InvalidArgument(
+            "the account owner is already a member".to_string(),
+        ));
+    }
+
+    let member = AccountMember::new(
+        account.id.clone(),
+        member_fp,
+        role,
+        user_ctx.user_fp.clone(),
+    );
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let saved_member = find_account_member(&mut *db_tx, &account.id, &member.user_fp).await?;
+    if let Some(saved_member) = &saved_member {
+        check_signers_after(&mut db_tx, &account.id, saved_member, Some(&member.role)).await?;
+    }
+    if !save_account_member(&mut *db_tx, &member).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not save account member".to_string(),
+        ));
+    }
+    audit_change(
+        &mut db_tx,
+        EntityType::AccountMember,
+        &member.entity_id(),
+        saved_member.as_ref(),
+        Some(&member),
+        &user_ctx.user_fp,
+        Some(&req_context),
+    )
+    .await?;
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "account {} shared with a {}",
+        account.id,
+        member.role.to_string().to_lowercase()
+    );
+    Ok(member)
+}
+
+/// Removes a member from an account. Owners remove anyone, other members only themselves.
+pub async fn remove_account_member(
+    pool: &PgPool,
+    account_id: &str,
+    member_fp: &str,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<(), OrchestrateError> {
+    let event = "removeAccountMember";
+    let required = if member_fp == user_ctx.user_fp {
+        AccountRole::Viewer
+    } else {
+        AccountRole::Owner
+    };
+    let account = find_member_account(pool, account_id, user_ctx, required).await?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let member = match find_account_member(&mut *db_tx, &account.id, member_fp).await? {
+        Some(member) => member,
+        None => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(OrchestrateError::NotFoundError(
+                "member not found".to_string(),
+            ));
+        }
+    };
+    check_signers_after(&mut db_tx, &account.id, &member, None).await?;
+    if !delete_account_member(&mut *db_tx, &account.id, member_fp).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not remove account member".to_string(),
+        ));
+    }
+    audit_change(
+        &mut db_tx,
+        EntityType::AccountMember,
+        &member.entity_id(),
+        Some(&member),
+        None,
+        &user_ctx.user_fp,
+        Some(&req_context),
+    )
+    .await?;
+    commit_db_transaction(db_tx, event).await?;
+
+    info!("removed a member of account {}", account.id);
+    Ok(())
+}
+
+/// The members of an account, its owner first, with the signing policy of the account if it has
+/// one. Every member can read them.
+pub async fn list_account_members(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+) -> Result<(Vec<AccountMember>, Option<SigningPolicy>), OrchestrateError> {
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+    let owner = AccountMember {
+        account_id: account.id.clone(),
+        user_fp: account.user_fp.clone(),
+        role: AccountRole::Owner,
+        added_by: account.user_fp.clone(),
+        creation_time: account.creation_time,
+        modification_time: account.modification_time,
+    };
+    let mut members = vec![owner];
+    members.extend(find_account_members(pool, &account.id).await?);
+    let policy = find_signing_policy(pool, &account.id).await?;
+    Ok((members, policy))
+}
+
+/// Sets how many distinct owners/spenders must approve the debits of more than `threshold`.
+/// Approvals are waited for `approval_window` seconds, a day when unset.
+pub async fn set_signing_policy(
+    pool: &PgPool,
+    account_id: &str,
+    threshold: String,
+    required_approvals: u32,
+    approval_window: Option<u32>,
+    user_ctx: &UserContext,
+    req_context: RequestContext,
+) -> Result<SigningPolicy, OrchestrateError> {
+    let event = "setSigningPolicy";
+    let threshold = Decimal::from_str(&threshold)
+        .map_err(|_| OrchestrateError::InvalidArgument("cannot parse threshold".to_string()))?;
+    let account = find_member_account(pool, account_id, user_ctx, AccountRole::Owner).await?;
+    if account.account_type != AccountType::Normal {
+        return Err(OrchestrateError::InvalidArgument(
+            "only regular accounts have a signing policy".to_string(),
+        ));
+    }
+    let policy = SigningPolicy::build(
+        account.id.clone(),
+        threshold,
+        required_approvals,
+        approval_window.unwrap_or(DEFAULT_APPROVAL_WINDOW),
+        user_ctx.user_fp.clone(),
+    )
+    .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let signers = count_signers(&mut db_tx, &account.id).await?;
+    if policy.required_approvals as usize > signers {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidArgument(format!(
+            "the account only has {} owners/spenders to approve debits",
+            signers
+        )));
+    }
+    let saved_policy = find_signing_policy(&mut *db_tx, &account.id).await?;
+    if !save_signing_policy(&mut *db_tx, &policy).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not save signing policy".to_string(),
+        ));
+    }
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::Account,
+        &account.id,
+        saved_policy.as_ref(),
+        Some(&policy),
+        &user_ctx.user_fp,
+        Some(&req_context),
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    info!("updated signing policy of account {}", account.id);
+    Ok(policy)
+}
+
+/// Rejects a debit that would need approvals. Only direct debits wait for approvals, the other
+/// ways of moving money out of an account must stay under the threshold.
+pub async fn reject_unapproved_debit(
+    pool: &PgPool,
+    account_id: &str,
+    amount: Decimal,
+) -> Result<(), OrchestrateError> {
+    match find_signing_policy(pool, account_id).await? {
+        Some(policy) if policy.requires_approvals(amount) => {
+            Err(OrchestrateError::PermissionDenied(format!(
+                "debits of more than {} from account {} need {} approvals",
+                policy.threshold, account_id, policy.required_approvals
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Records a debit as `Pending` until `policy` is satisfied. The requester approves it right away.
+pub async fn hold_for_approvals(
+    pool: &PgPool,
+    amount: Decimal,
+    account: &Account,
+    tx_type: TransactionType,
+    policy: &SigningPolicy,
+    user_ctx: &UserContext,
+    app_cxt: &ApplicationContext,
+) -> Result<MonetaryTransaction, OrchestrateError> {
+    let event = "holdForApprovals";
+    let (amount, commission) = split_commission(amount, app_cxt);
+    let wallet_tx = MonetaryTransaction::build(
+        amount,
+        account.id.clone(),
+        tx_type,
+        TransactionStatus::Pending,
+    )
+    .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    ////// 0. Reject what could not be applied before asking anyone to approve it
+    let precheck = if account.accepts_transactions(account.status == AccountStatus::Inactive) {
+        check_transaction_limits(pool, &account.id, amount, &EntryType::Debit, app_cxt).await
+    } else {
+        Err(OrchestrateError::InvalidRecordState(
+            "the user's account is locked/frozen/inactive/closed".to_string(),
+        ))
+    };
+    if let Err(err) = precheck {
+        warn!(
+            "event={} :: transaction {} rejected: {}",
+            event, wallet_tx.id, err
+        );
+        record_unsuccessful_transaction(pool, wallet_tx, &err).await;
+        return Err(err);
+    }
+
+    ////// 1. Record the pending debit with the approval of its requester
+    let request = ApprovalRequest::new(&wallet_tx, user_ctx.user_fp.clone(), commission, policy);
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let held = save_monetary_tx(&mut *db_tx, &wallet_tx).await?
+        && save_approval_request(&mut *db_tx, &request).await?
+        && save_transaction_approval(
+            &mut *db_tx,
+            &request.transaction_id,
+            &request.requested_by,
+            request.creation_time,
+        )
+        .await?;
+    if !held {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not hold transaction for approvals".to_string(),
+        ));
+    }
+    if let Err(err) = audit_held_transaction(&mut db_tx, &wallet_tx, &request).await {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "transaction {} on account {} waits for {} approvals until {}",
+        wallet_tx.id, account.id, request.required_approvals, request.expires_at
+    );
+    Ok(wallet_tx)
+}
+
+/// Approves a debit waiting for approvals, each owner/spender approves once. The approval
+/// completing the policy applies the debit on behalf of its requester. Returns the transaction,
+/// its approval request and who approved it so far.
+pub async fn approve_transaction(
+    pool: &PgPool,
+    transaction_id: &str,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<(MonetaryTransaction, ApprovalRequest, Vec<String>), OrchestrateError> {
+    let event = "approveTransaction";
+    let not_found = || OrchestrateError::NotFoundError("transaction not found".to_string());
+    let mut request = find_approval_request(pool, transaction_id)
+        .await?
+        .ok_or_else(not_found)?;
+    find_member_account(pool, &request.account_id, user_ctx, AccountRole::Spender)
+        .await
+        .map_err(|err| match err {
+            OrchestrateError::NotFoundError(_) => not_found(),
+            err => err,
+        })?;
+    let transaction = find_monetary_tx_by_id(pool, transaction_id)
+        .await?
+        .ok_or_else(not_found)?;
+
+    ////// 0. Only pending requests within their window take approvals
+    if request.status != ApprovalStatus::Pending {
+        return Err(OrchestrateError::IllegalState(format!(
+            "transaction is no longer waiting for approvals, its request is {}",
+            request.status
+        )));
+    }
+    let now = Utc::now();
+    if request.is_expired(now) {
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        match expire_approval_request(request, &mut db_tx).await {
+            Ok(_) => commit_db_transaction(db_tx, event).await?,
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        }
+        return Err(OrchestrateError::IllegalState(
+            "the transaction was not approved in time".to_string(),
+        ));
+    }
+
+    ////// 1. Record the approval
+    if !save_transaction_approval(pool, transaction_id, &user_ctx.user_fp, now).await? {
+        return Err(OrchestrateError::RecordAlreadyExists(
+            "transaction already approved by the user".to_string(),
+        ));
+    }
+    let approvers = find_transaction_approvers(pool, transaction_id).await?;
+    if approvers.len() < request.required_approvals as usize {
+        info!(
+            "transaction {} approved {} out of {} times",
+            transaction_id,
+            approvers.len(),
+            request.required_approvals
+        );
+        return Ok((transaction, request, approvers));
+    }
+
+    ////// 2. Close the request, only one of concurrent approvals applies the debit
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let closed = match close_pending_request(
+        &mut db_tx,
+        &mut request,
+        ApprovalStatus::Approved,
+        &user_ctx.user_fp,
+    )
+    .await
+    {
+        Ok(closed) => closed,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err);
+        }
+    };
+    commit_db_transaction(db_tx, event).await?;
+    if !closed {
+        let request = find_approval_request(pool, transaction_id)
+            .await?
+            .ok_or_else(not_found)?;
+        let transaction = find_monetary_tx_by_id(pool, transaction_id)
+            .await?
+            .ok_or_else(not_found)?;
+        return Ok((transaction, request, approvers));
+    }
+
+    ////// 3. Apply the debit
+    let requester_ctx = UserContext::load_user_context(
+        request.requested_by.clone(),
+        user_ctx.timezone.clone(),
+        Some(request.account_id.clone()),
+        None,
+    );
+    let transaction = settle_wallet_transaction(
+        event,
+        pool,
+        transaction,
+        request.commission,
+        EntryType::Debit,
+        vec!["approved debit of joint account".to_string()],
+        &requester_ctx,
+        cassandra_session,
+        app_cxt,
+    )
+    .await?;
+    Ok((transaction, request, approvers))
+}
+
+/// Expires up to `batch_size` requests whose window is over and rejects their transactions.
+/// Returns the number of expired requests.
+pub async fn expire_approval_requests(
+    pool: &PgPool,
+    batch_size: i64,
+) -> Result<usize, OrchestrateError> {
+    let event = "expireApprovalRequests";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let requests = match lock_expired_approval_requests(&mut *db_tx, Utc::now(), batch_size).await {
+        Ok(requests) => requests,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err.into());
+        }
+    };
+
+    let mut expired = Vec::with_capacity(requests.len());
+    for request in requests {
+        match expire_approval_request(request, &mut db_tx).await {
+            Ok(transaction_id) => expired.push(transaction_id),
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        }
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    for transaction_id in &expired {
+        info!(
+            "transaction {} rejected, it was not approved in time",
+            transaction_id
+        );
+    }
+    Ok(expired.len())
+}
+
+async fn expire_approval_request(
+    mut request: ApprovalRequest,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<String, OrchestrateError> {
+    if !close_pending_request(db_tx, &mut request, ApprovalStatus::Expired, SYSTEM_USER_FP).await? {
+        return Err(OrchestrateError::InvalidRecordState(format!(
+            "approval request of transaction {} is no longer pending",
+            request.transaction_id
+        )));
+    }
+    let mut transaction =
+        match find_monetary_tx_by_id(&mut **db_tx, &request.transaction_id).await? {
+            Some(transaction) => transaction,
+            None => {
+                return Err(OrchestrateError::InvalidRecordState(format!(
+                    "transaction {} of approval request not found",
+                    request.transaction_id
+                )));
+            }
+        };
+    change_transaction_status(
+        db_tx,
+        &mut transaction,
+        TransactionStatus::Rejected,
+        SYSTEM_USER_FP,
+    )
+    .await?;
+    Ok(transaction.id)
+}
+
+/// Closes a pending request with `status`. Returns false when it was closed already.
+async fn close_pending_request(
+    db_tx: &mut Transaction<'_, Postgres>,
+    request: &mut ApprovalRequest,
+    status: ApprovalStatus,
+    user_fp: &str,
+) -> Result<bool, OrchestrateError> {
+    let saved_request = request.clone();
+    request
+        .close(status)
+        .map_err(|err| OrchestrateError::IllegalState(err.to_string()))?;
+    if !close_approval_request(&mut **db_tx, request).await? {
+        return Ok(false);
+    }
+    audit_change(
+        db_tx,
+        EntityType::ApprovalRequest,
+        &request.transaction_id,
+        Some(&saved_request),
+        Some(&*request),
+        user_fp,
+        None,
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Audits a debit held for approvals and its approval request, both created by the requester.
+async fn audit_held_transaction(
+    db_tx: &mut Transaction<'_, Postgres>,
+    transaction: &MonetaryTransaction,
+    request: &ApprovalRequest,
+) -> Result<(), OrchestrateError> {
+    audit_change(
+        db_tx,
+        EntityType::Transaction,
+        &transaction.id,
+        None,
+        Some(transaction),
+        &request.requested_by,
+        None,
+    )
+    .await?;
+    audit_change(
+        db_tx,
+        EntityType::ApprovalRequest,
+        &request.transaction_id,
+        None,
+        Some(request),
+        &request.requested_by,
+        None,
+    )
+    .await
+}
+
+/// The owner and the members who can approve debits.
+async fn count_signers(
+    db_tx: &mut Transaction<'_, Postgres>,
+    account_id: &str,
+) -> Result<usize, OrchestrateError> {
+    let members = find_account_members(&mut **db_tx, account_id).await?;
+    Ok(1 + members
+        .iter()
+        .filter(|member| member.role.grants(&AccountRole::Spender))
+        .count())
+}
+
+/// Checks that the signing policy of the account can still be satisfied once `member` has
+/// `new_role`, or is removed when there is none.
+async fn check_signers_after(
+    db_tx: &mut Transaction<'_, Postgres>,
+    account_id: &str,
+    member: &AccountMember,
+    new_role: Option<&AccountRole>,
+) -> Result<(), OrchestrateError> {
+    let loses_signer = member.role.grants(&AccountRole::Spender)
+        && !new_role.is_some_and(|role| role.grants(&AccountRole::Spender));
+    if !loses_signer {
+        return Ok(());
+    }
+    if let Some(policy) = find_signing_policy(&mut **db_tx, account_id).await? {
+        if policy.required_approvals as usize >= count_signers(db_tx, account_id).await? {
+            return Err(OrchestrateError::IllegalState(format!(
+                "the signing policy of the account needs {} owners/spenders",
+                policy.required_approvals
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_account_wallet;
+    use crate::orchestrator::testing::{test_request_context, TestEnv};
+
+    #[tokio::test]
+    pub async fn test_only_members_read_the_wallets_of_a_joint_account() {
+        let env = TestEnv::start().await;
+        let owner_ctx = UserContext::load_test_ctx();
+        let viewer_ctx = UserContext::load_test_ctx();
+        let stranger_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&owner_ctx, "Normal", "USD").await;
+        add_account_member(
+            &env.pool,
+            &account.id,
+            viewer_ctx.user_fp.clone(),
+            "Viewer".to_string(),
+            &owner_ctx,
+            test_request_context(),
+        )
+        .await
+        .expect("Failed to add member");
+
+        for user_ctx in [&owner_ctx, &viewer_ctx] {
+            let wallet = get_account_wallet(&env.pool, &account.id, "USD", user_ctx, false)
+                .await
+                .expect("Failed to read wallet");
+            assert!(wallet.is_some());
+        }
+        let denied = get_account_wallet(&env.pool, &account.id, "USD", &stranger_ctx, false).await;
+        assert!(matches!(denied, Err(OrchestrateError::NotFoundError(_))));
+
+        // admins read any wallet
+        let wallet = get_account_wallet(&env.pool, &account.id, "USD", &stranger_ctx, true)
+            .await
+            .expect("Failed to read wallet");
+        assert!(wallet.is_some());
+    }
+}
@@ -12,7 +12,7 @@ pub async fn create_ledger<'a, E>(
 where
     E: Executor<'a, Database = Postgres>,
 {
-    let ledger = LedgerEntry::new(account_id.clone(), desc, entry);
+    let ledger = LedgerEntry::new(account_id.clone(), desc, entry, None);
 
     // store ledger entry into the database
     let ledger_entry_created = save_ledger(pool, &ledger).await?;
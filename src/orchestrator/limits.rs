@@ -0,0 +1,269 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::{
+    Account, AccountLimits, AccountLimitsReq, AccountRole, EntityType, EntryType, LimitUsage,
+    LimitWindow, TransactionLimits,
+};
+use crate::error::{OrchestrateError, PgDatabaseError};
+use crate::orchestrator::account::find_account_for_change;
+use crate::orchestrator::joint::find_member_account;
+use crate::storage::{
+    add_limit_usage, fetch_wallets_for_update, find_account_ancestors, find_account_by_id,
+    find_account_limit_usage, find_account_limits, find_accounts_limits, get_limit_usage,
+    save_account_limits, save_limit_usage,
+};
+use crate::{
+    audit_change, commit_db_transaction, rollback_db_transaction, start_db_transaction,
+    LIMIT_USAGE_KEY_PREFIX,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::{Executor, PgConnection, PgPool, Postgres, Transaction};
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// Checks a customer-initiated transaction against the limits of the account before anything is
+/// applied, so most breaches are rejected without opening a DB transaction. Usage is read from
+/// Redis; when it is missing or would breach a limit it is recounted from Postgres and Redis is
+/// refreshed with it. Concurrent transactions can all pass this check, debits are checked again
+/// by [`enforce_debit_limits`] within the DB transaction applying them.
+pub async fn check_transaction_limits(
+    pool: &PgPool,
+    account_id: &str,
+    amount: Decimal,
+    tx_entry_type: &EntryType,
+    app_cxt: &ApplicationContext,
+) -> Result<(), OrchestrateError> {
+    let account = match find_account_by_id(pool, account_id).await? {
+        Some(account) => account,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "account not found".to_string(),
+            ));
+        }
+    };
+    let mut db_conn = pool.acquire().await.map_err(PgDatabaseError::from)?;
+    let limits = effective_limits(&mut db_conn, &account, app_cxt).await?;
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+    limits
+        .check_amount(amount)
+        .map_err(|breach| OrchestrateError::AmountLimitExceeded(breach.to_string()))?;
+
+    let is_debit = *tx_entry_type == EntryType::Debit;
+    let now = Utc::now();
+    let keys = limit_usage_keys(account_id, now);
+    let mut redis_conn = app_cxt.redis_conn.clone();
+    let cached_usage = get_limit_usage(&keys, &mut redis_conn)
+        .await
+        .unwrap_or_else(|err| {
+            warn!(
+                "could not read limit usage of account {}: {}",
+                account_id, err
+            );
+            None
+        });
+    if let Some(usage) = cached_usage {
+        if limits.check_usage(amount, is_debit, &usage).is_ok() {
+            return Ok(());
+        }
+    }
+
+    let usage = count_limit_usage(pool, account_id, now).await?;
+    let ttls = LimitWindow::ALL.map(|window| (window.end(now) - now).num_seconds().max(1) as u64);
+    if let Err(err) = save_limit_usage(&keys, &ttls, &usage, &mut redis_conn).await {
+        warn!(
+            "could not save limit usage of account {}: {}",
+            account_id, err
+        );
+    }
+    limits
+        .check_usage(amount, is_debit, &usage)
+        .map_err(|breach| OrchestrateError::LimitExceeded(breach.to_string()))
+}
+
+/// Checks a customer-initiated debit against the limits of the account within the DB transaction
+/// applying it. The wallets of the account stay locked until `db_tx` ends and the usage is
+/// recounted from Postgres, so concurrent debits of an account are checked one after the other
+/// and each one counts the debits committed before it.
+pub async fn enforce_debit_limits(
+    db_tx: &mut Transaction<'_, Postgres>,
+    account: &Account,
+    amount: Decimal,
+    app_cxt: &ApplicationContext,
+) -> Result<(), OrchestrateError> {
+    fetch_wallets_for_update(db_tx, &account.id).await?;
+    let limits = effective_limits(db_tx, account, app_cxt).await?;
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+    limits
+        .check_amount(amount)
+        .map_err(|breach| OrchestrateError::AmountLimitExceeded(breach.to_string()))?;
+    let usage = count_limit_usage(&mut **db_tx, &account.id, Utc::now()).await?;
+    limits
+        .check_usage(amount, true, &usage)
+        .map_err(|breach| OrchestrateError::LimitExceeded(breach.to_string()))
+}
+
+/// Adds a committed transaction to the usage kept in Redis. Failures are only logged, the next
+/// check that would breach a limit recounts from Postgres anyway.
+pub async fn record_limit_usage(
+    account_id: &str,
+    amount: Decimal,
+    tx_entry_type: &EntryType,
+    app_cxt: &ApplicationContext,
+) {
+    let debit = if *tx_entry_type == EntryType::Debit {
+        amount
+    } else {
+        Decimal::ZERO
+    };
+    let keys = limit_usage_keys(account_id, Utc::now());
+    if let Err(err) = add_limit_usage(&keys, debit, &mut app_cxt.redis_conn.clone()).await {
+        warn!(
+            "could not record limit usage of account {}: {}",
+            account_id, err
+        );
+    }
+}
+
+/// Overrides the limits of an account, only admins can. Limits left unset fall back to the
+/// defaults of the account type. Returns the limits now applied to the account.
+pub async fn set_account_limits(
+    pool: &PgPool,
+    account_id: &str,
+    limits_req: AccountLimitsReq,
+    user_ctx: &UserContext,
+    is_admin: bool,
+    req_context: RequestContext,
+    app_cxt: &ApplicationContext,
+) -> Result<TransactionLimits, OrchestrateError> {
+    let event = "setAccountLimits";
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can change account limits".to_string(),
+        ));
+    }
+    let parse_limit = |limit: Option<String>| match limit {
+        Some(limit) => Decimal::from_str(&limit)
+            .map(Some)
+            .map_err(|_| OrchestrateError::InvalidArgument("cannot parse limit".to_string())),
+        None => Ok(None),
+    };
+    let limits = AccountLimits::build(
+        account_id.to_string(),
+        parse_limit(limits_req.max_amount)?,
+        parse_limit(limits_req.daily_debit)?,
+        parse_limit(limits_req.monthly_debit)?,
+        limits_req.hourly_count,
+        user_ctx.user_fp.clone(),
+    )
+    .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    find_account_for_change(&mut db_tx, account_id, user_ctx, is_admin).await?;
+    let saved_limits = find_account_limits(&mut *db_tx, account_id).await?;
+    if !save_account_limits(&mut *db_tx, &limits).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not save account limits".to_string(),
+        ));
+    }
+
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::AccountLimits,
+        account_id,
+        saved_limits.as_ref(),
+        Some(&limits),
+        &user_ctx.user_fp,
+        Some(&req_context),
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    info!("updated limits of account {}", account_id);
+    get_account_limits(pool, account_id, user_ctx, is_admin, app_cxt).await
+}
+
+/// The limits applied to an account, readable by its owner and admins.
+pub async fn get_account_limits(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+    is_admin: bool,
+    app_cxt: &ApplicationContext,
+) -> Result<TransactionLimits, OrchestrateError> {
+    let account = match find_account_by_id(pool, account_id).await? {
+        Some(account) if is_admin => account,
+        Some(_) => find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "account not found".to_string(),
+            ));
+        }
+    };
+    let mut db_conn = pool.acquire().await.map_err(PgDatabaseError::from)?;
+    effective_limits(&mut db_conn, &account, app_cxt).await
+}
+
+/// The limits of an account: the defaults of its root account's type, overridden by the limits set
+/// on each account from the root down to the account itself. Child accounts inherit the limits of
+/// their parent.
+async fn effective_limits(
+    db_conn: &mut PgConnection,
+    account: &Account,
+    app_cxt: &ApplicationContext,
+) -> Result<TransactionLimits, OrchestrateError> {
+    let lineage = match account.parent_account_id {
+        Some(_) => find_account_ancestors(&mut *db_conn, &account.id).await?,
+        None => vec![account.clone()],
+    };
+    let root = lineage.last().unwrap_or(account);
+    let lineage_ids = lineage
+        .iter()
+        .map(|account| account.id.clone())
+        .collect::<Vec<_>>();
+    let overrides = find_accounts_limits(db_conn, &lineage_ids).await?;
+
+    let defaults = app_cxt.transaction_limits.limits_for(&root.account_type);
+    Ok(lineage_ids
+        .iter()
+        .rev()
+        .filter_map(|account_id| {
+            overrides
+                .iter()
+                .find(|limits| limits.account_id == *account_id)
+        })
+        .fold(defaults.clone(), |limits, overrides| {
+            limits.overridden_by(overrides)
+        }))
+}
+
+async fn count_limit_usage<'a, E>(
+    db_conn: E,
+    account_id: &str,
+    now: DateTime<Utc>,
+) -> Result<LimitUsage, OrchestrateError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let [hour_start, day_start, month_start] = LimitWindow::ALL.map(|window| window.start(now));
+    Ok(find_account_limit_usage(db_conn, account_id, hour_start, day_start, month_start).await?)
+}
+
+fn limit_usage_keys(account_id: &str, now: DateTime<Utc>) -> [String; 3] {
+    LimitWindow::ALL.map(|window| {
+        format!(
+            "{}:{}:{}",
+            LIMIT_USAGE_KEY_PREFIX,
+            account_id,
+            window.bucket(now)
+        )
+    })
+}
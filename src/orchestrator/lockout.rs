@@ -0,0 +1,160 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::{FailedAttemptClass, UpdateAccountReq};
+use crate::error::OrchestrateError;
+use crate::storage::{clear_failure_counts, increment_failure_count};
+use crate::{update_user_account, DEFAULT_TIMEZONE, FAILED_ATTEMPTS_KEY_PREFIX, SYSTEM_USER_FP};
+use sqlx::PgPool;
+use tracing::{error, warn};
+
+/// Counts a failed transaction attempt against the account and locks the account when the
+/// attempts of the same class reach the configured threshold within the window. Failures that
+/// say nothing about the account, e.g. a database outage, are not counted.
+pub async fn register_failed_attempt(
+    pool: &PgPool,
+    account_id: &str,
+    err: &OrchestrateError,
+    app_cxt: &ApplicationContext,
+) {
+    let event = "registerFailedAttempt";
+    let failure_class = match classify_failure(err) {
+        Some(failure_class) => failure_class,
+        None => return,
+    };
+    let policy = &app_cxt.failed_attempts;
+    let key = failed_attempts_key(account_id, &failure_class);
+    let attempts =
+        match increment_failure_count(&key, policy.window, &mut app_cxt.redis_conn.clone()).await {
+            Ok(attempts) => attempts,
+            Err(err) => {
+                error!("event={} :: {}", event, err);
+                return;
+            }
+        };
+    // lock once, when the threshold is crossed, not on every attempt made after that
+    if attempts != policy.threshold as u64 {
+        return;
+    }
+
+    let system_ctx = UserContext::load_user_context(
+        SYSTEM_USER_FP.to_string(),
+        DEFAULT_TIMEZONE.to_string(),
+        Some(account_id.to_string()),
+        None,
+    );
+    let req_context = RequestContext {
+        request_ip: None,
+        user_agent: None,
+        request_id: None,
+        service_id: None,
+        rpc_method: None,
+    };
+    match update_user_account(
+        pool,
+        account_id,
+        &system_ctx,
+        UpdateAccountReq::new(Some(true), None),
+        true,
+        req_context,
+    )
+    .await
+    {
+        Ok(_) => warn!(
+            "event={} :: locked account {} after {} failed attempts ({})",
+            event, account_id, attempts, failure_class
+        ),
+        Err(err) => error!(
+            "event={} :: could not lock account {}: {}",
+            event, account_id, err
+        ),
+    }
+}
+
+/// Unlocks an account, only admins can. The failed attempts counted so far are forgotten.
+pub async fn unlock_account(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+    is_admin: bool,
+    req_context: RequestContext,
+    app_cxt: &ApplicationContext,
+) -> Result<bool, OrchestrateError> {
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can unlock an account".to_string(),
+        ));
+    }
+    let updated = update_user_account(
+        pool,
+        account_id,
+        user_ctx,
+        UpdateAccountReq::new(Some(false), None),
+        is_admin,
+        req_context,
+    )
+    .await?;
+
+    let keys: Vec<String> = FailedAttemptClass::ALL
+        .iter()
+        .map(|failure_class| failed_attempts_key(account_id, failure_class))
+        .collect();
+    if let Err(err) = clear_failure_counts(&keys, &mut app_cxt.redis_conn.clone()).await {
+        error!(
+            "could not clear failed attempts of account {}: {}",
+            account_id, err
+        );
+    }
+    Ok(updated)
+}
+
+fn classify_failure(err: &OrchestrateError) -> Option<FailedAttemptClass> {
+    match err {
+        OrchestrateError::InsufficientFunds(_) => Some(FailedAttemptClass::InsufficientFunds),
+        OrchestrateError::InvalidRecordState(_) | OrchestrateError::IllegalState(_) => {
+            Some(FailedAttemptClass::InvalidState)
+        }
+        OrchestrateError::UnsupportedCurrency(_) => Some(FailedAttemptClass::BadCurrency),
+        _ => None,
+    }
+}
+
+fn failed_attempts_key(account_id: &str, failure_class: &FailedAttemptClass) -> String {
+    format!(
+        "{}:{}:{}",
+        FAILED_ATTEMPTS_KEY_PREFIX, account_id, failure_class
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_account_failures_are_counted() {
+        let insufficient = OrchestrateError::InsufficientFunds("balance".to_string());
+        let locked = OrchestrateError::InvalidRecordState("locked".to_string());
+        let currency = OrchestrateError::UnsupportedCurrency("no EUR wallet".to_string());
+        let outage = OrchestrateError::DatabaseError("pool closed".to_string());
+
+        assert_eq!(
+            classify_failure(&insufficient),
+            Some(FailedAttemptClass::InsufficientFunds)
+        );
+        assert_eq!(
+            classify_failure(&locked),
+            Some(FailedAttemptClass::InvalidState)
+        );
+        assert_eq!(
+            classify_failure(&currency),
+            Some(FailedAttemptClass::BadCurrency)
+        );
+        assert_eq!(classify_failure(&outage), None);
+    }
+
+    #[test]
+    fn test_failed_attempts_key_is_per_account_and_class() {
+        assert_eq!(
+            failed_attempts_key("acct", &FailedAttemptClass::BadCurrency),
+            "xrfq3:failed-tx-attempts:acct:bad_currency"
+        );
+    }
+}
@@ -1,28 +1,74 @@
 mod account;
 mod activity;
 mod audit;
+mod audit_archive;
 mod block;
 mod blockchain;
 mod chain;
+mod closure;
 mod currency;
+mod dormancy;
+mod fee;
+mod freeze;
+mod health;
 mod helper;
+mod hierarchy;
+mod interest;
+mod joint;
 mod ledger;
+mod limits;
+mod lockout;
+mod overdraft;
+mod payout;
+mod schedule;
+#[cfg(test)]
+mod testing;
 mod transaction;
 mod wallet;
 
 pub use account::{
-    create_account, create_new_beneficiary_acct, find_account_by_currency_and_type,
-    get_user_account_by_id, get_user_accounts_by_currencies_or_types, update_user_account,
+    change_account_status, change_account_type, create_account, create_new_beneficiary_acct,
+    find_account_by_currency_and_type, get_account_wallet, get_user_account_by_id,
+    get_user_accounts_by_currencies_or_types, update_user_account,
 };
 pub use activity::{create_activity, find_last_user_activity};
-pub use audit::{create_new_audit, fetch_audit_history};
+pub use audit::{
+    audit_access_denial, audit_change, checkpoint_audit_chain, create_new_audit,
+    fetch_audit_history, search_audit_history, verify_audit_chain,
+};
+pub use audit_archive::{
+    archive_audit_logs, list_audit_archives, release_audit_archive, restore_audit_archive,
+};
 pub use block::create_block;
-pub use blockchain::{create_chained_block_chain, create_initial_block_chain};
+pub use blockchain::{
+    create_chained_block, create_chained_block_chain, create_initial_block_chain,
+};
 pub use chain::create_chain_stamp;
+pub use closure::close_account;
 pub use currency::{convert_amount, save_currencies_rate};
+pub use dormancy::mark_dormant_accounts;
+pub use freeze::lift_expired_freezes;
+pub use health::probe_dependencies;
 pub use helper::{commit_db_transaction, rollback_db_transaction, start_db_transaction};
+pub use hierarchy::{get_consolidated_balance, list_child_accounts, transfer_within_hierarchy};
+pub use interest::{accrue_interest, capitalize_interest, get_accrued_interest};
+pub use joint::{
+    add_account_member, approve_transaction, expire_approval_requests, list_account_members,
+    remove_account_member, set_signing_policy,
+};
 pub use ledger::create_ledger;
-pub use transaction::{credit_wallet, debit_wallet_transaction};
+pub use limits::{get_account_limits, set_account_limits};
+pub use lockout::{register_failed_attempt, unlock_account};
+pub use overdraft::{charge_overdrafts, set_overdraft_limit};
+pub use payout::{submit_payout_batch, PayoutItemOutcome};
+pub use schedule::{
+    cancel_scheduled_payment, create_scheduled_payment, list_scheduled_payments,
+    run_due_scheduled_payments,
+};
+pub use transaction::{
+    change_transaction_status, credit_wallet, debit_wallet_transaction, get_account_transaction,
+    list_account_transactions,
+};
 pub use wallet::{
     create_wallet_holding, credit_wallet_holding, debit_wallet, find_user_wallet_for_acct,
     find_user_wallets_for_acct,
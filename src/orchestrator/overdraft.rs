@@ -0,0 +1,292 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::{
+    Account, AccountStatus, Currency, EntityType, OverdraftCharge, OverdraftTerms, WalletHolding,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::account::find_account_for_change;
+use crate::orchestrator::fee::build_fee_transactions;
+use crate::orchestrator::transaction::{apply_transfer_legs, record_unsuccessful_transaction};
+use crate::storage::{
+    fetch_wallet_for_update, find_account_by_id, find_overdraft_candidates, save_overdraft_charge,
+    update_wallet_overdraft_limit, OverdraftCandidate,
+};
+use crate::{
+    audit_change, commit_db_transaction, rollback_db_transaction, start_db_transaction,
+    SYSTEM_USER_FP,
+};
+use cassandra_cpp::Session;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+/// Grants, changes or removes (with a zero limit) the credit line of a wallet. Admins only, and
+/// the limit can not drop below what the wallet already overdrew.
+pub async fn set_overdraft_limit(
+    pool: &PgPool,
+    account_id: &str,
+    currency: String,
+    overdraft_limit: String,
+    user_ctx: &UserContext,
+    is_admin: bool,
+    req_context: RequestContext,
+) -> Result<WalletHolding, OrchestrateError> {
+    let event = "setOverdraftLimit";
+    if !is_admin {
+        return Err(OrchestrateError::PermissionDenied(
+            "only an admin can change overdraft limits".to_string(),
+        ));
+    }
+    let currency = Currency::from_str(&currency)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let overdraft_limit = Decimal::from_str(&overdraft_limit).map_err(|_| {
+        OrchestrateError::InvalidArgument("cannot parse overdraft limit".to_string())
+    })?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let account = find_account_for_change(&mut db_tx, account_id, user_ctx, is_admin).await?;
+    if account.status == AccountStatus::Closed {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidRecordState(
+            "account is closed".to_string(),
+        ));
+    }
+    let saved_wallet = match fetch_wallet_for_update(&mut db_tx, account_id, &currency).await? {
+        Some(wallet) => wallet,
+        None => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(OrchestrateError::UnsupportedCurrency(format!(
+                "account has no {} wallet",
+                currency
+            )));
+        }
+    };
+    let mut wallet = saved_wallet.clone();
+    if let Err(err) = wallet.set_overdraft_limit(overdraft_limit) {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidArgument(err.to_string()));
+    }
+    if !update_wallet_overdraft_limit(&mut *db_tx, &wallet).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not update overdraft limit".to_string(),
+        ));
+    }
+
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::Wallet,
+        &wallet.entity_id(),
+        Some(&saved_wallet),
+        Some(&wallet),
+        &user_ctx.user_fp,
+        Some(&req_context),
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    info!(
+        "overdraft limit of the {} wallet of account {} set to {}",
+        currency, account_id, overdraft_limit
+    );
+    Ok(wallet)
+}
+
+/// Charges the overdraft fee and interest of one day on up to `batch_size` wallets of each
+/// currency with overdraft terms, the day before the current one in the account timezone or an
+/// older day the job missed. Charges go to the fee account as commissions. Returns how many days
+/// were charged, an account failing is left for the next run.
+pub async fn charge_overdrafts(
+    pool: &PgPool,
+    batch_size: i64,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let event = "chargeOverdrafts";
+    let fee_account_id = match &app_cxt.overdraft.fee_account_id {
+        Some(fee_account_id) => fee_account_id,
+        None => return Ok(0),
+    };
+    let fee_account = match find_account_by_id(pool, fee_account_id).await? {
+        Some(account) => account,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "overdraft fee account not found".to_string(),
+            ));
+        }
+    };
+
+    let now = Utc::now();
+    let mut charged = 0;
+    for terms in &app_cxt.overdraft.terms {
+        let candidates =
+            find_overdraft_candidates(pool, terms.currency.clone(), now, batch_size).await?;
+        for candidate in &candidates {
+            match charge_overdraft(
+                pool,
+                candidate,
+                terms,
+                &fee_account,
+                cassandra_session,
+                app_cxt,
+            )
+            .await
+            {
+                Ok(()) => charged += 1,
+                Err(err) => {
+                    warn!(
+                        "event={} :: overdraft of the {} wallet of account {} not charged for {}: {}",
+                        event, candidate.currency, candidate.account_id, candidate.charge_date, err
+                    );
+                }
+            }
+        }
+    }
+    Ok(charged)
+}
+
+async fn charge_overdraft(
+    pool: &PgPool,
+    candidate: &OverdraftCandidate,
+    terms: &OverdraftTerms,
+    fee_account: &Account,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<(), OrchestrateError> {
+    let event = "chargeOverdraft";
+    let mut charge = OverdraftCharge::new(
+        candidate.account_id.clone(),
+        candidate.charge_date,
+        candidate.balance,
+        terms,
+    );
+
+    // fees are debited like any transaction of the account, from its account-currency wallet
+    if !charge.total().is_zero() && candidate.currency != candidate.account_currency {
+        return Err(OrchestrateError::UnsupportedCurrency(format!(
+            "overdraft charges are only debited from the {} wallet",
+            candidate.account_currency
+        )));
+    }
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !charge.total().is_zero() {
+        ////// 1. Charge no more than the credit line has left
+        let wallet =
+            match fetch_wallet_for_update(&mut db_tx, &charge.account_id, &charge.currency).await {
+                Ok(Some(wallet)) => wallet,
+                Ok(None) => {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(OrchestrateError::UnsupportedCurrency(format!(
+                        "account has no {} wallet",
+                        charge.currency
+                    )));
+                }
+                Err(err) => {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(err.into());
+                }
+            };
+        let total = charge.total();
+        charge.cap_to(wallet.available_balance());
+        if charge.total() < total {
+            warn!(
+                "event={} :: {} of the overdraft charges of account {} waived, over its limit",
+                event,
+                total - charge.total(),
+                charge.account_id
+            );
+        }
+    }
+
+    ////// 2. Pay the charges to the fee account
+    if !charge.total().is_zero() {
+        let mut transactions = match build_fee_transactions(
+            pool,
+            &charge.account_id,
+            charge.total(),
+            charge.currency.clone(),
+            fee_account,
+            app_cxt,
+        )
+        .await
+        {
+            Ok(transactions) => transactions,
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+        };
+        let user_ctx = UserContext::load_user_context(
+            candidate.user_fp.clone(),
+            candidate.timezone.clone(),
+            Some(candidate.account_id.clone()),
+            None,
+        );
+        if let Err(err) = apply_transfer_legs(
+            &mut transactions,
+            Decimal::ZERO,
+            "overdraft fee and interest",
+            &user_ctx,
+            cassandra_session,
+            app_cxt,
+            &mut db_tx,
+        )
+        .await
+        {
+            error!(
+                "event={} :: failed to charge the overdraft of account {}: {}",
+                event, charge.account_id, err
+            );
+            rollback_db_transaction(db_tx, event).await?;
+            for transaction in transactions {
+                record_unsuccessful_transaction(pool, transaction, &err).await;
+            }
+            return Err(err);
+        }
+        charge.transaction_id = transactions.first().map(|debit| debit.id.clone());
+    }
+
+    ////// 3. Record the day as charged, unless another worker charged it first
+    match save_overdraft_charge(&mut *db_tx, &charge).await {
+        Ok(true) => {
+            if let Err(err) = audit_change(
+                &mut db_tx,
+                EntityType::OverdraftCharge,
+                &charge.entity_id(),
+                None,
+                Some(&charge),
+                SYSTEM_USER_FP,
+                None,
+            )
+            .await
+            {
+                rollback_db_transaction(db_tx, event).await?;
+                return Err(err);
+            }
+            commit_db_transaction(db_tx, event).await?;
+            if !charge.total().is_zero() {
+                info!(
+                    "charged {} of overdraft fee and interest to account {} for {}",
+                    charge.total(),
+                    charge.account_id,
+                    charge.charge_date
+                );
+            }
+            Ok(())
+        }
+        Ok(false) => {
+            rollback_db_transaction(db_tx, event).await?;
+            Ok(())
+        }
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            Err(err.into())
+        }
+    }
+}
@@ -0,0 +1,700 @@
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    Block, EntityType, EntryType, LedgerEntry, MonetaryTransaction, PayoutBatch, PayoutBatchItem,
+    PayoutBatchMode, PayoutItemReq, PayoutItemType, TransactionStatus, TransactionType,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::joint::reject_unapproved_debit;
+use crate::orchestrator::limits::{check_transaction_limits, record_limit_usage};
+use crate::orchestrator::transaction::{
+    apply_wallet_mutation, record_unsuccessful_transaction, split_commission,
+};
+use crate::storage::{
+    bulk_save_ledger, find_account_by_id, find_payout_items_by_idempotency_keys, save_payout_batch,
+    save_payout_batch_item, set_monetary_txs_block, update_payout_batch, update_payout_batch_item,
+};
+use crate::{
+    audit_change, change_transaction_status, commit_db_transaction, convert_amount,
+    create_chained_block, rollback_db_transaction, start_db_transaction, MAX_PAYOUT_BATCH_ITEMS,
+    PAYOUT_BATCH_CONCURRENCY,
+};
+use cassandra_cpp::Session;
+use futures::{stream, StreamExt};
+use rust_decimal::Decimal;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// The result of one submitted item. `replayed` items were applied by an earlier request with the
+/// same idempotency key and are returned as they were recorded then.
+pub struct PayoutItemOutcome {
+    pub item: PayoutBatchItem,
+    pub replayed: bool,
+}
+
+/// A monetary transaction produced by a payout item. Transfers have a debit & a credit leg.
+struct PayoutLeg {
+    entry_type: EntryType,
+    commission: Decimal,
+    description: String,
+    transaction: MonetaryTransaction,
+}
+
+/// Applies a batch of credits/transfers. Every item is validated before any is applied, the new
+/// transfers out of each source account are checked against its limits as a whole. Credits are not
+/// funded by any account, only callers that `may_issue_credits` can submit them.
+///
+/// Best-effort batches apply each item in its own DB transaction, at most
+/// `PAYOUT_BATCH_CONCURRENCY` at a time. All-or-nothing batches apply every item in a single DB
+/// transaction, one after the other, and roll back all of them when one fails. In both modes the
+/// ledger entries of the completed items go into a single block.
+pub async fn submit_payout_batch(
+    pool: &PgPool,
+    item_reqs: Vec<PayoutItemReq>,
+    mode: PayoutBatchMode,
+    may_issue_credits: bool,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<(PayoutBatch, Vec<PayoutItemOutcome>), OrchestrateError> {
+    let event = "submitPayoutBatch";
+    if item_reqs.is_empty() || item_reqs.len() > MAX_PAYOUT_BATCH_ITEMS {
+        return Err(OrchestrateError::InvalidArgument(format!(
+            "a batch must have between 1 and {} items",
+            MAX_PAYOUT_BATCH_ITEMS
+        )));
+    }
+    let mut batch = PayoutBatch::new(user_ctx.user_fp.clone(), mode, item_reqs.len());
+
+    ////// 0. Validate every item up front
+    let items = validate_payout_items(pool, &batch, item_reqs, may_issue_credits, user_ctx).await?;
+
+    ////// 1. Items retried with a known idempotency key return their first result
+    let keys = items
+        .iter()
+        .map(|item| item.idempotency_key.clone())
+        .collect::<Vec<_>>();
+    let mut previous_items = find_payout_items_by_idempotency_keys(pool, &user_ctx.user_fp, &keys)
+        .await?
+        .into_iter()
+        .map(|item| (item.idempotency_key.clone(), item))
+        .collect::<HashMap<_, _>>();
+
+    let mut outcomes: Vec<Option<PayoutItemOutcome>> = Vec::with_capacity(items.len());
+    let mut new_items = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match previous_items.remove(&item.idempotency_key) {
+            Some(previous) if !previous.is_same_payout(&item) => {
+                return Err(OrchestrateError::InvalidArgument(format!(
+                    "item {}: idempotency key {} was used for a different payout",
+                    index, item.idempotency_key
+                )));
+            }
+            Some(previous) => outcomes.push(Some(PayoutItemOutcome {
+                item: previous,
+                replayed: true,
+            })),
+            None => {
+                outcomes.push(None);
+                new_items.push(item);
+            }
+        }
+    }
+    batch.item_count = new_items.len() as i32;
+    check_payout_limits(pool, &new_items, app_cxt).await?;
+
+    ////// 2. Record the batch with its pending items
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if let Err(err) = save_payout_batch_with_items(&batch, &new_items, &mut db_tx).await {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    ////// 3. Apply the items
+    let block_id = match batch.mode {
+        PayoutBatchMode::AllOrNothing => {
+            apply_all_or_nothing(pool, &mut new_items, user_ctx, cassandra_session, app_cxt).await?
+        }
+        PayoutBatchMode::BestEffort => {
+            apply_best_effort(pool, &mut new_items, user_ctx, cassandra_session, app_cxt).await
+        }
+    };
+
+    ////// 4. Complete the batch
+    let saved_batch = batch.clone();
+    batch.complete(&new_items, block_id);
+    if let Err(err) = complete_payout_batch(pool, &saved_batch, &batch).await {
+        error!(
+            "event={} :: could not update payout batch {}: {}",
+            event, batch.id, err
+        );
+    }
+    info!(
+        "payout batch {} is {} with {} new items",
+        batch.id, batch.status, batch.item_count
+    );
+
+    let mut new_items = new_items.into_iter();
+    let outcomes = outcomes
+        .into_iter()
+        .map(|outcome| {
+            outcome.or_else(|| {
+                new_items.next().map(|item| PayoutItemOutcome {
+                    item,
+                    replayed: false,
+                })
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| OrchestrateError::ServerError("payout item is missing".to_string()))?;
+    Ok((batch, outcomes))
+}
+
+async fn validate_payout_items(
+    pool: &PgPool,
+    batch: &PayoutBatch,
+    item_reqs: Vec<PayoutItemReq>,
+    may_issue_credits: bool,
+    user_ctx: &UserContext,
+) -> Result<Vec<PayoutBatchItem>, OrchestrateError> {
+    let mut keys = HashSet::new();
+    let mut items = Vec::with_capacity(item_reqs.len());
+    for (index, req) in item_reqs.into_iter().enumerate() {
+        let invalid_item =
+            |msg: String| OrchestrateError::InvalidArgument(format!("item {}: {}", index, msg));
+        if !keys.insert(req.idempotency_key.clone()) {
+            return Err(invalid_item("duplicate idempotency key".to_string()));
+        }
+        let amount = Decimal::from_str(&req.amount)
+            .map_err(|_| invalid_item("cannot parse amount".to_string()))?;
+        let item_type = PayoutItemType::from_str(&req.item_type)
+            .map_err(|err| invalid_item(err.to_string()))?;
+        if item_type == PayoutItemType::Credit && !may_issue_credits {
+            return Err(OrchestrateError::PermissionDenied(format!(
+                "item {}: only internal services and admins can issue credits",
+                index
+            )));
+        }
+        let item = PayoutBatchItem::build(
+            batch.id.clone(),
+            user_ctx.user_fp.clone(),
+            req.idempotency_key,
+            item_type,
+            req.account_id,
+            req.source_account_id,
+            amount,
+        )
+        .map_err(|err| invalid_item(err.to_string()))?;
+        items.push(item);
+    }
+
+    // every account must exist and money can only be moved out of the caller's own accounts
+    let mut account_owners = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let account_ids = std::iter::once(&item.account_id).chain(item.source_account_id.iter());
+        for account_id in account_ids {
+            if !account_owners.contains_key(account_id) {
+                let owner = find_account_by_id(pool, account_id)
+                    .await?
+                    .map(|account| account.user_fp);
+                account_owners.insert(account_id.clone(), owner);
+            }
+        }
+        if account_owners[&item.account_id].is_none() {
+            return Err(OrchestrateError::InvalidArgument(format!(
+                "item {}: account not found",
+                index
+            )));
+        }
+        if let Some(source_id) = &item.source_account_id {
+            if account_owners[source_id].as_ref() != Some(&user_ctx.user_fp) {
+                return Err(OrchestrateError::InvalidArgument(format!(
+                    "item {}: source account not found",
+                    index
+                )));
+            }
+            reject_unapproved_debit(pool, source_id, item.amount).await?;
+        }
+    }
+    Ok(items)
+}
+
+/// The limits apply to everything the batch takes out of an account, not to each item alone.
+/// Only the new items count, the replayed ones were checked when they were first submitted.
+async fn check_payout_limits(
+    pool: &PgPool,
+    items: &[PayoutBatchItem],
+    app_cxt: &ApplicationContext,
+) -> Result<(), OrchestrateError> {
+    let mut debit_totals = HashMap::new();
+    for item in items {
+        if let Some(source_id) = &item.source_account_id {
+            *debit_totals.entry(source_id).or_insert(Decimal::ZERO) += item.amount;
+        }
+    }
+    for (source_id, total) in debit_totals {
+        check_transaction_limits(pool, source_id, total, &EntryType::Debit, app_cxt).await?;
+    }
+    Ok(())
+}
+
+async fn save_payout_batch_with_items(
+    batch: &PayoutBatch,
+    items: &[PayoutBatchItem],
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), OrchestrateError> {
+    if !save_payout_batch(&mut **db_tx, batch).await? {
+        return Err(OrchestrateError::ServerError(
+            "could not save payout batch".to_string(),
+        ));
+    }
+    audit_change(
+        db_tx,
+        EntityType::PayoutBatch,
+        &batch.id,
+        None,
+        Some(batch),
+        &batch.user_fp,
+        None,
+    )
+    .await?;
+    for item in items {
+        if !save_payout_batch_item(&mut **db_tx, item).await? {
+            return Err(OrchestrateError::ServerError(
+                "could not save payout item".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Applies every item in one DB transaction. Returns the id of the block, `None` when the batch
+/// was rolled back.
+async fn apply_all_or_nothing(
+    pool: &PgPool,
+    items: &mut [PayoutBatchItem],
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<Option<String>, OrchestrateError> {
+    let event = "applyPayoutBatch";
+    let mut legs = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        match build_payout_legs(pool, item, app_cxt).await {
+            Ok(item_legs) => legs.push(item_legs),
+            Err(err) => {
+                error!("event={} :: payout item {} failed: {}", event, index, err);
+                fail_all_items(pool, items, legs, Some(index), &err).await;
+                return Ok(None);
+            }
+        }
+    }
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    match apply_items_in_transaction(
+        items,
+        &mut legs,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        &mut db_tx,
+    )
+    .await
+    {
+        Ok(block) => {
+            commit_db_transaction(db_tx, event).await?;
+            for item in items.iter() {
+                record_payout_usage(item, app_cxt).await;
+            }
+            Ok(Some(block.id))
+        }
+        Err((failed_index, err)) => {
+            error!("event={} :: payout batch rolled back: {}", event, err);
+            rollback_db_transaction(db_tx, event).await?;
+            fail_all_items(pool, items, legs, failed_index, &err).await;
+            Ok(None)
+        }
+    }
+}
+
+/// Records every item of an all-or-nothing batch that was not applied as failed, along with the
+/// transactions of the legs built for them. `failed_index` is the item that failed, None when the
+/// batch failed as a whole.
+async fn fail_all_items(
+    pool: &PgPool,
+    items: &mut [PayoutBatchItem],
+    legs: Vec<Vec<PayoutLeg>>,
+    failed_index: Option<usize>,
+    err: &OrchestrateError,
+) {
+    let event = "applyPayoutBatch";
+    let rolled_back = OrchestrateError::ServerError("payout batch was rolled back".into());
+    let mut legs = legs.into_iter();
+    for (index, item) in items.iter_mut().enumerate() {
+        let item_err = match failed_index {
+            Some(failed) if failed == index => err,
+            _ => &rolled_back,
+        };
+        for leg in legs.next().unwrap_or_default() {
+            record_unsuccessful_transaction(pool, leg.transaction, item_err).await;
+        }
+        item.fail(match failed_index {
+            Some(failed) if failed == index => err.to_string(),
+            Some(failed) => format!("batch rolled back, item {} failed", failed),
+            None => format!("batch rolled back: {}", err),
+        });
+        if let Err(update_err) = update_payout_batch_item(pool, item).await {
+            error!(
+                "event={} :: could not record payout item {}: {}",
+                event, item.id, update_err
+            );
+        }
+    }
+}
+
+async fn complete_payout_batch(
+    pool: &PgPool,
+    saved_batch: &PayoutBatch,
+    batch: &PayoutBatch,
+) -> Result<(), OrchestrateError> {
+    let event = "completePayoutBatch";
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !update_payout_batch(&mut *db_tx, batch).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::InvalidRecordState(
+            "payout batch not found".to_string(),
+        ));
+    }
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::PayoutBatch,
+        &batch.id,
+        Some(saved_batch),
+        Some(batch),
+        &batch.user_fp,
+        None,
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    Ok(())
+}
+
+/// Error carries the index of the item that failed, `None` when sealing the batch failed.
+async fn apply_items_in_transaction(
+    items: &mut [PayoutBatchItem],
+    legs: &mut [Vec<PayoutLeg>],
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Block, (Option<usize>, OrchestrateError)> {
+    let mut entry_ids = Vec::new();
+    for (index, item_legs) in legs.iter_mut().enumerate() {
+        let item_entry_ids = apply_payout_legs(item_legs, &user_ctx.user_fp, app_cxt, db_tx)
+            .await
+            .map_err(|err| (Some(index), err))?;
+        entry_ids.extend(item_entry_ids);
+    }
+
+    let transaction_ids = legs
+        .iter()
+        .flatten()
+        .map(|leg| leg.transaction.id.clone())
+        .collect::<Vec<_>>();
+    let block = seal_payout_block(
+        entry_ids,
+        &transaction_ids,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        db_tx,
+    )
+    .await
+    .map_err(|err| (None, err))?;
+
+    for (item, item_legs) in items.iter_mut().zip(legs.iter()) {
+        item.succeed(leg_transaction_ids(item_legs));
+        update_payout_batch_item(&mut **db_tx, item)
+            .await
+            .map_err(|err| (None, err.into()))?;
+    }
+    Ok(block)
+}
+
+/// Applies each item in its own DB transaction with bounded parallelism, then puts the ledger
+/// entries of the completed items in one block. Returns the id of that block, if any.
+async fn apply_best_effort(
+    pool: &PgPool,
+    items: &mut [PayoutBatchItem],
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Option<String> {
+    let event = "applyPayoutBatch";
+    let applied_items = items
+        .iter_mut()
+        .map(|item| apply_payout_item(pool, item, app_cxt))
+        .collect::<Vec<_>>();
+    let entry_ids = stream::iter(applied_items)
+        .buffer_unordered(PAYOUT_BATCH_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if entry_ids.is_empty() {
+        return None;
+    }
+
+    let transaction_ids = items
+        .iter()
+        .flat_map(|item| item.transaction_ids.clone())
+        .collect::<Vec<_>>();
+
+    // the items are applied already, a block that can't be sealed does not undo them
+    let sealed = async {
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        match seal_payout_block(
+            entry_ids,
+            &transaction_ids,
+            user_ctx,
+            cassandra_session,
+            app_cxt,
+            &mut db_tx,
+        )
+        .await
+        {
+            Ok(block) => {
+                commit_db_transaction(db_tx, event).await?;
+                Ok(block)
+            }
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                Err(err)
+            }
+        }
+    };
+    match sealed.await {
+        Ok(block) => Some(block.id),
+        Err(err) => {
+            error!("event={} :: could not seal payout block: {}", event, err);
+            None
+        }
+    }
+}
+
+/// Applies one item in its own DB transaction and records its outcome.
+/// Returns the ids of the ledger entries created, none when the item failed.
+async fn apply_payout_item(
+    pool: &PgPool,
+    item: &mut PayoutBatchItem,
+    app_cxt: &ApplicationContext,
+) -> Vec<String> {
+    let event = "applyPayoutItem";
+    let applied = async {
+        let mut legs = build_payout_legs(pool, item, app_cxt).await?;
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        match apply_payout_legs(&mut legs, &item.user_fp, app_cxt, &mut db_tx).await {
+            Ok(entry_ids) => {
+                let mut completed_item = item.clone();
+                completed_item.succeed(leg_transaction_ids(&legs));
+                update_payout_batch_item(&mut *db_tx, &completed_item).await?;
+                commit_db_transaction(db_tx, event).await?;
+                Ok((completed_item, entry_ids))
+            }
+            Err(err) => {
+                rollback_db_transaction(db_tx, event).await?;
+                for leg in legs {
+                    record_unsuccessful_transaction(pool, leg.transaction, &err).await;
+                }
+                Err(err)
+            }
+        }
+    };
+
+    match applied.await {
+        Ok((completed_item, entry_ids)) => {
+            *item = completed_item;
+            record_payout_usage(item, app_cxt).await;
+            entry_ids
+        }
+        Err(err) => {
+            error!(
+                "event={} :: payout item {} failed: {}",
+                event, item.idempotency_key, err
+            );
+            item.fail(err.to_string());
+            if let Err(update_err) = update_payout_batch_item(pool, item).await {
+                error!(
+                    "event={} :: could not record payout item {}: {}",
+                    event, item.id, update_err
+                );
+            }
+            vec![]
+        }
+    }
+}
+
+/// Adds a committed transfer to the limit usage of its source account.
+async fn record_payout_usage(item: &PayoutBatchItem, app_cxt: &ApplicationContext) {
+    if let Some(source_id) = &item.source_account_id {
+        record_limit_usage(source_id, item.amount, &EntryType::Debit, app_cxt).await;
+    }
+}
+
+/// The legs of an item. The credit leg of a transfer is converted to the currency of the
+/// destination account.
+async fn build_payout_legs(
+    pool: &PgPool,
+    item: &PayoutBatchItem,
+    app_cxt: &ApplicationContext,
+) -> Result<Vec<PayoutLeg>, OrchestrateError> {
+    let (amount, commission) = split_commission(item.amount, app_cxt);
+    let leg = |amount: Decimal,
+               account_id: &str,
+               entry_type: EntryType,
+               commission: Decimal,
+               description: &str| {
+        MonetaryTransaction::build(
+            amount,
+            account_id.to_string(),
+            TransactionType::Transfer,
+            TransactionStatus::Pending,
+        )
+        .map(|transaction| PayoutLeg {
+            entry_type,
+            commission,
+            transaction,
+            description: description.to_string(),
+        })
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))
+    };
+
+    match (&item.item_type, &item.source_account_id) {
+        (PayoutItemType::Transfer, Some(source_id)) => {
+            let source = find_account_by_id(pool, source_id).await?.ok_or_else(|| {
+                OrchestrateError::NotFoundError("source account not found".to_string())
+            })?;
+            let destination = find_account_by_id(pool, &item.account_id)
+                .await?
+                .ok_or_else(|| OrchestrateError::NotFoundError("account not found".to_string()))?;
+            let converted = convert_amount(
+                pool,
+                amount,
+                source.currency,
+                destination.currency,
+                &mut app_cxt.redis_conn.clone(),
+            )
+            .await?;
+            Ok(vec![
+                leg(
+                    amount,
+                    source_id,
+                    EntryType::Debit,
+                    commission,
+                    "batch transfer from user account",
+                )?,
+                leg(
+                    converted,
+                    &item.account_id,
+                    EntryType::Credit,
+                    Decimal::ZERO,
+                    "batch transfer to user account",
+                )?,
+            ])
+        }
+        (PayoutItemType::Credit, _) => Ok(vec![leg(
+            amount,
+            &item.account_id,
+            EntryType::Credit,
+            commission,
+            "batch payout to user account",
+        )?]),
+        (PayoutItemType::Transfer, None) => Err(OrchestrateError::InvalidArgument(
+            "transfers need a source account".to_string(),
+        )),
+    }
+}
+
+/// Applies the legs of an item inside `db_tx` the way `perform_wallet_transaction` does, except
+/// that the ledger entries are not put in a block yet. Returns the ids of the ledger entries.
+async fn apply_payout_legs(
+    legs: &mut [PayoutLeg],
+    user_fp: &str,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Vec<String>, OrchestrateError> {
+    let mut ledgers = Vec::new();
+    for leg in legs.iter_mut() {
+        apply_wallet_mutation(
+            &leg.transaction,
+            leg.commission,
+            leg.entry_type.clone(),
+            user_fp,
+            app_cxt,
+            db_tx,
+        )
+        .await?;
+
+        let mut descriptions = vec![leg.description.clone()];
+        if !leg.commission.is_zero() {
+            descriptions.push("charge user wallet with commission".to_string());
+        }
+        for description in descriptions {
+            ledgers.push(LedgerEntry::new(
+                leg.transaction.account_id.clone(),
+                Some(description),
+                leg.entry_type.clone(),
+                Some(leg.transaction.id.clone()),
+            ));
+        }
+        change_transaction_status(
+            db_tx,
+            &mut leg.transaction,
+            TransactionStatus::Completed,
+            user_fp,
+        )
+        .await?;
+    }
+
+    let entry_ids = ledgers
+        .iter()
+        .map(|ledger| ledger.id.clone())
+        .collect::<Vec<_>>();
+    let ledgers_saved = bulk_save_ledger(&mut **db_tx, ledgers).await? as usize;
+    if ledgers_saved != entry_ids.len() {
+        return Err(OrchestrateError::InvalidRecordState(
+            "ledgers count is not equal".to_string(),
+        ));
+    }
+    Ok(entry_ids)
+}
+
+async fn seal_payout_block(
+    entry_ids: Vec<String>,
+    transaction_ids: &[String],
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Block, OrchestrateError> {
+    let block =
+        create_chained_block(entry_ids, user_ctx, cassandra_session, app_cxt, db_tx).await?;
+    let linked = set_monetary_txs_block(&mut **db_tx, transaction_ids, &block.id).await?;
+    if linked as usize != transaction_ids.len() {
+        return Err(OrchestrateError::ServerError(
+            "could not link transactions to block".to_string(),
+        ));
+    }
+    Ok(block)
+}
+
+fn leg_transaction_ids(legs: &[PayoutLeg]) -> Vec<String> {
+    legs.iter().map(|leg| leg.transaction.id.clone()).collect()
+}
@@ -0,0 +1,493 @@
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    AccountRole, AccountStatus, EntityType, EntryType, MonetaryTransaction, PaymentSchedule,
+    Recurrence, ScheduledPayment, ScheduledPaymentExecution, ScheduledPaymentReq,
+    ScheduledPaymentType, TransactionStatus, TransactionType,
+};
+use crate::error::OrchestrateError;
+use crate::orchestrator::joint::{find_member_account, reject_unapproved_debit};
+use crate::orchestrator::limits::{check_transaction_limits, record_limit_usage};
+use crate::orchestrator::lockout::register_failed_attempt;
+use crate::orchestrator::transaction::{
+    apply_transfer_legs, find_owned_account, perform_wallet_transaction,
+    record_unsuccessful_transaction, split_commission,
+};
+use crate::storage::{
+    find_account_by_id, find_account_scheduled_payments, find_scheduled_payment_by_id,
+    lock_due_scheduled_payments, save_scheduled_payment, save_scheduled_payment_execution,
+    update_scheduled_payment,
+};
+use crate::{
+    audit_change, commit_db_transaction, convert_amount, rollback_db_transaction,
+    start_db_transaction, SYSTEM_USER_FP,
+};
+use cassandra_cpp::Session;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tracing::{error, info, warn};
+
+/// Schedules a payment on one of the caller's accounts. Credits are not funded by any account,
+/// only callers that `may_issue_credits` can schedule them, on any open account.
+pub async fn create_scheduled_payment(
+    pool: &PgPool,
+    payment_req: ScheduledPaymentReq,
+    may_issue_credits: bool,
+    user_ctx: &UserContext,
+) -> Result<ScheduledPayment, OrchestrateError> {
+    let event = "createScheduledPayment";
+    let ScheduledPaymentReq {
+        account_id,
+        destination_account_id,
+        amount,
+        payment_type,
+        recurrence,
+        cron_expression,
+        starts_at,
+    } = payment_req;
+    let amount = Decimal::from_str(&amount)
+        .map_err(|_| OrchestrateError::InvalidArgument("cannot parse amount".to_string()))?;
+    let payment_type = ScheduledPaymentType::from_str(&payment_type)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+    let recurrence = Recurrence::from_str(&recurrence)
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    ////// 0. Only owners can schedule payments on an account, credits need an issuer
+    let account = if payment_type == ScheduledPaymentType::Credit {
+        if !may_issue_credits {
+            return Err(OrchestrateError::PermissionDenied(
+                "only internal services and admins can schedule credits".to_string(),
+            ));
+        }
+        find_account_by_id(pool, &account_id)
+            .await?
+            .ok_or_else(|| OrchestrateError::NotFoundError("account not found".to_string()))?
+    } else {
+        find_owned_account(pool, &account_id, user_ctx).await?
+    };
+    if account.status == AccountStatus::Closed {
+        return Err(OrchestrateError::IllegalState(
+            "account is closed".to_string(),
+        ));
+    }
+    if payment_type != ScheduledPaymentType::Credit {
+        reject_unapproved_debit(pool, &account.id, amount).await?;
+    }
+    if let Some(destination_id) = &destination_account_id {
+        match find_account_by_id(pool, destination_id).await? {
+            Some(destination) if destination.status != AccountStatus::Closed => {}
+            _ => {
+                return Err(OrchestrateError::NotFoundError(
+                    "destination account not found".to_string(),
+                ));
+            }
+        }
+    }
+
+    ////// 1. Recurrences are evaluated in the account's timezone
+    let scheduled_payment = ScheduledPayment::build(
+        user_ctx.user_fp.clone(),
+        account.id,
+        destination_account_id,
+        amount,
+        payment_type,
+        PaymentSchedule {
+            recurrence,
+            cron_expression,
+            timezone: account.timezone,
+            starts_at,
+        },
+    )
+    .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !save_scheduled_payment(&mut *db_tx, &scheduled_payment).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not save scheduled payment".to_string(),
+        ));
+    }
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::ScheduledPayment,
+        &scheduled_payment.id,
+        None,
+        Some(&scheduled_payment),
+        &user_ctx.user_fp,
+        None,
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    info!(
+        "created scheduled payment {} for account {}, nextRunAt={}",
+        scheduled_payment.id, scheduled_payment.account_id, scheduled_payment.next_run_at
+    );
+    Ok(scheduled_payment)
+}
+
+pub async fn list_scheduled_payments(
+    pool: &PgPool,
+    account_id: &str,
+    include_inactive: bool,
+    user_ctx: &UserContext,
+) -> Result<Vec<ScheduledPayment>, OrchestrateError> {
+    find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+    Ok(find_account_scheduled_payments(pool, account_id, include_inactive).await?)
+}
+
+pub async fn cancel_scheduled_payment(
+    pool: &PgPool,
+    scheduled_payment_id: &str,
+    user_ctx: &UserContext,
+) -> Result<ScheduledPayment, OrchestrateError> {
+    let event = "cancelScheduledPayment";
+    let mut scheduled_payment =
+        match find_scheduled_payment_by_id(pool, scheduled_payment_id).await? {
+            Some(scheduled_payment) if scheduled_payment.user_fp == user_ctx.user_fp => {
+                scheduled_payment
+            }
+            _ => {
+                return Err(OrchestrateError::NotFoundError(
+                    "scheduled payment not found".to_string(),
+                ));
+            }
+        };
+    let saved_payment = scheduled_payment.clone();
+    scheduled_payment
+        .cancel()
+        .map_err(|err| OrchestrateError::IllegalState(err.to_string()))?;
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    if !update_scheduled_payment(&mut *db_tx, &scheduled_payment).await? {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(OrchestrateError::ServerError(
+            "could not cancel scheduled payment".to_string(),
+        ));
+    }
+    if let Err(err) = audit_change(
+        &mut db_tx,
+        EntityType::ScheduledPayment,
+        &scheduled_payment.id,
+        Some(&saved_payment),
+        Some(&scheduled_payment),
+        &user_ctx.user_fp,
+        None,
+    )
+    .await
+    {
+        rollback_db_transaction(db_tx, event).await?;
+        return Err(err);
+    }
+    commit_db_transaction(db_tx, event).await?;
+    Ok(scheduled_payment)
+}
+
+/// Runs the scheduled payments that are due. Due payments are claimed (moved to their next run)
+/// in one DB transaction before any of them executes, so a payment that fails is not retried
+/// until its next occurrence. A payment whose next run can't be computed is cancelled with a
+/// failed execution in that same DB transaction. Returns the number of payments that were
+/// executed.
+pub async fn run_due_scheduled_payments(
+    pool: &PgPool,
+    batch_size: i64,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<usize, OrchestrateError> {
+    let event = "runScheduledPayments";
+    let now = Utc::now();
+
+    ////// 1. Claim the due payments
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    let due_payments = match lock_due_scheduled_payments(&mut *db_tx, now, batch_size).await {
+        Ok(due_payments) => due_payments,
+        Err(err) => {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err.into());
+        }
+    };
+    let mut claimed = Vec::with_capacity(due_payments.len());
+    for mut scheduled_payment in due_payments {
+        let saved_payment = scheduled_payment.clone();
+        let scheduled_for = scheduled_payment.next_run_at;
+        // a payment that can't move to its next run would stay due forever, it is cancelled
+        let failed_execution = match scheduled_payment.advance(now) {
+            Ok(()) => None,
+            Err(err) => {
+                error!(
+                    "event={} :: cannot advance scheduled payment {}, cancelling it: {}",
+                    event, scheduled_payment.id, err
+                );
+                if let Err(cancel_err) = scheduled_payment.cancel() {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(OrchestrateError::IllegalState(cancel_err.to_string()));
+                }
+                Some(ScheduledPaymentExecution::failed(
+                    scheduled_payment.id.clone(),
+                    err.to_string(),
+                    scheduled_for,
+                ))
+            }
+        };
+        if let Err(err) = update_scheduled_payment(&mut *db_tx, &scheduled_payment).await {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err.into());
+        }
+        if let Err(err) = audit_change(
+            &mut db_tx,
+            EntityType::ScheduledPayment,
+            &scheduled_payment.id,
+            Some(&saved_payment),
+            Some(&scheduled_payment),
+            SYSTEM_USER_FP,
+            None,
+        )
+        .await
+        {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err);
+        }
+        match failed_execution {
+            Some(execution) => {
+                if let Err(err) = save_scheduled_payment_execution(&mut *db_tx, &execution).await {
+                    rollback_db_transaction(db_tx, event).await?;
+                    return Err(err.into());
+                }
+            }
+            None => claimed.push((scheduled_payment, scheduled_for)),
+        }
+    }
+    commit_db_transaction(db_tx, event).await?;
+
+    ////// 2. Execute each claimed payment and record the outcome
+    for (scheduled_payment, scheduled_for) in &claimed {
+        let execution =
+            match execute_scheduled_payment(pool, scheduled_payment, cassandra_session, app_cxt)
+                .await
+            {
+                Ok(transaction) => ScheduledPaymentExecution::succeeded(
+                    scheduled_payment.id.clone(),
+                    transaction.id,
+                    *scheduled_for,
+                ),
+                Err(err) => {
+                    warn!(
+                        "event={} :: scheduled payment {} failed: {}",
+                        event, scheduled_payment.id, err
+                    );
+                    ScheduledPaymentExecution::failed(
+                        scheduled_payment.id.clone(),
+                        err.to_string(),
+                        *scheduled_for,
+                    )
+                }
+            };
+        if let Err(err) = save_scheduled_payment_execution(pool, &execution).await {
+            error!(
+                "event={} :: failed to record execution of scheduled payment {}: {}",
+                event, scheduled_payment.id, err
+            );
+        }
+    }
+    Ok(claimed.len())
+}
+
+async fn execute_scheduled_payment(
+    pool: &PgPool,
+    scheduled_payment: &ScheduledPayment,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<MonetaryTransaction, OrchestrateError> {
+    let event = "executeScheduledPayment";
+    let user_ctx = UserContext::load_user_context(
+        scheduled_payment.user_fp.clone(),
+        scheduled_payment.timezone.clone(),
+        Some(scheduled_payment.account_id.clone()),
+        None,
+    );
+
+    let (tx_type, entry_type, description) = match scheduled_payment.payment_type {
+        ScheduledPaymentType::Debit => (
+            TransactionType::Payment,
+            EntryType::Debit,
+            "scheduled debit of user account",
+        ),
+        ScheduledPaymentType::Credit => (
+            TransactionType::Transfer,
+            EntryType::Credit,
+            "scheduled credit of user account",
+        ),
+        ScheduledPaymentType::Transfer => {
+            return execute_scheduled_transfer(
+                pool,
+                scheduled_payment,
+                &user_ctx,
+                cassandra_session,
+                app_cxt,
+            )
+            .await;
+        }
+    };
+    perform_wallet_transaction(
+        event,
+        pool,
+        scheduled_payment.amount,
+        scheduled_payment.account_id.clone(),
+        &user_ctx,
+        tx_type,
+        entry_type,
+        cassandra_session,
+        app_cxt,
+        vec![description.to_string()],
+    )
+    .await
+}
+
+/// Debits the account and credits the destination with the amount converted to its currency, in
+/// one DB transaction. Only the debited account pays the commission and has its limits checked.
+/// Returns the debit transaction.
+async fn execute_scheduled_transfer(
+    pool: &PgPool,
+    scheduled_payment: &ScheduledPayment,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<MonetaryTransaction, OrchestrateError> {
+    let event = "executeScheduledTransfer";
+    let destination_id = scheduled_payment
+        .destination_account_id
+        .as_deref()
+        .ok_or_else(|| {
+            OrchestrateError::InvalidRecordState(
+                "scheduled transfer has no destination account".to_string(),
+            )
+        })?;
+    let source = find_account_by_id(pool, &scheduled_payment.account_id)
+        .await?
+        .ok_or_else(|| OrchestrateError::NotFoundError("account not found".to_string()))?;
+    let destination = find_account_by_id(pool, destination_id)
+        .await?
+        .ok_or_else(|| {
+            OrchestrateError::NotFoundError("destination account not found".to_string())
+        })?;
+
+    let (amount, commission) = split_commission(scheduled_payment.amount, app_cxt);
+    check_transaction_limits(pool, &source.id, amount, &EntryType::Debit, app_cxt).await?;
+    let converted = convert_amount(
+        pool,
+        amount,
+        source.currency.clone(),
+        destination.currency.clone(),
+        &mut app_cxt.redis_conn.clone(),
+    )
+    .await?;
+    let build_tx = |amount: Decimal, account_id: &str| {
+        MonetaryTransaction::build(
+            amount,
+            account_id.to_string(),
+            TransactionType::Transfer,
+            TransactionStatus::Pending,
+        )
+        .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))
+    };
+    let mut transactions = vec![
+        build_tx(amount, &source.id)?,
+        build_tx(converted, &destination.id)?,
+    ];
+
+    let mut db_tx = start_db_transaction(pool, event).await?;
+    match apply_transfer_legs(
+        &mut transactions,
+        commission,
+        "scheduled transfer between user accounts",
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        &mut db_tx,
+    )
+    .await
+    {
+        Ok(()) => {
+            commit_db_transaction(db_tx, event).await?;
+            info!(
+                "transferred {} from account {} to account {} for scheduled payment {}",
+                amount, source.id, destination.id, scheduled_payment.id
+            );
+            record_limit_usage(&source.id, amount, &EntryType::Debit, app_cxt).await;
+            Ok(transactions.swap_remove(0))
+        }
+        Err(err) => {
+            error!(
+                "event={} :: failed to transfer from account {} to account {}: {}",
+                event, source.id, destination.id, err
+            );
+            rollback_db_transaction(db_tx, event).await?;
+            register_failed_attempt(pool, &source.id, &err, app_cxt).await;
+            for transaction in transactions {
+                record_unsuccessful_transaction(pool, transaction, &err).await;
+            }
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ScheduledPaymentStatus;
+    use crate::orchestrator::testing::TestEnv;
+
+    #[tokio::test]
+    pub async fn test_payments_that_can_not_advance_are_cancelled() {
+        let env = TestEnv::start().await;
+        let user_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&user_ctx, "Normal", "USD").await;
+        let scheduled_payment = ScheduledPayment::build(
+            user_ctx.user_fp.clone(),
+            account.id.clone(),
+            None,
+            Decimal::from(10),
+            ScheduledPaymentType::Credit,
+            PaymentSchedule {
+                recurrence: Recurrence::Daily,
+                cron_expression: None,
+                timezone: "UTC".to_string(),
+                starts_at: None,
+            },
+        )
+        .expect("Failed to build scheduled payment");
+        save_scheduled_payment(&env.pool, &scheduled_payment)
+            .await
+            .expect("Failed to save scheduled payment");
+        // its next run can no longer be computed
+        sqlx::query("UPDATE scheduled_payment SET timezone = 'Nowhere/Invalid' WHERE id = $1")
+            .bind(&scheduled_payment.id)
+            .execute(&env.pool)
+            .await
+            .expect("Failed to corrupt scheduled payment");
+
+        let executed =
+            run_due_scheduled_payments(&env.pool, 10, &env.cassandra_session, &env.app_cxt)
+                .await
+                .expect("Failed to run scheduled payments");
+        assert_eq!(executed, 0);
+
+        let saved_payment = find_scheduled_payment_by_id(&env.pool, &scheduled_payment.id)
+            .await
+            .expect("Failed to find scheduled payment")
+            .expect("scheduled payment not found");
+        assert_eq!(saved_payment.status, ScheduledPaymentStatus::Cancelled);
+        let succeeded: Vec<bool> = sqlx::query_scalar(
+            "SELECT succeeded FROM scheduled_payment_execution WHERE scheduled_payment_id = $1",
+        )
+        .bind(&scheduled_payment.id)
+        .fetch_all(&env.pool)
+        .await
+        .expect("Failed to find executions");
+        assert_eq!(succeeded, vec![false]);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::context::{ApplicationContext, RequestContext, UserContext};
+use crate::core::Account;
+use crate::storage::{apply_cql_migrations, create_keyspace, PreparedAppStatements};
+use crate::{create_account, ApplicationConfig, RedisConfig};
+use cassandra_cpp::{Cluster, Session};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::time::Duration;
+use testcontainers::core::{IntoContainerPort, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+const POSTGRES_PORT: u16 = 5432;
+const REDIS_PORT: u16 = 6379;
+const CASSANDRA_PORT: u16 = 9042;
+
+/// Postgres, Redis and Cassandra with the schemas applied, for tests going through whole
+/// transactions. The containers are removed when the environment is dropped.
+pub struct TestEnv {
+    pub pool: PgPool,
+    pub cassandra_session: Session,
+    pub app_cxt: ApplicationContext,
+    _containers: Vec<ContainerAsync<GenericImage>>,
+}
+
+impl TestEnv {
+    pub async fn start() -> Self {
+        let postgres_container = GenericImage::new("postgres", "14")
+            .with_exposed_port(POSTGRES_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stderr(
+                "database system is ready to accept connections",
+            ))
+            .with_env_var("POSTGRES_DB", "test_db")
+            .with_env_var("POSTGRES_USER", "runner")
+            .with_env_var("POSTGRES_PASSWORD", "password")
+            .start()
+            .await
+            .expect("Failed to start postgres container");
+        let redis_container = GenericImage::new("redis", "latest")
+            .with_exposed_port(REDIS_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"))
+            .start()
+            .await
+            .expect("Failed to start redis container");
+        let cassandra_container = GenericImage::new("cassandra", "4.1")
+            .with_exposed_port(CASSANDRA_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stdout(
+                "Starting listening for CQL clients",
+            ))
+            .with_env_var("MAX_HEAP_SIZE", "512M")
+            .with_env_var("HEAP_NEWSIZE", "128M")
+            .start()
+            .await
+            .expect("Failed to start cassandra container");
+
+        let pool = connect_postgres(&postgres_container).await;
+        let cassandra_session = connect_cassandra(&cassandra_container).await;
+        create_keyspace("xrf_q3_block", 1, &cassandra_session)
+            .await
+            .expect("Failed to create keyspace");
+        apply_cql_migrations("cql", &cassandra_session)
+            .await
+            .expect("Failed to apply cql migrations");
+        let statements = PreparedAppStatements::new(&cassandra_session)
+            .await
+            .expect("Failed to prepare statements");
+
+        let redis_host = redis_container
+            .get_host()
+            .await
+            .expect("Failed to get host");
+        let redis_port = redis_container
+            .get_host_port_ipv4(REDIS_PORT)
+            .await
+            .expect("Failed to get host port");
+        let redis_config = RedisConfig::test_config(redis_port.to_string(), redis_host.to_string());
+        let app_config: ApplicationConfig = serde_json::from_value(serde_json::json!({
+            "name": "xrfq3-test",
+            "failed_attempts": { "threshold": 5, "window": 900 },
+        }))
+        .expect("Failed to build application config");
+        let app_cxt = ApplicationContext::load_test_ctx(
+            "xrfq3-test".to_string(),
+            "MexicoCentral".to_string(),
+            &redis_config,
+            statements,
+            &app_config,
+        )
+        .await
+        .expect("Failed to load application context");
+
+        TestEnv {
+            pool,
+            cassandra_session,
+            app_cxt,
+            _containers: vec![postgres_container, redis_container, cassandra_container],
+        }
+    }
+
+    /// Opens an account of `acct_type` with an empty wallet in `currency`.
+    pub async fn open_account(
+        &self,
+        user_ctx: &UserContext,
+        acct_type: &str,
+        currency: &str,
+    ) -> Account {
+        let (account, _) = create_account(
+            &self.pool,
+            currency.to_string(),
+            acct_type.to_string(),
+            None,
+            None,
+            user_ctx,
+            &self.cassandra_session,
+            &self.app_cxt,
+            test_request_context(),
+        )
+        .await
+        .expect("Failed to create account");
+        account
+    }
+}
+
+pub fn test_request_context() -> RequestContext {
+    RequestContext {
+        request_ip: None,
+        user_agent: None,
+        request_id: None,
+        service_id: None,
+        rpc_method: None,
+    }
+}
+
+async fn connect_postgres(postgres_container: &ContainerAsync<GenericImage>) -> PgPool {
+    let host = postgres_container
+        .get_host()
+        .await
+        .expect("Failed to get host");
+    let host_port = postgres_container
+        .get_host_port_ipv4(POSTGRES_PORT)
+        .await
+        .expect("Failed to get host port");
+    let db_url = format!("postgres://runner:password@{}:{}/test_db", host, host_port);
+
+    // the first "ready" log line is emitted by the init process, retry until the server is up
+    let mut attempts = 0;
+    let pool = loop {
+        match PgPoolOptions::new()
+            .max_connections(20)
+            .connect(&db_url)
+            .await
+        {
+            Ok(pool) => break pool,
+            Err(err) if attempts < 20 => {
+                attempts += 1;
+                tracing::warn!("postgres not ready yet: {}", err);
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            Err(err) => panic!("Failed to connect to postgres: {}", err),
+        }
+    };
+
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
+    pool
+}
+
+async fn connect_cassandra(cassandra_container: &ContainerAsync<GenericImage>) -> Session {
+    let host = cassandra_container
+        .get_host()
+        .await
+        .expect("Failed to get host");
+    let host_port = cassandra_container
+        .get_host_port_ipv4(CASSANDRA_PORT)
+        .await
+        .expect("Failed to get host port");
+
+    let mut cluster = Cluster::default();
+    cluster
+        .set_contact_points(&host.to_string())
+        .expect("Failed to set contact points");
+    cluster.set_port(host_port).expect("Failed to set port");
+    cluster.set_connect_timeout(Duration::from_secs(10));
+    cluster
+        .connect()
+        .await
+        .expect("Failed to connect to cassandra")
+}
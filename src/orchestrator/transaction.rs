@@ -1,17 +1,31 @@
 use crate::context::{ApplicationContext, UserContext};
-use crate::core::{AccountStatus, Currency, EntryType, MonetaryTransaction, TransactionType};
+use crate::core::{
+    Account, AccountRole, AccountStatus, AccountStatusReason, Block, EntityType, EntryType,
+    LedgerEntry, MonetaryTransaction, TransactionCursor, TransactionFilter, TransactionStatus,
+    TransactionType,
+};
 use crate::error::OrchestrateError;
-use crate::storage::{find_account_by_id, save_monetary_tx};
+use crate::orchestrator::account::apply_system_status_change;
+use crate::orchestrator::joint::{find_member_account, hold_for_approvals};
+use crate::orchestrator::limits::{
+    check_transaction_limits, enforce_debit_limits, record_limit_usage,
+};
+use crate::orchestrator::lockout::register_failed_attempt;
+use crate::storage::{
+    bulk_save_ledger, find_account_by_id, find_account_monetary_txs,
+    find_ledgers_by_transaction_id, find_monetary_tx_by_id, find_signing_policy, save_monetary_tx,
+    set_monetary_tx_block, set_monetary_txs_block, update_transaction_status,
+};
 use crate::{
-    commit_db_transaction, convert_amount, create_chained_block_chain, credit_wallet_holding,
-    debit_wallet, rollback_db_transaction, start_db_transaction,
+    audit_change, commit_db_transaction, convert_amount, create_chained_block,
+    create_chained_block_chain, credit_wallet_holding, debit_wallet, rollback_db_transaction,
+    start_db_transaction, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, SYSTEM_USER_FP,
 };
 use cassandra_cpp::Session;
-use redis::aio::ConnectionManager;
 use rust_decimal::Decimal;
 use sqlx::{PgPool, Postgres, Transaction};
 use std::str::FromStr;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub async fn debit_wallet_transaction(
     pool: &PgPool,
@@ -39,6 +53,23 @@ pub async fn debit_wallet_transaction(
         ));
     }
 
+    ////// 1. Spenders debit the account, above the signing threshold the debit waits for approvals
+    let account = find_member_account(pool, &account_id, user_ctx, AccountRole::Spender).await?;
+    if let Some(policy) = find_signing_policy(pool, &account_id).await? {
+        if policy.requires_approvals(decimal_amount) {
+            return hold_for_approvals(
+                pool,
+                decimal_amount,
+                &account,
+                transaction_type,
+                &policy,
+                user_ctx,
+                app_cxt,
+            )
+            .await;
+        }
+    }
+
     let mut ledger_desc = Vec::new();
     ledger_desc.push("debit user account".to_string());
 
@@ -48,6 +79,7 @@ pub async fn debit_wallet_transaction(
         decimal_amount,
         account_id,
         user_ctx,
+        transaction_type,
         EntryType::Debit,
         cassandra_session,
         app_cxt,
@@ -62,13 +94,181 @@ pub async fn perform_wallet_transaction(
     amount: Decimal,
     account_id: String,
     user_ctx: &UserContext,
+    tx_type: TransactionType,
     tx_entry_type: EntryType,
     cassandra_session: &Session,
     app_cxt: &ApplicationContext,
-    mut ledger_desc: Vec<String>,
+    ledger_desc: Vec<String>,
 ) -> Result<MonetaryTransaction, OrchestrateError> {
+    let (amount, commission) = split_commission(amount, app_cxt);
+    let wallet_tx =
+        MonetaryTransaction::build(amount, account_id, tx_type, TransactionStatus::Pending)
+            .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?;
+
+    settle_wallet_transaction(
+        event,
+        pool,
+        wallet_tx,
+        commission,
+        tx_entry_type,
+        ledger_desc,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+    )
+    .await
+}
+
+/// Applies a `Pending` transaction in its own DB transaction. When it can not be applied, the
+/// attempt is recorded as `Rejected`/`Failed`.
+pub async fn settle_wallet_transaction(
+    event: &str,
+    pool: &PgPool,
+    mut wallet_tx: MonetaryTransaction,
+    commission: Decimal,
+    tx_entry_type: EntryType,
+    ledger_desc: Vec<String>,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+) -> Result<MonetaryTransaction, OrchestrateError> {
+    let account_id = wallet_tx.account_id.clone();
+    let amount = wallet_tx.amount;
+    let customer_initiated = wallet_tx.transaction_type.is_customer_initiated();
+    if customer_initiated {
+        if let Err(err) =
+            check_transaction_limits(pool, &account_id, amount, &tx_entry_type, app_cxt).await
+        {
+            warn!(
+                "event={} :: transaction {} rejected: {}",
+                event, wallet_tx.id, err
+            );
+            record_unsuccessful_transaction(pool, wallet_tx, &err).await;
+            return Err(err);
+        }
+    }
+
     let mut db_tx = start_db_transaction(pool, event).await?;
-    let user_acct = match find_account_by_id(&mut *db_tx, &account_id).await? {
+    match apply_wallet_transaction(
+        &mut wallet_tx,
+        commission,
+        user_ctx,
+        tx_entry_type.clone(),
+        cassandra_session,
+        app_cxt,
+        ledger_desc,
+        &mut db_tx,
+    )
+    .await
+    {
+        Ok(block) => {
+            commit_db_transaction(db_tx, event).await?;
+            info!(
+                "successfully applied transaction {} on account {} to block {}",
+                wallet_tx.id, account_id, block.id
+            );
+            if customer_initiated {
+                record_limit_usage(&account_id, amount, &tx_entry_type, app_cxt).await;
+            }
+            Ok(wallet_tx)
+        }
+        Err(err) => {
+            error!(
+                "event={} :: failed to apply transaction {}: {}",
+                event, wallet_tx.id, err
+            );
+            rollback_db_transaction(db_tx, event).await?;
+            if customer_initiated {
+                register_failed_attempt(pool, &account_id, &err, app_cxt).await;
+            }
+            record_unsuccessful_transaction(pool, wallet_tx, &err).await;
+            Err(err)
+        }
+    }
+}
+
+/// Splits an amount into what is moved and the commission charged on it. Nothing is charged
+/// while no commission fee account is configured.
+pub fn split_commission(amount: Decimal, app_cxt: &ApplicationContext) -> (Decimal, Decimal) {
+    if app_cxt.commission.fee_account_id.is_none() {
+        return (amount, Decimal::ZERO);
+    }
+    let commission = amount * Decimal::from_str("0.001").unwrap();
+    (amount - commission, commission) // subtract commission from final amount
+}
+
+/// Applies a pending transaction inside `db_tx`: the transaction is saved as `Pending`, the wallet
+/// is mutated and the transaction is moved to `Completed` all in the same DB transaction.
+async fn apply_wallet_transaction(
+    wallet_tx: &mut MonetaryTransaction,
+    commission: Decimal,
+    user_ctx: &UserContext,
+    tx_entry_type: EntryType,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    mut ledger_desc: Vec<String>,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<Block, OrchestrateError> {
+    let account_id = wallet_tx.account_id.clone();
+    apply_wallet_mutation(
+        wallet_tx,
+        commission,
+        tx_entry_type.clone(),
+        &user_ctx.user_fp,
+        app_cxt,
+        db_tx,
+    )
+    .await?;
+    if !commission.is_zero() {
+        ledger_desc.push("charge user wallet with commission".to_string());
+    }
+
+    ///// 4. Create blockchain
+    let block = create_chained_block_chain(
+        account_id.clone(),
+        tx_entry_type,
+        user_ctx,
+        cassandra_session,
+        app_cxt,
+        ledger_desc,
+        Some(wallet_tx.id.clone()),
+        db_tx,
+    )
+    .await?;
+
+    ///// 5. Link the transaction to the block holding its ledger entries
+    if !set_monetary_tx_block(&mut **db_tx, &wallet_tx.id, &block.id).await? {
+        return Err(OrchestrateError::ServerError(
+            "could not link transaction to block".to_string(),
+        ));
+    }
+    wallet_tx.block_id = Some(block.id.clone());
+
+    ///// 6. Complete the transaction
+    change_transaction_status(
+        db_tx,
+        wallet_tx,
+        TransactionStatus::Completed,
+        &user_ctx.user_fp,
+    )
+    .await?;
+
+    Ok(block)
+}
+
+/// Steps 0-4 of a wallet transaction: checks the account and the limits of a customer debit,
+/// records the transaction as `Pending`, charges the commission, debits/credits the wallet and
+/// reactivates an inactive account. Nothing is committed here.
+pub async fn apply_wallet_mutation(
+    wallet_tx: &MonetaryTransaction,
+    commission: Decimal,
+    tx_entry_type: EntryType,
+    user_fp: &str,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), OrchestrateError> {
+    let account_id = wallet_tx.account_id.clone();
+    let user_acct = match find_account_by_id(&mut **db_tx, &account_id).await? {
         Some(acct) => acct,
         None => {
             return Err(OrchestrateError::NotFoundError(
@@ -76,69 +276,315 @@ pub async fn perform_wallet_transaction(
             ));
         }
     };
-    if user_acct.locked
-        || user_acct.status == AccountStatus::Frozen
-        || user_acct.status == AccountStatus::Inactive
-    {
+    // a customer-initiated transaction reactivates an inactive account, see step 4
+    let reactivate = user_acct.status == AccountStatus::Inactive
+        && wallet_tx.transaction_type.is_customer_initiated();
+    if !user_acct.accepts_transactions(reactivate) {
         return Err(OrchestrateError::InvalidRecordState(
-            "the user's account is locked/frozen/inactive".to_string(),
+            "the user's account is locked/frozen/inactive/closed".to_string(),
         ));
     }
+    if tx_entry_type == EntryType::Debit && wallet_tx.transaction_type.is_customer_initiated() {
+        enforce_debit_limits(db_tx, &user_acct, wallet_tx.amount, app_cxt).await?;
+    }
 
-    // 1. Charge the user account with commission
-    let commission = amount * Decimal::from_str("0.001").unwrap();
-    charge_commission(
-        &account_id,
-        commission,
-        "TODO",
-        &mut app_cxt.redis_conn.clone(),
-        &mut db_tx,
-    )
-    .await?;
+    ////// 1. Record the pending transaction, unless it was recorded while waiting for approvals
+    if save_monetary_tx(&mut **db_tx, wallet_tx).await? {
+        audit_change(
+            db_tx,
+            EntityType::Transaction,
+            &wallet_tx.id,
+            None,
+            Some(wallet_tx),
+            user_fp,
+            None,
+        )
+        .await?;
+    } else if !is_held(wallet_tx, db_tx).await? {
+        return Err(OrchestrateError::ServerError(
+            "could not save wallet transaction".to_string(),
+        ));
+    }
 
-    ////// 2. Debit user wallet
-    let amount = amount - commission; // subtract commission from final amount
-    let debit_tx = MonetaryTransaction::payment(amount, account_id.clone());
-    debit_wallet(&mut db_tx, amount, &account_id, user_acct.currency).await?;
+    ////// 2. Charge the user account with commission
+    if !commission.is_zero() {
+        charge_commission(
+            &user_acct,
+            commission,
+            &tx_entry_type,
+            user_fp,
+            app_cxt,
+            db_tx,
+        )
+        .await?;
+    }
 
-    let account_debited = save_monetary_tx(&mut *db_tx, &debit_tx).await?;
-    if !account_debited {
-        rollback_db_transaction(db_tx, event).await?;
+    ////// 3. Debit/Credit user wallet
+    let wallet_updated = match tx_entry_type {
+        EntryType::Credit => {
+            credit_wallet_holding(
+                db_tx,
+                wallet_tx.amount,
+                &account_id,
+                user_acct.currency.clone(),
+                user_fp,
+            )
+            .await?
+        }
+        _ => {
+            debit_wallet(
+                db_tx,
+                wallet_tx.amount,
+                &account_id,
+                user_acct.currency.clone(),
+                user_fp,
+            )
+            .await?
+        }
+    };
+    if !wallet_updated {
         return Err(OrchestrateError::ServerError(
-            "could not perform wallet transaction".to_string(),
+            "could not update wallet balance".to_string(),
         ));
     }
 
-    ledger_desc.push("charge user wallet with commission".to_string());
+    ////// 4. Reactivate the account, rolled back with the rest if the transaction fails
+    if reactivate {
+        apply_system_status_change(
+            user_acct,
+            AccountStatus::Active,
+            AccountStatusReason::CustomerActivity,
+            db_tx,
+        )
+        .await?;
+    }
+    Ok(())
+}
 
-    ///// 3. Create blockchain
-    let block = match create_chained_block_chain(
-        account_id.clone(),
-        tx_entry_type,
-        user_ctx,
-        cassandra_session,
-        app_cxt,
-        ledger_desc,
-        &mut db_tx,
+/// Whether `wallet_tx` is already recorded as the `Pending` transaction it is.
+async fn is_held(
+    wallet_tx: &MonetaryTransaction,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<bool, OrchestrateError> {
+    Ok(matches!(
+        find_monetary_tx_by_id(&mut **db_tx, &wallet_tx.id).await?,
+        Some(saved_tx) if saved_tx.status == TransactionStatus::Pending
+            && saved_tx.account_id == wallet_tx.account_id
+            && saved_tx.amount == wallet_tx.amount
+    ))
+}
+
+/// Debits the first transaction and credits the second, charging `commission` to the debited
+/// account only, then ledgers both legs under `description` in one block and completes them. Runs
+/// within the caller's DB transaction.
+pub(crate) async fn apply_transfer_legs(
+    transactions: &mut [MonetaryTransaction],
+    commission: Decimal,
+    description: &str,
+    user_ctx: &UserContext,
+    cassandra_session: &Session,
+    app_cxt: &ApplicationContext,
+    db_tx: &mut Transaction<'_, Postgres>,
+) -> Result<(), OrchestrateError> {
+    let mut ledgers = Vec::new();
+    let legs = [
+        (EntryType::Debit, commission),
+        (EntryType::Credit, Decimal::ZERO),
+    ];
+    for (transaction, (entry_type, commission)) in transactions.iter().zip(legs) {
+        apply_wallet_mutation(
+            transaction,
+            commission,
+            entry_type.clone(),
+            &user_ctx.user_fp,
+            app_cxt,
+            db_tx,
+        )
+        .await?;
+        let mut descriptions = vec![description.to_string()];
+        if !commission.is_zero() {
+            descriptions.push("charge user wallet with commission".to_string());
+        }
+        for description in descriptions {
+            ledgers.push(LedgerEntry::new(
+                transaction.account_id.clone(),
+                Some(description),
+                entry_type.clone(),
+                Some(transaction.id.clone()),
+            ));
+        }
+    }
+
+    let entry_ids = ledgers
+        .iter()
+        .map(|ledger| ledger.id.clone())
+        .collect::<Vec<_>>();
+    let ledgers_saved = bulk_save_ledger(&mut **db_tx, ledgers).await? as usize;
+    if ledgers_saved != entry_ids.len() {
+        return Err(OrchestrateError::InvalidRecordState(
+            "ledgers count is not equal".to_string(),
+        ));
+    }
+    let block =
+        create_chained_block(entry_ids, user_ctx, cassandra_session, app_cxt, db_tx).await?;
+    let transaction_ids = transactions
+        .iter()
+        .map(|transaction| transaction.id.clone())
+        .collect::<Vec<_>>();
+    let linked = set_monetary_txs_block(&mut **db_tx, &transaction_ids, &block.id).await?;
+    if linked as usize != transaction_ids.len() {
+        return Err(OrchestrateError::ServerError(
+            "could not link transactions to block".to_string(),
+        ));
+    }
+    for transaction in transactions.iter_mut() {
+        transaction.block_id = Some(block.id.clone());
+        change_transaction_status(
+            db_tx,
+            transaction,
+            TransactionStatus::Completed,
+            &user_ctx.user_fp,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Moves a transaction to a new status. The update only succeeds when the stored status is still the
+/// one held by `transaction`, so concurrent status changes can not silently overwrite each other.
+pub async fn change_transaction_status(
+    db_tx: &mut Transaction<'_, Postgres>,
+    transaction: &mut MonetaryTransaction,
+    status: TransactionStatus,
+    user_fp: &str,
+) -> Result<(), OrchestrateError> {
+    let saved_tx = transaction.clone();
+    let current_status = transaction.status.clone();
+    transaction
+        .change_status(status)
+        .map_err(|err| OrchestrateError::IllegalState(err.to_string()))?;
+
+    let updated = update_transaction_status(
+        &mut **db_tx,
+        &transaction.id,
+        &current_status,
+        &transaction.status,
+        transaction.modification_date,
+    )
+    .await?;
+    if !updated {
+        return Err(OrchestrateError::InvalidRecordState(format!(
+            "transaction {} is no longer {}",
+            transaction.id, current_status
+        )));
+    }
+    audit_change(
+        db_tx,
+        EntityType::Transaction,
+        &transaction.id,
+        Some(&saved_tx),
+        Some(&*transaction),
+        user_fp,
+        None,
     )
     .await
-    {
-        Ok(block) => {
-            commit_db_transaction(db_tx, event).await?;
-            block
+}
+
+/// Persists a transaction whose DB transaction was rolled back so the attempt is not lost, unless
+/// its account does not exist. Business rule violations are `Rejected`, anything else `Failed`.
+pub async fn record_unsuccessful_transaction(
+    pool: &PgPool,
+    mut transaction: MonetaryTransaction,
+    err: &OrchestrateError,
+) {
+    // an attempt on an account that does not exist is not recorded
+    match find_account_by_id(pool, &transaction.account_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            info!(
+                "transaction {} is not recorded, account {} does not exist",
+                transaction.id, transaction.account_id
+            );
+            return;
         }
-        Err(err) => {
-            error!("failed to create blockchain for debit account: {}", err);
+        Err(find_err) => {
+            error!(
+                "can not record unsuccessful transaction {}: {}",
+                transaction.id, find_err
+            );
+            return;
+        }
+    }
+
+    let status = match err {
+        OrchestrateError::InvalidArgument(_)
+        | OrchestrateError::IllegalState(_)
+        | OrchestrateError::InvalidRecordState(_)
+        | OrchestrateError::InsufficientFunds(_)
+        | OrchestrateError::UnsupportedCurrency(_)
+        | OrchestrateError::LimitExceeded(_)
+        | OrchestrateError::AmountLimitExceeded(_) => TransactionStatus::Rejected,
+        _ => TransactionStatus::Failed,
+    };
+    transaction.block_id = None;
+    let pending_tx = transaction.clone();
+    if let Err(status_err) = transaction.change_status(status) {
+        error!(
+            "can not record unsuccessful transaction {}: {}",
+            transaction.id, status_err
+        );
+        return;
+    }
+
+    let event = "recordUnsuccessfulTransaction";
+    let recorded = async {
+        let mut db_tx = start_db_transaction(pool, event).await?;
+        // a transaction held for approvals is already recorded as `Pending`
+        let saved_tx = if save_monetary_tx(&mut *db_tx, &transaction).await? {
+            None
+        } else if update_transaction_status(
+            &mut *db_tx,
+            &transaction.id,
+            &TransactionStatus::Pending,
+            &transaction.status,
+            transaction.modification_date,
+        )
+        .await?
+        {
+            Some(&pending_tx)
+        } else {
             rollback_db_transaction(db_tx, event).await?;
-            return Err(err.into());
+            return Ok(false);
+        };
+        if let Err(err) = audit_change(
+            &mut db_tx,
+            EntityType::Transaction,
+            &transaction.id,
+            saved_tx,
+            Some(&transaction),
+            SYSTEM_USER_FP,
+            None,
+        )
+        .await
+        {
+            rollback_db_transaction(db_tx, event).await?;
+            return Err(err);
         }
+        commit_db_transaction(db_tx, event).await?;
+        Ok::<_, OrchestrateError>(true)
     };
-    info!(
-        "successfully debited account {} to block {}",
-        account_id, block.id
-    );
 
-    Ok(debit_tx)
+    match recorded.await {
+        Ok(true) => info!(
+            "recorded transaction {} as {}",
+            transaction.id, transaction.status
+        ),
+        Ok(false) => error!("transaction {} was not recorded", transaction.id),
+        Err(save_err) => error!(
+            "failed to record transaction {} as {}: {}",
+            transaction.id, transaction.status, save_err
+        ),
+    }
 }
 
 pub async fn credit_wallet(
@@ -181,6 +627,7 @@ pub async fn credit_wallet(
         decimal_amount,
         account_id,
         user_ctx,
+        transaction_type,
         EntryType::Credit,
         cassandra_session,
         app_cxt,
@@ -189,59 +636,382 @@ pub async fn credit_wallet(
     .await
 }
 
+/// Pays the commission to the fee account, converted to its currency. The commission of a debit
+/// is taken from the wallet on top of the amount debited, the one of a credit was already withheld
+/// from the amount credited. `user_acct` was checked by the caller, an inactive account being
+/// reactivated by the transaction.
 async fn charge_commission(
-    acct_id: &str,
+    user_acct: &Account,
     amount: Decimal,
-    beneficiary_account_id: &str,
-    redis_conn: &mut ConnectionManager,
+    tx_entry_type: &EntryType,
+    user_fp: &str,
+    app_cxt: &ApplicationContext,
     db_tx: &mut Transaction<'_, Postgres>,
 ) -> Result<(), OrchestrateError> {
-    if amount.is_sign_negative() || amount.is_sign_positive() {
+    if amount.is_sign_negative() {
         return Err(OrchestrateError::InvalidArgument(
-            "amount must be positive".to_string(),
+            "commission can not be negative".to_string(),
         ));
     }
 
-    let user_acct = match find_account_by_id(&mut **db_tx, &acct_id).await? {
-        Some(account) => account,
+    let fee_account_id = match &app_cxt.commission.fee_account_id {
+        Some(fee_account_id) => fee_account_id,
         None => {
-            return Err(OrchestrateError::NotFoundError(
-                "account not found".to_string(),
+            return Err(OrchestrateError::InvalidRecordState(
+                "commission fee account is not configured".to_string(),
             ));
         }
     };
-    if user_acct.locked
-        || user_acct.status == AccountStatus::Frozen
-        || user_acct.status == AccountStatus::Inactive
-    {
-        return Err(OrchestrateError::InvalidRecordState(
-            "the user's account is locked/frozen/inactive".to_string(),
-        ));
-    }
-
-    let system_acct = match find_account_by_id(&mut **db_tx, &beneficiary_account_id).await? {
+    let fee_acct = match find_account_by_id(&mut **db_tx, fee_account_id).await? {
         Some(account) => account,
         None => {
             return Err(OrchestrateError::InvalidRecordState(
-                "system account not found".to_string(),
+                "commission fee account not found".to_string(),
             ));
         }
     };
-    let amount_to_save = convert_amount(
+
+    if *tx_entry_type == EntryType::Debit
+        && !debit_wallet(
+            db_tx,
+            amount,
+            &user_acct.id,
+            user_acct.currency.clone(),
+            user_fp,
+        )
+        .await?
+    {
+        return Err(OrchestrateError::ServerError(
+            "could not charge the commission".to_string(),
+        ));
+    }
+    let fee = convert_amount(
         &mut **db_tx,
         amount,
-        Currency::ADA,
-        system_acct.currency,
-        redis_conn,
+        user_acct.currency.clone(),
+        fee_acct.currency.clone(),
+        &mut app_cxt.redis_conn.clone(),
     )
     .await?;
+    if !credit_wallet_holding(db_tx, fee, &fee_acct.id, fee_acct.currency, user_fp).await? {
+        return Err(OrchestrateError::ServerError(
+            "could not pay the commission".to_string(),
+        ));
+    }
+    Ok(())
+}
 
-    credit_wallet_holding(
-        &mut *db_tx,
-        amount_to_save,
-        &system_acct.id,
-        user_acct.currency,
+/// Lists an account's transactions, newest first unless `filter.ascending` is set.
+/// Returns the page and the cursor for the next page, if there is one.
+pub async fn list_account_transactions(
+    pool: &PgPool,
+    account_id: &str,
+    filter: TransactionFilter,
+    cursor: Option<String>,
+    page_size: u32,
+    user_ctx: &UserContext,
+) -> Result<(Vec<MonetaryTransaction>, Option<String>), OrchestrateError> {
+    let cursor = match cursor.filter(|c| !c.is_empty()) {
+        Some(c) => Some(
+            TransactionCursor::decode(&c)
+                .map_err(|err| OrchestrateError::InvalidArgument(err.to_string()))?,
+        ),
+        None => None,
+    };
+    let page_size = match page_size {
+        0 => DEFAULT_PAGE_SIZE,
+        size => size.min(MAX_PAGE_SIZE),
+    } as usize;
+
+    find_member_account(pool, account_id, user_ctx, AccountRole::Viewer).await?;
+
+    // fetch one extra row to know if there is a next page
+    let mut transactions = find_account_monetary_txs(
+        pool,
+        account_id,
+        &filter,
+        cursor.as_ref(),
+        page_size as i64 + 1,
     )
     .await?;
-    Ok(())
+
+    let next_cursor = if transactions.len() > page_size {
+        transactions.truncate(page_size);
+        transactions
+            .last()
+            .map(|last_tx| TransactionCursor::new(last_tx).encode())
+    } else {
+        None
+    };
+
+    Ok((transactions, next_cursor))
+}
+
+/// Finds a transaction with the ledger entries recorded for it.
+pub async fn get_account_transaction(
+    pool: &PgPool,
+    transaction_id: &str,
+    user_ctx: &UserContext,
+) -> Result<(MonetaryTransaction, Vec<LedgerEntry>), OrchestrateError> {
+    let transaction = match find_monetary_tx_by_id(pool, transaction_id).await? {
+        Some(transaction) => transaction,
+        None => {
+            return Err(OrchestrateError::NotFoundError(
+                "transaction not found".to_string(),
+            ));
+        }
+    };
+    find_member_account(pool, &transaction.account_id, user_ctx, AccountRole::Viewer)
+        .await
+        .map_err(|_| OrchestrateError::NotFoundError("transaction not found".to_string()))?;
+
+    let ledger_entries = find_ledgers_by_transaction_id(pool, transaction_id).await?;
+    Ok((transaction, ledger_entries))
+}
+
+pub async fn find_owned_account(
+    pool: &PgPool,
+    account_id: &str,
+    user_ctx: &UserContext,
+) -> Result<Account, OrchestrateError> {
+    match find_account_by_id(pool, account_id).await? {
+        // Only owners are allowed to read or act on an account's transactions
+        Some(account) if account.user_fp == user_ctx.user_fp => Ok(account),
+        _ => Err(OrchestrateError::NotFoundError(
+            "account not found".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AccountLimitsReq;
+    use crate::orchestrator::limits::set_account_limits;
+    use crate::orchestrator::testing::{test_request_context, TestEnv};
+    use crate::{find_user_wallet_for_acct, CommissionConfig};
+    use std::sync::Arc;
+
+    /// Opens the fee account commissions are paid to.
+    async fn open_fee_account(env: &mut TestEnv) -> Account {
+        let fee_acct = env
+            .open_account(&UserContext::load_test_ctx(), "SystemFee", "USD")
+            .await;
+        env.app_cxt.commission = Arc::new(CommissionConfig {
+            fee_account_id: Some(fee_acct.id.clone()),
+        });
+        fee_acct
+    }
+
+    async fn apply_transaction(
+        env: &TestEnv,
+        account_id: &str,
+        amount: i64,
+        tx_type: TransactionType,
+        tx_entry_type: EntryType,
+        user_ctx: &UserContext,
+    ) -> MonetaryTransaction {
+        perform_wallet_transaction(
+            "test",
+            &env.pool,
+            Decimal::from(amount),
+            account_id.to_string(),
+            user_ctx,
+            tx_type,
+            tx_entry_type,
+            &env.cassandra_session,
+            &env.app_cxt,
+            vec!["test transaction".to_string()],
+        )
+        .await
+        .expect("Failed to apply transaction")
+    }
+
+    async fn balance_of(env: &TestEnv, account_id: &str) -> Decimal {
+        find_user_wallet_for_acct(&env.pool, account_id, "USD")
+            .await
+            .expect("Failed to find wallet")
+            .expect("wallet not found")
+            .balance
+    }
+
+    #[tokio::test]
+    pub async fn test_wallet_transactions_complete_and_pay_their_commission() {
+        let mut env = TestEnv::start().await;
+        let fee_acct = open_fee_account(&mut env).await;
+        let user_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&user_ctx, "Normal", "USD").await;
+
+        let credit_tx = apply_transaction(
+            &env,
+            &account.id,
+            100,
+            TransactionType::Transfer,
+            EntryType::Credit,
+            &user_ctx,
+        )
+        .await;
+        let debit_tx = apply_transaction(
+            &env,
+            &account.id,
+            50,
+            TransactionType::Payment,
+            EntryType::Debit,
+            &user_ctx,
+        )
+        .await;
+
+        for transaction in [credit_tx, debit_tx] {
+            assert_eq!(transaction.status, TransactionStatus::Completed);
+            let saved_tx = find_monetary_tx_by_id(&env.pool, &transaction.id)
+                .await
+                .expect("Failed to find transaction")
+                .expect("transaction not found");
+            assert_eq!(saved_tx.status, TransactionStatus::Completed);
+            assert!(saved_tx.block_id.is_some());
+        }
+        // 99.9 credited, 49.95 debited along with its 0.05 commission
+        assert_eq!(balance_of(&env, &account.id).await, Decimal::new(499, 1));
+        assert_eq!(balance_of(&env, &fee_acct.id).await, Decimal::new(15, 2));
+    }
+
+    #[tokio::test]
+    pub async fn test_debit_with_commission_reactivates_an_inactive_account() {
+        let mut env = TestEnv::start().await;
+        open_fee_account(&mut env).await;
+        let user_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&user_ctx, "Normal", "USD").await;
+        apply_transaction(
+            &env,
+            &account.id,
+            100,
+            TransactionType::Transfer,
+            EntryType::Credit,
+            &user_ctx,
+        )
+        .await;
+
+        let mut db_tx = env.pool.begin().await.expect("Failed to start transaction");
+        let saved_acct = find_account_by_id(&mut *db_tx, &account.id)
+            .await
+            .expect("Failed to find account")
+            .expect("account not found");
+        apply_system_status_change(
+            saved_acct,
+            AccountStatus::Inactive,
+            AccountStatusReason::Dormancy,
+            &mut db_tx,
+        )
+        .await
+        .expect("Failed to mark account inactive");
+        db_tx.commit().await.expect("Failed to commit");
+
+        let debit_tx = apply_transaction(
+            &env,
+            &account.id,
+            50,
+            TransactionType::Payment,
+            EntryType::Debit,
+            &user_ctx,
+        )
+        .await;
+        assert_eq!(debit_tx.status, TransactionStatus::Completed);
+
+        let account = find_account_by_id(&env.pool, &account.id)
+            .await
+            .expect("Failed to find account")
+            .expect("account not found");
+        assert_eq!(account.status, AccountStatus::Active);
+        assert_eq!(
+            account.status_reason,
+            Some(AccountStatusReason::CustomerActivity)
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_transactions_on_missing_accounts_are_not_recorded() {
+        let env = TestEnv::start().await;
+        let wallet_tx = MonetaryTransaction::build(
+            Decimal::from(10),
+            "missing-account".to_string(),
+            TransactionType::Payment,
+            TransactionStatus::Pending,
+        )
+        .expect("Failed to build transaction");
+        let result = settle_wallet_transaction(
+            "test",
+            &env.pool,
+            wallet_tx.clone(),
+            Decimal::ZERO,
+            EntryType::Credit,
+            vec!["test transaction".to_string()],
+            &UserContext::load_test_ctx(),
+            &env.cassandra_session,
+            &env.app_cxt,
+        )
+        .await;
+
+        assert!(matches!(result, Err(OrchestrateError::NotFoundError(_))));
+        let saved_tx = find_monetary_tx_by_id(&env.pool, &wallet_tx.id)
+            .await
+            .expect("Failed to find transaction");
+        assert!(saved_tx.is_none());
+    }
+
+    #[tokio::test]
+    pub async fn test_concurrent_debits_do_not_exceed_the_daily_limit() {
+        let env = TestEnv::start().await;
+        let user_ctx = UserContext::load_test_ctx();
+        let account = env.open_account(&user_ctx, "Normal", "USD").await;
+        apply_transaction(
+            &env,
+            &account.id,
+            1_000,
+            TransactionType::Transfer,
+            EntryType::Credit,
+            &user_ctx,
+        )
+        .await;
+        set_account_limits(
+            &env.pool,
+            &account.id,
+            AccountLimitsReq {
+                max_amount: None,
+                daily_debit: Some("100".to_string()),
+                monthly_debit: None,
+                hourly_count: None,
+            },
+            &user_ctx,
+            true,
+            test_request_context(),
+            &env.app_cxt,
+        )
+        .await
+        .expect("Failed to set account limits");
+
+        // the debits race past the early check, the one within their DB transaction stops them
+        let debits = (0..10).map(|_| {
+            perform_wallet_transaction(
+                "test",
+                &env.pool,
+                Decimal::from(20),
+                account.id.clone(),
+                &user_ctx,
+                TransactionType::Payment,
+                EntryType::Debit,
+                &env.cassandra_session,
+                &env.app_cxt,
+                vec!["test debit".to_string()],
+            )
+        });
+        let results = futures::future::join_all(debits).await;
+
+        let completed = results.iter().filter(|result| result.is_ok()).count();
+        assert_eq!(completed, 5);
+        assert!(results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .all(|err| matches!(err, OrchestrateError::LimitExceeded(_))));
+        assert_eq!(balance_of(&env, &account.id).await, Decimal::from(900));
+    }
 }
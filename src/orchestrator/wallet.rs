@@ -1,27 +1,38 @@
-use crate::core::{Currency, WalletHolding};
+use crate::core::{Currency, EntityType, WalletHolding};
 use crate::error::OrchestrateError;
-use crate::storage::{create_wallet, fetch_wallets, update_wallet_balance};
+use crate::orchestrator::audit_change;
+use crate::storage::{
+    create_wallet, fetch_wallet_for_update, fetch_wallets, update_wallet_balance,
+};
 use chrono::Utc;
 use rust_decimal::prelude::Zero;
 use rust_decimal::Decimal;
-use sqlx::{Executor, PgConnection, PgPool, Postgres, Transaction};
+use sqlx::{PgPool, Postgres, Transaction};
 use std::ops::Add;
 use std::str::FromStr;
 
-pub async fn create_wallet_holding<'a, E>(
-    pool: E,
+pub async fn create_wallet_holding(
+    db_tx: &mut Transaction<'_, Postgres>,
     acct_id: String,
     currency: Currency,
-) -> Result<Option<WalletHolding>, OrchestrateError>
-where
-    E: Executor<'a, Database = Postgres>,
-{
+    user_fp: &str,
+) -> Result<Option<WalletHolding>, OrchestrateError> {
     let wallet_holding = WalletHolding::new(acct_id, currency);
 
-    let wallet_created = create_wallet(pool, &wallet_holding).await?;
+    let wallet_created = create_wallet(&mut **db_tx, &wallet_holding).await?;
     if !wallet_created {
         return Ok(None);
     }
+    audit_change(
+        db_tx,
+        EntityType::Wallet,
+        &wallet_holding.entity_id(),
+        None,
+        Some(&wallet_holding),
+        user_fp,
+        None,
+    )
+    .await?;
 
     Ok(Some(wallet_holding))
 }
@@ -31,25 +42,24 @@ pub async fn credit_wallet_holding(
     amount: Decimal,
     acct_id: &str,
     currency: Currency,
+    user_fp: &str,
 ) -> Result<bool, OrchestrateError> {
     if amount == Decimal::zero() {
         return Err(OrchestrateError::InvalidArgument(
             "Amount cannot be zero".to_string(),
         ));
     }
-    let mut wallet_holding = match get_wallets_for_account(&mut **db_tx, &acct_id)
-        .await?
-        .into_iter()
-        .find(|w| w.currency == currency)
-    {
+    let mut wallet_holding = match fetch_wallet_for_update(db_tx, acct_id, &currency).await? {
         Some(wallet) => wallet,
         None => {
-            return Err(OrchestrateError::NotFoundError(
-                "No wallet for account found".to_string(),
-            ))
+            return Err(OrchestrateError::UnsupportedCurrency(format!(
+                "account has no {} wallet",
+                currency
+            )))
         }
     };
 
+    let saved_wallet = wallet_holding.clone();
     wallet_holding.modification_time = Utc::now();
     wallet_holding.balance = wallet_holding.balance.add(amount);
 
@@ -58,14 +68,16 @@ pub async fn credit_wallet_holding(
     if updated_wallet.balance != wallet_holding.balance {
         return Ok(false);
     }
+    audit_wallet_balance(db_tx, &saved_wallet, &updated_wallet, user_fp).await?;
     Ok(true)
 }
 
 pub async fn debit_wallet(
-    tx: &mut PgConnection,
+    db_tx: &mut Transaction<'_, Postgres>,
     amount: Decimal,
     acct_id: &str,
     currency: Currency,
+    user_fp: &str,
 ) -> Result<bool, OrchestrateError> {
     if amount == Decimal::zero() {
         return Err(OrchestrateError::InvalidArgument(
@@ -73,40 +85,53 @@ pub async fn debit_wallet(
         ));
     };
 
-    let mut wallet_holding = match get_wallets_for_account(&mut *tx, &acct_id)
-        .await?
-        .into_iter()
-        .find(|w| w.currency == currency)
-    {
+    // the wallet row stays locked until `db_tx` ends, concurrent debits wait for this one to
+    // finish before reading the balance, so two debits can't both pass the balance check.
+    let mut wallet_holding = match fetch_wallet_for_update(db_tx, acct_id, &currency).await? {
         Some(wallet) => wallet,
         None => {
-            return Err(OrchestrateError::NotFoundError(
-                "No wallet for account found".to_string(),
-            ))
+            return Err(OrchestrateError::UnsupportedCurrency(format!(
+                "account has no {} wallet",
+                currency
+            )))
         }
     };
 
-    if wallet_holding.balance < amount {
-        return Err(OrchestrateError::InvalidArgument(
-            "debit amount is higher than the balance".to_string(),
+    // the credit line of the wallet, if any, can be drawn down to its limit
+    if wallet_holding.available_balance() < amount {
+        return Err(OrchestrateError::InsufficientFunds(
+            "debit amount is higher than the available balance".to_string(),
         ));
     }
 
+    let saved_wallet = wallet_holding.clone();
     wallet_holding.modification_time = Utc::now();
     wallet_holding.balance = wallet_holding.balance - amount;
 
-    let updated_wallet = update_wallet_balance(&mut *tx, &wallet_holding).await?;
-    Ok(updated_wallet.balance == wallet_holding.balance)
+    let updated_wallet = update_wallet_balance(&mut **db_tx, &wallet_holding).await?;
+    if updated_wallet.balance != wallet_holding.balance {
+        return Ok(false);
+    }
+    audit_wallet_balance(db_tx, &saved_wallet, &updated_wallet, user_fp).await?;
+    Ok(true)
 }
 
-async fn get_wallets_for_account<'a, E>(
-    pool: E,
-    acct_id: &str,
-) -> Result<Vec<WalletHolding>, OrchestrateError>
-where
-    E: Executor<'a, Database = Postgres>,
-{
-    Ok(fetch_wallets(pool, acct_id).await?)
+async fn audit_wallet_balance(
+    db_tx: &mut Transaction<'_, Postgres>,
+    saved_wallet: &WalletHolding,
+    updated_wallet: &WalletHolding,
+    user_fp: &str,
+) -> Result<(), OrchestrateError> {
+    audit_change(
+        db_tx,
+        EntityType::Wallet,
+        &updated_wallet.entity_id(),
+        Some(saved_wallet),
+        Some(updated_wallet),
+        user_fp,
+        None,
+    )
+    .await
 }
 
 pub async fn find_user_wallet_for_acct(
@@ -129,3 +154,146 @@ pub async fn find_user_wallets_for_acct(
 ) -> Result<Vec<WalletHolding>, OrchestrateError> {
     Ok(fetch_wallets(pool, acct_id).await?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{generate_str_id, Account, AccountType};
+    use crate::storage::save_account;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+    use testcontainers::core::{IntoContainerPort, WaitFor};
+    use testcontainers::runners::AsyncRunner;
+    use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+    const POSTGRES_PORT: u16 = 5432;
+
+    async fn create_postgres_img() -> ContainerAsync<GenericImage> {
+        GenericImage::new("postgres", "14")
+            .with_exposed_port(POSTGRES_PORT.tcp())
+            .with_wait_for(WaitFor::message_on_stderr(
+                "database system is ready to accept connections",
+            ))
+            .with_env_var("POSTGRES_DB", "test_db")
+            .with_env_var("POSTGRES_USER", "runner")
+            .with_env_var("POSTGRES_PASSWORD", "password")
+            .start()
+            .await
+            .expect("Failed to start container")
+    }
+
+    async fn connect_and_migrate(postgres_container: &ContainerAsync<GenericImage>) -> PgPool {
+        let host = postgres_container
+            .get_host()
+            .await
+            .expect("Failed to get host");
+        let host_port = postgres_container
+            .get_host_port_ipv4(POSTGRES_PORT)
+            .await
+            .expect("Failed to get host port");
+        let db_url = format!("postgres://runner:password@{}:{}/test_db", host, host_port);
+
+        // the first "ready" log line is emitted by the init process, retry until the server is up
+        let mut attempts = 0;
+        let pool = loop {
+            match PgPoolOptions::new()
+                .max_connections(20)
+                .connect(&db_url)
+                .await
+            {
+                Ok(pool) => break pool,
+                Err(err) if attempts < 20 => {
+                    attempts += 1;
+                    tracing::warn!("postgres not ready yet: {}", err);
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Err(err) => panic!("Failed to connect to postgres: {}", err),
+            }
+        };
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+        pool
+    }
+
+    async fn create_funded_wallet(
+        pool: &PgPool,
+        account_id: &str,
+        currency: Currency,
+        balance: i64,
+    ) {
+        let mut wallet = WalletHolding::new(account_id.to_string(), currency);
+        wallet.balance = Decimal::from(balance);
+        assert!(create_wallet(pool, &wallet)
+            .await
+            .expect("Failed to create wallet"));
+    }
+
+    #[tokio::test]
+    pub async fn test_parallel_debits_do_not_overdraw_wallet() {
+        let postgres_container = create_postgres_img().await;
+        let pool = connect_and_migrate(&postgres_container).await;
+
+        let account = Account::new(
+            generate_str_id(),
+            "UTC".to_string(),
+            Currency::USD,
+            AccountType::Wallet,
+        );
+        assert!(save_account(&pool, &account)
+            .await
+            .expect("Failed to save account"));
+        create_funded_wallet(&pool, &account.id, Currency::USD, 100).await;
+        create_funded_wallet(&pool, &account.id, Currency::EUR, 50).await;
+
+        // 10 parallel debits of 20 against a balance of 100, only 5 can succeed
+        let mut handles = Vec::new();
+        for _ in 0..10 {
+            let pool = pool.clone();
+            let account_id = account.id.clone();
+            handles.push(tokio::spawn(async move {
+                let mut db_tx = pool.begin().await.expect("Failed to start transaction");
+                let debited = debit_wallet(
+                    &mut db_tx,
+                    Decimal::from(20),
+                    &account_id,
+                    Currency::USD,
+                    "user_fp",
+                )
+                .await;
+                match debited {
+                    Ok(true) => {
+                        db_tx.commit().await.expect("Failed to commit");
+                        true
+                    }
+                    _ => false,
+                }
+            }));
+        }
+
+        let mut successful_debits = 0;
+        for handle in handles {
+            if handle.await.expect("debit task panicked") {
+                successful_debits += 1;
+            }
+        }
+        assert_eq!(successful_debits, 5);
+
+        let wallets = fetch_wallets(&pool, &account.id)
+            .await
+            .expect("Failed to fetch wallets");
+        let usd_wallet = wallets
+            .iter()
+            .find(|w| w.currency == Currency::USD)
+            .unwrap();
+        let eur_wallet = wallets
+            .iter()
+            .find(|w| w.currency == Currency::EUR)
+            .unwrap();
+        assert_eq!(usd_wallet.balance, Decimal::zero());
+        // debiting the USD wallet must not touch the account's other wallets
+        assert_eq!(eur_wallet.balance, Decimal::from(50));
+    }
+}
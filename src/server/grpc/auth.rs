@@ -0,0 +1,530 @@
+use crate::context::CallerRoles;
+use crate::core::{AccessDenial, UserRole};
+use crate::server::grpc::permission::allowed_roles;
+use crate::server::grpc::request::request_context;
+use crate::server::grpc::ServiceIdentity;
+use crate::{
+    audit_access_denial, AuthConfig, TokenKeyConfig, UserTokenConfig, AUTHORIZATION_KEY,
+    XRF_API_KEY, XRF_USER_FINGERPRINT,
+};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha3::{Digest, Sha3_256};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+use tracing::{debug, error, warn};
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+#[derive(Deserialize)]
+pub(super) struct UserClaims {
+    pub(super) sub: String,
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+/// Verifies the signature and the claims of the user tokens with the configured token keys.
+#[derive(Clone)]
+pub(super) struct UserTokenVerifier {
+    token_keys: Arc<HashMap<String, (DecodingKey, Validation)>>,
+}
+
+impl UserTokenVerifier {
+    pub(super) fn new(config: &UserTokenConfig) -> Result<Self, String> {
+        let token_keys = config
+            .keys
+            .iter()
+            .map(|key| {
+                let mut validation = Validation::new(parse_algorithm(key)?);
+                validation.set_required_spec_claims(&["exp", "sub"]);
+                validation.leeway = config.leeway;
+                if let Some(issuer) = &config.issuer {
+                    validation.set_issuer(&[issuer]);
+                }
+                match &config.audience {
+                    Some(audience) => validation.set_audience(&[audience]),
+                    None => validation.validate_aud = false,
+                }
+                Ok((key.kid.clone(), (decoding_key(key)?, validation)))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        Ok(UserTokenVerifier {
+            token_keys: Arc::new(token_keys),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.token_keys.is_empty()
+    }
+
+    /// The claims of `token`, None when it is not signed by a configured key or its claims are
+    /// invalid.
+    pub(super) fn verify(&self, token: &str) -> Option<UserClaims> {
+        // a token without a key id is only accepted when a single key is configured
+        let header = decode_header(token).ok()?;
+        let (key, validation) = match header.kid {
+            Some(kid) => self.token_keys.get(&kid),
+            None if self.token_keys.len() == 1 => self.token_keys.values().next(),
+            None => None,
+        }?;
+        let claims = decode::<UserClaims>(token, key, validation)
+            .map_err(|err| debug!("rejected user token: {}", err))
+            .ok()?;
+        Some(claims.claims).filter(|claims| !claims.sub.is_empty())
+    }
+}
+
+/// Authenticates every call: the caller presents the API key of a registered client in
+/// `xrf-api-key`, or the client certificate of a registered service, and a signed user token in
+/// `authorization`. The token subject becomes the
+/// `xrf-user-fp` of the request, a `xrf-user-fp` sent by the caller is only accepted when it is
+/// the token subject.
+///
+/// The caller then needs one of the roles the RPC declares in [`allowed_roles`]. Its roles are
+/// the `roles` claim of its token, ***Admin*** for the `admin_user_fps`, and ***Service*** for a
+/// service identified by its client certificate. Denied calls are audited.
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    // client id by the hash of its API key
+    api_clients: Arc<HashMap<String, String>>,
+    token_verifier: UserTokenVerifier,
+    admin_user_fps: Arc<Vec<String>>,
+    // None while denials are not audited, e.g. in tests
+    audit_pool: Option<Arc<PgPool>>,
+}
+
+impl AuthInterceptor {
+    pub fn new(config: &AuthConfig, admin_user_fps: Arc<Vec<String>>) -> Result<Self, String> {
+        let api_clients = config
+            .api_clients
+            .iter()
+            .map(|client| (client.key_hash.to_lowercase(), client.client_id.clone()))
+            .collect::<HashMap<_, _>>();
+        let token_verifier = UserTokenVerifier::new(&config.token)?;
+        if api_clients.is_empty() || token_verifier.is_empty() {
+            warn!("no API clients or user token keys configured, every call will be rejected");
+        }
+
+        Ok(AuthInterceptor {
+            api_clients: Arc::new(api_clients),
+            token_verifier,
+            admin_user_fps,
+            audit_pool: None,
+        })
+    }
+
+    /// Audits the denied calls in `pool`.
+    pub fn with_denial_audit(mut self, pool: Arc<PgPool>) -> Self {
+        self.audit_pool = Some(pool);
+        self
+    }
+
+    fn authenticate_client(&self, metadata: &MetadataMap) -> Result<&str, Status> {
+        let api_key = metadata_str(metadata, XRF_API_KEY)
+            .ok_or_else(|| Status::unauthenticated(format!("missing {}", XRF_API_KEY)))?;
+        self.api_clients
+            .get(&hash_api_key(api_key))
+            .map(String::as_str)
+            .ok_or_else(|| Status::unauthenticated("invalid API key"))
+    }
+
+    fn authenticate_user(&self, metadata: &MetadataMap) -> Result<UserClaims, Status> {
+        let token = metadata_str(metadata, AUTHORIZATION_KEY)
+            .and_then(|value| value.strip_prefix(BEARER_PREFIX))
+            .ok_or_else(|| Status::unauthenticated("missing user token"))?;
+        self.token_verifier
+            .verify(token)
+            .ok_or_else(|| Status::unauthenticated("invalid user token"))
+    }
+
+    /// Every user is a ***User***, a token can't make its user a ***Service***.
+    fn resolve_roles(&self, claims: &UserClaims, is_service: bool) -> Vec<UserRole> {
+        let mut roles = vec![UserRole::User];
+        for claim in &claims.roles {
+            match UserRole::from_str(claim) {
+                Ok(UserRole::Service) => debug!("ignored the service role of a user token"),
+                Ok(role) => roles.push(role),
+                Err(_) => debug!("ignored the unknown role {} of a user token", claim),
+            }
+        }
+        if self.admin_user_fps.contains(&claims.sub) {
+            roles.push(UserRole::Admin);
+        }
+        if is_service {
+            roles.push(UserRole::Service);
+        }
+        roles.sort();
+        roles.dedup();
+        roles
+    }
+
+    fn audit_denial<T>(&self, request: &Request<T>, denial: AccessDenial, user_fp: String) {
+        let pool = match &self.audit_pool {
+            Some(pool) => pool.clone(),
+            None => return,
+        };
+        let req_context = request_context(request);
+        tokio::spawn(async move {
+            if let Err(err) = audit_access_denial(&pool, &denial, &user_fp, &req_context).await {
+                error!(
+                    "failed to audit access denial to {}: {}",
+                    denial.rpc_method, err
+                );
+            }
+        });
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        // a service identified by its client certificate needs no API key
+        let service_identity = request.extensions().get::<ServiceIdentity>();
+        let is_service = service_identity.is_some();
+        let client_id = match service_identity {
+            Some(identity) => identity.service_id.clone(),
+            None => self.authenticate_client(request.metadata())?.to_string(),
+        };
+        let claims = self.authenticate_user(request.metadata())?;
+        let roles = self.resolve_roles(&claims, is_service);
+        let user_fp = claims.sub;
+        if let Some(header_fp) = metadata_str(request.metadata(), XRF_USER_FINGERPRINT) {
+            if header_fp != user_fp {
+                warn!(
+                    "{} does not match the user token :: clientId={}",
+                    XRF_USER_FINGERPRINT, client_id
+                );
+                return Err(Status::permission_denied(format!(
+                    "{} does not match the user token",
+                    XRF_USER_FINGERPRINT
+                )));
+            }
+        }
+
+        // the RPC is only known from the request context
+        let rpc_method = request_context(&request).rpc_method.unwrap_or_default();
+        let allowed = allowed_roles(&rpc_method)
+            .is_some_and(|allowed| allowed.iter().any(|role| roles.contains(role)));
+        if !allowed {
+            warn!(
+                "caller is not allowed to call {} :: clientId={} :: roles={:?}",
+                rpc_method, client_id, roles
+            );
+            let status = Status::permission_denied(format!("not allowed to call {}", rpc_method));
+            let denial = AccessDenial {
+                rpc_method,
+                client_id,
+                roles,
+            };
+            self.audit_denial(&request, denial, user_fp);
+            return Err(status);
+        }
+
+        let header_fp = MetadataValue::try_from(user_fp.as_str())
+            .map_err(|_| Status::unauthenticated("invalid user token"))?;
+        request
+            .metadata_mut()
+            .insert(XRF_USER_FINGERPRINT, header_fp);
+        debug!("authenticated call :: clientId={}", client_id);
+        request.extensions_mut().insert(CallerRoles { roles });
+        Ok(request)
+    }
+}
+
+/// SHA3-256 of an API key, hex encoded, the form the keys are configured in.
+pub(super) fn hash_api_key(api_key: &str) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(api_key.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn metadata_str<'a>(metadata: &'a MetadataMap, key: &str) -> Option<&'a str> {
+    metadata.get(key).and_then(|value| value.to_str().ok())
+}
+
+fn parse_algorithm(key: &TokenKeyConfig) -> Result<Algorithm, String> {
+    Algorithm::from_str(&key.algorithm).map_err(|_| {
+        format!(
+            "invalid algorithm {} of token key {}",
+            key.algorithm, key.kid
+        )
+    })
+}
+
+fn decoding_key(key: &TokenKeyConfig) -> Result<DecodingKey, String> {
+    let algorithm = parse_algorithm(key)?;
+    if let Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 = algorithm {
+        let secret = key
+            .secret
+            .as_ref()
+            .ok_or_else(|| format!("token key {} has no secret", key.kid))?;
+        return Ok(DecodingKey::from_secret(secret.as_bytes()));
+    }
+
+    let pem = key
+        .public_key
+        .as_ref()
+        .ok_or_else(|| format!("token key {} has no public_key", key.kid))?
+        .as_bytes();
+    match algorithm {
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(pem),
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(pem),
+        _ => DecodingKey::from_rsa_pem(pem),
+    }
+    .map_err(|err| format!("invalid token key {}: {}", key.kid, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::RequestContext;
+    use crate::{ApiClientConfig, UserTokenConfig};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    const API_KEY: &str = "api-key";
+    const SECRET: &str = "token-secret";
+    const USER_FP: &str = "c2a9f6d81b7e4c0aa5d3f2e1b0c9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0";
+    const USER_RPC: &str = "/proto.account.v1.AccountService/FindAccountById";
+    const ADMIN_RPC: &str = "/proto.account.v1.AccountService/SetAccountLimits";
+
+    fn interceptor() -> AuthInterceptor {
+        interceptor_with_admins(vec![])
+    }
+
+    fn interceptor_with_admins(admin_user_fps: Vec<String>) -> AuthInterceptor {
+        let config = AuthConfig {
+            api_clients: vec![ApiClientConfig {
+                client_id: "backoffice".to_string(),
+                key_hash: hash_api_key(API_KEY),
+            }],
+            token: UserTokenConfig {
+                issuer: Some("xrfq3-auth".to_string()),
+                audience: Some("xrfq3".to_string()),
+                leeway: 0,
+                keys: vec![TokenKeyConfig {
+                    kid: "k1".to_string(),
+                    algorithm: "HS256".to_string(),
+                    secret: Some(SECRET.to_string()),
+                    public_key: None,
+                }],
+            },
+        };
+        AuthInterceptor::new(&config, Arc::new(admin_user_fps))
+            .expect("failed to build interceptor")
+    }
+
+    fn token(secret: &str, expires_in: i64) -> String {
+        token_with_roles(secret, expires_in, &[])
+    }
+
+    fn token_with_roles(secret: &str, expires_in: i64, roles: &[&str]) -> String {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some("k1".to_string());
+        let claims = json!({
+            "sub": USER_FP,
+            "iss": "xrfq3-auth",
+            "aud": "xrfq3",
+            "exp": chrono::Utc::now().timestamp() + expires_in,
+            "roles": roles,
+        });
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .expect("failed to sign token")
+    }
+
+    fn build_request(
+        api_key: Option<&str>,
+        token: Option<String>,
+        user_fp: Option<&str>,
+    ) -> Request<()> {
+        let mut request = Request::new(());
+        let metadata = request.metadata_mut();
+        if let Some(api_key) = api_key {
+            metadata.insert(XRF_API_KEY, api_key.parse().unwrap());
+        }
+        if let Some(token) = token {
+            metadata.insert(
+                AUTHORIZATION_KEY,
+                format!("{}{}", BEARER_PREFIX, token).parse().unwrap(),
+            );
+        }
+        if let Some(user_fp) = user_fp {
+            metadata.insert(XRF_USER_FINGERPRINT, user_fp.parse().unwrap());
+        }
+        with_rpc_method(request, USER_RPC)
+    }
+
+    fn with_rpc_method(mut request: Request<()>, rpc_method: &str) -> Request<()> {
+        request.extensions_mut().insert(RequestContext {
+            request_ip: None,
+            user_agent: None,
+            request_id: None,
+            service_id: None,
+            rpc_method: Some(rpc_method.to_string()),
+        });
+        request
+    }
+
+    #[test]
+    fn test_token_subject_becomes_the_user_fingerprint() {
+        let request = interceptor()
+            .call(build_request(Some(API_KEY), Some(token(SECRET, 60)), None))
+            .expect("request rejected");
+        assert_eq!(
+            metadata_str(request.metadata(), XRF_USER_FINGERPRINT),
+            Some(USER_FP)
+        );
+
+        let request = interceptor().call(build_request(
+            Some(API_KEY),
+            Some(token(SECRET, 60)),
+            Some(USER_FP),
+        ));
+        assert!(request.is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_clients_and_invalid_tokens() {
+        let code = |request| interceptor().call(request).unwrap_err().code();
+        assert_eq!(
+            code(build_request(None, Some(token(SECRET, 60)), None)),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            code(build_request(
+                Some("other-key"),
+                Some(token(SECRET, 60)),
+                None
+            )),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            code(build_request(Some(API_KEY), None, None)),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            code(build_request(
+                Some(API_KEY),
+                Some(token("other-secret", 60)),
+                None
+            )),
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            code(build_request(Some(API_KEY), Some(token(SECRET, -60)), None)),
+            tonic::Code::Unauthenticated
+        );
+    }
+
+    #[test]
+    fn test_service_identity_replaces_the_api_key() {
+        let mut request = build_request(None, Some(token(SECRET, 60)), None);
+        request.extensions_mut().insert(ServiceIdentity {
+            service_id: "payments".to_string(),
+        });
+        assert!(interceptor().call(request).is_ok());
+
+        let mut request = build_request(None, None, None);
+        request.extensions_mut().insert(ServiceIdentity {
+            service_id: "payments".to_string(),
+        });
+        assert_eq!(
+            interceptor().call(request).unwrap_err().code(),
+            tonic::Code::Unauthenticated
+        );
+    }
+
+    #[test]
+    fn test_rpcs_are_only_open_to_their_roles() {
+        let request = interceptor()
+            .call(build_request(Some(API_KEY), Some(token(SECRET, 60)), None))
+            .expect("request rejected");
+        let caller = request
+            .extensions()
+            .get::<CallerRoles>()
+            .expect("no caller roles");
+        assert_eq!(caller.roles, vec![UserRole::User]);
+
+        let request = with_rpc_method(
+            build_request(Some(API_KEY), Some(token(SECRET, 60)), None),
+            ADMIN_RPC,
+        );
+        assert_eq!(
+            interceptor().call(request).unwrap_err().code(),
+            tonic::Code::PermissionDenied
+        );
+
+        let request = with_rpc_method(
+            build_request(
+                Some(API_KEY),
+                Some(token_with_roles(SECRET, 60, &["admin", "unknown"])),
+                None,
+            ),
+            ADMIN_RPC,
+        );
+        let request = interceptor().call(request).expect("request rejected");
+        assert!(request
+            .extensions()
+            .get::<CallerRoles>()
+            .is_some_and(CallerRoles::is_admin));
+
+        // a token can't make its user a service, an RPC without permissions is closed
+        let request = with_rpc_method(
+            build_request(
+                Some(API_KEY),
+                Some(token_with_roles(SECRET, 60, &["service"])),
+                None,
+            ),
+            USER_RPC,
+        );
+        let request = interceptor().call(request).expect("request rejected");
+        assert_eq!(
+            request
+                .extensions()
+                .get::<CallerRoles>()
+                .map(|caller| caller.roles.clone()),
+            Some(vec![UserRole::User])
+        );
+        let request = with_rpc_method(
+            build_request(Some(API_KEY), Some(token(SECRET, 60)), None),
+            "/proto.account.v1.AccountService/Unknown",
+        );
+        assert!(interceptor().call(request).is_err());
+    }
+
+    #[test]
+    fn test_admin_user_fps_are_admins() {
+        let request = with_rpc_method(
+            build_request(Some(API_KEY), Some(token(SECRET, 60)), None),
+            ADMIN_RPC,
+        );
+        assert!(interceptor_with_admins(vec![USER_FP.to_string()])
+            .call(request)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rejects_a_user_fingerprint_other_than_the_token_subject() {
+        let other_fp = "f".repeat(64);
+        let status = interceptor()
+            .call(build_request(
+                Some(API_KEY),
+                Some(token(SECRET, 60)),
+                Some(&other_fp),
+            ))
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    }
+}
@@ -30,6 +30,8 @@ pub fn get_header_value(metadata_map: &MetadataMap, header_name: &str) -> Option
     }
 }
 
+/// The user fingerprint of the call, set by [`crate::server::grpc::AuthInterceptor`] from the
+/// user token.
 pub fn get_xrf_user_auth_header(
     metadata_map: &MetadataMap,
     header_name: &str,
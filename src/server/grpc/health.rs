@@ -0,0 +1,118 @@
+use crate::core::DependencyHealth;
+use crate::grpc_services::{
+    account_service_server, audit_service_server, transaction_service_server,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+/// Health service name for orchestrator liveness checks: the process serves gRPC, whatever the
+/// state of its dependencies. A server failing it is restarted.
+pub const LIVENESS_SERVICE: &str = "liveness";
+/// Health service name for orchestrator readiness checks: every dependency is up. A server
+/// failing it is taken out of load balancing, not restarted.
+pub const READINESS_SERVICE: &str = "readiness";
+// the gRPC infrastructure services: health checking and server reflection
+const INFRASTRUCTURE_PATH_PREFIX: &str = "/grpc.";
+
+/// Whether `rpc_method` belongs to a gRPC infrastructure service, which is neither
+/// authenticated nor rate limited so that probes and tooling always reach it.
+pub fn is_infrastructure_rpc(rpc_method: &str) -> bool {
+    rpc_method.starts_with(INFRASTRUCTURE_PATH_PREFIX)
+}
+
+/// The serving status published through `grpc.health.v1`, set from the dependency probes. The
+/// empty service name is the overall status and follows readiness.
+#[derive(Clone)]
+pub struct ServiceHealth {
+    reporter: HealthReporter,
+    ready: Arc<AtomicBool>,
+}
+
+impl ServiceHealth {
+    /// Not ready, and the services unknown, until the health worker reports its first probes.
+    pub fn new(reporter: HealthReporter) -> Self {
+        ServiceHealth {
+            reporter,
+            ready: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub async fn report(&self, health: &DependencyHealth) {
+        self.ready.store(health.is_ready(), Ordering::Relaxed);
+        for (service, serving) in service_statuses(health) {
+            let status = if serving {
+                ServingStatus::Serving
+            } else {
+                ServingStatus::NotServing
+            };
+            self.reporter.set_service_status(service, status).await;
+        }
+    }
+}
+
+/// Whether each health service name is serving with the dependencies in `health`.
+fn service_statuses(health: &DependencyHealth) -> [(&'static str, bool); 6] {
+    let ready = health.is_ready();
+    [
+        ("", ready),
+        (READINESS_SERVICE, ready),
+        (LIVENESS_SERVICE, true),
+        // the ledger reads accounts from Postgres, chains blocks in Cassandra and locks in Redis
+        (account_service_server::SERVICE_NAME, ready),
+        (transaction_service_server::SERVICE_NAME, ready),
+        // audit logs are kept in Postgres and anchored in Cassandra
+        (
+            audit_service_server::SERVICE_NAME,
+            health.postgres && health.cassandra,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_of(health: &DependencyHealth, service: &str) -> bool {
+        service_statuses(health)
+            .into_iter()
+            .find(|(name, _)| *name == service)
+            .map(|(_, serving)| serving)
+            .expect("unknown health service")
+    }
+
+    #[test]
+    fn test_liveness_does_not_follow_dependencies() {
+        let down = DependencyHealth::default();
+        assert!(status_of(&down, LIVENESS_SERVICE));
+        assert!(!status_of(&down, READINESS_SERVICE));
+        assert!(!status_of(&down, ""));
+    }
+
+    #[test]
+    fn test_services_follow_their_dependencies() {
+        let redis_down = DependencyHealth {
+            postgres: true,
+            cassandra: true,
+            redis: false,
+        };
+        assert!(!status_of(&redis_down, READINESS_SERVICE));
+        assert!(!status_of(
+            &redis_down,
+            "proto.transaction.v1.TransactionService"
+        ));
+        assert!(status_of(&redis_down, "proto.audit.v1.AuditService"));
+
+        let up = DependencyHealth {
+            redis: true,
+            ..redis_down
+        };
+        assert!(status_of(&up, ""));
+        assert!(status_of(&up, "proto.account.v1.AccountService"));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::context::RequestContext;
+use crate::server::grpc::is_infrastructure_rpc;
+use crate::ServiceIdentityConfig;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::codegen::http::{Request as HttpRequest, Response};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::{debug, warn};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+/// The internal service a request was made by, authenticated by its client certificate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceIdentity {
+    pub service_id: String,
+}
+
+/// Maps the client certificate of a request to a registered service, whose id is then set on the
+/// [`RequestContext`] and as a [`ServiceIdentity`] extension. A certificate of no registered
+/// service, or a call to an RPC the service is not allowed, is rejected. Requests without a client
+/// certificate pass through, they are authenticated with an API key.
+#[derive(Clone, Default)]
+pub struct ServiceIdentityLayer {
+    services: Arc<Vec<ServiceIdentityConfig>>,
+}
+
+impl ServiceIdentityLayer {
+    pub fn new(services: Vec<ServiceIdentityConfig>) -> Self {
+        ServiceIdentityLayer {
+            services: Arc::new(services),
+        }
+    }
+}
+
+impl<S> Layer<S> for ServiceIdentityLayer {
+    type Service = ServiceIdentityService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ServiceIdentityService {
+            inner,
+            services: self.services.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ServiceIdentityService<S> {
+    inner: S,
+    services: Arc<Vec<ServiceIdentityConfig>>,
+}
+
+impl<S> ServiceIdentityService<S> {
+    fn identify<B>(&self, request: &HttpRequest<B>) -> Result<Option<ServiceIdentity>, Status> {
+        let certs = match request
+            .extensions()
+            .get::<TlsConnectInfo<TcpConnectInfo>>()
+            .and_then(|info| info.peer_certs())
+        {
+            Some(certs) => certs,
+            None => return Ok(None),
+        };
+        // the first certificate is the client's own, the others its chain
+        let leaf = match certs.first() {
+            Some(leaf) => leaf,
+            None => return Ok(None),
+        };
+        let names = certificate_names(leaf).map_err(|err| {
+            warn!("failed to read client certificate: {}", err);
+            Status::unauthenticated("invalid client certificate")
+        })?;
+
+        let service = resolve_service(&self.services, &names).ok_or_else(|| {
+            warn!(
+                "client certificate of no registered service :: names={:?}",
+                names
+            );
+            Status::permission_denied("client certificate of no registered service")
+        })?;
+        let path = request.uri().path();
+        // every service may check the health of the server
+        if !service.allows(path) && !is_infrastructure_rpc(path) {
+            warn!(
+                "service is not allowed to call {} :: serviceId={}",
+                path, service.service_id
+            );
+            return Err(Status::permission_denied(format!(
+                "service {} is not allowed to call {}",
+                service.service_id, path
+            )));
+        }
+        Ok(Some(ServiceIdentity {
+            service_id: service.service_id.clone(),
+        }))
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for ServiceIdentityService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: HttpRequest<ReqBody>) -> Self::Future {
+        match self.identify(&request) {
+            Ok(Some(identity)) => {
+                debug!(
+                    "identified client certificate :: serviceId={}",
+                    identity.service_id
+                );
+                if let Some(req_context) = request.extensions_mut().get_mut::<RequestContext>() {
+                    req_context.service_id = Some(identity.service_id.clone());
+                }
+                request.extensions_mut().insert(identity);
+            }
+            Ok(None) => {}
+            Err(status) => return Box::pin(async move { Ok(status.into_http()) }),
+        }
+
+        // the service that was polled ready handles this request, a clone takes its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move { inner.call(request).await })
+    }
+}
+
+/// The subject common names and the DNS and URI subject alternative names of a DER certificate.
+fn certificate_names(der: &[u8]) -> Result<Vec<String>, String> {
+    let (_, cert) = parse_x509_certificate(der).map_err(|err| err.to_string())?;
+    let mut names = cert
+        .subject()
+        .iter_common_name()
+        .filter_map(|name| name.as_str().ok())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if let Some(san) = cert
+        .subject_alternative_name()
+        .map_err(|err| err.to_string())?
+    {
+        names.extend(
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(name) | GeneralName::URI(name) => Some(name.to_string()),
+                    _ => None,
+                }),
+        );
+    }
+    Ok(names)
+}
+
+fn resolve_service<'a>(
+    services: &'a [ServiceIdentityConfig],
+    names: &[String],
+) -> Option<&'a ServiceIdentityConfig> {
+    services
+        .iter()
+        .find(|service| service.names.iter().any(|name| names.contains(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn services() -> Vec<ServiceIdentityConfig> {
+        vec![
+            ServiceIdentityConfig {
+                service_id: "payments".to_string(),
+                names: vec![
+                    "payments.internal".to_string(),
+                    "spiffe://xrf/payments".to_string(),
+                ],
+                allowed_rpcs: vec![
+                    "/proto.transaction.v1.TransactionService/*".to_string(),
+                    "/proto.account.v1.AccountService/FindAccountById".to_string(),
+                ],
+            },
+            ServiceIdentityConfig {
+                service_id: "reporting".to_string(),
+                names: vec!["reporting.internal".to_string()],
+                allowed_rpcs: vec![],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_certificate_names_resolve_to_a_registered_service() {
+        let services = services();
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            resolve_service(&services, &names(&["spiffe://xrf/payments"]))
+                .map(|service| service.service_id.as_str()),
+            Some("payments")
+        );
+        assert_eq!(
+            resolve_service(&services, &names(&["other", "reporting.internal"]))
+                .map(|service| service.service_id.as_str()),
+            Some("reporting")
+        );
+        assert!(resolve_service(&services, &names(&["payments"])).is_none());
+        assert!(resolve_service(&services, &[]).is_none());
+    }
+
+    #[test]
+    fn test_services_only_call_their_allowed_rpcs() {
+        let services = services();
+        let payments = &services[0];
+        assert!(payments.allows("/proto.transaction.v1.TransactionService/InternalTransfer"));
+        assert!(payments.allows("/proto.account.v1.AccountService/FindAccountById"));
+        assert!(!payments.allows("/proto.account.v1.AccountService/FreezeAccount"));
+        assert!(!payments.allows("/proto.transaction.v1.TransactionServiceX/InternalTransfer"));
+        assert!(!services[1].allows("/proto.audit.v1.AuditService/SearchAudit"));
+    }
+}
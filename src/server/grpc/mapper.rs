@@ -0,0 +1,42 @@
+use crate::error::OrchestrateError;
+use chrono::{DateTime, Utc};
+use prost_types::Timestamp;
+use tonic::Status;
+use tracing::error;
+
+pub fn map_orchestrator_err_to_grpc_error(event: &str, err: OrchestrateError) -> Status {
+    const INTERNAL_SERVER_ERR: &str = "Internal server error";
+    match err {
+        OrchestrateError::InvalidArgument(err) => Status::invalid_argument(err.to_string()),
+        OrchestrateError::NotFoundError(err) => Status::not_found(format!("Not found: {}", err)),
+        OrchestrateError::DatabaseError(err) => {
+            error!("event={} :: database error: {}", event, err);
+            Status::internal(INTERNAL_SERVER_ERR)
+        }
+        OrchestrateError::RecordAlreadyExists(err) => Status::already_exists(err.to_string()),
+        OrchestrateError::IllegalState(err) => Status::failed_precondition(err.to_string()),
+        OrchestrateError::PermissionDenied(err) => Status::permission_denied(err.to_string()),
+        OrchestrateError::InsufficientFunds(err) => Status::failed_precondition(err.to_string()),
+        OrchestrateError::UnsupportedCurrency(err) => Status::invalid_argument(err.to_string()),
+        OrchestrateError::LimitExceeded(err) => Status::resource_exhausted(err.to_string()),
+        OrchestrateError::AmountLimitExceeded(err) => Status::failed_precondition(err.to_string()),
+        _ => Status::internal(INTERNAL_SERVER_ERR),
+    }
+}
+
+pub fn to_grpc_timestamp(time: &DateTime<Utc>) -> Timestamp {
+    Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub fn from_grpc_timestamp(timestamp: Option<Timestamp>) -> Result<Option<DateTime<Utc>>, Status> {
+    match timestamp {
+        None => Ok(None),
+        Some(ts) => match DateTime::from_timestamp(ts.seconds, ts.nanos as u32) {
+            Some(time) => Ok(Some(time)),
+            None => Err(Status::invalid_argument("invalid timestamp".to_string())),
+        },
+    }
+}
@@ -1,5 +1,19 @@
+mod auth;
 mod header;
+mod health;
+mod identity;
 mod macros;
+mod mapper;
+mod permission;
+mod rate_limit;
+mod request;
 mod services;
 
-pub use services::{AccountServiceManager, AppServiceManager};
+pub use auth::AuthInterceptor;
+pub use health::{is_infrastructure_rpc, ServiceHealth};
+pub use identity::{ServiceIdentity, ServiceIdentityLayer};
+pub use rate_limit::RateLimitLayer;
+pub use request::RequestContextLayer;
+pub use services::{
+    AccountServiceManager, AppServiceManager, AuditServiceManager, TransactionServiceManager,
+};
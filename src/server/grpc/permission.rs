@@ -0,0 +1,162 @@
+use crate::core::UserRole;
+
+const USERS: &[UserRole] = &[UserRole::User];
+// support reads the accounts of the users it helps, it never moves their money
+const READERS: &[UserRole] = &[UserRole::User, UserRole::Support];
+const AUDITORS: &[UserRole] = &[UserRole::Support, UserRole::Compliance, UserRole::Admin];
+const COMPLIANCE: &[UserRole] = &[UserRole::Compliance, UserRole::Admin];
+const ADMINS: &[UserRole] = &[UserRole::Admin];
+
+// the methods of a service and the roles allowed to call each of them
+type ServicePermissions = (&'static str, &'static [(&'static str, &'static [UserRole])]);
+
+/// The roles allowed to call each RPC, by service and method. A call is allowed when its caller
+/// holds one of them. The handlers still check the caller owns or shares what it acts on.
+const RPC_PERMISSIONS: &[ServicePermissions] = &[
+    (
+        "proto.account.v1.AccountService",
+        &[
+            ("FindWallet", READERS),
+            ("LockAccount", USERS),
+            ("UpdateAccount", USERS),
+            // freezing or unfreezing an account is an investigation measure, not a user action
+            ("FreezeAccount", COMPLIANCE),
+            ("CloseAccount", USERS),
+            ("SetAccountLimits", ADMINS),
+            ("FindAccountLimits", READERS),
+            ("SetOverdraftLimit", ADMINS),
+            ("ListChildAccounts", READERS),
+            ("FindConsolidatedBalance", READERS),
+            ("FindAccruedInterest", READERS),
+            ("AddAccountMember", USERS),
+            ("RemoveAccountMember", USERS),
+            ("ListAccountMembers", READERS),
+            ("SetSigningPolicy", USERS),
+            ("CreateAccount", USERS),
+            ("FindAccountById", READERS),
+            ("FindAccountsByCurrencyOrType", READERS),
+            ("FindAccountByCurrencyAndType", READERS),
+        ],
+    ),
+    (
+        "proto.audit.v1.AuditService",
+        &[
+            ("GetEntityHistory", AUDITORS),
+            ("SearchAudit", AUDITORS),
+            ("VerifyAuditChain", AUDITORS),
+            ("ListAuditArchives", AUDITORS),
+            ("RestoreAuditArchive", ADMINS),
+            ("ReleaseAuditArchive", ADMINS),
+        ],
+    ),
+    (
+        "proto.transaction.v1.TransactionService",
+        &[
+            ("GetTransaction", READERS),
+            ("ListTransactions", READERS),
+            ("CreateScheduledPayment", USERS),
+            ("ListScheduledPayments", READERS),
+            ("CancelScheduledPayment", USERS),
+            ("SubmitBatch", USERS),
+            ("InternalTransfer", USERS),
+            ("DebitAccount", USERS),
+            ("ApproveTransaction", USERS),
+        ],
+    ),
+];
+
+/// The roles allowed to call `rpc_method`, a full path such as
+/// `/proto.account.v1.AccountService/FindWallet`. None for an RPC without declared permissions,
+/// which no one may call.
+pub fn allowed_roles(rpc_method: &str) -> Option<&'static [UserRole]> {
+    let (service, method) = rpc_method.strip_prefix('/')?.split_once('/')?;
+    RPC_PERMISSIONS
+        .iter()
+        .find(|(name, _)| *name == service)?
+        .1
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, roles)| *roles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the protos of the served services
+    const PROTOS: [(&str, &str); 3] = [
+        (
+            "proto.account.v1",
+            include_str!("../../../proto/account/v1/account.proto"),
+        ),
+        (
+            "proto.audit.v1",
+            include_str!("../../../proto/audit/v1/audit.proto"),
+        ),
+        (
+            "proto.transaction.v1",
+            include_str!("../../../proto/transaction/v1/transaction.proto"),
+        ),
+    ];
+
+    #[test]
+    fn test_every_rpc_declares_its_permissions() {
+        for (package, proto) in PROTOS {
+            let mut service = "";
+            for line in proto.lines().map(str::trim) {
+                if let Some(name) = line.strip_prefix("service ") {
+                    service = name.trim_end_matches('{').trim();
+                } else if let Some(rpc) = line.strip_prefix("rpc ") {
+                    let method = rpc.split('(').next().unwrap_or_default().trim();
+                    let path = format!("/{}.{}/{}", package, service, method);
+                    assert!(
+                        allowed_roles(&path).is_some(),
+                        "{} has no permissions",
+                        path
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_admin_operations_are_not_open_to_users() {
+        for rpc_method in [
+            "/proto.account.v1.AccountService/FreezeAccount",
+            "/proto.account.v1.AccountService/SetAccountLimits",
+            "/proto.account.v1.AccountService/SetOverdraftLimit",
+            "/proto.audit.v1.AuditService/SearchAudit",
+        ] {
+            let roles = allowed_roles(rpc_method).expect("no permissions declared");
+            assert!(!roles.contains(&UserRole::User), "{}", rpc_method);
+        }
+        assert_eq!(
+            allowed_roles("/proto.account.v1.AccountService/Unknown"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_support_only_reads() {
+        let support = [UserRole::Support];
+        let granted = |rpc_method: &str| {
+            allowed_roles(rpc_method).is_some_and(|roles| roles.iter().any(|r| support.contains(r)))
+        };
+        for rpc_method in [
+            "/proto.account.v1.AccountService/FindWallet",
+            "/proto.account.v1.AccountService/FindAccountById",
+            "/proto.transaction.v1.TransactionService/ListTransactions",
+            "/proto.audit.v1.AuditService/GetEntityHistory",
+        ] {
+            assert!(granted(rpc_method), "{}", rpc_method);
+        }
+        for rpc_method in [
+            "/proto.account.v1.AccountService/CloseAccount",
+            "/proto.account.v1.AccountService/FreezeAccount",
+            "/proto.transaction.v1.TransactionService/InternalTransfer",
+            "/proto.audit.v1.AuditService/ReleaseAuditArchive",
+        ] {
+            assert!(!granted(rpc_method), "{}", rpc_method);
+        }
+    }
+}
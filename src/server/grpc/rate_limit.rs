@@ -0,0 +1,339 @@
+use crate::server::grpc::auth::{hash_api_key, UserTokenVerifier};
+use crate::server::grpc::{is_infrastructure_rpc, ServiceIdentity};
+use crate::storage::take_rate_limit_tokens;
+use crate::{
+    AuthConfig, RateLimitBucketsConfig, RateLimitConfig, TokenBucketConfig, AUTHORIZATION_KEY,
+    RATE_LIMIT_KEY_PREFIX, XRF_API_KEY,
+};
+use redis::aio::ConnectionManager;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::codegen::http::{HeaderMap, Request as HttpRequest, Response};
+use tonic::metadata::MetadataValue;
+use tonic::Status;
+use tower::{Layer, Service};
+use tracing::warn;
+
+const RETRY_AFTER_KEY: &str = "retry-after";
+// the bucket of the callers whose API key is missing or unknown
+const ANONYMOUS_CLIENT: &str = "anonymous";
+// methods moving money between accounts, or out of them
+const MONEY_MOVEMENT_METHODS: [&str; 6] = [
+    "InternalTransfer",
+    "DebitAccount",
+    "SubmitBatch",
+    "CreateScheduledPayment",
+    "ApproveTransaction",
+    "CloseAccount",
+];
+const READ_METHOD_PREFIXES: [&str; 5] = ["Find", "Get", "List", "Search", "Verify"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RpcClass {
+    Read,
+    Write,
+    MoneyMovement,
+}
+
+impl RpcClass {
+    fn of(rpc_method: &str) -> Self {
+        let method = rpc_method.rsplit('/').next().unwrap_or_default();
+        if MONEY_MOVEMENT_METHODS.contains(&method) {
+            RpcClass::MoneyMovement
+        } else if READ_METHOD_PREFIXES
+            .iter()
+            .any(|prefix| method.starts_with(prefix))
+        {
+            RpcClass::Read
+        } else {
+            RpcClass::Write
+        }
+    }
+
+    fn bucket<'a>(&self, buckets: &'a RateLimitBucketsConfig) -> &'a TokenBucketConfig {
+        match self {
+            RpcClass::Read => &buckets.read,
+            RpcClass::Write => &buckets.write,
+            RpcClass::MoneyMovement => &buckets.money_movement,
+        }
+    }
+}
+
+/// Rate limits calls with token buckets kept in Redis, one per API client and RPC method and one
+/// per user of the client and RPC method. A limited call gets `RESOURCE_EXHAUSTED` with the
+/// seconds to wait in `retry-after`.
+///
+/// Calls are limited before they are authenticated, so a flood of calls never reaches the
+/// database. The client is the registered client of the API key or the service of the client
+/// certificate, the user is the subject of the user token once its signature is verified. A call
+/// without a valid token, or with a `xrf-user-fp` alone, only takes from the bucket of its client.
+/// Calls go through when Redis fails.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    config: Option<Arc<RateLimitConfig>>,
+    // client id by the hash of its API key
+    api_clients: Arc<HashMap<String, String>>,
+    token_verifier: UserTokenVerifier,
+    redis_conn: ConnectionManager,
+}
+
+impl RateLimitLayer {
+    /// Calls are not limited when `config` is None.
+    pub fn new(
+        config: Option<RateLimitConfig>,
+        auth: &AuthConfig,
+        redis_conn: ConnectionManager,
+    ) -> Result<Self, String> {
+        if let Some(config) = &config {
+            for buckets in [&config.per_client, &config.per_user] {
+                for bucket in [&buckets.read, &buckets.write, &buckets.money_movement] {
+                    if bucket.capacity == 0 || bucket.refill_per_second <= 0.0 {
+                        return Err(format!("invalid rate limit bucket {:?}", bucket));
+                    }
+                }
+            }
+        }
+        let api_clients = auth
+            .api_clients
+            .iter()
+            .map(|client| (client.key_hash.to_lowercase(), client.client_id.clone()))
+            .collect::<HashMap<_, _>>();
+
+        Ok(RateLimitLayer {
+            config: config.map(Arc::new),
+            api_clients: Arc::new(api_clients),
+            token_verifier: UserTokenVerifier::new(&auth.token)?,
+            redis_conn,
+        })
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            config: self.config.clone(),
+            api_clients: self.api_clients.clone(),
+            token_verifier: self.token_verifier.clone(),
+            redis_conn: self.redis_conn.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    config: Option<Arc<RateLimitConfig>>,
+    api_clients: Arc<HashMap<String, String>>,
+    token_verifier: UserTokenVerifier,
+    redis_conn: ConnectionManager,
+}
+
+impl<S> RateLimitService<S> {
+    fn client_id<B>(&self, request: &HttpRequest<B>) -> String {
+        if let Some(identity) = request.extensions().get::<ServiceIdentity>() {
+            return identity.service_id.clone();
+        }
+        header_str(request.headers(), XRF_API_KEY)
+            .and_then(|api_key| self.api_clients.get(&hash_api_key(api_key)))
+            .cloned()
+            .unwrap_or_else(|| ANONYMOUS_CLIENT.to_string())
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for RateLimitService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: HttpRequest<ReqBody>) -> Self::Future {
+        // the service that was polled ready handles this request, a clone takes its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = match &self.config {
+            Some(config) if !is_infrastructure_rpc(request.uri().path()) => config.clone(),
+            // health checks and reflection are never limited
+            _ => return Box::pin(async move { inner.call(request).await }),
+        };
+
+        let rpc_method = request.uri().path().to_string();
+        let client_id = self.client_id(&request);
+        let user_fp = verified_user_fp(&self.token_verifier, request.headers());
+        let mut redis_conn = self.redis_conn.clone();
+        Box::pin(async move {
+            let class = RpcClass::of(&rpc_method);
+            let mut buckets = vec![(
+                bucket_key(&client_id, None, &rpc_method),
+                class.bucket(&config.per_client),
+            )];
+            if let Some(user_fp) = &user_fp {
+                buckets.push((
+                    bucket_key(&client_id, Some(user_fp), &rpc_method),
+                    class.bucket(&config.per_user),
+                ));
+            }
+
+            match take_rate_limit_tokens(&buckets, &mut redis_conn).await {
+                Ok(0) => {}
+                Ok(retry_after_ms) => {
+                    warn!(
+                        "rate limited {} :: clientId={} :: retryAfterMs={}",
+                        rpc_method, client_id, retry_after_ms
+                    );
+                    return Ok(rate_limited(retry_after_ms).into_http());
+                }
+                // a rate limiter down does not take the API down with it
+                Err(err) => warn!("calls are not rate limited: {}", err),
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+}
+
+/// The subject of the user token once verified, the call is authorized later on.
+fn verified_user_fp(token_verifier: &UserTokenVerifier, headers: &HeaderMap) -> Option<String> {
+    let token = header_str(headers, AUTHORIZATION_KEY)?.strip_prefix("Bearer ")?;
+    token_verifier.verify(token).map(|claims| claims.sub)
+}
+
+fn bucket_key(client_id: &str, user_fp: Option<&str>, rpc_method: &str) -> String {
+    match user_fp {
+        Some(user_fp) => format!(
+            "{}:{}:{}:{}",
+            RATE_LIMIT_KEY_PREFIX, client_id, user_fp, rpc_method
+        ),
+        None => format!("{}:{}:{}", RATE_LIMIT_KEY_PREFIX, client_id, rpc_method),
+    }
+}
+
+fn rate_limited(retry_after_ms: u64) -> Status {
+    let mut status = Status::resource_exhausted("rate limit exceeded, retry later");
+    // whole seconds, rounded up so a retry on time is not limited again
+    let retry_after = retry_after_ms.div_ceil(1000).max(1);
+    status
+        .metadata_mut()
+        .insert(RETRY_AFTER_KEY, MetadataValue::from(retry_after));
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TokenKeyConfig, UserTokenConfig, XRF_USER_FINGERPRINT};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use tonic::codegen::http::HeaderValue;
+
+    #[test]
+    fn test_rpcs_are_limited_by_class() {
+        assert_eq!(
+            RpcClass::of("/proto.transaction.v1.TransactionService/InternalTransfer"),
+            RpcClass::MoneyMovement
+        );
+        assert_eq!(
+            RpcClass::of("/proto.account.v1.AccountService/CloseAccount"),
+            RpcClass::MoneyMovement
+        );
+        assert_eq!(
+            RpcClass::of("/proto.account.v1.AccountService/FindAccountById"),
+            RpcClass::Read
+        );
+        assert_eq!(
+            RpcClass::of("/proto.audit.v1.AuditService/SearchAudit"),
+            RpcClass::Read
+        );
+        assert_eq!(
+            RpcClass::of("/proto.account.v1.AccountService/FreezeAccount"),
+            RpcClass::Write
+        );
+    }
+
+    #[test]
+    fn test_only_verified_users_get_a_bucket() {
+        let token_verifier = UserTokenVerifier::new(&UserTokenConfig {
+            issuer: None,
+            audience: None,
+            leeway: 0,
+            keys: vec![TokenKeyConfig {
+                kid: "k1".to_string(),
+                algorithm: "HS256".to_string(),
+                secret: Some("token-secret".to_string()),
+                public_key: None,
+            }],
+        })
+        .expect("failed to build token verifier");
+        let token = |secret: &str| {
+            encode(
+                &Header::default(),
+                &json!({"sub": "user-1", "exp": chrono::Utc::now().timestamp() + 60}),
+                &EncodingKey::from_secret(secret.as_bytes()),
+            )
+            .expect("failed to sign token")
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert(XRF_USER_FINGERPRINT, HeaderValue::from_static("user-2"));
+        assert_eq!(verified_user_fp(&token_verifier, &headers), None);
+
+        headers.insert(
+            AUTHORIZATION_KEY,
+            HeaderValue::from_str(&format!("Bearer {}", token("forged-secret"))).unwrap(),
+        );
+        assert_eq!(verified_user_fp(&token_verifier, &headers), None);
+
+        headers.insert(
+            AUTHORIZATION_KEY,
+            HeaderValue::from_str(&format!("Bearer {}", token("token-secret"))).unwrap(),
+        );
+        assert_eq!(
+            verified_user_fp(&token_verifier, &headers),
+            Some("user-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_limited_calls_say_when_to_retry() {
+        let status = rate_limited(1_200);
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            status
+                .metadata()
+                .get(RETRY_AFTER_KEY)
+                .and_then(|value| value.to_str().ok()),
+            Some("2")
+        );
+        assert_eq!(
+            rate_limited(1)
+                .metadata()
+                .get(RETRY_AFTER_KEY)
+                .and_then(|value| value.to_str().ok()),
+            Some("1")
+        );
+        assert_eq!(
+            bucket_key("backoffice", Some("user-1"), "/pkg.Service/Method"),
+            "xrfq3:rate-limit:backoffice:user-1:/pkg.Service/Method"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use crate::context::{CallerRoles, RequestContext};
+use crate::{generate_request_id, RequestId, REQUEST_ID_KEY};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::codegen::http::{HeaderMap, HeaderValue, Request as HttpRequest, Response};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo};
+use tonic::Request;
+use tower::{Layer, Service};
+
+const USER_AGENT_KEY: &str = "user-agent";
+const FORWARDED_FOR_KEY: &str = "x-forwarded-for";
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Fills the [`RequestContext`] of every request from the connection and its metadata, and echoes
+/// the request id back in the response metadata. A request without a usable `request-id` gets a
+/// generated one, which is also set on the request so handlers and their spans see the same id.
+#[derive(Clone, Default)]
+pub struct RequestContextLayer {
+    trusted_proxies: Arc<Vec<IpAddr>>,
+}
+
+impl RequestContextLayer {
+    /// `x-forwarded-for` is only read from the peers in `trusted_proxies`.
+    pub fn new(trusted_proxies: Vec<IpAddr>) -> Self {
+        RequestContextLayer {
+            trusted_proxies: Arc::new(trusted_proxies),
+        }
+    }
+}
+
+impl<S> Layer<S> for RequestContextLayer {
+    type Service = RequestContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestContextService {
+            inner,
+            trusted_proxies: self.trusted_proxies.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestContextService<S> {
+    inner: S,
+    trusted_proxies: Arc<Vec<IpAddr>>,
+}
+
+impl<S, ReqBody, ResBody> Service<HttpRequest<ReqBody>> for RequestContextService<S>
+where
+    S: Service<HttpRequest<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: HttpRequest<ReqBody>) -> Self::Future {
+        let req_context = build_request_context(&request, &self.trusted_proxies);
+        let request_id = req_context
+            .request_id
+            .as_ref()
+            .and_then(|request_id| HeaderValue::from_str(&request_id.0).ok());
+        if let Some(request_id) = &request_id {
+            request
+                .headers_mut()
+                .insert(REQUEST_ID_KEY, request_id.clone());
+        }
+        request.extensions_mut().insert(req_context);
+
+        // the service that was polled ready handles this request, a clone takes its place
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let mut response = inner.call(request).await?;
+            if let Some(request_id) = request_id {
+                response.headers_mut().insert(REQUEST_ID_KEY, request_id);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// The context filled by [`RequestContextLayer`]. Requests that did not go through it, e.g. in
+/// tests, get a context with a generated request id only.
+pub fn request_context<T>(request: &Request<T>) -> RequestContext {
+    request
+        .extensions()
+        .get::<RequestContext>()
+        .cloned()
+        .unwrap_or_else(|| RequestContext {
+            request_ip: None,
+            user_agent: None,
+            request_id: Some(RequestId(generate_request_id())),
+            service_id: None,
+            rpc_method: None,
+        })
+}
+
+/// The roles of the caller, resolved by [`crate::server::grpc::AuthInterceptor`]. Requests that
+/// did not go through it, e.g. in tests, have none.
+pub fn caller_roles<T>(request: &Request<T>) -> CallerRoles {
+    request
+        .extensions()
+        .get::<CallerRoles>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn build_request_context<B>(
+    request: &HttpRequest<B>,
+    trusted_proxies: &[IpAddr],
+) -> RequestContext {
+    let headers = request.headers();
+    let request_ip = resolve_client_ip(
+        remote_addr(request).map(|addr| addr.ip()),
+        header_str(headers, FORWARDED_FOR_KEY),
+        trusted_proxies,
+    );
+    let request_id = header_str(headers, REQUEST_ID_KEY)
+        .map(str::trim)
+        .filter(|request_id| !request_id.is_empty() && request_id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(generate_request_id);
+
+    RequestContext {
+        request_ip: request_ip.map(|ip| ip.to_string()),
+        user_agent: header_str(headers, USER_AGENT_KEY).map(str::to_string),
+        request_id: Some(RequestId(request_id)),
+        service_id: None,
+        rpc_method: Some(request.uri().path().to_string()),
+    }
+}
+
+fn remote_addr<B>(request: &HttpRequest<B>) -> Option<SocketAddr> {
+    let extensions = request.extensions();
+    extensions
+        .get::<TcpConnectInfo>()
+        .and_then(|info| info.remote_addr())
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .and_then(|info| info.get_ref().remote_addr())
+        })
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// The address of the client. `x-forwarded-for` is only trusted when the peer is a trusted proxy,
+/// it is then read from the right, skipping the trusted proxies, since the addresses on its left
+/// are set by the client and can be forged.
+fn resolve_client_ip(
+    peer: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+    let forwarded = forwarded_for
+        .into_iter()
+        .flat_map(|forwarded_for| forwarded_for.split(','))
+        .map(|addr| addr.trim().parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>();
+    match forwarded {
+        Ok(forwarded) => forwarded
+            .iter()
+            .rev()
+            .find(|addr| !trusted_proxies.contains(addr))
+            .or(forwarded.first())
+            .copied()
+            .or(Some(peer)),
+        // a malformed header is not trusted at all
+        Err(_) => Some(peer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().expect("invalid ip")
+    }
+
+    #[test]
+    fn test_forwarded_for_is_ignored_from_untrusted_peers() {
+        let proxies = [ip("10.0.0.1")];
+        assert_eq!(
+            resolve_client_ip(Some(ip("203.0.113.7")), Some("198.51.100.1"), &proxies),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(
+            resolve_client_ip(None, Some("198.51.100.1"), &proxies),
+            None
+        );
+    }
+
+    #[test]
+    fn test_forwarded_for_is_read_from_the_right_past_trusted_proxies() {
+        let proxies = [ip("10.0.0.1"), ip("10.0.0.2")];
+        assert_eq!(
+            resolve_client_ip(
+                Some(ip("10.0.0.1")),
+                Some("192.0.2.9, 198.51.100.1, 10.0.0.2"),
+                &proxies
+            ),
+            Some(ip("198.51.100.1"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), Some("10.0.0.2"), &proxies),
+            Some(ip("10.0.0.2"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), None, &proxies),
+            Some(ip("10.0.0.1"))
+        );
+        assert_eq!(
+            resolve_client_ip(Some(ip("10.0.0.1")), Some("198.51.100.1, junk"), &proxies),
+            Some(ip("10.0.0.1"))
+        );
+    }
+
+    #[test]
+    fn test_request_context_keeps_a_valid_request_id() {
+        let request = HttpRequest::builder()
+            .header(REQUEST_ID_KEY, "req-1")
+            .header(USER_AGENT_KEY, "grpc-go/1.60")
+            .body(())
+            .expect("invalid request");
+        let req_context = build_request_context(&request, &[]);
+        assert_eq!(
+            req_context.request_id.map(|id| id.0),
+            Some("req-1".to_string())
+        );
+        assert_eq!(req_context.user_agent, Some("grpc-go/1.60".to_string()));
+        assert_eq!(req_context.request_ip, None);
+
+        let request = HttpRequest::builder()
+            .header(REQUEST_ID_KEY, "x".repeat(MAX_REQUEST_ID_LEN + 1))
+            .body(())
+            .expect("invalid request");
+        let request_id = build_request_context(&request, &[])
+            .request_id
+            .map(|id| id.0);
+        assert_ne!(request_id, Some("x".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
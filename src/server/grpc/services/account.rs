@@ -1,21 +1,38 @@
-use crate::context::{ApplicationContext, RequestContext, UserContext};
-use crate::core::{Account, AccountStatus, UpdateAccountReq, WalletHolding};
-use crate::error::OrchestrateError;
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    Account, AccountLimitsReq, AccountMember, AccountStatus, SigningPolicy, TransactionLimits,
+    UpdateAccountReq, UserRole, WalletHolding,
+};
 use crate::grpc_services::account_service_server::AccountService;
 use crate::grpc_services::{
-    AccountResponse, CreateAccountRequest, CreateAccountResponse,
-    FindAccountByCurrencyAndTypeRequest, FindAccountByCurrencyAndTypeResponse,
-    FindAccountByIdRequest, FindAccountByIdResponse, FindAccountsByCurrencyOrTypeRequest,
-    FindAccountsByCurrencyOrTypeResponse, FindWalletRequest, FindWalletResponse,
-    FreezeAccountRequest, FreezeAccountResponse, LockAccountRequest, LockAccountResponse,
-    UpdateAccountRequest, UpdateAccountResponse, WalletResponse,
+    AccountLimitsResponse, AccountMemberResponse, AccountResponse, AddAccountMemberRequest,
+    AddAccountMemberResponse, CloseAccountRequest, CloseAccountResponse, CreateAccountRequest,
+    CreateAccountResponse, FindAccountByCurrencyAndTypeRequest,
+    FindAccountByCurrencyAndTypeResponse, FindAccountByIdRequest, FindAccountByIdResponse,
+    FindAccountLimitsRequest, FindAccountLimitsResponse, FindAccountsByCurrencyOrTypeRequest,
+    FindAccountsByCurrencyOrTypeResponse, FindAccruedInterestRequest, FindAccruedInterestResponse,
+    FindConsolidatedBalanceRequest, FindConsolidatedBalanceResponse, FindWalletRequest,
+    FindWalletResponse, FreezeAccountRequest, FreezeAccountResponse, ListAccountMembersRequest,
+    ListAccountMembersResponse, ListChildAccountsRequest, ListChildAccountsResponse,
+    LockAccountRequest, LockAccountResponse, RemoveAccountMemberRequest,
+    RemoveAccountMemberResponse, SetAccountLimitsRequest, SetAccountLimitsResponse,
+    SetOverdraftLimitRequest, SetOverdraftLimitResponse, SetSigningPolicyRequest,
+    SetSigningPolicyResponse, SigningPolicyResponse, TreeWalletResponse, UpdateAccountRequest,
+    UpdateAccountResponse, WalletResponse,
 };
 use crate::server::grpc::header::{get_xrf_user_auth_header, get_xrf_user_timezone};
-use crate::server::grpc::macros::{trace_and_get_id, trace_request};
+use crate::server::grpc::macros::trace_request;
+use crate::server::grpc::mapper::{
+    from_grpc_timestamp, map_orchestrator_err_to_grpc_error, to_grpc_timestamp,
+};
+use crate::server::grpc::request::{caller_roles, request_context};
 use crate::{
-    create_account, find_account_by_currency_and_type, find_user_wallet_for_acct,
-    generate_request_id, get_user_account_by_id, get_user_accounts_by_currencies_or_types,
-    update_user_account, RequestId, DEFAULT_TIMEZONE, REQUEST_ID_KEY, XRF_USER_FINGERPRINT,
+    add_account_member, change_account_status, change_account_type, close_account, create_account,
+    find_account_by_currency_and_type, generate_request_id, get_account_limits, get_account_wallet,
+    get_accrued_interest, get_consolidated_balance, get_user_account_by_id,
+    get_user_accounts_by_currencies_or_types, list_account_members, list_child_accounts,
+    remove_account_member, set_account_limits, set_overdraft_limit, set_signing_policy,
+    unlock_account, update_user_account, DEFAULT_TIMEZONE, REQUEST_ID_KEY, XRF_USER_FINGERPRINT,
     XRF_USER_TIMEZONE,
 };
 use cassandra_cpp::Session;
@@ -24,7 +41,7 @@ use rust_decimal::prelude::ToPrimitive;
 use sqlx::PgPool;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
-use tracing::{error, info, info_span, warn};
+use tracing::{info, info_span, warn};
 
 pub struct AccountServiceManager {
     pg_pool: Arc<PgPool>,
@@ -54,12 +71,21 @@ impl AccountService for AccountServiceManager {
     ) -> Result<Response<FindWalletResponse>, Status> {
         let event = "getWalletHolding";
         trace_request!(request, "get_wallet_holding");
-        get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
-
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
         let req = request.into_inner();
-        let wallet = find_user_wallet_for_acct(&self.pg_pool, &req.account_id, &req.currency)
-            .await
-            .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let wallet = get_account_wallet(
+            &self.pg_pool,
+            &req.account_id,
+            &req.currency,
+            &user_ctx,
+            caller.may_read_any_account(),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
         match wallet {
             None => Err(Status::not_found(format!(
@@ -74,6 +100,8 @@ impl AccountService for AccountServiceManager {
                         seconds: found_wallet.modification_time.timestamp(),
                         nanos: found_wallet.modification_time.timestamp_subsec_nanos() as i32,
                     }),
+                    overdraft_limit: found_wallet.overdraft_limit.to_string(),
+                    available_credit: found_wallet.available_credit().to_string(),
                 }),
             })),
         }
@@ -84,9 +112,11 @@ impl AccountService for AccountServiceManager {
         request: Request<LockAccountRequest>,
     ) -> Result<Response<LockAccountResponse>, Status> {
         let event = "lockAccount";
-        let req_id = trace_and_get_id!(request, "lock_account");
-        let timezone = get_xrf_user_timezone(&request.metadata(), XRF_USER_TIMEZONE)?;
-        let user_fp = get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
+        trace_request!(request, "lock_account");
+        let req_context = request_context(&request);
+        let timezone = get_xrf_user_timezone(request.metadata(), XRF_USER_TIMEZONE)?;
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
         let req = request.into_inner();
 
         info!("lock account, actionLock={}", &req.lock);
@@ -94,21 +124,29 @@ impl AccountService for AccountServiceManager {
         let user_ctx =
             UserContext::load_user_context(user_fp, timezone, Some(req.account_id.clone()), None);
 
-        let update_req = UpdateAccountReq::new(Some(req.lock), None, None, None);
-        let req_context = RequestContext {
-            request_ip: None,
-            user_agent: None,
-            request_id: Some(RequestId(req_id)),
-        };
-
-        let updated = update_user_account(
-            &self.pg_pool,
-            &req.account_id,
-            &user_ctx,
-            update_req,
-            req_context,
-        )
-        .await
+        let is_admin = caller.is_admin();
+
+        let updated = if req.lock {
+            update_user_account(
+                &self.pg_pool,
+                &req.account_id,
+                &user_ctx,
+                UpdateAccountReq::new(Some(true), None),
+                is_admin,
+                req_context,
+            )
+            .await
+        } else {
+            unlock_account(
+                &self.pg_pool,
+                &req.account_id,
+                &user_ctx,
+                is_admin,
+                req_context,
+                &self.app_ctx,
+            )
+            .await
+        }
         .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
         Ok(Response::new(LockAccountResponse { success: updated }))
@@ -119,9 +157,10 @@ impl AccountService for AccountServiceManager {
         request: Request<UpdateAccountRequest>,
     ) -> Result<Response<UpdateAccountResponse>, Status> {
         let event = "updateAccount";
-        let req_id = trace_and_get_id!(request, "update_account");
-        let timezone = get_xrf_user_timezone(&request.metadata(), XRF_USER_TIMEZONE)?;
-        let user_fp = get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
+        trace_request!(request, "update_account");
+        let req_context = request_context(&request);
+        let timezone = get_xrf_user_timezone(request.metadata(), XRF_USER_TIMEZONE)?;
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
 
         info!("updating account, accountId={}", &req.account_id);
@@ -129,67 +168,439 @@ impl AccountService for AccountServiceManager {
         let user_ctx =
             UserContext::load_user_context(user_fp, timezone, Some(req.account_id.clone()), None);
 
-        let update_req = UpdateAccountReq::build(None, req.timezone, None, None)
-            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+        if req.timezone.is_none() && req.account_type.is_none() {
+            return Err(Status::invalid_argument("nothing to update".to_string()));
+        }
 
-        let req_context = RequestContext {
-            request_ip: None,
-            user_agent: None,
-            request_id: Some(RequestId(req_id)),
+        if let Some(timezone) = req.timezone {
+            update_user_account(
+                &self.pg_pool,
+                &req.account_id,
+                &user_ctx,
+                UpdateAccountReq::new(None, Some(timezone)),
+                false,
+                req_context.clone(),
+            )
+            .await
+            .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+        }
+        if let Some(account_type) = req.account_type {
+            change_account_type(
+                &self.pg_pool,
+                &req.account_id,
+                &account_type,
+                &user_ctx,
+                req_context,
+            )
+            .await
+            .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+        }
+
+        Ok(Response::new(UpdateAccountResponse { updated: true }))
+    }
+
+    async fn freeze_account(
+        &self,
+        request: Request<FreezeAccountRequest>,
+    ) -> Result<Response<FreezeAccountResponse>, Status> {
+        let event = "freezeAccount";
+        trace_request!(request, "freeze_account");
+        let req_context = request_context(&request);
+        let timezone = get_xrf_user_timezone(request.metadata(), XRF_USER_TIMEZONE)?;
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        info!("freeze account, actionFreeze={}", &req.freeze);
+
+        let user_ctx =
+            UserContext::load_user_context(user_fp, timezone, Some(req.account_id.clone()), None);
+
+        let status = if req.freeze {
+            AccountStatus::Frozen
+        } else {
+            AccountStatus::Active
         };
+        let expires_at = from_grpc_timestamp(req.expires_at)?;
+        // compliance freezes and unfreezes the accounts it investigates
+        let is_admin = caller.has_any_role(&[UserRole::Admin, UserRole::Compliance]);
 
-        let updated = update_user_account(
+        change_account_status(
             &self.pg_pool,
             &req.account_id,
+            status,
+            &req.reason_code,
+            expires_at,
+            is_admin,
             &user_ctx,
-            update_req,
             req_context,
         )
         .await
         .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
-        Ok(Response::new(UpdateAccountResponse { updated }))
+        Ok(Response::new(FreezeAccountResponse { success: true }))
     }
 
-    async fn freeze_account(
+    async fn close_account(
         &self,
-        request: Request<FreezeAccountRequest>,
-    ) -> Result<Response<FreezeAccountResponse>, Status> {
-        let event = "freezeAccount";
-        let req_id = trace_and_get_id!(request, "freeze_account");
-        let timezone = get_xrf_user_timezone(&request.metadata(), XRF_USER_TIMEZONE)?;
-        let user_fp = get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
+        request: Request<CloseAccountRequest>,
+    ) -> Result<Response<CloseAccountResponse>, Status> {
+        let event = "closeAccount";
+        trace_request!(request, "close_account");
+        let req_context = request_context(&request);
+        let timezone = get_xrf_user_timezone(request.metadata(), XRF_USER_TIMEZONE)?;
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
 
-        info!("freeze account, actionFreeze={}", &req.freeze);
+        info!(
+            "close account, destinationAccountId={}",
+            &req.destination_account_id
+        );
 
         let user_ctx =
             UserContext::load_user_context(user_fp, timezone, Some(req.account_id.clone()), None);
 
-        let mut status = AccountStatus::Active;
-        if req.freeze {
-            status = AccountStatus::Inactive;
-        }
+        let (account, sweep_txs) = close_account(
+            &self.pg_pool,
+            &req.account_id,
+            &req.destination_account_id,
+            &user_ctx,
+            &self.cassandra_session,
+            &self.app_ctx,
+            req_context,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
-        let update_req = UpdateAccountReq::new(None, None, Some(status), None);
+        Ok(Response::new(CloseAccountResponse {
+            account: Some(map_account_response(&account, vec![])),
+            transaction_ids: sweep_txs.into_iter().map(|tx| tx.id).collect(),
+        }))
+    }
 
-        let req_context = RequestContext {
-            request_ip: None,
-            user_agent: None,
-            request_id: Some(RequestId(req_id)),
-        };
+    async fn set_account_limits(
+        &self,
+        request: Request<SetAccountLimitsRequest>,
+    ) -> Result<Response<SetAccountLimitsResponse>, Status> {
+        let event = "setAccountLimits";
+        trace_request!(request, "set_account_limits");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+        let is_admin = caller.is_admin();
 
-        let updated = update_user_account(
+        let limits = set_account_limits(
             &self.pg_pool,
             &req.account_id,
+            AccountLimitsReq {
+                max_amount: req.max_amount,
+                daily_debit: req.daily_debit,
+                monthly_debit: req.monthly_debit,
+                hourly_count: req.hourly_count,
+            },
             &user_ctx,
-            update_req,
+            is_admin,
             req_context,
+            &self.app_ctx,
         )
         .await
         .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
-        Ok(Response::new(FreezeAccountResponse { success: updated }))
+        Ok(Response::new(SetAccountLimitsResponse {
+            limits: Some(map_limits_response(&limits)),
+        }))
+    }
+
+    async fn set_overdraft_limit(
+        &self,
+        request: Request<SetOverdraftLimitRequest>,
+    ) -> Result<Response<SetOverdraftLimitResponse>, Status> {
+        let event = "setOverdraftLimit";
+        trace_request!(request, "set_overdraft_limit");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+        let is_admin = caller.is_admin();
+
+        let wallet = set_overdraft_limit(
+            &self.pg_pool,
+            &req.account_id,
+            req.currency,
+            req.overdraft_limit,
+            &user_ctx,
+            is_admin,
+            req_context,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(SetOverdraftLimitResponse {
+            wallet: Some(WalletResponse {
+                balance: wallet.balance.to_f32().unwrap_or_default(),
+                currency: wallet.currency.to_string(),
+                modification_time: Some(to_grpc_timestamp(&wallet.modification_time)),
+                overdraft_limit: wallet.overdraft_limit.to_string(),
+                available_credit: wallet.available_credit().to_string(),
+            }),
+        }))
+    }
+
+    async fn find_account_limits(
+        &self,
+        request: Request<FindAccountLimitsRequest>,
+    ) -> Result<Response<FindAccountLimitsResponse>, Status> {
+        let event = "findAccountLimits";
+        trace_request!(request, "find_account_limits");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+        let is_admin = caller.may_read_any_account();
+
+        let limits = get_account_limits(
+            &self.pg_pool,
+            &req.account_id,
+            &user_ctx,
+            is_admin,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(FindAccountLimitsResponse {
+            limits: Some(map_limits_response(&limits)),
+        }))
+    }
+
+    async fn list_child_accounts(
+        &self,
+        request: Request<ListChildAccountsRequest>,
+    ) -> Result<Response<ListChildAccountsResponse>, Status> {
+        let event = "listChildAccounts";
+        trace_request!(request, "list_child_accounts");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let children = list_child_accounts(
+            &self.pg_pool,
+            &req.account_id,
+            req.include_closed,
+            req.include_wallets,
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ListChildAccountsResponse {
+            accounts: children
+                .into_iter()
+                .map(|(child, wallets)| map_account_response(&child, wallets))
+                .collect(),
+        }))
+    }
+
+    async fn find_consolidated_balance(
+        &self,
+        request: Request<FindConsolidatedBalanceRequest>,
+    ) -> Result<Response<FindConsolidatedBalanceResponse>, Status> {
+        let event = "findConsolidatedBalance";
+        trace_request!(request, "find_consolidated_balance");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let (currency, balance, wallets) = get_consolidated_balance(
+            &self.pg_pool,
+            &req.account_id,
+            req.currency,
+            &user_ctx,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(FindConsolidatedBalanceResponse {
+            currency: currency.to_string(),
+            balance: balance.to_string(),
+            wallets: wallets
+                .into_iter()
+                .map(|wallet| TreeWalletResponse {
+                    account_id: wallet.account_id,
+                    currency: wallet.currency.to_string(),
+                    balance: wallet.balance.to_string(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn find_accrued_interest(
+        &self,
+        request: Request<FindAccruedInterestRequest>,
+    ) -> Result<Response<FindAccruedInterestResponse>, Status> {
+        let event = "findAccruedInterest";
+        trace_request!(request, "find_accrued_interest");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let accrued =
+            get_accrued_interest(&self.pg_pool, &req.account_id, &user_ctx, &self.app_ctx)
+                .await
+                .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(FindAccruedInterestResponse {
+            account_id: accrued.wallet.account_id,
+            currency: accrued.wallet.currency.to_string(),
+            balance: accrued.wallet.balance.to_string(),
+            accrued_interest: accrued.amount.to_string(),
+            apr: accrued.apr.map(|apr| apr.to_string()),
+            accrued_since: accrued.accrued_since.map(|date| date.to_string()),
+            accrued_until: accrued.accrued_until.map(|date| date.to_string()),
+        }))
+    }
+
+    async fn add_account_member(
+        &self,
+        request: Request<AddAccountMemberRequest>,
+    ) -> Result<Response<AddAccountMemberResponse>, Status> {
+        let event = "addAccountMember";
+        trace_request!(request, "add_account_member");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let member = add_account_member(
+            &self.pg_pool,
+            &req.account_id,
+            req.user_fp,
+            req.role,
+            &user_ctx,
+            req_context,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(AddAccountMemberResponse {
+            member: Some(map_member_response(&member)),
+        }))
+    }
+
+    async fn remove_account_member(
+        &self,
+        request: Request<RemoveAccountMemberRequest>,
+    ) -> Result<Response<RemoveAccountMemberResponse>, Status> {
+        let event = "removeAccountMember";
+        trace_request!(request, "remove_account_member");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        remove_account_member(
+            &self.pg_pool,
+            &req.account_id,
+            &req.user_fp,
+            &user_ctx,
+            req_context,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(RemoveAccountMemberResponse {}))
+    }
+
+    async fn list_account_members(
+        &self,
+        request: Request<ListAccountMembersRequest>,
+    ) -> Result<Response<ListAccountMembersResponse>, Status> {
+        let event = "listAccountMembers";
+        trace_request!(request, "list_account_members");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let (members, policy) = list_account_members(&self.pg_pool, &req.account_id, &user_ctx)
+            .await
+            .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ListAccountMembersResponse {
+            members: members.iter().map(map_member_response).collect(),
+            signing_policy: policy.as_ref().map(map_signing_policy_response),
+        }))
+    }
+
+    async fn set_signing_policy(
+        &self,
+        request: Request<SetSigningPolicyRequest>,
+    ) -> Result<Response<SetSigningPolicyResponse>, Status> {
+        let event = "setSigningPolicy";
+        trace_request!(request, "set_signing_policy");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let policy = set_signing_policy(
+            &self.pg_pool,
+            &req.account_id,
+            req.threshold,
+            req.required_approvals,
+            req.approval_window,
+            &user_ctx,
+            req_context,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(SetSigningPolicyResponse {
+            signing_policy: Some(map_signing_policy_response(&policy)),
+        }))
     }
 
     async fn create_account(
@@ -197,8 +608,9 @@ impl AccountService for AccountServiceManager {
         request: Request<CreateAccountRequest>,
     ) -> Result<Response<CreateAccountResponse>, Status> {
         let event = "createUserAccount";
-        let req_id = trace_and_get_id!(request, "create_account");
-        let user_fp = get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
+        trace_request!(request, "create_account");
+        let req_context = request_context(&request);
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
 
         info!(
@@ -207,16 +619,12 @@ impl AccountService for AccountServiceManager {
         );
         let user_ctx = UserContext::load_user_context(user_fp, req.timezone.clone(), None, None);
 
-        let req_context = RequestContext {
-            request_ip: None,
-            user_agent: None,
-            request_id: Some(RequestId(req_id)),
-        };
-
         let (account, wallet) = create_account(
             &self.pg_pool,
             req.currency,
             req.acct_type,
+            req.label,
+            req.parent_account_id,
             &user_ctx,
             &self.cassandra_session,
             &self.app_ctx,
@@ -239,13 +647,19 @@ impl AccountService for AccountServiceManager {
     ) -> Result<Response<FindAccountByIdResponse>, Status> {
         let event = "findAccountById";
         trace_request!(request, "find_account_by_id");
-
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
 
-        let (account, wallets) =
-            get_user_account_by_id(&self.pg_pool, &req.account_id, req.include_wallets)
-                .await
-                .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+        let (account, wallets) = get_user_account_by_id(
+            &self.pg_pool,
+            &req.account_id,
+            req.include_wallets,
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
 
         Ok(Response::new(FindAccountByIdResponse {
             account: Some(map_account_response(&account, wallets)),
@@ -258,7 +672,7 @@ impl AccountService for AccountServiceManager {
     ) -> Result<Response<FindAccountsByCurrencyOrTypeResponse>, Status> {
         let event = "get_user_accounts";
         trace_request!(request, "get_user_account");
-        let user_fp = get_xrf_user_auth_header(&request.metadata(), XRF_USER_FINGERPRINT)?;
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
         let user_ctx =
             UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
@@ -288,18 +702,26 @@ impl AccountService for AccountServiceManager {
     ) -> Result<Response<FindAccountByCurrencyAndTypeResponse>, Status> {
         let event = "getUserAccount";
         trace_request!(request, "get_user_account");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
         let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
 
-        let (account, wallets) =
-            match find_account_by_currency_and_type(&self.pg_pool, &req.currency, &req.acct_type)
-                .await
-                .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?
-            {
-                Some((account, wallets)) => (account, wallets),
-                None => {
-                    return Err(Status::not_found("no account found"));
-                }
-            };
+        let (account, wallets) = match find_account_by_currency_and_type(
+            &self.pg_pool,
+            &req.currency,
+            &req.acct_type,
+            req.label,
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?
+        {
+            Some((account, wallets)) => (account, wallets),
+            None => {
+                return Err(Status::not_found("no account found"));
+            }
+        };
 
         Ok(Response::new(FindAccountByCurrencyAndTypeResponse {
             account: Some(map_account_response(&account, wallets)),
@@ -307,17 +729,29 @@ impl AccountService for AccountServiceManager {
     }
 }
 
-fn map_orchestrator_err_to_grpc_error(event: &str, err: OrchestrateError) -> Status {
-    const INTERNAL_SERVER_ERR: &'static str = "Internal server error";
-    match err {
-        OrchestrateError::InvalidArgument(err) => Status::invalid_argument(err.to_string()),
-        OrchestrateError::NotFoundError(err) => Status::not_found(format!("Not found: {}", err)),
-        OrchestrateError::DatabaseError(err) => {
-            error!("event={} :: database error: {}", event, err);
-            Status::internal(INTERNAL_SERVER_ERR)
-        }
-        OrchestrateError::RecordAlreadyExists(err) => Status::already_exists(err.to_string()),
-        _ => Status::internal(INTERNAL_SERVER_ERR),
+fn map_member_response(member: &AccountMember) -> AccountMemberResponse {
+    AccountMemberResponse {
+        user_fp: member.user_fp.clone(),
+        role: member.role.to_string(),
+        added_by: member.added_by.clone(),
+        creation_time: Some(to_grpc_timestamp(&member.creation_time)),
+    }
+}
+
+fn map_signing_policy_response(policy: &SigningPolicy) -> SigningPolicyResponse {
+    SigningPolicyResponse {
+        threshold: policy.threshold.to_string(),
+        required_approvals: policy.required_approvals as u32,
+        approval_window: policy.approval_window as u32,
+    }
+}
+
+fn map_limits_response(limits: &TransactionLimits) -> AccountLimitsResponse {
+    AccountLimitsResponse {
+        max_amount: limits.max_amount.map(|limit| limit.to_string()),
+        daily_debit: limits.daily_debit.map(|limit| limit.to_string()),
+        monthly_debit: limits.monthly_debit.map(|limit| limit.to_string()),
+        hourly_count: limits.hourly_count,
     }
 }
 
@@ -327,6 +761,8 @@ fn map_account_response(account: &Account, wallets: Vec<WalletHolding>) -> Accou
         status: account.status.to_string(),
         account_id: account.id.to_string(),
         account_type: account.account_type.to_string(),
+        label: account.label.clone(),
+        parent_account_id: account.parent_account_id.clone(),
         creation_time: Some(Timestamp {
             seconds: account.creation_time.timestamp(),
             nanos: account.creation_time.timestamp_subsec_nanos() as i32,
@@ -335,6 +771,11 @@ fn map_account_response(account: &Account, wallets: Vec<WalletHolding>) -> Accou
             seconds: account.modification_time.timestamp(),
             nanos: account.modification_time.timestamp_subsec_nanos() as i32,
         }),
+        status_reason: account
+            .status_reason
+            .as_ref()
+            .map(|reason| reason.to_string()),
+        status_expires_at: account.status_expires_at.as_ref().map(to_grpc_timestamp),
         wallets: wallets
             .iter()
             .map(|w_holding| WalletResponse {
@@ -347,6 +788,8 @@ fn map_account_response(account: &Account, wallets: Vec<WalletHolding>) -> Accou
                     seconds: w_holding.modification_time.timestamp(),
                     nanos: w_holding.modification_time.timestamp_subsec_nanos() as i32,
                 }),
+                overdraft_limit: w_holding.overdraft_limit.to_string(),
+                available_credit: w_holding.available_credit().to_string(),
             })
             .collect(),
     }
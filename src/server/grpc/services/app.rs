@@ -1,16 +1,21 @@
 use crate::context::ApplicationContext;
 use crate::grpc_services::app_service_server::AppService;
 use crate::grpc_services::{CheckHealthRequest, CheckHealthResponse};
+use crate::server::grpc::ServiceHealth;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
 pub struct AppServiceManager {
     app_ctx: Arc<ApplicationContext>,
+    service_health: ServiceHealth,
 }
 
 impl AppServiceManager {
-    pub fn new(app_ctx: Arc<ApplicationContext>) -> Self {
-        AppServiceManager { app_ctx }
+    pub fn new(app_ctx: Arc<ApplicationContext>, service_health: ServiceHealth) -> Self {
+        AppServiceManager {
+            app_ctx,
+            service_health,
+        }
     }
 }
 
@@ -21,7 +26,8 @@ impl AppService for AppServiceManager {
         _: Request<CheckHealthRequest>,
     ) -> Result<Response<CheckHealthResponse>, Status> {
         Ok(Response::new(CheckHealthResponse {
-            is_up: true,
+            // up once the dependencies answer the health probes
+            is_up: self.service_health.is_ready(),
             app_id: self.app_ctx.app_id.clone().to_string(),
             region: self.app_ctx.block_region.clone().to_string(),
         }))
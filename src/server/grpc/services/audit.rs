@@ -0,0 +1,289 @@
+use crate::context::ApplicationContext;
+use crate::core::{AuditArchive, AuditChainIssue, AuditFilter, AuditLog, FieldChange, UserRole};
+use crate::grpc_services::audit_service_server::AuditService;
+use crate::grpc_services::{
+    AuditArchiveResponse, AuditChainIssueResponse, AuditLogResponse, FieldChangeResponse,
+    GetEntityHistoryRequest, GetEntityHistoryResponse, ListAuditArchivesRequest,
+    ListAuditArchivesResponse, ReleaseAuditArchiveRequest, ReleaseAuditArchiveResponse,
+    RestoreAuditArchiveRequest, RestoreAuditArchiveResponse, SearchAuditRequest,
+    SearchAuditResponse, VerifyAuditChainRequest, VerifyAuditChainResponse,
+};
+use crate::server::grpc::header::get_xrf_user_auth_header;
+use crate::server::grpc::macros::trace_request;
+use crate::server::grpc::mapper::{
+    from_grpc_timestamp, map_orchestrator_err_to_grpc_error, to_grpc_timestamp,
+};
+use crate::server::grpc::request::caller_roles;
+use crate::{
+    fetch_audit_history, generate_request_id, list_audit_archives, release_audit_archive,
+    restore_audit_archive, search_audit_history, verify_audit_chain, MAX_PAGE_SIZE, REQUEST_ID_KEY,
+    XRF_USER_FINGERPRINT,
+};
+use cassandra_cpp::Session;
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{info, info_span};
+
+// the roles that review the audit logs
+const REVIEWERS: &[UserRole] = &[UserRole::Admin, UserRole::Compliance, UserRole::Support];
+
+pub struct AuditServiceManager {
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl AuditServiceManager {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        AuditServiceManager {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AuditService for AuditServiceManager {
+    async fn get_entity_history(
+        &self,
+        request: Request<GetEntityHistoryRequest>,
+    ) -> Result<Response<GetEntityHistoryResponse>, Status> {
+        let event = "getEntityHistory";
+        trace_request!(request, "get_entity_history");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        info!(
+            "fetching audit history, entityType={}, entityId={}",
+            &req.entity_type, &req.entity_id
+        );
+
+        let (audit_logs, next_cursor) = fetch_audit_history(
+            &self.pg_pool,
+            &req.entity_type,
+            &req.entity_id,
+            req.cursor,
+            req.page_size,
+            caller.has_any_role(REVIEWERS),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(GetEntityHistoryResponse {
+            next_cursor,
+            audit_logs: audit_logs.iter().map(map_audit_log_response).collect(),
+        }))
+    }
+
+    async fn search_audit(
+        &self,
+        request: Request<SearchAuditRequest>,
+    ) -> Result<Response<SearchAuditResponse>, Status> {
+        let event = "searchAudit";
+        trace_request!(request, "search_audit");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let filter = AuditFilter::build(
+            req.user_fp,
+            from_grpc_timestamp(req.from_time)?,
+            from_grpc_timestamp(req.to_time)?,
+            req.audit_type,
+        )
+        .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let (audit_logs, next_cursor) = search_audit_history(
+            &self.pg_pool,
+            filter,
+            req.cursor,
+            req.page_size,
+            caller.has_any_role(REVIEWERS),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(SearchAuditResponse {
+            next_cursor,
+            audit_logs: audit_logs.iter().map(map_audit_log_response).collect(),
+        }))
+    }
+
+    async fn verify_audit_chain(
+        &self,
+        request: Request<VerifyAuditChainRequest>,
+    ) -> Result<Response<VerifyAuditChainResponse>, Status> {
+        let event = "verifyAuditChain";
+        trace_request!(request, "verify_audit_chain");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+
+        let report = verify_audit_chain(
+            &self.pg_pool,
+            MAX_PAGE_SIZE as i64,
+            &self.cassandra_session,
+            &self.app_ctx,
+            caller.has_any_role(REVIEWERS),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+        info!(
+            "audit chain verified, checked={}, issues={}",
+            report.checked,
+            report.issues.len()
+        );
+
+        Ok(Response::new(VerifyAuditChainResponse {
+            intact: report.is_intact(),
+            checked: report.checked,
+            last_sequence: report.last_sequence,
+            issues: report.issues.iter().map(map_chain_issue_response).collect(),
+        }))
+    }
+
+    async fn list_audit_archives(
+        &self,
+        request: Request<ListAuditArchivesRequest>,
+    ) -> Result<Response<ListAuditArchivesResponse>, Status> {
+        let event = "listAuditArchives";
+        trace_request!(request, "list_audit_archives");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let (archives, next_cursor) = list_audit_archives(
+            &self.pg_pool,
+            req.entity_type,
+            parse_archive_date(req.from_date)?,
+            parse_archive_date(req.to_date)?,
+            req.cursor,
+            req.page_size,
+            caller.has_any_role(REVIEWERS),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ListAuditArchivesResponse {
+            next_cursor,
+            archives: archives.iter().map(map_audit_archive_response).collect(),
+        }))
+    }
+
+    async fn restore_audit_archive(
+        &self,
+        request: Request<RestoreAuditArchiveRequest>,
+    ) -> Result<Response<RestoreAuditArchiveResponse>, Status> {
+        let event = "restoreAuditArchive";
+        trace_request!(request, "restore_audit_archive");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        info!("restoring audit archive, archiveId={}", &req.archive_id);
+        let archive = restore_audit_archive(
+            &self.pg_pool,
+            &req.archive_id,
+            &self.app_ctx,
+            caller.is_admin(),
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(RestoreAuditArchiveResponse {
+            archive: Some(map_audit_archive_response(&archive)),
+        }))
+    }
+
+    async fn release_audit_archive(
+        &self,
+        request: Request<ReleaseAuditArchiveRequest>,
+    ) -> Result<Response<ReleaseAuditArchiveResponse>, Status> {
+        let event = "releaseAuditArchive";
+        trace_request!(request, "release_audit_archive");
+        let caller = caller_roles(&request);
+        get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        info!("releasing audit archive, archiveId={}", &req.archive_id);
+        let archive = release_audit_archive(&self.pg_pool, &req.archive_id, caller.is_admin())
+            .await
+            .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ReleaseAuditArchiveResponse {
+            archive: Some(map_audit_archive_response(&archive)),
+        }))
+    }
+}
+
+fn parse_archive_date(date: Option<String>) -> Result<Option<NaiveDate>, Status> {
+    date.filter(|date| !date.is_empty())
+        .map(|date| {
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .map_err(|_| Status::invalid_argument(format!("invalid date: {}", date)))
+        })
+        .transpose()
+}
+
+fn map_audit_archive_response(archive: &AuditArchive) -> AuditArchiveResponse {
+    AuditArchiveResponse {
+        archive_id: archive.id.clone(),
+        entity_type: archive.entity_type.to_string(),
+        archive_date: archive.archive_date.to_string(),
+        object_path: archive.object_path.clone(),
+        manifest_path: archive.manifest_path.clone(),
+        row_count: archive.row_count,
+        checksum: archive.checksum.clone(),
+        from_time: Some(to_grpc_timestamp(&archive.from_time)),
+        to_time: Some(to_grpc_timestamp(&archive.to_time)),
+        creation_time: Some(to_grpc_timestamp(&archive.creation_time)),
+        restored_at: archive.restored_at.as_ref().map(to_grpc_timestamp),
+    }
+}
+
+fn map_audit_log_response(audit_log: &AuditLog) -> AuditLogResponse {
+    AuditLogResponse {
+        audit_id: audit_log.id.clone(),
+        user_fp: audit_log.user_fp.clone(),
+        entity_type: audit_log.entity_type.to_string(),
+        entity_id: audit_log.entity_id.clone(),
+        audit_type: audit_log.audit_type.to_string(),
+        request_ip: audit_log.request_ip.clone(),
+        request_id: audit_log.request_id.clone(),
+        user_agent: audit_log.request_user_agent.clone(),
+        creation_time: Some(to_grpc_timestamp(&audit_log.creation_time)),
+        changes: audit_log
+            .field_changes()
+            .iter()
+            .map(map_field_change_response)
+            .collect(),
+        sequence_num: audit_log.sequence_num,
+        hash: audit_log.hash.clone(),
+        service_id: audit_log.service_id.clone(),
+    }
+}
+
+fn map_field_change_response(change: &FieldChange) -> FieldChangeResponse {
+    FieldChangeResponse {
+        field: change.field.clone(),
+        old_value: change.old.clone(),
+        new_value: change.new.clone(),
+        description: change.to_string(),
+    }
+}
+
+fn map_chain_issue_response(issue: &AuditChainIssue) -> AuditChainIssueResponse {
+    AuditChainIssueResponse {
+        kind: issue.kind().to_string(),
+        sequence_num: issue.sequence_num(),
+        audit_id: issue.audit_id().map(str::to_string),
+        description: issue.to_string(),
+    }
+}
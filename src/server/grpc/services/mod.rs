@@ -1,5 +1,9 @@
 mod account;
 mod app;
+mod audit;
+mod transaction;
 
 pub use account::AccountServiceManager;
 pub use app::AppServiceManager;
+pub use audit::AuditServiceManager;
+pub use transaction::TransactionServiceManager;
@@ -0,0 +1,425 @@
+use crate::context::{ApplicationContext, UserContext};
+use crate::core::{
+    LedgerEntry, MonetaryTransaction, PayoutBatchMode, PayoutItemReq, ScheduledPayment,
+    ScheduledPaymentReq, TransactionFilter,
+};
+use crate::grpc_services::transaction_service_server::TransactionService;
+use crate::grpc_services::{
+    ApproveTransactionRequest, ApproveTransactionResponse, BatchItemResult,
+    CancelScheduledPaymentRequest, CancelScheduledPaymentResponse, CreateScheduledPaymentRequest,
+    CreateScheduledPaymentResponse, DebitAccountRequest, DebitAccountResponse,
+    GetTransactionRequest, GetTransactionResponse, InternalTransferRequest,
+    InternalTransferResponse, LedgerEntryResponse, ListScheduledPaymentsRequest,
+    ListScheduledPaymentsResponse, ListTransactionsRequest, ListTransactionsResponse,
+    ScheduledPaymentResponse, SubmitBatchRequest, SubmitBatchResponse, TransactionResponse,
+};
+use crate::server::grpc::header::get_xrf_user_auth_header;
+use crate::server::grpc::macros::trace_request;
+use crate::server::grpc::mapper::{
+    from_grpc_timestamp, map_orchestrator_err_to_grpc_error, to_grpc_timestamp,
+};
+use crate::server::grpc::request::caller_roles;
+use crate::{
+    approve_transaction, cancel_scheduled_payment, create_scheduled_payment,
+    debit_wallet_transaction, generate_request_id, get_account_transaction,
+    list_account_transactions, list_scheduled_payments, submit_payout_batch,
+    transfer_within_hierarchy, PayoutItemOutcome, DEFAULT_TIMEZONE, REQUEST_ID_KEY,
+    XRF_USER_FINGERPRINT,
+};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{info, info_span};
+
+pub struct TransactionServiceManager {
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl TransactionServiceManager {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        TransactionServiceManager {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl TransactionService for TransactionServiceManager {
+    async fn get_transaction(
+        &self,
+        request: Request<GetTransactionRequest>,
+    ) -> Result<Response<GetTransactionResponse>, Status> {
+        let event = "getTransaction";
+        trace_request!(request, "get_transaction");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let (transaction, ledger_entries) =
+            get_account_transaction(&self.pg_pool, &req.transaction_id, &user_ctx)
+                .await
+                .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(GetTransactionResponse {
+            transaction: Some(map_transaction_response(&transaction)),
+            ledger_entries: ledger_entries
+                .iter()
+                .map(map_ledger_entry_response)
+                .collect(),
+        }))
+    }
+
+    async fn list_transactions(
+        &self,
+        request: Request<ListTransactionsRequest>,
+    ) -> Result<Response<ListTransactionsResponse>, Status> {
+        let event = "listTransactions";
+        trace_request!(request, "list_transactions");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        info!("listing transactions, accountId={}", &req.account_id);
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let filter = TransactionFilter::build(
+            req.ascending,
+            &req.transaction_types,
+            &req.statuses,
+            from_grpc_timestamp(req.from_time)?,
+            from_grpc_timestamp(req.to_time)?,
+            req.min_amount,
+            req.max_amount,
+        )
+        .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let (transactions, next_cursor) = list_account_transactions(
+            &self.pg_pool,
+            &req.account_id,
+            filter,
+            req.cursor,
+            req.page_size,
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ListTransactionsResponse {
+            next_cursor,
+            transactions: transactions.iter().map(map_transaction_response).collect(),
+        }))
+    }
+
+    async fn create_scheduled_payment(
+        &self,
+        request: Request<CreateScheduledPaymentRequest>,
+    ) -> Result<Response<CreateScheduledPaymentResponse>, Status> {
+        let event = "createScheduledPayment";
+        trace_request!(request, "create_scheduled_payment");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        info!("scheduling payment, accountId={}", &req.account_id);
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let payment_req = ScheduledPaymentReq {
+            account_id: req.account_id,
+            destination_account_id: req.destination_account_id,
+            amount: req.amount,
+            payment_type: req.payment_type,
+            recurrence: req.recurrence,
+            cron_expression: req.cron_expression,
+            starts_at: from_grpc_timestamp(req.start_at)?,
+        };
+        let scheduled_payment = create_scheduled_payment(
+            &self.pg_pool,
+            payment_req,
+            caller.may_issue_credits(),
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(CreateScheduledPaymentResponse {
+            scheduled_payment: Some(map_scheduled_payment_response(&scheduled_payment)),
+        }))
+    }
+
+    async fn list_scheduled_payments(
+        &self,
+        request: Request<ListScheduledPaymentsRequest>,
+    ) -> Result<Response<ListScheduledPaymentsResponse>, Status> {
+        let event = "listScheduledPayments";
+        trace_request!(request, "list_scheduled_payments");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let scheduled_payments = list_scheduled_payments(
+            &self.pg_pool,
+            &req.account_id,
+            req.include_inactive,
+            &user_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ListScheduledPaymentsResponse {
+            scheduled_payments: scheduled_payments
+                .iter()
+                .map(map_scheduled_payment_response)
+                .collect(),
+        }))
+    }
+
+    async fn cancel_scheduled_payment(
+        &self,
+        request: Request<CancelScheduledPaymentRequest>,
+    ) -> Result<Response<CancelScheduledPaymentResponse>, Status> {
+        let event = "cancelScheduledPayment";
+        trace_request!(request, "cancel_scheduled_payment");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let scheduled_payment =
+            cancel_scheduled_payment(&self.pg_pool, &req.scheduled_payment_id, &user_ctx)
+                .await
+                .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(CancelScheduledPaymentResponse {
+            scheduled_payment: Some(map_scheduled_payment_response(&scheduled_payment)),
+        }))
+    }
+
+    async fn submit_batch(
+        &self,
+        request: Request<SubmitBatchRequest>,
+    ) -> Result<Response<SubmitBatchResponse>, Status> {
+        let event = "submitBatch";
+        trace_request!(request, "submit_batch");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let caller = caller_roles(&request);
+        let req = request.into_inner();
+
+        info!("submitting payout batch, items={}", req.items.len());
+
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+        let mode = if req.all_or_nothing {
+            PayoutBatchMode::AllOrNothing
+        } else {
+            PayoutBatchMode::BestEffort
+        };
+        let item_reqs = req
+            .items
+            .into_iter()
+            .map(|item| PayoutItemReq {
+                amount: item.amount,
+                item_type: item.item_type,
+                account_id: item.account_id,
+                idempotency_key: item.idempotency_key,
+                source_account_id: item.source_account_id,
+            })
+            .collect();
+
+        let (batch, outcomes) = submit_payout_batch(
+            &self.pg_pool,
+            item_reqs,
+            mode,
+            caller.may_issue_credits(),
+            &user_ctx,
+            &self.cassandra_session,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(SubmitBatchResponse {
+            batch_id: batch.id,
+            block_id: batch.block_id,
+            status: batch.status.to_string(),
+            results: outcomes.into_iter().map(map_batch_item_result).collect(),
+        }))
+    }
+
+    async fn internal_transfer(
+        &self,
+        request: Request<InternalTransferRequest>,
+    ) -> Result<Response<InternalTransferResponse>, Status> {
+        let event = "internalTransfer";
+        trace_request!(request, "internal_transfer");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.source_account_id.clone()),
+            None,
+        );
+
+        let transactions = transfer_within_hierarchy(
+            &self.pg_pool,
+            &req.source_account_id,
+            &req.destination_account_id,
+            req.amount,
+            &user_ctx,
+            &self.cassandra_session,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(InternalTransferResponse {
+            transactions: transactions.iter().map(map_transaction_response).collect(),
+        }))
+    }
+
+    async fn debit_account(
+        &self,
+        request: Request<DebitAccountRequest>,
+    ) -> Result<Response<DebitAccountResponse>, Status> {
+        let event = "debitAccount";
+        trace_request!(request, "debit_account");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx = UserContext::load_user_context(
+            user_fp,
+            DEFAULT_TIMEZONE.to_string(),
+            Some(req.account_id.clone()),
+            None,
+        );
+
+        let transaction = debit_wallet_transaction(
+            &self.pg_pool,
+            req.amount,
+            req.transaction_type,
+            req.account_id,
+            &user_ctx,
+            &self.cassandra_session,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(DebitAccountResponse {
+            transaction: Some(map_transaction_response(&transaction)),
+        }))
+    }
+
+    async fn approve_transaction(
+        &self,
+        request: Request<ApproveTransactionRequest>,
+    ) -> Result<Response<ApproveTransactionResponse>, Status> {
+        let event = "approveTransaction";
+        trace_request!(request, "approve_transaction");
+        let user_fp = get_xrf_user_auth_header(request.metadata(), XRF_USER_FINGERPRINT)?;
+        let req = request.into_inner();
+
+        let user_ctx =
+            UserContext::load_user_context(user_fp, DEFAULT_TIMEZONE.to_string(), None, None);
+
+        let (transaction, approval_request, approved_by) = approve_transaction(
+            &self.pg_pool,
+            &req.transaction_id,
+            &user_ctx,
+            &self.cassandra_session,
+            &self.app_ctx,
+        )
+        .await
+        .map_err(|err| map_orchestrator_err_to_grpc_error(event, err))?;
+
+        Ok(Response::new(ApproveTransactionResponse {
+            transaction: Some(map_transaction_response(&transaction)),
+            approval_status: approval_request.status.to_string(),
+            required_approvals: approval_request.required_approvals as u32,
+            approved_by,
+            expires_at: Some(to_grpc_timestamp(&approval_request.expires_at)),
+        }))
+    }
+}
+
+fn map_transaction_response(transaction: &MonetaryTransaction) -> TransactionResponse {
+    TransactionResponse {
+        block_id: transaction.block_id.clone(),
+        amount: transaction.amount.to_string(),
+        status: transaction.status.to_string(),
+        transaction_id: transaction.id.to_string(),
+        account_id: transaction.account_id.to_string(),
+        transaction_type: transaction.transaction_type.to_string(),
+        timestamp: Some(to_grpc_timestamp(&transaction.timestamp)),
+        modification_time: Some(to_grpc_timestamp(&transaction.modification_date)),
+    }
+}
+
+fn map_ledger_entry_response(entry: &LedgerEntry) -> LedgerEntryResponse {
+    LedgerEntryResponse {
+        entry_id: entry.id.to_string(),
+        description: entry.description.clone(),
+        entry_type: entry.entry_type.to_string(),
+        sequence_number: entry.sequence_number,
+        timestamp: Some(to_grpc_timestamp(&entry.timestamp)),
+    }
+}
+
+fn map_scheduled_payment_response(
+    scheduled_payment: &ScheduledPayment,
+) -> ScheduledPaymentResponse {
+    ScheduledPaymentResponse {
+        amount: scheduled_payment.amount.to_string(),
+        status: scheduled_payment.status.to_string(),
+        timezone: scheduled_payment.timezone.clone(),
+        account_id: scheduled_payment.account_id.clone(),
+        recurrence: scheduled_payment.recurrence.to_string(),
+        scheduled_payment_id: scheduled_payment.id.clone(),
+        payment_type: scheduled_payment.payment_type.to_string(),
+        cron_expression: scheduled_payment.cron_expression.clone(),
+        destination_account_id: scheduled_payment.destination_account_id.clone(),
+        next_run_at: Some(to_grpc_timestamp(&scheduled_payment.next_run_at)),
+        last_run_at: scheduled_payment
+            .last_run_at
+            .as_ref()
+            .map(to_grpc_timestamp),
+        creation_time: Some(to_grpc_timestamp(&scheduled_payment.creation_time)),
+    }
+}
+
+fn map_batch_item_result(outcome: PayoutItemOutcome) -> BatchItemResult {
+    BatchItemResult {
+        replayed: outcome.replayed,
+        status: outcome.item.status.to_string(),
+        failure_reason: outcome.item.failure_reason,
+        transaction_ids: outcome.item.transaction_ids,
+        idempotency_key: outcome.item.idempotency_key,
+    }
+}
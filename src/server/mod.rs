@@ -4,5 +4,10 @@ mod server;
 pub mod grpc_services {
     tonic::include_proto!("proto.xrfq3.v1");
     tonic::include_proto!("proto.account.v1");
+    tonic::include_proto!("proto.transaction.v1");
+    tonic::include_proto!("proto.audit.v1");
+
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("xrfq3_descriptor");
 }
+pub use grpc::ServiceHealth;
 pub use server::GrpcServer;
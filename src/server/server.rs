@@ -1,8 +1,16 @@
 use crate::context::ApplicationContext;
 use crate::grpc_services::account_service_server::AccountServiceServer;
 use crate::grpc_services::app_service_server::AppServiceServer;
-use crate::server::grpc::{AccountServiceManager, AppServiceManager};
-use crate::{Environment, GrpcServerConfig, CERT_PEM_PATH, KEY_PEM_PATH};
+use crate::grpc_services::audit_service_server::AuditServiceServer;
+use crate::grpc_services::transaction_service_server::TransactionServiceServer;
+use crate::grpc_services::FILE_DESCRIPTOR_SET;
+use crate::server::grpc::{
+    AccountServiceManager, AppServiceManager, AuditServiceManager, AuthInterceptor, RateLimitLayer,
+    RequestContextLayer, ServiceHealth, ServiceIdentityLayer, TransactionServiceManager,
+};
+use crate::{
+    Environment, GrpcServerConfig, MtlsConfig, CERT_PEM_PATH, CLIENT_CA_PEM_PATH, KEY_PEM_PATH,
+};
 use anyhow::Context;
 use bytes::Bytes;
 use cassandra_cpp::Session;
@@ -11,51 +19,93 @@ use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
-use tonic::transport::{Identity, Server, ServerTlsConfig};
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic_health::pb::health_server::HealthServer;
+use tonic_health::server::{HealthReporter, HealthService};
 use tracing::{debug, info, warn};
 
 const SSL_PEM_SERVE_KEY_PATH: &str = "local/secrets/ssl/server.key";
 const SSL_PEM_SERVE_CERT_PATH: &str = "local/secrets/ssl/server.crt";
+const SSL_PEM_CLIENT_CA_PATH: &str = "local/secrets/ssl/ca.crt";
 
 pub struct GrpcServer {
     timeout: Duration,
     addr: core::net::SocketAddr,
+    request_context_layer: RequestContextLayer,
+    service_identity_layer: ServiceIdentityLayer,
+    mtls: MtlsConfig,
+    rate_limit_layer: RateLimitLayer,
+    auth_interceptor: AuthInterceptor,
+    service_health: ServiceHealth,
+    health_server: HealthServer<HealthService>,
     app_service_manager: AppServiceManager,
+    audit_service_manager: AuditServiceManager,
     account_service_manager: AccountServiceManager,
+    transaction_service_manager: TransactionServiceManager,
 }
 
 impl GrpcServer {
     pub fn new(
-        pg_pool: PgPool,
+        pg_pool: Arc<PgPool>,
         config: GrpcServerConfig,
-        cassandra_session: Session,
-        app_ctx: ApplicationContext,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
     ) -> Result<Self, anyhow::Error> {
         let addr = format!("[::]:{}", config.port)
             .parse()
             .context("Failed to parse grpc server address")?;
 
-        let pg_pool_arc = Arc::new(pg_pool);
-        let app_ctx = Arc::new(app_ctx);
-        let cassandra_session_arc = Arc::new(cassandra_session);
+        let account_service_manager =
+            AccountServiceManager::new(pg_pool.clone(), cassandra_session.clone(), app_ctx.clone());
 
-        let account_service_manager = AccountServiceManager::new(
-            pg_pool_arc.clone(),
-            cassandra_session_arc.clone(),
+        let health_reporter = HealthReporter::new();
+        let health_server =
+            HealthServer::new(HealthService::from_health_reporter(health_reporter.clone()));
+        let service_health = ServiceHealth::new(health_reporter);
+
+        let app_service_manager = AppServiceManager::new(app_ctx.clone(), service_health.clone());
+        let audit_service_manager =
+            AuditServiceManager::new(pg_pool.clone(), cassandra_session.clone(), app_ctx.clone());
+        let transaction_service_manager = TransactionServiceManager::new(
+            pg_pool.clone(),
+            cassandra_session.clone(),
             app_ctx.clone(),
         );
 
-        let app_service_manager = AppServiceManager::new(app_ctx.clone());
+        let auth_interceptor = AuthInterceptor::new(&config.auth, app_ctx.admin_user_fps.clone())
+            .map_err(|err| anyhow::anyhow!("Failed to load auth config: {}", err))?
+            .with_denial_audit(pg_pool.clone());
+
+        let rate_limit_layer = RateLimitLayer::new(
+            config.rate_limit.clone(),
+            &config.auth,
+            app_ctx.redis_conn.clone(),
+        )
+        .map_err(|err| anyhow::anyhow!("Failed to load rate limit config: {}", err))?;
 
         let config_timeout = config.timeout;
         Ok(GrpcServer {
             addr,
+            request_context_layer: RequestContextLayer::new(config.trusted_proxies),
+            service_identity_layer: ServiceIdentityLayer::new(config.mtls.services.clone()),
+            mtls: config.mtls,
+            rate_limit_layer,
+            auth_interceptor,
+            service_health,
+            health_server,
             app_service_manager,
+            audit_service_manager,
             account_service_manager,
+            transaction_service_manager,
             timeout: Duration::from_millis(config_timeout as u64),
         })
     }
 
+    /// The serving status published by the health service, kept up to date by the health worker.
+    pub fn service_health(&self) -> ServiceHealth {
+        self.service_health.clone()
+    }
+
     pub async fn run_until_stopped(self, app_env: &Environment) -> anyhow::Result<()> {
         info!("starting gRPC server :: port {}", &self.addr.port());
         let key_path = &get_path_from_env_or(KEY_PEM_PATH, SSL_PEM_SERVE_KEY_PATH, &app_env)?;
@@ -66,14 +116,52 @@ impl GrpcServer {
         //// Load the PEM-encoded data directly. Pem (Privacy-Enhanced Mail)
         let cert_pem = load_pem_data(Path::new(cert_path))?;
         let key_pem = load_pem_data(Path::new(key_path))?;
+        let mut tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert_pem, key_pem));
+
+        //// Clients presenting a certificate signed by the CA bundle are internal services
+        if self.mtls.enabled {
+            let ca_path =
+                &get_path_from_env_or(CLIENT_CA_PEM_PATH, SSL_PEM_CLIENT_CA_PATH, &app_env)?;
+            info!(
+                "mTLS enabled :: ca_path={}, require_client_cert={}",
+                ca_path, self.mtls.require_client_cert
+            );
+            let ca_pem = load_pem_data(Path::new(ca_path))?;
+            tls_config = tls_config
+                .client_ca_root(Certificate::from_pem(ca_pem))
+                .client_auth_optional(!self.mtls.require_client_cert);
+        }
+
+        let reflection_service = tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+            .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+            .build_v1()
+            .context("Failed to build reflection service")?;
 
         info!("starting... gRPC server");
         Server::builder()
-            .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert_pem, key_pem)))
+            .tls_config(tls_config)
             .context("Failed to create TLS config")?
             .max_connection_age(self.timeout)
+            .layer(self.request_context_layer)
+            .layer(self.service_identity_layer)
+            .layer(self.rate_limit_layer)
+            // health checks and reflection are not authenticated
+            .add_service(self.health_server)
+            .add_service(reflection_service)
             .add_service(AppServiceServer::new(self.app_service_manager))
-            .add_service(AccountServiceServer::new(self.account_service_manager))
+            .add_service(AuditServiceServer::with_interceptor(
+                self.audit_service_manager,
+                self.auth_interceptor.clone(),
+            ))
+            .add_service(AccountServiceServer::with_interceptor(
+                self.account_service_manager,
+                self.auth_interceptor.clone(),
+            ))
+            .add_service(TransactionServiceServer::with_interceptor(
+                self.transaction_service_manager,
+                self.auth_interceptor,
+            ))
             .serve(self.addr)
             .await
             .context("gRPC server failed")
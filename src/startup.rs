@@ -1,10 +1,24 @@
-use crate::{ApplicationContext, Configurations, DatabaseConfig, GrpcServer};
+use crate::{
+    ApplicationContext, ApprovalExpiryWorker, AuditArchiveWorker, AuditCheckpointWorker,
+    Configurations, DatabaseConfig, DormancyWorker, FreezeExpiryWorker, GrpcServer, HealthWorker,
+    InterestWorker, OverdraftWorker, ScheduledPaymentWorker,
+};
 use cassandra_cpp::Session;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use std::sync::Arc;
 
 pub struct Server {
     pub grpc_server: GrpcServer,
+    pub scheduled_payment_worker: ScheduledPaymentWorker,
+    pub dormancy_worker: DormancyWorker,
+    pub freeze_expiry_worker: FreezeExpiryWorker,
+    pub approval_worker: ApprovalExpiryWorker,
+    pub interest_worker: InterestWorker,
+    pub overdraft_worker: OverdraftWorker,
+    pub audit_checkpoint_worker: AuditCheckpointWorker,
+    pub audit_archive_worker: AuditArchiveWorker,
+    pub health_worker: HealthWorker,
 }
 
 impl Server {
@@ -13,12 +27,76 @@ impl Server {
         cassandra_session: Session,
         app_ctx: ApplicationContext,
     ) -> Result<Self, anyhow::Error> {
-        let pool = get_connection_pool(&config.database);
+        let pool = Arc::new(get_connection_pool(&config.database));
+        let app_ctx = Arc::new(app_ctx);
+        let cassandra_session = Arc::new(cassandra_session);
 
-        let grpc_server = GrpcServer::new(pool, config.server.grpc, cassandra_session, app_ctx)
-            .map_err(|err| anyhow::anyhow!("{}", err))?;
+        let grpc_server = GrpcServer::new(
+            pool.clone(),
+            config.server.grpc,
+            cassandra_session.clone(),
+            app_ctx.clone(),
+        )
+        .map_err(|err| anyhow::anyhow!("{}", err))?;
 
-        Ok(Server { grpc_server })
+        let scheduled_payment_worker = ScheduledPaymentWorker::new(
+            pool.clone(),
+            config.worker.scheduled_payments,
+            cassandra_session.clone(),
+            app_ctx.clone(),
+        );
+
+        let interest_worker = InterestWorker::new(
+            pool.clone(),
+            config.worker.interest,
+            cassandra_session.clone(),
+            app_ctx.clone(),
+        );
+
+        let overdraft_worker = OverdraftWorker::new(
+            pool.clone(),
+            config.worker.overdraft,
+            cassandra_session.clone(),
+            app_ctx.clone(),
+        );
+
+        let audit_checkpoint_worker = AuditCheckpointWorker::new(
+            pool.clone(),
+            config.worker.audit_checkpoint,
+            cassandra_session.clone(),
+            app_ctx.clone(),
+        );
+
+        let health_worker = HealthWorker::new(
+            pool.clone(),
+            config.worker.health,
+            cassandra_session,
+            app_ctx.clone(),
+            grpc_server.service_health(),
+        );
+
+        let audit_archive_worker =
+            AuditArchiveWorker::new(pool.clone(), config.worker.audit_archive, app_ctx.clone());
+
+        let approval_worker = ApprovalExpiryWorker::new(pool.clone(), config.worker.approvals);
+
+        let freeze_expiry_worker =
+            FreezeExpiryWorker::new(pool.clone(), config.worker.freeze_expiry, app_ctx.clone());
+
+        let dormancy_worker = DormancyWorker::new(pool, config.worker.dormancy, app_ctx);
+
+        Ok(Server {
+            grpc_server,
+            dormancy_worker,
+            freeze_expiry_worker,
+            approval_worker,
+            interest_worker,
+            overdraft_worker,
+            audit_checkpoint_worker,
+            audit_archive_worker,
+            health_worker,
+            scheduled_payment_worker,
+        })
     }
 }
 
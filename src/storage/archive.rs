@@ -0,0 +1,68 @@
+use crate::{ArchiveStoreError, AuditArchiveStorageConfig};
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use std::sync::Arc;
+use tracing::info;
+
+/// The store audit archives are written to, credentials missing from the config are read from the
+/// `AWS_*` environment variables.
+pub fn get_archive_store(
+    config: &AuditArchiveStorageConfig,
+) -> Result<Arc<dyn ObjectStore>, String> {
+    match config {
+        AuditArchiveStorageConfig::Local { path } => {
+            info!("Writing audit archives to ===> {}", path);
+            std::fs::create_dir_all(path)
+                .map_err(|err| format!("failed to create archive directory {}: {}", path, err))?;
+            let store = LocalFileSystem::new_with_prefix(path)
+                .map_err(|err| format!("failed to open archive directory {}: {}", path, err))?;
+            Ok(Arc::new(store))
+        }
+        AuditArchiveStorageConfig::S3 {
+            bucket,
+            region,
+            endpoint,
+            access_key_id,
+            secret_access_key,
+            allow_http,
+        } => {
+            info!("Writing audit archives to ===> s3://{}", bucket);
+            let mut builder = AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .with_region(region)
+                .with_allow_http(*allow_http);
+            if let Some(endpoint) = endpoint {
+                builder = builder.with_endpoint(endpoint);
+            }
+            if let Some(access_key_id) = access_key_id {
+                builder = builder.with_access_key_id(access_key_id);
+            }
+            if let Some(secret_access_key) = secret_access_key {
+                builder = builder.with_secret_access_key(secret_access_key);
+            }
+            let store = builder
+                .build()
+                .map_err(|err| format!("failed to open archive bucket {}: {}", bucket, err))?;
+            Ok(Arc::new(store))
+        }
+    }
+}
+
+pub async fn put_archive_object(
+    store: &dyn ObjectStore,
+    path: &str,
+    data: Vec<u8>,
+) -> Result<(), ArchiveStoreError> {
+    store.put(&Path::from(path), PutPayload::from(data)).await?;
+    Ok(())
+}
+
+pub async fn get_archive_object(
+    store: &dyn ObjectStore,
+    path: &str,
+) -> Result<Vec<u8>, ArchiveStoreError> {
+    let data = store.get(&Path::from(path)).await?.bytes().await?;
+    Ok(data.to_vec())
+}
@@ -1,7 +1,8 @@
 use crate::core::Block;
 use crate::CassandraDBError;
 use cassandra_cpp::{
-    BindRustType, CassCollection, Consistency, PreparedStatement, RetryPolicy, Session,
+    BindRustType, CassCollection, Consistency, LendingIterator, PreparedStatement, RetryPolicy,
+    Session,
 };
 
 pub async fn save_block_chain(
@@ -75,3 +76,51 @@ pub async fn prepare_insert_block_statement(
         .map_err(|err| CassandraDBError::ServerError(err.to_string()))?;
     Ok(prepared_stmt)
 }
+
+/// The entry ids of the block at `sequence_num` of the app, None when there is no such block.
+pub async fn find_block_entry_ids(
+    app_id: &str,
+    sequence_num: i64,
+    session: &Session,
+) -> Result<Option<Vec<String>>, CassandraDBError> {
+    let mut statement = session.statement(
+        "SELECT entry_ids FROM xrf_q3_block.block_chain WHERE app_id = ? AND sequence_num = ?",
+    );
+    statement
+        .set_consistency(Consistency::LOCAL_QUORUM)
+        .map_err(|err| CassandraDBError::ServerError(err.to_string()))?;
+    statement
+        .bind(0, app_id)
+        .map_err(|err| CassandraDBError::SetValueError(err.to_string()))?;
+    statement
+        .bind(1, sequence_num)
+        .map_err(|err| CassandraDBError::SetValueError(err.to_string()))?;
+
+    let result = statement
+        .execute()
+        .await
+        .map_err(|err| CassandraDBError::ExecutionError(err.to_string()))?;
+    let row = match result.first_row() {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    let mut entry_ids = Vec::new();
+    let column = row
+        .get_column(0)
+        .map_err(|err| CassandraDBError::ExecutionError(err.to_string()))?;
+    // a block saved without entries reads as a null list
+    if !column.is_null() {
+        let mut values = column
+            .get_set()
+            .map_err(|err| CassandraDBError::ExecutionError(err.to_string()))?;
+        while let Some(value) = values.next() {
+            entry_ids.push(
+                value
+                    .get_string()
+                    .map_err(|err| CassandraDBError::ExecutionError(err.to_string()))?,
+            );
+        }
+    }
+    Ok(Some(entry_ids))
+}
@@ -3,7 +3,7 @@ mod parser;
 mod setup;
 mod statements;
 
-pub use chain::{prepare_insert_block_statement, save_block_chain};
+pub use chain::{find_block_entry_ids, prepare_insert_block_statement, save_block_chain};
 pub use parser::apply_cql_file;
-pub use setup::{apply_cql_migrations, connect_session, create_keyspace};
+pub use setup::{apply_cql_migrations, connect_session, create_keyspace, ping_cassandra};
 pub use statements::PreparedAppStatements;
@@ -1,5 +1,5 @@
 use crate::storage::apply_cql_file;
-use crate::CassandraConfig;
+use crate::{CassandraConfig, CassandraDBError};
 use anyhow::anyhow;
 use cassandra_cpp::Cluster;
 use std::fs::read_dir;
@@ -108,3 +108,12 @@ pub async fn apply_cql_migrations(
     info!("CQL migrations successfully run.");
     Ok(())
 }
+
+/// Health probe, reads the local node of the session.
+pub async fn ping_cassandra(session: &cassandra_cpp::Session) -> Result<(), CassandraDBError> {
+    session
+        .execute("SELECT release_version FROM system.local")
+        .await
+        .map_err(|err| CassandraDBError::ExecutionError(err.to_string()))?;
+    Ok(())
+}
@@ -1,9 +1,15 @@
+mod archive;
 mod cassandra;
 mod postgres;
 mod redis;
 mod timescale;
 
+pub use archive::{get_archive_object, get_archive_store, put_archive_object};
 pub use cassandra::*;
 pub use postgres::*;
-pub use redis::{get_exchange_rate, get_redis_client, save_exchange_rate};
+pub use redis::{
+    add_limit_usage, clear_failure_counts, get_exchange_rate, get_limit_usage, get_redis_client,
+    increment_failure_count, ping_redis, publish_account_status_event, save_exchange_rate,
+    save_limit_usage, take_rate_limit_tokens,
+};
 pub use timescale::setup_timescale_db;
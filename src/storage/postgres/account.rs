@@ -1,4 +1,6 @@
-use crate::core::{Account, AccountStatus, AccountType, BeneficiaryAccount, Currency};
+use crate::core::{
+    Account, AccountStatus, AccountStatusReason, AccountType, BeneficiaryAccount, Currency,
+};
 use crate::PgDatabaseError;
 use chrono::{DateTime, Utc};
 use sqlx::{Error, Executor, Postgres};
@@ -13,6 +15,11 @@ struct AccountDO {
     pub currency: Currency,
     pub status: AccountStatus,
     pub acct_type: AccountType,
+    pub label: Option<String>,
+    pub parent_account_id: Option<String>,
+    pub status_reason: Option<AccountStatusReason>,
+    pub status_changed_by: Option<String>,
+    pub status_expires_at: Option<DateTime<Utc>>,
     pub creation_time: DateTime<Utc>,
     pub modification_time: DateTime<Utc>,
 }
@@ -27,6 +34,11 @@ impl From<AccountDO> for Account {
             timezone: db_acct.timezone,
             currency: db_acct.currency,
             account_type: db_acct.acct_type,
+            label: db_acct.label,
+            parent_account_id: db_acct.parent_account_id,
+            status_reason: db_acct.status_reason,
+            status_changed_by: db_acct.status_changed_by,
+            status_expires_at: db_acct.status_expires_at,
             creation_time: db_acct.creation_time,
             modification_time: db_acct.modification_time,
         }
@@ -49,10 +61,12 @@ INSERT INTO user_account (
                      timezone,
                      currency,
                      acct_type,
+                     label,
+                     parent_account_id,
                      creation_time,
                      modification_time
                      )
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
 ",
         account.id,
         account.status.clone() as AccountStatus,
@@ -61,6 +75,8 @@ VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         account.timezone,
         account.currency.clone() as Currency,
         account.account_type.clone() as AccountType,
+        account.label,
+        account.parent_account_id,
         account.creation_time,
         account.modification_time
     )
@@ -90,12 +106,20 @@ UPDATE user_account SET
                     status = $1,
                     locked = $2,
                     timezone = $3,
-                    modification_time = $4
-WHERE id = $5
+                    acct_type = $4,
+                    status_reason = $5,
+                    status_changed_by = $6,
+                    status_expires_at = $7,
+                    modification_time = $8
+WHERE id = $9
                        ",
         account.status.clone() as AccountStatus,
         account.locked,
         account.timezone,
+        account.account_type.clone() as AccountType,
+        account.status_reason.clone() as Option<AccountStatusReason>,
+        account.status_changed_by,
+        account.status_expires_at,
         account.modification_time,
         acct_id
     )
@@ -129,7 +153,12 @@ SELECT  id,
         currency as "currency: _",
         creation_time,
         modification_time,
-        acct_type as "acct_type: _"
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
 FROM user_account WHERE id = $1
     "#,
         account_id
@@ -140,15 +169,18 @@ FROM user_account WHERE id = $1
     handle_saved_account_result(saved_account)
 }
 
+/// Finds the open account of a user with the given currency, type and label.
 #[tracing::instrument(
     level = "debug",
-    skip(pg_pool, currency, acct_type),
+    skip(pg_pool, user_fp, currency, acct_type, label),
     name = "Find account by currency and account type"
 )]
 pub async fn find_account_by_currency_and_acct_type<'a, E>(
     pg_pool: E,
+    user_fp: &str,
     currency: Currency,
     acct_type: AccountType,
+    label: Option<&str>,
 ) -> Result<Option<Account>, PgDatabaseError>
 where
     E: Executor<'a, Database = Postgres>,
@@ -165,10 +197,22 @@ SELECT  id,
         currency as "currency: _",
         creation_time,
         modification_time,
-        acct_type as "acct_type: _"
-FROM user_account WHERE currency = $1 AND acct_type = $2"#,
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
+FROM user_account
+WHERE user_fp = $1
+  AND currency = $2
+  AND acct_type = $3
+  AND label IS NOT DISTINCT FROM $4
+  AND status <> 'Closed'"#,
+        user_fp,
         currency as Currency,
         acct_type as AccountType,
+        label,
     )
     .fetch_one(pg_pool)
     .await;
@@ -231,7 +275,7 @@ VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
 
 #[tracing::instrument(
     level = "debug",
-    skip(pg_pool, user_fp),
+    skip(pg_pool, user_fp, currencies, acct_types),
     name = "Find all user accounts"
 )]
 pub async fn fetch_user_accounts_by_currencies_and_types<'a, E>(
@@ -256,12 +300,19 @@ SELECT  id,
         currency as "currency: _",
         creation_time,
         modification_time,
-        acct_type as "acct_type: _"
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
 FROM user_account
 WHERE user_fp = $1
-    AND (array_length($2::account_type[], 1) IS NULL OR acct_type = ANY($2::account_type[]))
+    AND (array_length($2::currency_enum[], 1) IS NULL OR currency = ANY($2::currency_enum[]))
+    AND (array_length($3::account_type[], 1) IS NULL OR acct_type = ANY($3::account_type[]))
 "#,
         user_fp,
+        currencies as &[Currency],
         acct_types as &[AccountType]
     )
     .fetch_all(pg_pool)
@@ -272,16 +323,18 @@ WHERE user_fp = $1
     Ok(result)
 }
 
-#[tracing::instrument(level = "debug", skip(pg_pool, acct_type, user_fp))]
+/// Finds the open account of a user with the given type and label.
+#[tracing::instrument(level = "debug", skip(pg_pool, acct_type, user_fp, label))]
 pub async fn find_account_by_acct_type<'a, E>(
     pg_pool: E,
     user_fp: &str,
     acct_type: AccountType,
+    label: Option<&str>,
 ) -> Result<Option<Account>, PgDatabaseError>
 where
     E: Executor<'a, Database = Postgres>,
 {
-    info!("finding account by currency and acct_type");
+    info!("finding account by acct_type and label");
 
     let result = sqlx::query_as!(
         AccountDO,
@@ -294,14 +347,194 @@ SELECT  id,
         currency as "currency: _",
         creation_time,
         modification_time,
-        acct_type as "acct_type: _"
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
 FROM user_account
-WHERE user_fp = $1 AND acct_type = $2"#,
+WHERE user_fp = $1 AND acct_type = $2 AND label IS NOT DISTINCT FROM $3 AND status <> 'Closed'"#,
         user_fp,
         acct_type as AccountType,
+        label,
     )
     .fetch_one(pg_pool)
     .await;
 
     handle_saved_account_result(result)
 }
+
+/// Locks up to `limit` active accounts of `acct_type` whose owner has no activity since
+/// `inactive_since`. Accounts created or changed since then are left alone, and rows locked by
+/// another worker are skipped.
+#[tracing::instrument(level = "debug", skip(pg_pool, acct_type))]
+pub async fn lock_dormant_accounts<'a, E>(
+    pg_pool: E,
+    acct_type: AccountType,
+    inactive_since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Account>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountDO,
+        r#"
+SELECT  id,
+        locked,
+        user_fp,
+        timezone,
+        status as "status: _",
+        currency as "currency: _",
+        creation_time,
+        modification_time,
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
+FROM user_account ua
+WHERE ua.status = 'Active'
+  AND ua.acct_type = $1
+  AND ua.modification_time < $2
+  AND NOT EXISTS (SELECT 1 FROM activity a WHERE a.user_fp = ua.user_fp AND a.timestamp >= $2)
+ORDER BY ua.modification_time
+LIMIT $3
+FOR UPDATE SKIP LOCKED"#,
+        acct_type as AccountType,
+        inactive_since,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result.into_iter().map(Account::from).collect())
+}
+
+/// Frozen accounts whose freeze expired at `now`, locked until the transaction ends.
+#[tracing::instrument(level = "debug", skip(pg_pool))]
+pub async fn lock_expired_frozen_accounts<'a, E>(
+    pg_pool: E,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<Account>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountDO,
+        r#"
+SELECT  id,
+        locked,
+        user_fp,
+        timezone,
+        status as "status: _",
+        currency as "currency: _",
+        creation_time,
+        modification_time,
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
+FROM user_account
+WHERE status = 'Frozen'
+  AND status_expires_at <= $1
+ORDER BY status_expires_at
+LIMIT $2
+FOR UPDATE SKIP LOCKED"#,
+        now,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result.into_iter().map(Account::from).collect())
+}
+
+/// The account followed by its ancestors, nearest first: the last account is the root.
+#[tracing::instrument(level = "debug", skip(pg_pool, account_id))]
+pub async fn find_account_ancestors<'a, E>(
+    pg_pool: E,
+    account_id: &str,
+) -> Result<Vec<Account>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountDO,
+        r#"
+WITH RECURSIVE ancestor AS (
+    SELECT ua.*, 0 AS depth
+    FROM user_account ua
+    WHERE ua.id = $1
+    UNION ALL
+    SELECT parent.*, ancestor.depth + 1
+    FROM user_account parent
+    JOIN ancestor ON parent.id = ancestor.parent_account_id
+)
+SELECT  id as "id!",
+        locked as "locked!",
+        user_fp as "user_fp!",
+        timezone as "timezone!",
+        status as "status!: _",
+        currency as "currency!: _",
+        creation_time as "creation_time!",
+        modification_time as "modification_time!",
+        acct_type as "acct_type!: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
+FROM ancestor
+ORDER BY depth"#,
+        account_id
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result.into_iter().map(Account::from).collect())
+}
+
+#[tracing::instrument(level = "debug", skip(pg_pool, parent_account_id))]
+pub async fn find_child_accounts<'a, E>(
+    pg_pool: E,
+    parent_account_id: &str,
+    include_closed: bool,
+) -> Result<Vec<Account>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountDO,
+        r#"
+SELECT  id,
+        locked,
+        user_fp,
+        timezone,
+        status as "status: _",
+        currency as "currency: _",
+        creation_time,
+        modification_time,
+        acct_type as "acct_type: _",
+        label,
+        parent_account_id,
+        status_reason as "status_reason: _",
+        status_changed_by,
+        status_expires_at
+FROM user_account
+WHERE parent_account_id = $1
+  AND ($2 OR status <> 'Closed')
+ORDER BY creation_time"#,
+        parent_account_id,
+        include_closed,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result.into_iter().map(Account::from).collect())
+}
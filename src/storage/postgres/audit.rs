@@ -1,8 +1,15 @@
-use crate::core::{AuditEventType, AuditLog};
+use crate::core::{
+    AuditCheckpoint, AuditCursor, AuditEventType, AuditFilter, AuditLog, EntityType,
+};
 use crate::PgDatabaseError;
 use sqlx::{Executor, Postgres};
 use tracing::info;
 
+// advisory lock key held while logs are appended to the audit chain
+const AUDIT_CHAIN_LOCK_KEY: i64 = 0x6175_6469_745f_6c67;
+
+/// Saves the log unchained with the hash it was sealed with, `chain_audit_log` links it to the
+/// chain later.
 #[tracing::instrument(level = "debug", skip(pg_pool, audit_log), name = "Create audit log")]
 pub async fn save_audit_log<'a, E>(pg_pool: E, audit_log: AuditLog) -> Result<bool, PgDatabaseError>
 where
@@ -25,9 +32,12 @@ INSERT INTO audit_log (
                        request_ip,
                        request_id,
                        creation_time,
-                       request_user_agent
+                       request_user_agent,
+                       service_id,
+                       hash,
+                       chain_pending
                        )
-                       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, TRUE)",
         audit_log.id,
         audit_log.changes,
         audit_log.user_fp,
@@ -37,7 +47,9 @@ INSERT INTO audit_log (
         audit_log.request_ip,
         audit_log.request_id,
         audit_log.creation_time,
-        audit_log.request_user_agent
+        audit_log.request_user_agent,
+        audit_log.service_id,
+        audit_log.hash,
     )
     .execute(pg_pool)
     .await?;
@@ -45,6 +57,7 @@ INSERT INTO audit_log (
     Ok(result.rows_affected() == 1)
 }
 
+/// The timeline of an entity, oldest first, starting after `cursor`.
 #[tracing::instrument(
     level = "debug",
     skip(pg_pool, entity_id, entity_type),
@@ -53,11 +66,16 @@ INSERT INTO audit_log (
 pub async fn find_audit_logs<'a, E>(
     pg_pool: E,
     entity_id: &str,
-    entity_type: &str,
+    entity_type: &EntityType,
+    cursor: Option<&AuditCursor>,
+    limit: i64,
 ) -> Result<Vec<AuditLog>, PgDatabaseError>
 where
     E: Executor<'a, Database = Postgres>,
 {
+    let cursor_time = cursor.map(|c| c.creation_time);
+    let cursor_id = cursor.map(|c| c.audit_id.clone());
+
     let result = sqlx::query_as!(
         AuditLog,
         r#"
@@ -70,15 +88,285 @@ SELECT id,
        request_id,
        creation_time,
        request_user_agent,
+       sequence_num,
+       prev_hash,
+       hash,
+       service_id,
        audit_type as "audit_type: _"
 FROM audit_log
-WHERE entity_id = $1
-    AND entity_type = $2"#,
+WHERE entity_type = $1
+    AND entity_id = $2
+    AND ($3::TIMESTAMPTZ IS NULL OR (creation_time, id) > ($3, $4::VARCHAR))
+ORDER BY creation_time, id
+LIMIT $5"#,
+        entity_type.to_string(),
         entity_id,
-        entity_type
+        cursor_time,
+        cursor_id,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Audit logs matching `filter`, newest first, starting after `cursor`.
+#[tracing::instrument(level = "debug", skip(pg_pool), name = "Search audit logs")]
+pub async fn search_audit_logs<'a, E>(
+    pg_pool: E,
+    filter: &AuditFilter,
+    cursor: Option<&AuditCursor>,
+    limit: i64,
+) -> Result<Vec<AuditLog>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let cursor_time = cursor.map(|c| c.creation_time);
+    let cursor_id = cursor.map(|c| c.audit_id.clone());
+
+    let result = sqlx::query_as!(
+        AuditLog,
+        r#"
+SELECT id,
+       changes,
+       user_fp,
+       entity_id,
+       entity_type,
+       request_ip,
+       request_id,
+       creation_time,
+       request_user_agent,
+       sequence_num,
+       prev_hash,
+       hash,
+       service_id,
+       audit_type as "audit_type: _"
+FROM audit_log
+WHERE ($1::VARCHAR IS NULL OR user_fp = $1)
+    AND ($2::audit_event_type IS NULL OR audit_type = $2)
+    AND ($3::TIMESTAMPTZ IS NULL OR creation_time >= $3)
+    AND ($4::TIMESTAMPTZ IS NULL OR creation_time <= $4)
+    AND ($5::TIMESTAMPTZ IS NULL OR (creation_time, id) < ($5, $6::VARCHAR))
+ORDER BY creation_time DESC, id DESC
+LIMIT $7"#,
+        filter.user_fp,
+        filter.audit_type.clone() as Option<AuditEventType>,
+        filter.from_time,
+        filter.to_time,
+        cursor_time,
+        cursor_id,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Serializes appends to the audit chain until the transaction ends.
+pub async fn lock_audit_chain<'a, E>(pg_pool: E) -> Result<(), PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    sqlx::query!(
+        "SELECT 1 AS locked FROM pg_advisory_xact_lock($1)",
+        AUDIT_CHAIN_LOCK_KEY
+    )
+    .fetch_one(pg_pool)
+    .await?;
+    Ok(())
+}
+
+/// The sequence number and hash of the last chained audit log.
+pub async fn find_audit_chain_head<'a, E>(
+    pg_pool: E,
+) -> Result<Option<(i64, String)>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+SELECT sequence_num AS "sequence_num!",
+       hash         AS "hash!"
+FROM audit_log
+WHERE sequence_num IS NOT NULL
+ORDER BY sequence_num DESC
+LIMIT 1"#
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(result.map(|head| (head.sequence_num, head.hash)))
+}
+
+/// Audit logs waiting to be chained, oldest first.
+#[tracing::instrument(level = "debug", skip(pg_pool))]
+pub async fn find_unchained_audit_logs<'a, E>(
+    pg_pool: E,
+    limit: i64,
+) -> Result<Vec<AuditLog>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditLog,
+        r#"
+SELECT id,
+       changes,
+       user_fp,
+       entity_id,
+       entity_type,
+       request_ip,
+       request_id,
+       creation_time,
+       request_user_agent,
+       sequence_num,
+       prev_hash,
+       hash,
+       service_id,
+       audit_type as "audit_type: _"
+FROM audit_log
+WHERE chain_pending
+ORDER BY creation_time, id
+LIMIT $1"#,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Links a pending log to the audit chain. Returns false when the log was chained already.
+pub async fn chain_audit_log<'a, E>(
+    pg_pool: E,
+    audit_log: &AuditLog,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE audit_log
+SET sequence_num  = $2,
+    prev_hash     = $3,
+    hash          = $4,
+    chain_pending = FALSE
+WHERE id = $1
+    AND chain_pending",
+        audit_log.id,
+        audit_log.sequence_num,
+        audit_log.prev_hash,
+        audit_log.hash,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Chained audit logs after `after_sequence`, in chain order.
+#[tracing::instrument(level = "debug", skip(pg_pool))]
+pub async fn find_chained_audit_logs<'a, E>(
+    pg_pool: E,
+    after_sequence: i64,
+    limit: i64,
+) -> Result<Vec<AuditLog>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditLog,
+        r#"
+SELECT id,
+       changes,
+       user_fp,
+       entity_id,
+       entity_type,
+       request_ip,
+       request_id,
+       creation_time,
+       request_user_agent,
+       sequence_num,
+       prev_hash,
+       hash,
+       service_id,
+       audit_type as "audit_type: _"
+FROM audit_log
+WHERE sequence_num > $1
+ORDER BY sequence_num
+LIMIT $2"#,
+        after_sequence,
+        limit,
     )
     .fetch_all(pg_pool)
     .await?;
 
     Ok(result)
 }
+
+#[tracing::instrument(level = "debug", skip(pg_pool, checkpoint))]
+pub async fn save_audit_checkpoint<'a, E>(
+    pg_pool: E,
+    checkpoint: &AuditCheckpoint,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO audit_checkpoint (sequence_num, audit_id, hash, block_id, chain_id, creation_time)
+VALUES ($1, $2, $3, $4, $5, $6)",
+        checkpoint.sequence_num,
+        checkpoint.audit_id,
+        checkpoint.hash,
+        checkpoint.block_id,
+        checkpoint.chain_id,
+        checkpoint.creation_time,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Every checkpoint of the audit chain, oldest first.
+pub async fn find_audit_checkpoints<'a, E>(
+    pg_pool: E,
+) -> Result<Vec<AuditCheckpoint>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditCheckpoint,
+        "
+SELECT sequence_num, audit_id, hash, block_id, chain_id, creation_time
+FROM audit_checkpoint
+ORDER BY sequence_num"
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+pub async fn find_last_audit_checkpoint<'a, E>(
+    pg_pool: E,
+) -> Result<Option<AuditCheckpoint>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditCheckpoint,
+        "
+SELECT sequence_num, audit_id, hash, block_id, chain_id, creation_time
+FROM audit_checkpoint
+ORDER BY sequence_num DESC
+LIMIT 1"
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(result)
+}
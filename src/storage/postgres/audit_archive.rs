@@ -0,0 +1,360 @@
+use crate::core::{
+    ArchivedAuditLink, AuditArchive, AuditArchiveCursor, AuditEventType, AuditLog, EntityType,
+};
+use crate::PgDatabaseError;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Executor, Postgres};
+use tracing::info;
+
+/// The oldest audit logs of `entity_type` created before `cutoff`, locked until the transaction
+/// ends. Restored logs, logs not chained yet and chained logs from `before_sequence` on are left
+/// out, so only the part of the chain a checkpoint anchored is archived.
+#[tracing::instrument(level = "debug", skip(pg_pool), name = "Lock archivable audit logs")]
+pub async fn lock_archivable_audit_logs<'a, E>(
+    pg_pool: E,
+    entity_type: &EntityType,
+    cutoff: DateTime<Utc>,
+    before_sequence: i64,
+    limit: i64,
+) -> Result<Vec<AuditLog>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditLog,
+        r#"
+SELECT id,
+       changes,
+       user_fp,
+       entity_id,
+       entity_type,
+       request_ip,
+       request_id,
+       creation_time,
+       request_user_agent,
+       sequence_num,
+       prev_hash,
+       hash,
+       service_id,
+       audit_type as "audit_type: _"
+FROM audit_log
+WHERE entity_type = $1
+    AND creation_time < $2
+    AND restored_from IS NULL
+    AND NOT chain_pending
+    AND (sequence_num IS NULL OR sequence_num < $3)
+ORDER BY creation_time, id
+LIMIT $4
+FOR UPDATE SKIP LOCKED"#,
+        entity_type.to_string(),
+        cutoff,
+        before_sequence,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pg_pool, archive), name = "Create audit archive")]
+pub async fn save_audit_archive<'a, E>(
+    pg_pool: E,
+    archive: &AuditArchive,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    info!(
+        "creating new audit archive :: archiveId={} :: entityType={} :: rows={}",
+        &archive.id, archive.entity_type, archive.row_count
+    );
+
+    let result = sqlx::query!(
+        "
+INSERT INTO audit_archive (
+                           id,
+                           entity_type,
+                           archive_date,
+                           object_path,
+                           manifest_path,
+                           row_count,
+                           checksum,
+                           from_time,
+                           to_time,
+                           creation_time
+                           )
+                           VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        archive.id,
+        archive.entity_type.to_string(),
+        archive.archive_date,
+        archive.object_path,
+        archive.manifest_path,
+        archive.row_count,
+        archive.checksum,
+        archive.from_time,
+        archive.to_time,
+        archive.creation_time,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+pub async fn find_audit_archive_by_id<'a, E>(
+    pg_pool: E,
+    archive_id: &str,
+) -> Result<Option<AuditArchive>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AuditArchive,
+        "
+SELECT id,
+       entity_type,
+       archive_date,
+       object_path,
+       manifest_path,
+       row_count,
+       checksum,
+       from_time,
+       to_time,
+       creation_time,
+       restored_at
+FROM audit_archive
+WHERE id = $1",
+        archive_id,
+    )
+    .fetch_optional(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Archives of the logs created between `from_date` and `to_date`, oldest archive first, starting
+/// after `cursor`. A `None` criterion means "any".
+#[tracing::instrument(level = "debug", skip(pg_pool), name = "Find audit archives")]
+pub async fn find_audit_archives<'a, E>(
+    pg_pool: E,
+    entity_type: Option<&EntityType>,
+    from_date: Option<NaiveDate>,
+    to_date: Option<NaiveDate>,
+    cursor: Option<&AuditArchiveCursor>,
+    limit: i64,
+) -> Result<Vec<AuditArchive>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let cursor_time = cursor.map(|c| c.creation_time);
+    let cursor_id = cursor.map(|c| c.archive_id.clone());
+
+    let result = sqlx::query_as!(
+        AuditArchive,
+        "
+SELECT id,
+       entity_type,
+       archive_date,
+       object_path,
+       manifest_path,
+       row_count,
+       checksum,
+       from_time,
+       to_time,
+       creation_time,
+       restored_at
+FROM audit_archive
+WHERE ($1::VARCHAR IS NULL OR entity_type = $1)
+    AND ($2::DATE IS NULL OR archive_date >= $2)
+    AND ($3::DATE IS NULL OR archive_date <= $3)
+    AND ($4::TIMESTAMPTZ IS NULL OR (creation_time, id) > ($4, $5::VARCHAR))
+ORDER BY creation_time, id
+LIMIT $6",
+        entity_type.map(|entity_type| entity_type.to_string()),
+        from_date,
+        to_date,
+        cursor_time,
+        cursor_id,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Marks the archive restored, or released with a `None` ***restored_at***. False when it already
+/// was, so two restores of the same archive can't both load its logs.
+pub async fn update_audit_archive_restored_at<'a, E>(
+    pg_pool: E,
+    archive_id: &str,
+    restored_at: Option<DateTime<Utc>>,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE audit_archive
+SET restored_at = $2
+WHERE id = $1 AND (restored_at IS NULL) = ($2::TIMESTAMPTZ IS NOT NULL)",
+        archive_id,
+        restored_at,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Keeps the chain links of the audit logs `audit_ids`, about to be moved to `archive_id`.
+pub async fn save_archived_audit_links<'a, E>(
+    pg_pool: E,
+    archive_id: &str,
+    audit_ids: &[String],
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO audit_log_archived (sequence_num, audit_id, prev_hash, hash, archive_id)
+SELECT sequence_num, id, prev_hash, hash, $1
+FROM audit_log
+WHERE id = ANY($2) AND sequence_num IS NOT NULL AND hash IS NOT NULL",
+        archive_id,
+        audit_ids,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Chain links of archived audit logs after `after_sequence`, in chain order.
+pub async fn find_archived_audit_links<'a, E>(
+    pg_pool: E,
+    after_sequence: i64,
+    limit: i64,
+) -> Result<Vec<ArchivedAuditLink>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ArchivedAuditLink,
+        "
+SELECT sequence_num, audit_id, prev_hash, hash, archive_id
+FROM audit_log_archived
+WHERE sequence_num > $1
+ORDER BY sequence_num
+LIMIT $2",
+        after_sequence,
+        limit,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_archived_audit_links<'a, E>(
+    pg_pool: E,
+    archive_id: &str,
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "DELETE FROM audit_log_archived WHERE archive_id = $1",
+        archive_id,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_audit_logs<'a, E>(
+    pg_pool: E,
+    audit_ids: &[String],
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!("DELETE FROM audit_log WHERE id = ANY($1)", audit_ids)
+        .execute(pg_pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Loads an archived audit log back into `audit_log`, as it was chained.
+#[tracing::instrument(level = "debug", skip(pg_pool, audit_log), name = "Restore audit log")]
+pub async fn save_restored_audit_log<'a, E>(
+    pg_pool: E,
+    audit_log: &AuditLog,
+    archive_id: &str,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO audit_log (
+                       id,
+                       changes,
+                       user_fp,
+                       entity_id,
+                       entity_type,
+                       audit_type,
+                       request_ip,
+                       request_id,
+                       creation_time,
+                       request_user_agent,
+                       sequence_num,
+                       prev_hash,
+                       hash,
+                       service_id,
+                       restored_from
+                       )
+                       VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)",
+        audit_log.id,
+        audit_log.changes,
+        audit_log.user_fp,
+        audit_log.entity_id,
+        audit_log.entity_type.to_string(),
+        audit_log.audit_type.clone() as AuditEventType,
+        audit_log.request_ip,
+        audit_log.request_id,
+        audit_log.creation_time,
+        audit_log.request_user_agent,
+        audit_log.sequence_num,
+        audit_log.prev_hash,
+        audit_log.hash,
+        audit_log.service_id,
+        archive_id,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// The ids of the audit logs restored from `archive_id`.
+pub async fn find_restored_audit_ids<'a, E>(
+    pg_pool: E,
+    archive_id: &str,
+) -> Result<Vec<String>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_scalar!(
+        "SELECT id FROM audit_log WHERE restored_from = $1",
+        archive_id,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
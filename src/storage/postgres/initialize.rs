@@ -1,4 +1,4 @@
-use crate::PostgresConfig;
+use crate::{PgDatabaseError, PostgresConfig};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::sync::OnceLock;
@@ -11,3 +11,11 @@ pub fn setup_postgres(postgres_config: PostgresConfig) -> &'static PgPool {
             .connect_lazy_with(postgres_config.connect_to_database(&postgres_config.name))
     })
 }
+
+/// Health probe, fails when no connection of the pool can run a query.
+pub async fn ping_postgres(pg_pool: &PgPool) -> Result<(), PgDatabaseError> {
+    sqlx::query_scalar!("SELECT 1 AS one")
+        .fetch_one(pg_pool)
+        .await?;
+    Ok(())
+}
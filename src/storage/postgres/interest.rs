@@ -0,0 +1,256 @@
+use crate::core::{AccountType, Currency, InterestAccrual};
+use crate::PgDatabaseError;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::{Executor, Postgres};
+
+/// An account with a day of interest to accrue: ***accrual_date*** in the account timezone and
+/// ***balance***, what its account-currency wallet held when that day ended.
+#[derive(Debug, Clone)]
+pub struct InterestCandidate {
+    pub account_id: String,
+    pub balance: Decimal,
+    pub accrual_date: NaiveDate,
+}
+
+/// An account with interest accrued before the current month of its timezone and not paid yet.
+#[derive(Debug, Clone)]
+pub struct CapitalizationCandidate {
+    pub account_id: String,
+    pub user_fp: String,
+    pub timezone: String,
+    pub accrued_until: NaiveDate,
+}
+
+/// Accounts of a product with a past day not accrued yet, oldest days first. An account starts
+/// accruing on the day before the first run that sees it, later days the job missed are caught up
+/// one per call.
+///
+/// The end-of-day balance is the current balance less what the completed transactions moved since
+/// the day ended, a transaction being a debit when one of its ledger entries is. Both are read in
+/// the same statement so they agree.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn find_interest_candidates<'a, E>(
+    pool: E,
+    acct_type: AccountType,
+    currency: Currency,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<InterestCandidate>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        InterestCandidate,
+        r#"
+SELECT ua.id                      AS account_id,
+       w.balance - since.amount   AS "balance!",
+       next.accrual_date          AS "accrual_date!"
+FROM user_account ua
+JOIN wallet w ON w.account_id = ua.id AND w.currency = ua.currency
+CROSS JOIN LATERAL (
+    SELECT COALESCE(MAX(ia.accrual_date) + 1,
+                    GREATEST((ua.creation_time AT TIME ZONE ua.timezone)::date,
+                             ($1 AT TIME ZONE ua.timezone)::date - 1)) AS accrual_date
+    FROM interest_accrual ia
+    WHERE ia.account_id = ua.id
+) next
+CROSS JOIN LATERAL (
+    SELECT COALESCE(SUM(CASE
+                            WHEN EXISTS (SELECT 1
+                                         FROM ledger_entry le
+                                         WHERE le.transaction_id = mt.transaction_id
+                                           AND le.entry_type = 'Debit') THEN -mt.amount
+                            ELSE mt.amount END), 0) AS amount
+    FROM monetary_transaction mt
+    WHERE mt.account_id = ua.id
+      AND mt.status = 'Completed'
+      AND mt.modification_date >= ((next.accrual_date + 1)::timestamp AT TIME ZONE ua.timezone)
+) since
+WHERE ua.acct_type = $2
+  AND ua.currency = $3
+  AND ua.status <> 'Closed'
+  AND next.accrual_date < ($1 AT TIME ZONE ua.timezone)::date
+ORDER BY next.accrual_date, ua.id
+LIMIT $4"#,
+        now,
+        acct_type as AccountType,
+        currency as Currency,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Records the accrual of a day. Returns false when the day was already accrued.
+#[tracing::instrument(level = "debug", skip(pool, accrual))]
+pub async fn save_interest_accrual<'a, E>(
+    pool: E,
+    accrual: &InterestAccrual,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO interest_accrual
+(
+ account_id,
+ accrual_date,
+ currency,
+ balance,
+ apr,
+ amount,
+ transaction_id,
+ capitalized_at,
+ creation_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+ON CONFLICT (account_id, accrual_date) DO NOTHING",
+        accrual.account_id,
+        accrual.accrual_date,
+        accrual.currency.clone() as Currency,
+        accrual.balance,
+        accrual.apr,
+        accrual.amount,
+        accrual.transaction_id,
+        accrual.capitalized_at,
+        accrual.creation_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Active accounts with unpaid interest accrued before the current month of their timezone.
+/// Interest of the other accounts waits until they are active again.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn find_capitalization_candidates<'a, E>(
+    pool: E,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<CapitalizationCandidate>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        CapitalizationCandidate,
+        r#"
+SELECT ia.account_id,
+       ua.user_fp,
+       ua.timezone,
+       MAX(ia.accrual_date) AS "accrued_until!"
+FROM interest_accrual ia
+JOIN user_account ua ON ua.id = ia.account_id
+WHERE ia.capitalized_at IS NULL
+  AND ua.status = 'Active'
+  AND NOT ua.locked
+  AND ia.accrual_date < date_trunc('month', $1 AT TIME ZONE ua.timezone)::date
+GROUP BY ia.account_id, ua.user_fp, ua.timezone
+ORDER BY ia.account_id
+LIMIT $2"#,
+        now,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Locks the unpaid accruals of an account up to `accrued_until`, skipping the ones another
+/// worker holds.
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn lock_unpaid_interest_accruals<'a, E>(
+    pool: E,
+    account_id: &str,
+    accrued_until: NaiveDate,
+) -> Result<Vec<InterestAccrual>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        InterestAccrual,
+        r#"
+SELECT account_id,
+       accrual_date,
+       currency as "currency: _",
+       balance,
+       apr,
+       amount,
+       transaction_id,
+       capitalized_at,
+       creation_time
+FROM interest_accrual
+WHERE account_id = $1
+  AND accrual_date <= $2
+  AND capitalized_at IS NULL
+ORDER BY accrual_date
+FOR UPDATE SKIP LOCKED"#,
+        account_id,
+        accrued_until,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Marks the unpaid accruals of an account up to `accrued_until` as paid by `transaction_id`.
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn capitalize_interest_accruals<'a, E>(
+    pool: E,
+    account_id: &str,
+    accrued_until: NaiveDate,
+    transaction_id: Option<String>,
+    capitalized_at: DateTime<Utc>,
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE interest_accrual
+SET transaction_id = $1,
+    capitalized_at = $2
+WHERE account_id = $3
+  AND accrual_date <= $4
+  AND capitalized_at IS NULL",
+        transaction_id,
+        capitalized_at,
+        account_id,
+        accrued_until,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The interest accrued on an account and not paid yet, with the first and last day it covers.
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_unpaid_interest<'a, E>(
+    pool: E,
+    account_id: &str,
+) -> Result<(Decimal, Option<NaiveDate>, Option<NaiveDate>), PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+SELECT COALESCE(SUM(amount), 0) AS "amount!",
+       MIN(accrual_date)        AS accrued_since,
+       MAX(accrual_date)        AS accrued_until
+FROM interest_accrual
+WHERE account_id = $1
+  AND capitalized_at IS NULL"#,
+        account_id,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok((result.amount, result.accrued_since, result.accrued_until))
+}
@@ -19,15 +19,17 @@ INSERT INTO ledger_entry (
             description,
             sequence_number,
             timestamp,
-            entry_type
+            entry_type,
+            transaction_id
         )
-VALUES ($1, $2, $3, $4, $5, $6)",
+VALUES ($1, $2, $3, $4, $5, $6, $7)",
         ledger_entry.id.clone(),
         ledger_entry.account_id.clone(),
         ledger_entry.description,
         ledger_entry.sequence_number.clone() as i64,
         ledger_entry.timestamp,
         ledger_entry.entry_type.clone() as EntryType,
+        ledger_entry.transaction_id,
     )
     .execute(pg_pool)
     .await?;
@@ -49,6 +51,7 @@ where
     let mut account_ids = Vec::new();
     let mut timestamps = Vec::new();
     let mut descriptions = Vec::new();
+    let mut transaction_ids = Vec::new();
     for entry in entries {
         ids.push(entry.id);
         account_ids.push(entry.account_id);
@@ -56,6 +59,7 @@ where
         entry_types.push(entry.entry_type.to_string());
         seq_numbers.push(entry.sequence_number as i64);
         descriptions.push(entry.description.unwrap_or_else(|| "".to_string()));
+        transaction_ids.push(entry.transaction_id);
     }
     let rows_affected = sqlx::query!(
         r#"
@@ -65,7 +69,8 @@ INSERT INTO ledger_entry (
                           description,
                           sequence_number,
                           timestamp,
-                          entry_type
+                          entry_type,
+                          transaction_id
 )
 SELECT * FROM UNNEST(
                 $1::VARCHAR[],
@@ -73,7 +78,8 @@ SELECT * FROM UNNEST(
                 $3::TEXT[],
                 $4::BIGINT[],
                 $5::TIMESTAMP[],
-                $6::text[]::entry_type[]
+                $6::text[]::entry_type[],
+                $7::VARCHAR[]
             )
 "#,
         ids.as_slice(),
@@ -82,9 +88,49 @@ SELECT * FROM UNNEST(
         seq_numbers.as_slice(),
         timestamps.as_slice(),
         entry_types.as_slice(),
+        transaction_ids.as_slice() as &[Option<String>],
     )
     .execute(pool)
     .await?
     .rows_affected();
     Ok(rows_affected)
 }
+
+#[tracing::instrument(level = "debug", skip(pool, transaction_id))]
+pub async fn find_ledgers_by_transaction_id<'a, E>(
+    pool: E,
+    transaction_id: &str,
+) -> Result<Vec<LedgerEntry>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+SELECT id,
+       account_id,
+       description,
+       sequence_number,
+       timestamp,
+       transaction_id,
+       entry_type as "entry_type: EntryType"
+FROM ledger_entry
+WHERE transaction_id = $1
+ORDER BY timestamp, id"#,
+        transaction_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result
+        .into_iter()
+        .map(|row| LedgerEntry {
+            id: row.id,
+            timestamp: row.timestamp,
+            entry_type: row.entry_type,
+            account_id: row.account_id,
+            description: row.description,
+            transaction_id: row.transaction_id,
+            sequence_number: row.sequence_number as u64,
+        })
+        .collect())
+}
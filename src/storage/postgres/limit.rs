@@ -0,0 +1,148 @@
+use crate::core::{AccountLimits, LimitUsage};
+use crate::PgDatabaseError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+
+/// Saves the limits of an account, replacing the ones it had.
+#[tracing::instrument(level = "debug", skip(pool, limits))]
+pub async fn save_account_limits<'a, E>(
+    pool: E,
+    limits: &AccountLimits,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO account_limit
+(
+ account_id,
+ max_amount,
+ daily_debit,
+ monthly_debit,
+ hourly_count,
+ changed_by,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7)
+ON CONFLICT (account_id) DO UPDATE
+SET max_amount = EXCLUDED.max_amount,
+    daily_debit = EXCLUDED.daily_debit,
+    monthly_debit = EXCLUDED.monthly_debit,
+    hourly_count = EXCLUDED.hourly_count,
+    changed_by = EXCLUDED.changed_by,
+    modification_time = EXCLUDED.modification_time",
+        limits.account_id,
+        limits.max_amount,
+        limits.daily_debit,
+        limits.monthly_debit,
+        limits.hourly_count,
+        limits.changed_by,
+        limits.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_account_limits<'a, E>(
+    pool: E,
+    account_id: &str,
+) -> Result<Option<AccountLimits>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountLimits,
+        "
+SELECT account_id,
+       max_amount,
+       daily_debit,
+       monthly_debit,
+       hourly_count,
+       changed_by,
+       modification_time
+FROM account_limit
+WHERE account_id = $1",
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_ids))]
+pub async fn find_accounts_limits<'a, E>(
+    pool: E,
+    account_ids: &[String],
+) -> Result<Vec<AccountLimits>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountLimits,
+        "
+SELECT account_id,
+       max_amount,
+       daily_debit,
+       monthly_debit,
+       hourly_count,
+       changed_by,
+       modification_time
+FROM account_limit
+WHERE account_id = ANY($1)",
+        account_ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Adds up the completed customer-initiated transactions of an account since `month_start`: how
+/// many were made since `hour_start` and how much was debited since `day_start` and `month_start`.
+/// A transaction is a debit when one of its ledger entries is.
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_account_limit_usage<'a, E>(
+    pool: E,
+    account_id: &str,
+    hour_start: DateTime<Utc>,
+    day_start: DateTime<Utc>,
+    month_start: DateTime<Utc>,
+) -> Result<LimitUsage, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        r#"
+SELECT COUNT(*) FILTER (WHERE mt.timestamp >= $2)                       AS "hourly_count!",
+       COALESCE(SUM(mt.amount) FILTER (WHERE mt.timestamp >= $3 AND debit.is_debit), 0) AS "daily_debit!",
+       COALESCE(SUM(mt.amount) FILTER (WHERE debit.is_debit), 0)         AS "monthly_debit!"
+FROM monetary_transaction mt
+CROSS JOIN LATERAL (
+    SELECT EXISTS (SELECT 1
+                   FROM ledger_entry le
+                   WHERE le.transaction_id = mt.transaction_id
+                     AND le.entry_type = 'Debit') AS is_debit
+) debit
+WHERE mt.account_id = $1
+  AND mt.status = 'Completed'
+  AND mt.transaction_type IN ('Payment', 'Transfer')
+  AND mt.timestamp >= $4"#,
+        account_id,
+        hour_start,
+        day_start,
+        month_start,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(LimitUsage {
+        hourly_count: result.hourly_count as u64,
+        daily_debit: result.daily_debit,
+        monthly_debit: result.monthly_debit,
+    })
+}
@@ -0,0 +1,368 @@
+use crate::core::{AccountMember, AccountRole, ApprovalRequest, ApprovalStatus, SigningPolicy};
+use crate::PgDatabaseError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+
+/// Adds a member to an account, or changes the role of an existing member.
+#[tracing::instrument(level = "debug", skip(pool, member))]
+pub async fn save_account_member<'a, E>(
+    pool: E,
+    member: &AccountMember,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO account_member
+(
+ account_id,
+ user_fp,
+ role,
+ added_by,
+ creation_time,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (account_id, user_fp) DO UPDATE
+SET role = EXCLUDED.role,
+    modification_time = EXCLUDED.modification_time",
+        member.account_id,
+        member.user_fp,
+        member.role.clone() as AccountRole,
+        member.added_by,
+        member.creation_time,
+        member.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id, user_fp))]
+pub async fn delete_account_member<'a, E>(
+    pool: E,
+    account_id: &str,
+    user_fp: &str,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "DELETE FROM account_member WHERE account_id = $1 AND user_fp = $2",
+        account_id,
+        user_fp,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id, user_fp))]
+pub async fn find_account_member<'a, E>(
+    pool: E,
+    account_id: &str,
+    user_fp: &str,
+) -> Result<Option<AccountMember>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountMember,
+        r#"
+SELECT account_id,
+       user_fp,
+       role as "role: _",
+       added_by,
+       creation_time,
+       modification_time
+FROM account_member
+WHERE account_id = $1
+  AND user_fp = $2"#,
+        account_id,
+        user_fp,
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_account_members<'a, E>(
+    pool: E,
+    account_id: &str,
+) -> Result<Vec<AccountMember>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        AccountMember,
+        r#"
+SELECT account_id,
+       user_fp,
+       role as "role: _",
+       added_by,
+       creation_time,
+       modification_time
+FROM account_member
+WHERE account_id = $1
+ORDER BY creation_time"#,
+        account_id,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Saves the signing policy of an account, replacing the one it had.
+#[tracing::instrument(level = "debug", skip(pool, policy))]
+pub async fn save_signing_policy<'a, E>(
+    pool: E,
+    policy: &SigningPolicy,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO signing_policy
+(
+ account_id,
+ threshold,
+ required_approvals,
+ approval_window,
+ changed_by,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT (account_id) DO UPDATE
+SET threshold = EXCLUDED.threshold,
+    required_approvals = EXCLUDED.required_approvals,
+    approval_window = EXCLUDED.approval_window,
+    changed_by = EXCLUDED.changed_by,
+    modification_time = EXCLUDED.modification_time",
+        policy.account_id,
+        policy.threshold,
+        policy.required_approvals,
+        policy.approval_window,
+        policy.changed_by,
+        policy.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_signing_policy<'a, E>(
+    pool: E,
+    account_id: &str,
+) -> Result<Option<SigningPolicy>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        SigningPolicy,
+        "
+SELECT account_id,
+       threshold,
+       required_approvals,
+       approval_window,
+       changed_by,
+       modification_time
+FROM signing_policy
+WHERE account_id = $1",
+        account_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, request))]
+pub async fn save_approval_request<'a, E>(
+    pool: E,
+    request: &ApprovalRequest,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO approval_request
+(
+ transaction_id,
+ account_id,
+ requested_by,
+ commission,
+ required_approvals,
+ status,
+ expires_at,
+ creation_time,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        request.transaction_id,
+        request.account_id,
+        request.requested_by,
+        request.commission,
+        request.required_approvals,
+        request.status.clone() as ApprovalStatus,
+        request.expires_at,
+        request.creation_time,
+        request.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, transaction_id))]
+pub async fn find_approval_request<'a, E>(
+    pool: E,
+    transaction_id: &str,
+) -> Result<Option<ApprovalRequest>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ApprovalRequest,
+        r#"
+SELECT transaction_id,
+       account_id,
+       requested_by,
+       commission,
+       required_approvals,
+       status as "status: _",
+       expires_at,
+       creation_time,
+       modification_time
+FROM approval_request
+WHERE transaction_id = $1"#,
+        transaction_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Compare-and-swap on the request status: it is only closed while still `Pending`, so a request
+/// is approved or expired once.
+#[tracing::instrument(level = "debug", skip(pool, request))]
+pub async fn close_approval_request<'a, E>(
+    pool: E,
+    request: &ApprovalRequest,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE approval_request
+SET status = $1,
+    modification_time = $2
+WHERE transaction_id = $3
+  AND status = 'Pending'",
+        request.status.clone() as ApprovalStatus,
+        request.modification_time,
+        request.transaction_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Locks up to `limit` pending requests expired at `now`, skipping the ones another worker holds.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn lock_expired_approval_requests<'a, E>(
+    pool: E,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ApprovalRequest>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ApprovalRequest,
+        r#"
+SELECT transaction_id,
+       account_id,
+       requested_by,
+       commission,
+       required_approvals,
+       status as "status: _",
+       expires_at,
+       creation_time,
+       modification_time
+FROM approval_request
+WHERE status = 'Pending'
+  AND expires_at <= $1
+ORDER BY expires_at
+LIMIT $2
+FOR UPDATE SKIP LOCKED"#,
+        now,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Records the approval of `user_fp`. Returns false when they already approved the transaction.
+#[tracing::instrument(level = "debug", skip(pool, transaction_id, user_fp))]
+pub async fn save_transaction_approval<'a, E>(
+    pool: E,
+    transaction_id: &str,
+    user_fp: &str,
+    approved_at: DateTime<Utc>,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO transaction_approval (transaction_id, user_fp, approved_at)
+VALUES ($1, $2, $3)
+ON CONFLICT (transaction_id, user_fp) DO NOTHING",
+        transaction_id,
+        user_fp,
+        approved_at,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// The users who approved a transaction, in the order they approved it.
+#[tracing::instrument(level = "debug", skip(pool, transaction_id))]
+pub async fn find_transaction_approvers<'a, E>(
+    pool: E,
+    transaction_id: &str,
+) -> Result<Vec<String>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_scalar!(
+        "
+SELECT user_fp
+FROM transaction_approval
+WHERE transaction_id = $1
+ORDER BY approved_at",
+        transaction_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
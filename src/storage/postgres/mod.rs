@@ -1,23 +1,71 @@
 mod account;
 mod activity;
 mod audit;
+mod audit_archive;
 mod chain;
 mod currency;
 mod initialize;
+mod interest;
 mod ledger;
+mod limit;
+mod member;
+mod overdraft;
+mod payout;
+mod schedule;
 mod transaction;
 mod wallet;
 
 pub use account::{
-    fetch_user_accounts_by_currencies_and_types, find_account_by_acct_type,
-    find_account_by_currency_and_acct_type, find_account_by_id, save_account,
-    save_beneficiary_account, update_account,
+    fetch_user_accounts_by_currencies_and_types, find_account_ancestors, find_account_by_acct_type,
+    find_account_by_currency_and_acct_type, find_account_by_id, find_child_accounts,
+    lock_dormant_accounts, lock_expired_frozen_accounts, save_account, save_beneficiary_account,
+    update_account,
 };
 pub use activity::{find_last_activity, save_activity};
-pub use audit::{find_audit_logs, save_audit_log};
+pub use audit::{
+    chain_audit_log, find_audit_chain_head, find_audit_checkpoints, find_audit_logs,
+    find_chained_audit_logs, find_last_audit_checkpoint, find_unchained_audit_logs,
+    lock_audit_chain, save_audit_checkpoint, save_audit_log, search_audit_logs,
+};
+pub use audit_archive::{
+    delete_archived_audit_links, delete_audit_logs, find_archived_audit_links,
+    find_audit_archive_by_id, find_audit_archives, find_restored_audit_ids,
+    lock_archivable_audit_logs, save_archived_audit_links, save_audit_archive,
+    save_restored_audit_log, update_audit_archive_restored_at,
+};
 pub use chain::{add_child_cs_to_parent, find_chain_stamp_by_id, save_chain_stamp};
 pub use currency::{fetch_currency_rate, save_currency_rate_record};
-pub use initialize::setup_postgres;
-pub use ledger::{bulk_save_ledger, save_ledger};
-pub use transaction::save_monetary_tx;
-pub use wallet::{create_wallet, fetch_user_wallets, fetch_wallets, update_wallet_balance};
+pub use initialize::{ping_postgres, setup_postgres};
+pub use interest::{
+    capitalize_interest_accruals, find_capitalization_candidates, find_interest_candidates,
+    find_unpaid_interest, lock_unpaid_interest_accruals, save_interest_accrual,
+    CapitalizationCandidate, InterestCandidate,
+};
+pub use ledger::{bulk_save_ledger, find_ledgers_by_transaction_id, save_ledger};
+pub use limit::{
+    find_account_limit_usage, find_account_limits, find_accounts_limits, save_account_limits,
+};
+pub use member::{
+    close_approval_request, delete_account_member, find_account_member, find_account_members,
+    find_approval_request, find_signing_policy, find_transaction_approvers,
+    lock_expired_approval_requests, save_account_member, save_approval_request,
+    save_signing_policy, save_transaction_approval,
+};
+pub use overdraft::{find_overdraft_candidates, save_overdraft_charge, OverdraftCandidate};
+pub use payout::{
+    find_payout_items_by_idempotency_keys, save_payout_batch, save_payout_batch_item,
+    update_payout_batch, update_payout_batch_item,
+};
+pub use schedule::{
+    cancel_account_scheduled_payments, find_account_scheduled_payments,
+    find_scheduled_payment_by_id, lock_due_scheduled_payments, save_scheduled_payment,
+    save_scheduled_payment_execution, update_scheduled_payment,
+};
+pub use transaction::{
+    find_account_monetary_txs, find_monetary_tx_by_id, save_monetary_tx, set_monetary_tx_block,
+    set_monetary_txs_block, update_transaction_status,
+};
+pub use wallet::{
+    create_wallet, fetch_account_tree_wallets, fetch_user_wallets, fetch_wallet_for_update,
+    fetch_wallets, fetch_wallets_for_update, update_wallet_balance, update_wallet_overdraft_limit,
+};
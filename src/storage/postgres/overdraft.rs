@@ -0,0 +1,119 @@
+use crate::core::{Currency, OverdraftCharge};
+use crate::PgDatabaseError;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sqlx::{Executor, Postgres};
+
+/// A wallet with a credit line and a day to charge: ***charge_date*** in the account timezone and
+/// ***balance***, what the wallet held when that day ended.
+#[derive(Debug, Clone)]
+pub struct OverdraftCandidate {
+    pub account_id: String,
+    pub user_fp: String,
+    pub timezone: String,
+    pub currency: Currency,
+    pub account_currency: Currency,
+    pub balance: Decimal,
+    pub charge_date: NaiveDate,
+}
+
+/// Wallets in `currency` with a credit line and a past day not charged yet, oldest days first.
+/// Like interest accruals, a wallet starts on the day before the first run that sees it and later
+/// days the job missed are caught up one per call.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn find_overdraft_candidates<'a, E>(
+    pool: E,
+    currency: Currency,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<OverdraftCandidate>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        OverdraftCandidate,
+        r#"
+SELECT ua.id                      AS account_id,
+       ua.user_fp,
+       ua.timezone,
+       w.currency                 AS "currency: _",
+       ua.currency                AS "account_currency: _",
+       w.balance - since.amount   AS "balance!",
+       next.charge_date           AS "charge_date!"
+FROM user_account ua
+JOIN wallet w ON w.account_id = ua.id AND w.currency = $2
+CROSS JOIN LATERAL (
+    SELECT COALESCE(MAX(oc.charge_date) + 1,
+                    GREATEST((ua.creation_time AT TIME ZONE ua.timezone)::date,
+                             ($1 AT TIME ZONE ua.timezone)::date - 1)) AS charge_date
+    FROM overdraft_charge oc
+    WHERE oc.account_id = ua.id
+      AND oc.currency = w.currency
+) next
+CROSS JOIN LATERAL (
+    SELECT COALESCE(SUM(CASE
+                            WHEN EXISTS (SELECT 1
+                                         FROM ledger_entry le
+                                         WHERE le.transaction_id = mt.transaction_id
+                                           AND le.entry_type = 'Debit') THEN -mt.amount
+                            ELSE mt.amount END), 0) AS amount
+    FROM monetary_transaction mt
+    -- the transactions of an account move its account-currency wallet
+    WHERE mt.account_id = ua.id
+      AND w.currency = ua.currency
+      AND mt.status = 'Completed'
+      AND mt.modification_date >= ((next.charge_date + 1)::timestamp AT TIME ZONE ua.timezone)
+) since
+WHERE ua.status <> 'Closed'
+  AND w.overdraft_limit > 0
+  AND next.charge_date < ($1 AT TIME ZONE ua.timezone)::date
+ORDER BY next.charge_date, ua.id
+LIMIT $3"#,
+        now,
+        currency as Currency,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Records the charge of a day of a wallet. Returns false when the day was already charged.
+#[tracing::instrument(level = "debug", skip(pool, charge))]
+pub async fn save_overdraft_charge<'a, E>(
+    pool: E,
+    charge: &OverdraftCharge,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO overdraft_charge
+(
+ account_id,
+ charge_date,
+ currency,
+ balance,
+ fee,
+ interest,
+ transaction_id,
+ creation_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT (account_id, currency, charge_date) DO NOTHING",
+        charge.account_id,
+        charge.charge_date,
+        charge.currency.clone() as Currency,
+        charge.balance,
+        charge.fee,
+        charge.interest,
+        charge.transaction_id,
+        charge.creation_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
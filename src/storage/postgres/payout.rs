@@ -0,0 +1,179 @@
+use crate::core::{
+    PayoutBatch, PayoutBatchItem, PayoutBatchMode, PayoutBatchStatus, PayoutItemStatus,
+    PayoutItemType,
+};
+use crate::PgDatabaseError;
+use sqlx::{Executor, Postgres};
+
+#[tracing::instrument(skip(pool, batch))]
+pub async fn save_payout_batch<'a, E>(pool: E, batch: &PayoutBatch) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO payout_batch
+(
+ id,
+ user_fp,
+ mode,
+ status,
+ item_count,
+ block_id,
+ creation_time,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        batch.id,
+        batch.user_fp,
+        batch.mode.clone() as PayoutBatchMode,
+        batch.status.clone() as PayoutBatchStatus,
+        batch.item_count,
+        batch.block_id,
+        batch.creation_time,
+        batch.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, batch))]
+pub async fn update_payout_batch<'a, E>(
+    pool: E,
+    batch: &PayoutBatch,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE payout_batch
+SET status = $1,
+    block_id = $2,
+    modification_time = $3
+WHERE id = $4",
+        batch.status.clone() as PayoutBatchStatus,
+        batch.block_id,
+        batch.modification_time,
+        batch.id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, item))]
+pub async fn save_payout_batch_item<'a, E>(
+    pool: E,
+    item: &PayoutBatchItem,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO payout_batch_item
+(
+ id,
+ batch_id,
+ user_fp,
+ idempotency_key,
+ item_type,
+ account_id,
+ source_account_id,
+ amount,
+ status,
+ transaction_ids,
+ failure_reason,
+ creation_time,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+        item.id,
+        item.batch_id,
+        item.user_fp,
+        item.idempotency_key,
+        item.item_type.clone() as PayoutItemType,
+        item.account_id,
+        item.source_account_id,
+        item.amount,
+        item.status.clone() as PayoutItemStatus,
+        &item.transaction_ids,
+        item.failure_reason,
+        item.creation_time,
+        item.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Records the outcome of an item: its status, the transactions it produced or why it failed.
+#[tracing::instrument(level = "debug", skip(pool, item))]
+pub async fn update_payout_batch_item<'a, E>(
+    pool: E,
+    item: &PayoutBatchItem,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE payout_batch_item
+SET status = $1,
+    transaction_ids = $2,
+    failure_reason = $3,
+    modification_time = $4
+WHERE id = $5",
+        item.status.clone() as PayoutItemStatus,
+        &item.transaction_ids,
+        item.failure_reason,
+        item.modification_time,
+        item.id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, user_fp, idempotency_keys))]
+pub async fn find_payout_items_by_idempotency_keys<'a, E>(
+    pool: E,
+    user_fp: &str,
+    idempotency_keys: &[String],
+) -> Result<Vec<PayoutBatchItem>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        PayoutBatchItem,
+        r#"
+SELECT id,
+       batch_id,
+       user_fp,
+       idempotency_key,
+       item_type as "item_type: _",
+       account_id,
+       source_account_id,
+       amount,
+       status as "status: _",
+       transaction_ids as "transaction_ids: Vec<String>",
+       failure_reason,
+       creation_time,
+       modification_time
+FROM payout_batch_item
+WHERE user_fp = $1
+  AND idempotency_key = ANY($2)"#,
+        user_fp,
+        idempotency_keys,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
@@ -0,0 +1,268 @@
+use crate::core::{
+    Recurrence, ScheduledPayment, ScheduledPaymentExecution, ScheduledPaymentStatus,
+    ScheduledPaymentType,
+};
+use crate::PgDatabaseError;
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, Postgres};
+
+#[tracing::instrument(skip(pool, scheduled_payment))]
+pub async fn save_scheduled_payment<'a, E>(
+    pool: E,
+    scheduled_payment: &ScheduledPayment,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO scheduled_payment
+(
+ id,
+ user_fp,
+ account_id,
+ destination_account_id,
+ amount,
+ payment_type,
+ recurrence,
+ cron_expression,
+ timezone,
+ status,
+ starts_at,
+ next_run_at,
+ last_run_at,
+ creation_time,
+ modification_time
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)",
+        scheduled_payment.id,
+        scheduled_payment.user_fp,
+        scheduled_payment.account_id,
+        scheduled_payment.destination_account_id,
+        scheduled_payment.amount,
+        scheduled_payment.payment_type.clone() as ScheduledPaymentType,
+        scheduled_payment.recurrence.clone() as Recurrence,
+        scheduled_payment.cron_expression,
+        scheduled_payment.timezone,
+        scheduled_payment.status.clone() as ScheduledPaymentStatus,
+        scheduled_payment.starts_at,
+        scheduled_payment.next_run_at,
+        scheduled_payment.last_run_at,
+        scheduled_payment.creation_time,
+        scheduled_payment.modification_time,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Persists the run bookkeeping (`status`, `next_run_at`, `last_run_at`) of a scheduled payment.
+#[tracing::instrument(level = "debug", skip(pool, scheduled_payment))]
+pub async fn update_scheduled_payment<'a, E>(
+    pool: E,
+    scheduled_payment: &ScheduledPayment,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE scheduled_payment
+SET status = $1,
+    next_run_at = $2,
+    last_run_at = $3,
+    modification_time = $4
+WHERE id = $5",
+        scheduled_payment.status.clone() as ScheduledPaymentStatus,
+        scheduled_payment.next_run_at,
+        scheduled_payment.last_run_at,
+        scheduled_payment.modification_time,
+        scheduled_payment.id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Cancels the active payments paying from or to the account. Returns how many were cancelled.
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn cancel_account_scheduled_payments<'a, E>(
+    pool: E,
+    account_id: &str,
+    now: DateTime<Utc>,
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE scheduled_payment
+SET status = 'Cancelled',
+    modification_time = $1
+WHERE status = 'Active'
+  AND (account_id = $2 OR destination_account_id = $2)",
+        now,
+        account_id,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[tracing::instrument(level = "debug", skip(pool, scheduled_payment_id))]
+pub async fn find_scheduled_payment_by_id<'a, E>(
+    pool: E,
+    scheduled_payment_id: &str,
+) -> Result<Option<ScheduledPayment>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ScheduledPayment,
+        r#"
+SELECT id,
+       user_fp,
+       account_id,
+       destination_account_id,
+       amount,
+       payment_type as "payment_type: _",
+       recurrence as "recurrence: _",
+       cron_expression,
+       timezone,
+       status as "status: _",
+       starts_at,
+       next_run_at,
+       last_run_at,
+       creation_time,
+       modification_time
+FROM scheduled_payment
+WHERE id = $1"#,
+        scheduled_payment_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, account_id))]
+pub async fn find_account_scheduled_payments<'a, E>(
+    pool: E,
+    account_id: &str,
+    include_inactive: bool,
+) -> Result<Vec<ScheduledPayment>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ScheduledPayment,
+        r#"
+SELECT id,
+       user_fp,
+       account_id,
+       destination_account_id,
+       amount,
+       payment_type as "payment_type: _",
+       recurrence as "recurrence: _",
+       cron_expression,
+       timezone,
+       status as "status: _",
+       starts_at,
+       next_run_at,
+       last_run_at,
+       creation_time,
+       modification_time
+FROM scheduled_payment
+WHERE account_id = $1
+  AND ($2 OR status = 'Active')
+ORDER BY next_run_at"#,
+        account_id,
+        include_inactive,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Locks up to `limit` active payments due at `now`. Rows locked by another worker are skipped so
+/// several instances can poll the same table without running a payment twice.
+#[tracing::instrument(level = "debug", skip(pool))]
+pub async fn lock_due_scheduled_payments<'a, E>(
+    pool: E,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ScheduledPayment>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        ScheduledPayment,
+        r#"
+SELECT id,
+       user_fp,
+       account_id,
+       destination_account_id,
+       amount,
+       payment_type as "payment_type: _",
+       recurrence as "recurrence: _",
+       cron_expression,
+       timezone,
+       status as "status: _",
+       starts_at,
+       next_run_at,
+       last_run_at,
+       creation_time,
+       modification_time
+FROM scheduled_payment
+WHERE status = 'Active'
+  AND next_run_at <= $1
+ORDER BY next_run_at
+LIMIT $2
+FOR UPDATE SKIP LOCKED"#,
+        now,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, execution))]
+pub async fn save_scheduled_payment_execution<'a, E>(
+    pool: E,
+    execution: &ScheduledPaymentExecution,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+INSERT INTO scheduled_payment_execution
+(
+ id,
+ scheduled_payment_id,
+ transaction_id,
+ succeeded,
+ failure_reason,
+ scheduled_for,
+ executed_at
+)
+VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        execution.id,
+        execution.scheduled_payment_id,
+        execution.transaction_id,
+        execution.succeeded,
+        execution.failure_reason,
+        execution.scheduled_for,
+        execution.executed_at,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
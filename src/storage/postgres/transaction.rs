@@ -1,5 +1,8 @@
-use crate::core::{MonetaryTransaction, TransactionStatus, TransactionType};
+use crate::core::{
+    MonetaryTransaction, TransactionCursor, TransactionFilter, TransactionStatus, TransactionType,
+};
 use crate::PgDatabaseError;
+use chrono::{DateTime, Utc};
 use sqlx::{Executor, Postgres};
 
 #[tracing::instrument(skip(pool, transaction))]
@@ -17,17 +20,19 @@ INSERT INTO monetary_transaction
  status,
  amount,
  timestamp,
+ block_id,
  account_id,
  transaction_id,
  transaction_type,
  modification_date
  )
-VALUES ($1, $2, $3, $4, $5, $6, $7)
-ON CONFLICT(transaction_type) DO NOTHING
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+ON CONFLICT(transaction_id) DO NOTHING
 ",
         transaction.status.clone() as TransactionStatus,
         transaction.amount,
         transaction.timestamp,
+        transaction.block_id,
         transaction.account_id,
         transaction.id,
         transaction.transaction_type.clone() as TransactionType,
@@ -38,3 +43,173 @@ ON CONFLICT(transaction_type) DO NOTHING
 
     Ok(result.rows_affected() == 1)
 }
+
+/// Compare-and-swap on the transaction status: the row is only updated while it still has
+/// `current_status`. Returns false when another writer changed the status first.
+#[tracing::instrument(level = "debug", skip(pool, transaction_id, modification_date))]
+pub async fn update_transaction_status<'a, E>(
+    pool: E,
+    transaction_id: &str,
+    current_status: &TransactionStatus,
+    new_status: &TransactionStatus,
+    modification_date: DateTime<Utc>,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE monetary_transaction
+SET status = $1,
+    modification_date = $2
+WHERE transaction_id = $3
+  AND status = $4",
+        new_status.clone() as TransactionStatus,
+        modification_date,
+        transaction_id,
+        current_status.clone() as TransactionStatus,
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+#[tracing::instrument(level = "debug", skip(pool, transaction_id, block_id))]
+pub async fn set_monetary_tx_block<'a, E>(
+    pool: E,
+    transaction_id: &str,
+    block_id: &str,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "UPDATE monetary_transaction SET block_id = $1 WHERE transaction_id = $2",
+        block_id,
+        transaction_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Links several transactions to the block holding their ledger entries, returns the rows updated.
+#[tracing::instrument(level = "debug", skip(pool, transaction_ids, block_id))]
+pub async fn set_monetary_txs_block<'a, E>(
+    pool: E,
+    transaction_ids: &[String],
+    block_id: &str,
+) -> Result<u64, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "UPDATE monetary_transaction SET block_id = $1 WHERE transaction_id = ANY($2)",
+        block_id,
+        transaction_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[tracing::instrument(
+    level = "debug",
+    skip(pool, transaction_id),
+    name = "Find monetary transaction by id"
+)]
+pub async fn find_monetary_tx_by_id<'a, E>(
+    pool: E,
+    transaction_id: &str,
+) -> Result<Option<MonetaryTransaction>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query_as!(
+        MonetaryTransaction,
+        r#"
+SELECT transaction_id as id,
+       amount,
+       block_id,
+       timestamp,
+       account_id,
+       modification_date,
+       status as "status: _",
+       transaction_type as "transaction_type: _"
+FROM monetary_transaction
+WHERE transaction_id = $1"#,
+        transaction_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(result)
+}
+
+/// Returns at most `limit` transactions for an account, ordered by (timestamp, transaction_id)
+/// starting strictly after the `cursor` when one is given.
+#[tracing::instrument(
+    level = "debug",
+    skip(pool, account_id, filter, cursor),
+    name = "Find account monetary transactions"
+)]
+pub async fn find_account_monetary_txs<'a, E>(
+    pool: E,
+    account_id: &str,
+    filter: &TransactionFilter,
+    cursor: Option<&TransactionCursor>,
+    limit: i64,
+) -> Result<Vec<MonetaryTransaction>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let cursor_timestamp = cursor.map(|c| c.timestamp);
+    let cursor_tx_id = cursor.map(|c| c.transaction_id.clone());
+
+    let result = sqlx::query_as!(
+        MonetaryTransaction,
+        r#"
+SELECT transaction_id as id,
+       amount,
+       block_id,
+       timestamp,
+       account_id,
+       modification_date,
+       status as "status: _",
+       transaction_type as "transaction_type: _"
+FROM monetary_transaction
+WHERE account_id = $1
+    AND (array_length($2::monetary_tx_type[], 1) IS NULL OR transaction_type = ANY($2::monetary_tx_type[]))
+    AND (array_length($3::monetary_tx_status[], 1) IS NULL OR status = ANY($3::monetary_tx_status[]))
+    AND ($4::TIMESTAMPTZ IS NULL OR timestamp >= $4)
+    AND ($5::TIMESTAMPTZ IS NULL OR timestamp <= $5)
+    AND ($6::NUMERIC IS NULL OR amount >= $6)
+    AND ($7::NUMERIC IS NULL OR amount <= $7)
+    AND ($8::TIMESTAMPTZ IS NULL
+        OR ($9 AND (timestamp, transaction_id) > ($8, $10::VARCHAR))
+        OR (NOT $9 AND (timestamp, transaction_id) < ($8, $10::VARCHAR)))
+ORDER BY CASE WHEN $9 THEN timestamp END,
+         CASE WHEN $9 THEN transaction_id END,
+         CASE WHEN NOT $9 THEN timestamp END DESC,
+         CASE WHEN NOT $9 THEN transaction_id END DESC
+LIMIT $11"#,
+        account_id,
+        &filter.transaction_types as &[TransactionType],
+        &filter.statuses as &[TransactionStatus],
+        filter.from_time,
+        filter.to_time,
+        filter.min_amount,
+        filter.max_amount,
+        cursor_timestamp,
+        filter.ascending,
+        cursor_tx_id,
+        limit,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result)
+}
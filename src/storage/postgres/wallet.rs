@@ -1,7 +1,7 @@
 use crate::core::{Currency, WalletHolding};
 use crate::PgDatabaseError;
 use rust_decimal::Decimal;
-use sqlx::{Error, Executor, Postgres};
+use sqlx::{Error, Executor, PgConnection, Postgres};
 use tracing::info;
 
 #[tracing::instrument(skip(pg_pool, holding))]
@@ -19,13 +19,15 @@ INSERT INTO wallet (
                     balance,
                     currency,
                     account_id,
-                    modification_time
+                    modification_time,
+                    overdraft_limit
                     )
-VALUES ($1, $2, $3, $4)",
+VALUES ($1, $2, $3, $4, $5)",
         holding.balance as Decimal,
         holding.currency.clone() as Currency,
         holding.account_id,
         holding.modification_time,
+        holding.overdraft_limit,
     )
     .execute(pg_pool)
     .await?;
@@ -51,7 +53,8 @@ where
 SELECT balance,
        currency as "currency: _",
        account_id,
-       modification_time
+       modification_time,
+       overdraft_limit
 FROM wallet WHERE account_id = $1
        "#,
         account_id
@@ -66,6 +69,68 @@ FROM wallet WHERE account_id = $1
     }
 }
 
+/// Fetches the wallet for (account_id, currency) and locks its row (`FOR UPDATE`) until the
+/// surrounding DB transaction ends, so concurrent balance updates are serialized.
+#[tracing::instrument(
+    level = "debug",
+    skip(db_conn, account_id, currency),
+    name = "fetch and lock wallet holding"
+)]
+pub async fn fetch_wallet_for_update(
+    db_conn: &mut PgConnection,
+    account_id: &str,
+    currency: &Currency,
+) -> Result<Option<WalletHolding>, PgDatabaseError> {
+    let result = sqlx::query_as!(
+        WalletHolding,
+        r#"
+SELECT balance,
+       currency as "currency: _",
+       account_id,
+       modification_time,
+       overdraft_limit
+FROM wallet WHERE account_id = $1 AND currency = $2
+FOR UPDATE
+       "#,
+        account_id,
+        currency.clone() as Currency,
+    )
+    .fetch_optional(db_conn)
+    .await?;
+
+    Ok(result)
+}
+
+/// Fetches every wallet of the account and locks their rows until the surrounding DB transaction
+/// ends, so no balance can change while the caller relies on it.
+#[tracing::instrument(
+    level = "debug",
+    skip(db_conn, account_id),
+    name = "fetch and lock account wallet holdings"
+)]
+pub async fn fetch_wallets_for_update(
+    db_conn: &mut PgConnection,
+    account_id: &str,
+) -> Result<Vec<WalletHolding>, PgDatabaseError> {
+    let result = sqlx::query_as!(
+        WalletHolding,
+        r#"
+SELECT balance,
+       currency as "currency: _",
+       account_id,
+       modification_time,
+       overdraft_limit
+FROM wallet WHERE account_id = $1
+FOR UPDATE
+       "#,
+        account_id
+    )
+    .fetch_all(db_conn)
+    .await?;
+
+    Ok(result)
+}
+
 #[tracing::instrument(
     level = "debug",
     skip(pg_pool, account_ids),
@@ -84,7 +149,8 @@ where
 SELECT balance,
        currency as "currency: _",
        account_id,
-       modification_time
+       modification_time,
+       overdraft_limit
 FROM wallet WHERE account_id = ANY($1)
 "#,
         account_ids,
@@ -95,6 +161,44 @@ FROM wallet WHERE account_id = ANY($1)
     Ok(result)
 }
 
+/// The wallets of an account and of all its open descendants.
+#[tracing::instrument(level = "debug", skip(pg_pool, root_account_id))]
+pub async fn fetch_account_tree_wallets<'a, E>(
+    pg_pool: E,
+    root_account_id: &str,
+) -> Result<Vec<WalletHolding>, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result: Vec<WalletHolding> = sqlx::query_as!(
+        WalletHolding,
+        r#"
+WITH RECURSIVE tree AS (
+    SELECT id
+    FROM user_account
+    WHERE id = $1
+    UNION ALL
+    SELECT child.id
+    FROM user_account child
+    JOIN tree ON child.parent_account_id = tree.id
+    WHERE child.status <> 'Closed'
+)
+SELECT w.balance,
+       w.currency as "currency: _",
+       w.account_id,
+       w.modification_time,
+       w.overdraft_limit
+FROM wallet w
+JOIN tree ON w.account_id = tree.id
+"#,
+        root_account_id,
+    )
+    .fetch_all(pg_pool)
+    .await?;
+
+    Ok(result)
+}
+
 #[tracing::instrument(
     level = "debug",
     skip(pg_pool, holding),
@@ -110,14 +214,42 @@ where
     let result = sqlx::query_as!(
         WalletHolding,
         r#"UPDATE wallet SET balance = $1, modification_time = $2
-              WHERE account_id = $3
-              RETURNING balance, currency as "currency: _", modification_time, account_id"#,
+              WHERE account_id = $3 AND currency = $4
+              RETURNING balance, currency as "currency: _", modification_time, account_id, overdraft_limit"#,
         holding.balance as Decimal,
         holding.modification_time,
         holding.account_id,
+        holding.currency.clone() as Currency,
     )
     .fetch_one(pg_pool)
     .await?;
 
     Ok(result)
 }
+
+/// Changes the credit line of a wallet, the database refuses one below the overdrawn balance.
+#[tracing::instrument(level = "debug", skip(pg_pool, holding))]
+pub async fn update_wallet_overdraft_limit<'a, E>(
+    pg_pool: E,
+    holding: &WalletHolding,
+) -> Result<bool, PgDatabaseError>
+where
+    E: Executor<'a, Database = Postgres>,
+{
+    let result = sqlx::query!(
+        "
+UPDATE wallet
+SET overdraft_limit = $1,
+    modification_time = $2
+WHERE account_id = $3
+  AND currency = $4",
+        holding.overdraft_limit,
+        holding.modification_time,
+        holding.account_id,
+        holding.currency.clone() as Currency,
+    )
+    .execute(pg_pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,38 @@
+use redis::aio::ConnectionManager;
+use redis::AsyncTypedCommands;
+use tracing::warn;
+
+/// Counts one more failure under `key` and returns the count. The count starts over `window_secs`
+/// after the first failure it holds.
+pub async fn increment_failure_count(
+    key: &str,
+    window_secs: u64,
+    conn: &mut ConnectionManager,
+) -> Result<u64, String> {
+    let (count,): (u64,) = redis::pipe()
+        .atomic()
+        .incr(key, 1)
+        .cmd("EXPIRE")
+        .arg(key)
+        .arg(window_secs)
+        .arg("NX")
+        .ignore()
+        .query_async(conn)
+        .await
+        .map_err(|err| {
+            warn!("Failed to count failed attempt: {}", err);
+            format!("Failed to count failed attempt: {}", err)
+        })?;
+    Ok(count)
+}
+
+pub async fn clear_failure_counts(
+    keys: &[String],
+    conn: &mut ConnectionManager,
+) -> Result<(), String> {
+    conn.del(keys).await.map_err(|err| {
+        warn!("Failed to clear failed attempts: {}", err);
+        format!("Failed to clear failed attempts: {}", err)
+    })?;
+    Ok(())
+}
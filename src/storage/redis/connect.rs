@@ -28,3 +28,12 @@ pub async fn get_redis_client(config: &RedisConfig) -> Result<ConnectionManager,
 
     Ok(result)
 }
+
+/// Health probe, sends a `PING`.
+pub async fn ping_redis(conn: &mut ConnectionManager) -> Result<(), String> {
+    redis::cmd("PING")
+        .query_async::<String>(conn)
+        .await
+        .map_err(|err| format!("failed to ping redis: {}", err))?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::core::LimitUsage;
+use redis::aio::ConnectionManager;
+use redis::AsyncTypedCommands;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tracing::warn;
+
+// only windows already loaded are incremented, a missing one is loaded from the database
+const ADD_LIMIT_USAGE_SCRIPT: &str = r"
+for i, key in ipairs(KEYS) do
+  if redis.call('EXISTS', key) == 1 then
+    redis.call('INCRBYFLOAT', key, ARGV[i])
+  end
+end
+return 1";
+
+/// Reads the usage kept under the hourly count, daily debit and monthly debit `keys`. Returns
+/// `None` when any of them is missing.
+pub async fn get_limit_usage(
+    keys: &[String; 3],
+    conn: &mut ConnectionManager,
+) -> Result<Option<LimitUsage>, String> {
+    let values = conn.mget(keys.as_slice()).await.map_err(|err| {
+        warn!("Failed to get limit usage: {}", err);
+        format!("Failed to get limit usage: {}", err)
+    })?;
+    let values = match values.as_slice() {
+        [Some(hourly_count), Some(daily_debit), Some(monthly_debit)] => (
+            hourly_count.clone(),
+            daily_debit.clone(),
+            monthly_debit.clone(),
+        ),
+        _ => return Ok(None),
+    };
+
+    let parse_err = |err: rust_decimal::Error| format!("Failed to parse limit usage: {}", err);
+    let hourly_count = Decimal::from_str(&values.0).map_err(parse_err)?;
+    Ok(Some(LimitUsage {
+        hourly_count: u64::try_from(hourly_count).unwrap_or_default(),
+        daily_debit: Decimal::from_str(&values.1).map_err(parse_err)?,
+        monthly_debit: Decimal::from_str(&values.2).map_err(parse_err)?,
+    }))
+}
+
+/// Stores the usage under the hourly count, daily debit and monthly debit `keys`, each expiring
+/// after its ttl in seconds.
+pub async fn save_limit_usage(
+    keys: &[String; 3],
+    ttls: &[u64; 3],
+    usage: &LimitUsage,
+    conn: &mut ConnectionManager,
+) -> Result<(), String> {
+    let values = [
+        usage.hourly_count.to_string(),
+        usage.daily_debit.to_string(),
+        usage.monthly_debit.to_string(),
+    ];
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    for ((key, ttl), value) in keys.iter().zip(ttls).zip(values) {
+        pipe.set_ex(key, value, *ttl).ignore();
+    }
+    pipe.exec_async(conn).await.map_err(|err| {
+        warn!("Failed to save limit usage: {}", err);
+        format!("Failed to save limit usage: {}", err)
+    })?;
+    Ok(())
+}
+
+/// Adds one transaction to the usage kept under the hourly count, daily debit and monthly debit
+/// `keys`, `debit` being zero for credits.
+pub async fn add_limit_usage(
+    keys: &[String; 3],
+    debit: Decimal,
+    conn: &mut ConnectionManager,
+) -> Result<(), String> {
+    redis::Script::new(ADD_LIMIT_USAGE_SCRIPT)
+        .key(keys.as_slice())
+        .arg(1)
+        .arg(debit.to_string())
+        .arg(debit.to_string())
+        .invoke_async::<i64>(conn)
+        .await
+        .map_err(|err| {
+            warn!("Failed to add limit usage: {}", err);
+            format!("Failed to add limit usage: {}", err)
+        })?;
+    Ok(())
+}
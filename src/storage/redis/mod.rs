@@ -1,5 +1,13 @@
+mod attempts;
 mod connect;
 mod currency;
+mod limits;
+mod notification;
+mod rate_limit;
 
-pub use connect::get_redis_client;
+pub use attempts::{clear_failure_counts, increment_failure_count};
+pub use connect::{get_redis_client, ping_redis};
 pub use currency::{get_exchange_rate, save_exchange_rate};
+pub use limits::{add_limit_usage, get_limit_usage, save_limit_usage};
+pub use notification::publish_account_status_event;
+pub use rate_limit::take_rate_limit_tokens;
@@ -0,0 +1,21 @@
+use crate::core::AccountStatusEvent;
+use crate::ACCOUNT_STATUS_EVENTS_CHANNEL;
+use redis::aio::ConnectionManager;
+use redis::AsyncTypedCommands;
+use tracing::warn;
+
+pub async fn publish_account_status_event(
+    event: &AccountStatusEvent,
+    conn: &mut ConnectionManager,
+) -> Result<(), String> {
+    let event_json = serde_json::to_string(event)
+        .map_err(|err| format!("Failed to serialize account status event: {}", err))?;
+
+    conn.publish(ACCOUNT_STATUS_EVENTS_CHANNEL, event_json)
+        .await
+        .map_err(|err| {
+            warn!("Failed to publish account status event: {}", err);
+            format!("Failed to publish account status event: {}", err)
+        })?;
+    Ok(())
+}
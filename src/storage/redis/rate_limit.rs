@@ -0,0 +1,55 @@
+use crate::TokenBucketConfig;
+use redis::aio::ConnectionManager;
+use tracing::warn;
+
+// Refills each bucket for the time since it was last used, then takes a token from every bucket
+// or, when one is empty, from none. Returns 0, or the milliseconds until every bucket has a token.
+// Time is read from Redis so instances with skewed clocks share the same buckets.
+const TAKE_TOKENS_SCRIPT: &str = r"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local tokens = {}
+local retry_after = 0
+for i, key in ipairs(KEYS) do
+  local capacity = tonumber(ARGV[2 * i - 1])
+  local rate = tonumber(ARGV[2 * i])
+  local bucket = redis.call('HMGET', key, 'tokens', 'ts')
+  local available = tonumber(bucket[1]) or capacity
+  local ts = tonumber(bucket[2]) or now
+  available = math.min(capacity, available + math.max(0, now - ts) * rate)
+  if available < 1 then
+    retry_after = math.max(retry_after, math.ceil((1 - available) / rate))
+  end
+  tokens[i] = available
+end
+for i, key in ipairs(KEYS) do
+  local capacity = tonumber(ARGV[2 * i - 1])
+  local rate = tonumber(ARGV[2 * i])
+  if retry_after == 0 then
+    tokens[i] = tokens[i] - 1
+  end
+  redis.call('HSET', key, 'tokens', tostring(tokens[i]), 'ts', now)
+  redis.call('PEXPIRE', key, math.ceil(capacity / rate) + 1000)
+end
+return retry_after";
+
+/// Takes a call from each of the token `buckets`, by key. Returns 0 when the call is allowed, or
+/// the milliseconds to wait before it can be made again.
+pub async fn take_rate_limit_tokens(
+    buckets: &[(String, &TokenBucketConfig)],
+    conn: &mut ConnectionManager,
+) -> Result<u64, String> {
+    let script = redis::Script::new(TAKE_TOKENS_SCRIPT);
+    let mut invocation = script.prepare_invoke();
+    for (key, bucket) in buckets {
+        invocation
+            .key(key)
+            .arg(bucket.capacity)
+            // tokens per millisecond
+            .arg(bucket.refill_per_second / 1000.0);
+    }
+    invocation.invoke_async::<u64>(conn).await.map_err(|err| {
+        warn!("Failed to take rate limit tokens: {}", err);
+        format!("Failed to take rate limit tokens: {}", err)
+    })
+}
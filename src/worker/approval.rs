@@ -0,0 +1,52 @@
+use crate::{expire_approval_requests, ApprovalWorkerConfig};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Periodically rejects the debits that were not approved within their approval window.
+pub struct ApprovalExpiryWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+}
+
+impl ApprovalExpiryWorker {
+    pub fn new(pg_pool: Arc<PgPool>, config: ApprovalWorkerConfig) -> Self {
+        ApprovalExpiryWorker {
+            pg_pool,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting approval expiry worker :: interval={:?}",
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // drain the backlog before waiting for the next tick
+            loop {
+                match expire_approval_requests(&self.pg_pool, self.batch_size).await {
+                    Ok(expired) if expired as i64 == self.batch_size => {
+                        info!("expired {} approval requests", expired);
+                    }
+                    Ok(0) => break,
+                    Ok(expired) => {
+                        info!("expired {} approval requests", expired);
+                        break;
+                    }
+                    Err(err) => {
+                        error!("failed to expire approval requests: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
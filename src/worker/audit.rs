@@ -0,0 +1,62 @@
+use crate::context::ApplicationContext;
+use crate::{checkpoint_audit_chain, AuditCheckpointWorkerConfig};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Anchors the head of the audit hash chain into the Cassandra block chain.
+pub struct AuditCheckpointWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl AuditCheckpointWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: AuditCheckpointWorkerConfig,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        AuditCheckpointWorker {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting audit checkpoint worker :: interval={:?}",
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match checkpoint_audit_chain(
+                &self.pg_pool,
+                self.batch_size,
+                &self.cassandra_session,
+                &self.app_ctx,
+            )
+            .await
+            {
+                Ok(Some(checkpoint)) => {
+                    info!("audit chain anchored at {}", checkpoint.sequence_num);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    error!("failed to checkpoint the audit chain: {}", err);
+                }
+            }
+        }
+    }
+}
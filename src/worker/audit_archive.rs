@@ -0,0 +1,54 @@
+use crate::context::ApplicationContext;
+use crate::{archive_audit_logs, AuditArchiveWorkerConfig};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Moves the audit logs past their retention to the archive store.
+pub struct AuditArchiveWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+}
+
+impl AuditArchiveWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: AuditArchiveWorkerConfig,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        AuditArchiveWorker {
+            app_ctx,
+            pg_pool,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        if self.app_ctx.audit_archive.is_none() {
+            info!("no audit archive storage configured, audit logs are not archived");
+        }
+        info!(
+            "starting audit archive worker :: interval={:?}",
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match archive_audit_logs(&self.pg_pool, self.batch_size, &self.app_ctx).await {
+                Ok(0) => {}
+                Ok(archived) => {
+                    info!("archived {} audit logs", archived);
+                }
+                Err(err) => {
+                    error!("failed to archive audit logs: {}", err);
+                }
+            }
+        }
+    }
+}
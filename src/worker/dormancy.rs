@@ -0,0 +1,68 @@
+use crate::context::ApplicationContext;
+use crate::core::AccountType;
+use crate::{mark_dormant_accounts, DormancyWorkerConfig};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Periodically marks accounts without activity for the term of their type as `Inactive`.
+pub struct DormancyWorker {
+    batch_size: i64,
+    interval: Duration,
+    terms: Vec<(AccountType, u32)>,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+}
+
+impl DormancyWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: DormancyWorkerConfig,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        let terms = [
+            AccountType::Normal,
+            AccountType::Wallet,
+            AccountType::Escrow,
+            AccountType::SystemFee,
+        ]
+        .into_iter()
+        .filter_map(|acct_type| {
+            let days = config.terms.days_for(&acct_type)?;
+            Some((acct_type, days))
+        })
+        .collect();
+
+        DormancyWorker {
+            terms,
+            app_ctx,
+            pg_pool,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting dormancy worker :: interval={:?}, terms={:?}",
+            self.interval, self.terms
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if self.terms.is_empty() {
+                continue;
+            }
+            match mark_dormant_accounts(&self.pg_pool, &self.terms, self.batch_size, &self.app_ctx)
+                .await
+            {
+                Ok(0) => {}
+                Ok(marked) => info!("marked {} accounts inactive", marked),
+                Err(err) => error!("failed to mark dormant accounts: {}", err),
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use crate::context::ApplicationContext;
+use crate::{lift_expired_freezes, FreezeExpiryWorkerConfig};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Periodically moves accounts whose temporary freeze expired back to `Active`.
+pub struct FreezeExpiryWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+}
+
+impl FreezeExpiryWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: FreezeExpiryWorkerConfig,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        FreezeExpiryWorker {
+            app_ctx,
+            pg_pool,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting freeze expiry worker :: interval={:?}",
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match lift_expired_freezes(&self.pg_pool, self.batch_size, &self.app_ctx).await {
+                Ok(0) => {}
+                Ok(unfrozen) => info!("unfroze {} accounts past their freeze expiry", unfrozen),
+                Err(err) => error!("failed to lift expired freezes: {}", err),
+            }
+        }
+    }
+}
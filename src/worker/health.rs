@@ -0,0 +1,69 @@
+use crate::context::ApplicationContext;
+use crate::core::DependencyHealth;
+use crate::server::ServiceHealth;
+use crate::{probe_dependencies, HealthWorkerConfig};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Probes Postgres, Cassandra and Redis and publishes the outcome through the gRPC health service.
+pub struct HealthWorker {
+    interval: Duration,
+    timeout: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+    service_health: ServiceHealth,
+}
+
+impl HealthWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: HealthWorkerConfig,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+        service_health: ServiceHealth,
+    ) -> Self {
+        HealthWorker {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+            service_health,
+            interval: Duration::from_secs(config.interval),
+            timeout: Duration::from_millis(config.timeout),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting health worker :: interval={:?}, timeout={:?}",
+            self.interval, self.timeout
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_health: Option<DependencyHealth> = None;
+        loop {
+            ticker.tick().await;
+            let health = probe_dependencies(
+                &self.pg_pool,
+                &self.cassandra_session,
+                &self.app_ctx,
+                self.timeout,
+            )
+            .await;
+            self.service_health.report(&health).await;
+
+            if last_health != Some(health) {
+                if health.is_ready() {
+                    info!("server is ready :: {:?}", health);
+                } else {
+                    warn!("server is not ready :: {:?}", health);
+                }
+                last_health = Some(health);
+            }
+        }
+    }
+}
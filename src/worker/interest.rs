@@ -0,0 +1,81 @@
+use crate::context::ApplicationContext;
+use crate::{accrue_interest, capitalize_interest, InterestWorkerConfig};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Accrues the daily interest of the accounts with an interest product and capitalizes what was
+/// accrued in the previous months.
+pub struct InterestWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl InterestWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: InterestWorkerConfig,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        InterestWorker {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!("starting interest worker :: interval={:?}", self.interval);
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // accrue every day due before paying what was accrued
+            loop {
+                match accrue_interest(&self.pg_pool, self.batch_size, &self.app_ctx).await {
+                    Ok(0) => break,
+                    Ok(accrued) => {
+                        info!("accrued {} days of interest", accrued);
+                    }
+                    Err(err) => {
+                        error!("failed to accrue interest: {}", err);
+                        break;
+                    }
+                }
+            }
+            // a failed account ends the drain, it is retried on the next tick
+            loop {
+                match capitalize_interest(
+                    &self.pg_pool,
+                    self.batch_size,
+                    &self.cassandra_session,
+                    &self.app_ctx,
+                )
+                .await
+                {
+                    Ok(capitalized) if capitalized as i64 == self.batch_size => {
+                        info!("capitalized the interest of {} accounts", capitalized);
+                    }
+                    Ok(0) => break,
+                    Ok(capitalized) => {
+                        info!("capitalized the interest of {} accounts", capitalized);
+                        break;
+                    }
+                    Err(err) => {
+                        error!("failed to capitalize interest: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
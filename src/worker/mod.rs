@@ -0,0 +1,19 @@
+mod approval;
+mod audit;
+mod audit_archive;
+mod dormancy;
+mod freeze;
+mod health;
+mod interest;
+mod overdraft;
+mod scheduled_payment;
+
+pub use approval::ApprovalExpiryWorker;
+pub use audit::AuditCheckpointWorker;
+pub use audit_archive::AuditArchiveWorker;
+pub use dormancy::DormancyWorker;
+pub use freeze::FreezeExpiryWorker;
+pub use health::HealthWorker;
+pub use interest::InterestWorker;
+pub use overdraft::OverdraftWorker;
+pub use scheduled_payment::ScheduledPaymentWorker;
@@ -0,0 +1,63 @@
+use crate::context::ApplicationContext;
+use crate::{charge_overdrafts, OverdraftWorkerConfig};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Charges the daily overdraft fee and interest of the wallets that ended a day below zero.
+pub struct OverdraftWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl OverdraftWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: OverdraftWorkerConfig,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        OverdraftWorker {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!("starting overdraft worker :: interval={:?}", self.interval);
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // drain the backlog, a run charging nothing only has failed accounts left
+            loop {
+                match charge_overdrafts(
+                    &self.pg_pool,
+                    self.batch_size,
+                    &self.cassandra_session,
+                    &self.app_ctx,
+                )
+                .await
+                {
+                    Ok(0) => break,
+                    Ok(charged) => {
+                        info!("charged {} days of overdraft", charged);
+                    }
+                    Err(err) => {
+                        error!("failed to charge overdrafts: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::context::ApplicationContext;
+use crate::{run_due_scheduled_payments, ScheduledPaymentWorkerConfig};
+use cassandra_cpp::Session;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+
+/// Polls for due scheduled payments and executes them.
+pub struct ScheduledPaymentWorker {
+    batch_size: i64,
+    interval: Duration,
+    pg_pool: Arc<PgPool>,
+    app_ctx: Arc<ApplicationContext>,
+    cassandra_session: Arc<Session>,
+}
+
+impl ScheduledPaymentWorker {
+    pub fn new(
+        pg_pool: Arc<PgPool>,
+        config: ScheduledPaymentWorkerConfig,
+        cassandra_session: Arc<Session>,
+        app_ctx: Arc<ApplicationContext>,
+    ) -> Self {
+        ScheduledPaymentWorker {
+            app_ctx,
+            pg_pool,
+            cassandra_session,
+            batch_size: config.batch_size as i64,
+            interval: Duration::from_secs(config.interval),
+        }
+    }
+
+    pub async fn run_until_stopped(self) -> anyhow::Result<()> {
+        info!(
+            "starting scheduled payment worker :: interval={:?}",
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            // drain the backlog before waiting for the next tick
+            loop {
+                match run_due_scheduled_payments(
+                    &self.pg_pool,
+                    self.batch_size,
+                    &self.cassandra_session,
+                    &self.app_ctx,
+                )
+                .await
+                {
+                    Ok(executed) if executed as i64 == self.batch_size => {
+                        info!("executed {} scheduled payments", executed);
+                    }
+                    Ok(0) => break,
+                    Ok(executed) => {
+                        info!("executed {} scheduled payments", executed);
+                        break;
+                    }
+                    Err(err) => {
+                        error!("failed to run scheduled payments: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}